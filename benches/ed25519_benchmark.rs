@@ -1,5 +1,8 @@
 use criterion::{criterion_group, criterion_main, Criterion};
+use ed25519::edwards::{EdwardsPoint, ED25519_BASEPOINT, ED25519_BASEPOINT_TABLE};
 use ed25519::field::Field25519Element;
+use ed25519::scalar::Scalar;
+use ed25519::signing::{verify_batch, Keypair, PrecomputedVerifier};
 
 fn bench_inverse(c: &mut Criterion) {
     let mut items = [0; 32];
@@ -100,6 +103,65 @@ fn bench_pack(c: &mut Criterion) {
     c.bench_function("pack", |b| b.iter(|| unpacked.pack()));
 }
 
+fn bench_variable_base_mul(c: &mut Criterion) {
+    let scalar = Scalar::from_bytes_mod_order([0x5a; 32]);
+
+    c.bench_function("variable-base scalar mul", |b| {
+        b.iter(|| ED25519_BASEPOINT * scalar)
+    });
+}
+
+fn bench_variable_base_mul_vartime(c: &mut Criterion) {
+    let scalar = Scalar::from_bytes_mod_order([0x5a; 32]);
+
+    c.bench_function("variable-base scalar mul (vartime)", |b| {
+        b.iter(|| *ED25519_BASEPOINT.clone().mul_vartime(&scalar))
+    });
+}
+
+fn bench_double_scalar_mul_basepoint(c: &mut Criterion) {
+    let a = Scalar::from_bytes_mod_order([0x5a; 32]);
+    let b = Scalar::from_bytes_mod_order([0xa5; 32]);
+    let point = ED25519_BASEPOINT * a;
+
+    c.bench_function("double scalar mul basepoint (vartime)", |bench| {
+        bench.iter(|| EdwardsPoint::vartime_double_scalar_mul_basepoint(&a, &point, &b))
+    });
+}
+
+fn bench_basepoint_table_mul(c: &mut Criterion) {
+    let scalar = Scalar::from_bytes_mod_order([0x5a; 32]);
+    let table = &*ED25519_BASEPOINT_TABLE;
+
+    c.bench_function("basepoint table scalar mul", |b| b.iter(|| table * &scalar));
+}
+
+fn bench_sign_verify(c: &mut Criterion) {
+    let keypair = Keypair::from_seed([7; 32]);
+    let message = [0x42; 64];
+    let signature = keypair.sign(&message);
+
+    c.bench_function("sign", |b| b.iter(|| keypair.sign(&message)));
+    c.bench_function("verify", |b| {
+        b.iter(|| keypair.public().verify(&message, &signature))
+    });
+    let verifier = PrecomputedVerifier::new(keypair.public());
+    c.bench_function("verify precomputed", |b| {
+        b.iter(|| verifier.verify(&message, &signature))
+    });
+}
+
+fn bench_verify_batch(c: &mut Criterion) {
+    let keypairs: Vec<_> = (0..64u8).map(|i| Keypair::from_seed([i; 32])).collect();
+    let messages: Vec<&[u8]> = vec![&[0x42; 64]; 64];
+    let signatures: Vec<_> = keypairs.iter().map(|k| k.sign(messages[0])).collect();
+    let public_keys: Vec<_> = keypairs.iter().map(|k| *k.public()).collect();
+
+    c.bench_function("verify_batch 64", |b| {
+        b.iter(|| verify_batch(&messages, &signatures, &public_keys))
+    });
+}
+
 criterion_group!(
     benches,
     bench_inverse,
@@ -107,6 +169,12 @@ criterion_group!(
     bench_mul,
     bench_sub,
     bench_unpack,
-    bench_pack
+    bench_pack,
+    bench_variable_base_mul,
+    bench_variable_base_mul_vartime,
+    bench_double_scalar_mul_basepoint,
+    bench_basepoint_table_mul,
+    bench_sign_verify,
+    bench_verify_batch
 );
 criterion_main!(benches);
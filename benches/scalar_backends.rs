@@ -0,0 +1,65 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use ed25519::scalar::Scalar;
+use ed25519::scalar_montgomery::MontgomeryScalar;
+
+// Compares the schoolbook + modL multiplication of Scalar with Montgomery
+// multiplication, both for a single product and for a chain of products
+// where the Montgomery conversions are amortized.
+
+fn inputs() -> (Scalar, Scalar) {
+    let a = Scalar::from_bytes_mod_order([0x5a; 32]);
+    let b = Scalar::from_bytes_mod_order([0xa5; 32]);
+    (a, b)
+}
+
+fn bench_scalar_mul(c: &mut Criterion) {
+    let (a, b) = inputs();
+
+    c.bench_function("scalar mul", |bench| bench.iter(|| a * b));
+}
+
+fn bench_montgomery_mul(c: &mut Criterion) {
+    let (a, b) = inputs();
+    let a = MontgomeryScalar::from_scalar(&a);
+    let b = MontgomeryScalar::from_scalar(&b);
+
+    c.bench_function("montgomery mul", |bench| bench.iter(|| a.mul(&b)));
+}
+
+fn bench_scalar_mul_chain(c: &mut Criterion) {
+    let (a, b) = inputs();
+
+    c.bench_function("scalar mul chain of 64", |bench| {
+        bench.iter(|| {
+            let mut acc = a;
+            for _ in 0..64 {
+                acc *= b;
+            }
+            acc
+        })
+    });
+}
+
+fn bench_montgomery_mul_chain(c: &mut Criterion) {
+    let (a, b) = inputs();
+
+    c.bench_function("montgomery mul chain of 64", |bench| {
+        bench.iter(|| {
+            let mut acc = MontgomeryScalar::from_scalar(&a);
+            let b = MontgomeryScalar::from_scalar(&b);
+            for _ in 0..64 {
+                acc = acc.mul(&b);
+            }
+            acc.to_scalar()
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_scalar_mul,
+    bench_montgomery_mul,
+    bench_scalar_mul_chain,
+    bench_montgomery_mul_chain
+);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+// A verifier that only checks a signature, for measuring what a
+// verification-only build costs. Build it without the signing code with
+//
+//   cargo build --release --example verify_only --no-default-features
+//
+// or with the ignored verify_only_size test (cargo test -- --ignored),
+// which also fails if the binary grows past a size limit.
+//
+// It checks RFC 8032 test 2 and exits with status 1 if that fails.
+use ed25519::signing::{PublicKey, Signature};
+
+const PUBLIC_KEY: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+const MESSAGE: &str = "72";
+const SIGNATURE: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+                         085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+fn decode<const N: usize>(h: &str) -> [u8; N] {
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&h[2 * i..2 * i + 2], 16).unwrap();
+    }
+    bytes
+}
+
+fn main() {
+    let public = PublicKey::from_bytes(decode(PUBLIC_KEY)).unwrap();
+    let signature = Signature::from_bytes(&decode(SIGNATURE)).unwrap();
+    let message: [u8; 1] = decode(MESSAGE);
+    match public.verify(&message, &signature) {
+        Ok(()) => println!("signature ok"),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
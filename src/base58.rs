@@ -0,0 +1,80 @@
+// Base58 with the Bitcoin alphabet, which multibase calls base58btc
+// (prefix 'z') and Solana uses for keys; see did and solana. The data is
+// read as one big-endian number and written in base 58, with each leading
+// zero byte written as a '1'. That makes the encoding of a byte string
+// unique, so decoding needs no extra checks beyond the alphabet.
+//
+// The conversion is quadratic in the length, which is fine for keys.
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    let zeros = data.iter().take_while(|&&b| b == 0).count();
+    // The base-58 digits, least significant first.
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for &byte in &data[zeros..] {
+        let mut carry = u32::from(byte);
+        for digit in &mut digits {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut out = "1".repeat(zeros);
+    out.extend(digits.iter().rev().map(|&d| ALPHABET[d as usize] as char));
+    out
+}
+
+// None if text has a character outside the alphabet (which leaves out 0,
+// O, I and l).
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    let zeros = text.bytes().take_while(|&c| c == b'1').count();
+    // The bytes, least significant first.
+    let mut bytes: Vec<u8> = Vec::with_capacity(text.len() * 733 / 1000 + 1);
+    for c in text[zeros..].bytes() {
+        let mut carry = ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in &mut bytes {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut out = vec![0; zeros];
+    out.extend(bytes.iter().rev());
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From the base58 Internet-Draft (draft-msporny-base58).
+    #[test]
+    fn vectors() {
+        let vectors: [(&[u8], &str); 4] = [
+            (b"", ""),
+            (b"Hello World!", "2NEpo7TZRRrLZSi2U"),
+            (
+                b"The quick brown fox jumps over the lazy dog.",
+                "USm3fpXnKG5EUBx2ndxBDMPVciP5hGey2Jh4NDv6gmeo1LkMeiKrLJUUBk6Z",
+            ),
+            (&[0, 0, 0x28, 0x7f, 0xb4, 0xcd], "11233QC4"),
+        ];
+        for (data, encoded) in vectors {
+            assert_eq!(encode(data), encoded);
+            assert_eq!(decode(encoded).unwrap(), data);
+        }
+        assert_eq!(decode("1"), Some(vec![0]));
+        for bad in ["0", "O", "I", "l", "2NEpo7TZRRrLZSi2U!"] {
+            assert_eq!(decode(bad), None, "{bad}");
+        }
+    }
+}
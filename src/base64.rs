@@ -0,0 +1,157 @@
+// Standard base64 (RFC 4648 section 4), for the text formats keys and
+// fingerprints are exchanged in, and the URL-safe variant (section 5) JOSE
+// uses, which swaps + and / for - and _ and never pads.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+#[cfg(feature = "jose")]
+const URL_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// Encodes three bytes at a time as four characters. With pad, a final
+// partial group is filled out with '='; OpenSSH's fingerprints leave the
+// padding off.
+pub(crate) fn encode(data: &[u8], pad: bool) -> String {
+    encode_with(ALPHABET, data, pad)
+}
+
+#[cfg(feature = "jose")]
+pub(crate) fn encode_url(data: &[u8]) -> String {
+    encode_with(URL_ALPHABET, data, false)
+}
+
+fn encode_with(alphabet: &[u8; 64], data: &[u8], pad: bool) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let n = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(alphabet[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else if pad {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// Decodes padded base64, or None if it isn't valid. This is strict:
+// the length must be a multiple of 4, padding may only appear at the end,
+// and the bits the padding leaves over must be zero, so each byte string
+// has exactly one encoding that decodes to it.
+#[cfg(any(feature = "pkcs8", feature = "ssh", feature = "signify", test))]
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    decode_with(ALPHABET, text.as_bytes())
+}
+
+// Decodes unpadded base64url, with the same strictness as decode. A
+// length of 1 mod 4 can't come from any byte string.
+#[cfg(feature = "jose")]
+pub(crate) fn decode_url(text: &str) -> Option<Vec<u8>> {
+    if text.contains('=') || text.len() % 4 == 1 {
+        return None;
+    }
+    let mut padded = text.as_bytes().to_vec();
+    padded.resize(text.len().div_ceil(4) * 4, b'=');
+    decode_with(URL_ALPHABET, &padded)
+}
+
+#[cfg(any(
+    feature = "pkcs8",
+    feature = "ssh",
+    feature = "signify",
+    feature = "jose",
+    test
+))]
+fn decode_with(alphabet: &[u8; 64], text: &[u8]) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let groups = text.len() / 4;
+    for (g, chunk) in text.chunks(4).enumerate() {
+        let padding = match chunk {
+            [.., b'=', b'='] => 2,
+            [.., b'='] => 1,
+            _ => 0,
+        };
+        if padding > 0 && g + 1 != groups {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = alphabet.iter().position(|&a| a == c)? as u32;
+            n = n << 6 | value;
+        }
+        n <<= 6 * padding;
+        let bytes = n.to_be_bytes();
+        if bytes[4 - padding..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        out.extend_from_slice(&bytes[1..4 - padding]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4648 section 10.
+    #[test]
+    fn rfc4648() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (data, encoded) in vectors {
+            assert_eq!(encode(data.as_bytes(), true), encoded);
+            assert_eq!(
+                encode(data.as_bytes(), false),
+                encoded.trim_end_matches('=')
+            );
+        }
+        assert_eq!(encode(&[0xfb, 0xff], true), "+/8=");
+    }
+
+    #[test]
+    fn decode_rfc4648() {
+        for data in ["", "f", "fo", "foo", "foob", "fooba", "foobar"] {
+            assert_eq!(
+                decode(&encode(data.as_bytes(), true)).unwrap(),
+                data.as_bytes()
+            );
+        }
+        assert_eq!(decode("+/8=").unwrap(), [0xfb, 0xff]);
+
+        // missing padding, padding in the middle, a character outside the
+        // alphabet, and leftover bits that aren't zero ("Zh==" would be a
+        // second encoding of "f")
+        for bad in ["Zg", "Zg==Zm8=", "Zm9v!A==", "Zh==", "Zm9=", "Z==="] {
+            assert_eq!(decode(bad), None, "{bad}");
+        }
+    }
+
+    #[cfg(feature = "jose")]
+    #[test]
+    fn url_safe() {
+        assert_eq!(encode_url(&[0xfb, 0xff]), "-_8");
+        assert_eq!(decode_url("-_8").unwrap(), [0xfb, 0xff]);
+        for data in ["", "f", "fo", "foo", "foob", "fooba", "foobar"] {
+            assert_eq!(
+                decode_url(&encode_url(data.as_bytes())).unwrap(),
+                data.as_bytes()
+            );
+        }
+        // padding, the standard alphabet, an impossible length, nonzero
+        // leftover bits
+        for bad in ["Zg==", "+/8", "Zm9vY", "Zh"] {
+            assert_eq!(decode_url(bad), None, "{bad}");
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use bincode::de::{BorrowDecoder, Decoder};
+use bincode::enc::Encoder;
+use bincode::error::{DecodeError, EncodeError};
+use bincode::{impl_borrow_decode, BorrowDecode, Decode, Encode};
+
+use crate::edwards::{CompressedEdwardsY, EdwardsPoint};
+use crate::montgomery::MontgomeryPoint;
+use crate::scalar::Scalar;
+use crate::signing::{GenericPublicKey, Hash512, Signature};
+
+// bincode 2 Encode and Decode (and BorrowDecode) for the public types, so
+// they can be fields of structs deriving them.
+//
+// As with borsh, each is the type's usual encoding written as a
+// fixed-size array, which bincode does without a length prefix and
+// whatever the configuration: 32 bytes for points, scalars and keys, 64
+// for a signature. Decoding validates the same way, failing with
+// DecodeError::Other or OtherString. An EdwardsPoint is written compressed
+// and decompressed when read. Secret keys are left out.
+
+impl Encode for CompressedEdwardsY {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.as_bytes().encode(encoder)
+    }
+}
+
+impl<C> Decode<C> for CompressedEdwardsY {
+    fn decode<D: Decoder<Context = C>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        <[u8; 32]>::decode(decoder).map(CompressedEdwardsY)
+    }
+}
+
+impl_borrow_decode!(CompressedEdwardsY);
+
+impl Encode for EdwardsPoint {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.compress().as_bytes().encode(encoder)
+    }
+}
+
+impl<C> Decode<C> for EdwardsPoint {
+    fn decode<D: Decoder<Context = C>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let compressed = CompressedEdwardsY::decode(decoder)?;
+        compressed
+            .decompress()
+            .filter(|point| point.compress() == compressed)
+            .ok_or(DecodeError::Other("not a canonical point encoding"))
+    }
+}
+
+impl_borrow_decode!(EdwardsPoint);
+
+impl Encode for MontgomeryPoint {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.as_bytes().encode(encoder)
+    }
+}
+
+impl<C> Decode<C> for MontgomeryPoint {
+    fn decode<D: Decoder<Context = C>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        <[u8; 32]>::decode(decoder).map(MontgomeryPoint)
+    }
+}
+
+impl_borrow_decode!(MontgomeryPoint);
+
+impl Encode for Scalar {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.to_bytes().encode(encoder)
+    }
+}
+
+impl<C> Decode<C> for Scalar {
+    fn decode<D: Decoder<Context = C>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Scalar::from_canonical_bytes(<[u8; 32]>::decode(decoder)?)
+            .ok_or(DecodeError::Other("scalar is not reduced mod L"))
+    }
+}
+
+impl_borrow_decode!(Scalar);
+
+impl<D: Hash512> Encode for GenericPublicKey<D> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.as_bytes().encode(encoder)
+    }
+}
+
+impl<C, H: Hash512> Decode<C> for GenericPublicKey<H> {
+    fn decode<D: Decoder<Context = C>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        GenericPublicKey::from_bytes(<[u8; 32]>::decode(decoder)?)
+            .map_err(|e| DecodeError::OtherString(e.to_string()))
+    }
+}
+
+// impl_borrow_decode! can't add the Hash512 bound.
+impl<'de, C, H: Hash512> BorrowDecode<'de, C> for GenericPublicKey<H> {
+    fn borrow_decode<D: BorrowDecoder<'de, Context = C>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
+        Self::decode(decoder)
+    }
+}
+
+impl Encode for Signature {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.to_bytes().encode(encoder)
+    }
+}
+
+impl<C> Decode<C> for Signature {
+    fn decode<D: Decoder<Context = C>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Signature::from_bytes(&<[u8; 64]>::decode(decoder)?)
+            .map_err(|e| DecodeError::OtherString(e.to_string()))
+    }
+}
+
+impl_borrow_decode!(Signature);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edwards::ED25519_BASEPOINT;
+    use crate::signing::PublicKey;
+
+    const PUBLIC: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+        5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+    fn to_vec<T: Encode>(value: &T) -> Vec<u8> {
+        bincode::encode_to_vec(value, bincode::config::standard()).unwrap()
+    }
+
+    fn from_slice<T: Decode<()>>(bytes: &[u8]) -> Result<T, DecodeError> {
+        let (value, read) = bincode::decode_from_slice(bytes, bincode::config::standard())?;
+        assert_eq!(read, bytes.len());
+        Ok(value)
+    }
+
+    // A key, a nonce and a signature, as in a struct deriving the traits.
+    // With the legacy (fixed-width integer) configuration only the nonce's
+    // encoding changes.
+    #[test]
+    fn fixed_layout() {
+        let signer =
+            PublicKey::from_bytes(hex::decode(PUBLIC).unwrap().try_into().unwrap()).unwrap();
+        let signature: [u8; 64] = hex::decode(SIGNATURE).unwrap().try_into().unwrap();
+        let signature = Signature::from_bytes(&signature).unwrap();
+        let signed = (signer, 1u64, signature);
+        let bytes = to_vec(&signed);
+        assert_eq!(hex::encode(&bytes), format!("{PUBLIC}01{SIGNATURE}"));
+        assert_eq!(
+            from_slice::<(PublicKey, u64, Signature)>(&bytes).unwrap(),
+            signed
+        );
+        let legacy = bincode::encode_to_vec(signed, bincode::config::legacy()).unwrap();
+        assert_eq!(
+            hex::encode(&legacy),
+            format!("{PUBLIC}0100000000000000{SIGNATURE}")
+        );
+
+        // borrowed decoding goes through the same checks
+        let (borrowed, _): ((PublicKey, u64, Signature), _) =
+            bincode::borrow_decode_from_slice(&bytes, bincode::config::standard()).unwrap();
+        assert_eq!(borrowed, signed);
+
+        let point = ED25519_BASEPOINT;
+        let bytes = to_vec(&point);
+        assert_eq!(bytes, point.compress().as_bytes());
+        assert_eq!(from_slice::<EdwardsPoint>(&bytes).unwrap(), point);
+
+        let scalar = Scalar::from(12345u64);
+        assert_eq!(from_slice::<Scalar>(&to_vec(&scalar)).unwrap(), scalar);
+        let u = MontgomeryPoint([9; 32]);
+        assert_eq!(from_slice::<MontgomeryPoint>(&to_vec(&u)).unwrap().0, u.0);
+    }
+
+    #[test]
+    fn validation() {
+        // L itself isn't reduced, so isn't a scalar or a signature's S
+        let l = hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+            .unwrap();
+        assert!(from_slice::<Scalar>(&l).is_err());
+        let mut signature = hex::decode(SIGNATURE).unwrap();
+        signature[32..].copy_from_slice(&l);
+        assert!(from_slice::<Signature>(&signature).is_err());
+
+        // y = 2 isn't a point; y = p + 1 is, but not canonically
+        let mut not_a_point = [0; 32];
+        not_a_point[0] = 2;
+        assert!(from_slice::<PublicKey>(&not_a_point).is_err());
+        let mut non_canonical = [0xff; 32];
+        non_canonical[0] = 0xee;
+        non_canonical[31] = 0x7f;
+        assert!(from_slice::<CompressedEdwardsY>(&non_canonical).is_ok());
+        assert!(from_slice::<EdwardsPoint>(&non_canonical).is_err());
+
+        // too short
+        assert!(from_slice::<Scalar>(&[0; 31]).is_err());
+    }
+}
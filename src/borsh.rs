@@ -0,0 +1,173 @@
+use std::io::{self, Read, Write};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::edwards::{CompressedEdwardsY, EdwardsPoint};
+use crate::montgomery::MontgomeryPoint;
+use crate::scalar::Scalar;
+use crate::signing::{GenericPublicKey, Hash512, Signature};
+
+// borsh BorshSerialize and BorshDeserialize for the public types, so they
+// can be fields of structs deriving them (Solana and NEAR transactions and
+// account data, say).
+//
+// Each is its usual fixed-size encoding with no length prefix, the way
+// borsh writes a byte array: 32 bytes for points, scalars and keys, 64 for
+// a signature. Reading checks what the type's constructor would, so
+// non-canonical points and scalars, keys that aren't points and
+// signatures with S >= L fail to deserialize (with InvalidData). An
+// EdwardsPoint is written compressed and decompressed when read.
+//
+// As for cbor, secret keys are left out.
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl BorshSerialize for CompressedEdwardsY {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.as_bytes())
+    }
+}
+
+impl BorshDeserialize for CompressedEdwardsY {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        <[u8; 32]>::deserialize_reader(reader).map(CompressedEdwardsY)
+    }
+}
+
+impl BorshSerialize for EdwardsPoint {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.compress().as_bytes())
+    }
+}
+
+impl BorshDeserialize for EdwardsPoint {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let compressed = CompressedEdwardsY::deserialize_reader(reader)?;
+        compressed
+            .decompress()
+            .filter(|point| point.compress() == compressed)
+            .ok_or_else(|| invalid("not a canonical point encoding"))
+    }
+}
+
+impl BorshSerialize for MontgomeryPoint {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.as_bytes())
+    }
+}
+
+impl BorshDeserialize for MontgomeryPoint {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        <[u8; 32]>::deserialize_reader(reader).map(MontgomeryPoint)
+    }
+}
+
+impl BorshSerialize for Scalar {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+impl BorshDeserialize for Scalar {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Scalar::from_canonical_bytes(<[u8; 32]>::deserialize_reader(reader)?)
+            .ok_or_else(|| invalid("scalar is not reduced mod L"))
+    }
+}
+
+impl<D: Hash512> BorshSerialize for GenericPublicKey<D> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.as_bytes())
+    }
+}
+
+// Accepts the keys PublicKey::from_bytes does (see the note there).
+impl<D: Hash512> BorshDeserialize for GenericPublicKey<D> {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        GenericPublicKey::from_bytes(<[u8; 32]>::deserialize_reader(reader)?)
+            .map_err(|e| invalid(&e.to_string()))
+    }
+}
+
+impl BorshSerialize for Signature {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+impl BorshDeserialize for Signature {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Signature::from_bytes(&<[u8; 64]>::deserialize_reader(reader)?)
+            .map_err(|e| invalid(&e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edwards::ED25519_BASEPOINT;
+    use crate::signing::PublicKey;
+
+    const PUBLIC: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+        5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+    // A key, a nonce and a signature, laid out as a transaction struct
+    // deriving the traits would be.
+    #[test]
+    fn fixed_layout() {
+        let signer =
+            PublicKey::from_bytes(hex::decode(PUBLIC).unwrap().try_into().unwrap()).unwrap();
+        let signature: [u8; 64] = hex::decode(SIGNATURE).unwrap().try_into().unwrap();
+        let signature = Signature::from_bytes(&signature).unwrap();
+        let signed = (signer, 1u64, signature);
+        let bytes = borsh::to_vec(&signed).unwrap();
+        assert_eq!(
+            hex::encode(&bytes),
+            format!("{PUBLIC}0100000000000000{SIGNATURE}")
+        );
+        assert_eq!(
+            borsh::from_slice::<(PublicKey, u64, Signature)>(&bytes).unwrap(),
+            signed
+        );
+
+        let point = ED25519_BASEPOINT;
+        let bytes = borsh::to_vec(&point).unwrap();
+        assert_eq!(bytes, point.compress().as_bytes());
+        assert_eq!(borsh::from_slice::<EdwardsPoint>(&bytes).unwrap(), point);
+
+        let scalar = Scalar::from(12345u64);
+        let bytes = borsh::to_vec(&scalar).unwrap();
+        assert_eq!(borsh::from_slice::<Scalar>(&bytes).unwrap(), scalar);
+        let u = MontgomeryPoint([9; 32]);
+        let bytes = borsh::to_vec(&u).unwrap();
+        assert_eq!(borsh::from_slice::<MontgomeryPoint>(&bytes).unwrap().0, u.0);
+    }
+
+    #[test]
+    fn validation() {
+        // L itself isn't reduced, so isn't a scalar or a signature's S
+        let l = hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+            .unwrap();
+        assert!(borsh::from_slice::<Scalar>(&l).is_err());
+        let mut signature = hex::decode(SIGNATURE).unwrap();
+        signature[32..].copy_from_slice(&l);
+        assert!(borsh::from_slice::<Signature>(&signature).is_err());
+
+        // y = 2 isn't a point; y = p + 1 is, but not canonically
+        let mut not_a_point = [0; 32];
+        not_a_point[0] = 2;
+        assert!(borsh::from_slice::<PublicKey>(&not_a_point).is_err());
+        let mut non_canonical = [0xff; 32];
+        non_canonical[0] = 0xee;
+        non_canonical[31] = 0x7f;
+        assert!(borsh::from_slice::<CompressedEdwardsY>(&non_canonical).is_ok());
+        assert!(borsh::from_slice::<EdwardsPoint>(&non_canonical).is_err());
+
+        // too short, and trailing bytes
+        assert!(borsh::from_slice::<Scalar>(&[0; 31]).is_err());
+        assert!(borsh::from_slice::<Scalar>(&[0; 33]).is_err());
+    }
+}
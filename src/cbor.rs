@@ -0,0 +1,233 @@
+use minicbor::decode::{self, Decoder};
+use minicbor::encode::{self, Encoder, Write};
+use minicbor::{Decode, Encode};
+
+use crate::edwards::{CompressedEdwardsY, EdwardsPoint};
+use crate::montgomery::MontgomeryPoint;
+use crate::scalar::Scalar;
+use crate::signing::{GenericPublicKey, Hash512, Signature};
+
+// minicbor Encode and Decode for the public types: points, scalars,
+// public keys and signatures, so they can be fields of CBOR messages
+// (COSE, CTAP and the like) directly.
+//
+// Each is a CBOR byte string holding the type's usual encoding: 32 bytes
+// for points, scalars and keys, 64 for a signature. Encoding is
+// deterministic in the sense of RFC 8949 section 4.2 (a definite length in
+// its shortest form), and decoding accepts nothing else, so a value has
+// exactly one encoding: indefinite-length or over-long byte string heads
+// are rejected, as are non-canonical points and scalars. An EdwardsPoint
+// is written compressed and decompressed when read.
+//
+// Secret keys are deliberately left out; serializing one should take more
+// than adding a field to a struct.
+
+// A byte string of exactly N bytes. For the sizes used here (24 to 255)
+// the shortest head is the major type byte followed by one length byte.
+fn decode_bytes<const N: usize>(d: &mut Decoder<'_>) -> Result<[u8; N], decode::Error> {
+    let start = d.position();
+    let bytes = d.bytes()?;
+    if d.position() - start != N + 2 {
+        return Err(decode::Error::message("byte string length not in shortest form").at(start));
+    }
+    bytes
+        .try_into()
+        .map_err(|_| decode::Error::message(format!("expected {N} bytes")).at(start))
+}
+
+impl<C> Encode<C> for CompressedEdwardsY {
+    fn encode<W: Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _: &mut C,
+    ) -> Result<(), encode::Error<W::Error>> {
+        e.bytes(self.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl<'b, C> Decode<'b, C> for CompressedEdwardsY {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        decode_bytes(d).map(CompressedEdwardsY)
+    }
+}
+
+impl<C> Encode<C> for EdwardsPoint {
+    fn encode<W: Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _: &mut C,
+    ) -> Result<(), encode::Error<W::Error>> {
+        e.bytes(self.compress().as_bytes())?;
+        Ok(())
+    }
+}
+
+impl<'b, C> Decode<'b, C> for EdwardsPoint {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        let start = d.position();
+        let compressed = CompressedEdwardsY(decode_bytes(d)?);
+        compressed
+            .decompress()
+            .filter(|point| point.compress() == compressed)
+            .ok_or_else(|| decode::Error::message("not a canonical point encoding").at(start))
+    }
+}
+
+impl<C> Encode<C> for MontgomeryPoint {
+    fn encode<W: Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _: &mut C,
+    ) -> Result<(), encode::Error<W::Error>> {
+        e.bytes(self.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl<'b, C> Decode<'b, C> for MontgomeryPoint {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        decode_bytes(d).map(MontgomeryPoint)
+    }
+}
+
+impl<C> Encode<C> for Scalar {
+    fn encode<W: Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _: &mut C,
+    ) -> Result<(), encode::Error<W::Error>> {
+        e.bytes(&self.to_bytes())?;
+        Ok(())
+    }
+}
+
+impl<'b, C> Decode<'b, C> for Scalar {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        let start = d.position();
+        Scalar::from_canonical_bytes(decode_bytes(d)?)
+            .ok_or_else(|| decode::Error::message("scalar is not reduced mod L").at(start))
+    }
+}
+
+impl<C, D: Hash512> Encode<C> for GenericPublicKey<D> {
+    fn encode<W: Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _: &mut C,
+    ) -> Result<(), encode::Error<W::Error>> {
+        e.bytes(self.as_bytes())?;
+        Ok(())
+    }
+}
+
+// Accepts the keys PublicKey::from_bytes does (see the note there).
+impl<'b, C, D: Hash512> Decode<'b, C> for GenericPublicKey<D> {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        let start = d.position();
+        GenericPublicKey::from_bytes(decode_bytes(d)?)
+            .map_err(|e| decode::Error::message(e).at(start))
+    }
+}
+
+impl<C> Encode<C> for Signature {
+    fn encode<W: Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _: &mut C,
+    ) -> Result<(), encode::Error<W::Error>> {
+        e.bytes(&self.to_bytes())?;
+        Ok(())
+    }
+}
+
+impl<'b, C> Decode<'b, C> for Signature {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        let start = d.position();
+        Signature::from_bytes(&decode_bytes(d)?).map_err(|e| decode::Error::message(e).at(start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edwards::ED25519_BASEPOINT;
+    use crate::signing::PublicKey;
+
+    const PUBLIC: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+        5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+    #[test]
+    fn byte_strings() {
+        let public =
+            PublicKey::from_bytes(hex::decode(PUBLIC).unwrap().try_into().unwrap()).unwrap();
+        let encoded = minicbor::to_vec(public).unwrap();
+        assert_eq!(hex::encode(&encoded), format!("5820{PUBLIC}"));
+        assert_eq!(minicbor::decode::<PublicKey>(&encoded).unwrap(), public);
+
+        let signature: [u8; 64] = hex::decode(SIGNATURE).unwrap().try_into().unwrap();
+        let signature = Signature::from_bytes(&signature).unwrap();
+        let encoded = minicbor::to_vec(signature).unwrap();
+        assert_eq!(hex::encode(&encoded), format!("5840{SIGNATURE}"));
+        assert_eq!(minicbor::decode::<Signature>(&encoded).unwrap(), signature);
+
+        let point = ED25519_BASEPOINT;
+        let encoded = minicbor::to_vec(point).unwrap();
+        assert_eq!(encoded[..2], [0x58, 0x20]);
+        assert_eq!(&encoded[2..], point.compress().as_bytes());
+        assert_eq!(minicbor::decode::<EdwardsPoint>(&encoded).unwrap(), point);
+        assert_eq!(
+            minicbor::decode::<CompressedEdwardsY>(&encoded).unwrap(),
+            point.compress()
+        );
+
+        let scalar = Scalar::from(12345u64);
+        let encoded = minicbor::to_vec(scalar).unwrap();
+        assert_eq!(minicbor::decode::<Scalar>(&encoded).unwrap(), scalar);
+
+        let u = MontgomeryPoint([9; 32]);
+        let encoded = minicbor::to_vec(u).unwrap();
+        assert_eq!(
+            minicbor::decode::<MontgomeryPoint>(&encoded).unwrap().0,
+            u.0
+        );
+    }
+
+    #[test]
+    fn only_deterministic_encodings() {
+        let canonical = minicbor::to_vec(Scalar::from(1u64)).unwrap();
+
+        // the same 32 bytes with a two-byte length, and as an indefinite
+        // length string of one chunk
+        let mut long_head = vec![0x59, 0x00, 0x20];
+        long_head.extend_from_slice(&canonical[2..]);
+        let mut indefinite = vec![0x5f];
+        indefinite.extend_from_slice(&canonical);
+        indefinite.push(0xff);
+        for bad in [&long_head, &indefinite] {
+            assert!(minicbor::decode::<Scalar>(bad).is_err());
+        }
+
+        // wrong length, wrong type (a text string)
+        assert!(minicbor::decode::<Scalar>(&[0x58, 0x1f]).is_err());
+        let mut text = canonical.clone();
+        text[0] = 0x78;
+        assert!(minicbor::decode::<Scalar>(&text).is_err());
+
+        // L itself isn't reduced
+        let mut l = vec![0x58, 0x20];
+        l.extend_from_slice(
+            &hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+                .unwrap(),
+        );
+        assert!(minicbor::decode::<Scalar>(&l).is_err());
+
+        // y = p + 1 decodes to the point with y = 1, but isn't canonical
+        let mut non_canonical = vec![0x58, 0x20, 0xee];
+        non_canonical.extend_from_slice(&[0xff; 30]);
+        non_canonical.push(0x7f);
+        assert!(minicbor::decode::<CompressedEdwardsY>(&non_canonical).is_ok());
+        assert!(minicbor::decode::<EdwardsPoint>(&non_canonical).is_err());
+    }
+}
@@ -0,0 +1,4 @@
+// Facades that mirror other Ed25519 crates' APIs on top of this one, so
+// code can be pointed at either implementation (e.g. for differential
+// testing) by changing its imports.
+pub mod dalek;
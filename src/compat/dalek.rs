@@ -0,0 +1,270 @@
+use sha2::digest::consts::U64;
+use sha2::Digest;
+
+use crate::signing::{self, SignatureError};
+
+// The names, method signatures and byte formats of ed25519-dalek 2.x:
+// SigningKey, VerifyingKey and Signature, with the same lengths and the
+// same 64-byte "keypair bytes" (secret key followed by public key).
+//
+// Differences worth knowing about when comparing the two:
+// - errors are this crate's SignatureError rather than signature::Error;
+// - sign and verify are inherent methods here (dalek gets them from the
+//   signature crate's Signer and Verifier traits, which the crate-level
+//   signature feature provides for the native types);
+// - like dalek's, Signature is just 64 bytes, and S is only checked to be
+//   reduced when the signature is verified;
+// - SigningKey has no as_bytes, since the secret key only comes out
+//   through to_bytes.
+
+pub const SECRET_KEY_LENGTH: usize = 32;
+pub const PUBLIC_KEY_LENGTH: usize = 32;
+pub const KEYPAIR_LENGTH: usize = SECRET_KEY_LENGTH + PUBLIC_KEY_LENGTH;
+pub const SIGNATURE_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature([u8; SIGNATURE_LENGTH]);
+
+impl Signature {
+    pub fn from_bytes(bytes: &[u8; SIGNATURE_LENGTH]) -> Self {
+        Self(*bytes)
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, SignatureError> {
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| SignatureError::InvalidLength)?;
+        Ok(Self(bytes))
+    }
+
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_LENGTH] {
+        self.0
+    }
+
+    pub fn r_bytes(&self) -> &[u8; 32] {
+        self.0[..32].try_into().unwrap()
+    }
+
+    pub fn s_bytes(&self) -> &[u8; 32] {
+        self.0[32..].try_into().unwrap()
+    }
+
+    fn parse(&self) -> Result<signing::Signature, SignatureError> {
+        signing::Signature::from_bytes(&self.0)
+    }
+}
+
+impl From<signing::Signature> for Signature {
+    fn from(signature: signing::Signature) -> Self {
+        Self(signature.to_bytes())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyingKey(signing::PublicKey);
+
+impl VerifyingKey {
+    pub fn from_bytes(bytes: &[u8; PUBLIC_KEY_LENGTH]) -> Result<Self, SignatureError> {
+        signing::PublicKey::from_bytes(*bytes).map(Self)
+    }
+
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_LENGTH] {
+        self.0.to_bytes()
+    }
+
+    pub fn as_bytes(&self) -> &[u8; PUBLIC_KEY_LENGTH] {
+        self.0.as_bytes()
+    }
+
+    // A key of small order, for which (identity, 0) is a valid signature
+    // on every message.
+    pub fn is_weak(&self) -> bool {
+        self.0.as_point().is_small_order()
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        self.0.verify(message, &signature.parse()?)
+    }
+
+    pub fn verify_strict(
+        &self,
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        self.0.verify_strict(message, &signature.parse()?)
+    }
+
+    // Ed25519ph, given the hasher the message was fed into.
+    pub fn verify_prehashed<D: Digest<OutputSize = U64>>(
+        &self,
+        prehashed_message: D,
+        context: Option<&[u8]>,
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        let digest = prehashed_message.finalize().into();
+        self.0
+            .verify_prehashed(&digest, context, &signature.parse()?)
+    }
+}
+
+impl From<signing::PublicKey> for VerifyingKey {
+    fn from(public: signing::PublicKey) -> Self {
+        Self(public)
+    }
+}
+
+#[cfg(feature = "signing")]
+#[derive(Debug, Clone)]
+pub struct SigningKey(signing::Keypair);
+
+#[cfg(feature = "signing")]
+impl SigningKey {
+    pub fn from_bytes(secret_key: &[u8; SECRET_KEY_LENGTH]) -> Self {
+        Self(signing::Keypair::from_seed(*secret_key))
+    }
+
+    // The secret key followed by the public key. Fails if the public key
+    // isn't the one the secret key derives.
+    pub fn from_keypair_bytes(bytes: &[u8; KEYPAIR_LENGTH]) -> Result<Self, SignatureError> {
+        signing::Keypair::from_bytes(bytes).map(Self)
+    }
+
+    #[cfg(feature = "rand")]
+    pub fn generate<R: rand_core::RngCore + rand_core::CryptoRng>(csprng: &mut R) -> Self {
+        Self(signing::Keypair::generate(csprng))
+    }
+
+    pub fn to_bytes(&self) -> [u8; SECRET_KEY_LENGTH] {
+        self.0.secret().to_bytes()
+    }
+
+    pub fn to_keypair_bytes(&self) -> [u8; KEYPAIR_LENGTH] {
+        self.0.to_bytes()
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey(*self.0.public())
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        self.0.sign(message).into()
+    }
+
+    pub fn try_sign(&self, message: &[u8]) -> Result<Signature, SignatureError> {
+        Ok(self.sign(message))
+    }
+
+    pub fn sign_prehashed<D: Digest<OutputSize = U64>>(
+        &self,
+        prehashed_message: D,
+        context: Option<&[u8]>,
+    ) -> Result<Signature, SignatureError> {
+        let digest = prehashed_message.finalize().into();
+        self.0.sign_prehashed(&digest, context).map(Signature::from)
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        self.verifying_key().verify(message, signature)
+    }
+
+    pub fn verify_strict(
+        &self,
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        self.verifying_key().verify_strict(message, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes<const N: usize>(h: &str) -> [u8; N] {
+        hex::decode(h).unwrap().try_into().unwrap()
+    }
+
+    // RFC 8032 section 7.1, test 2
+    #[cfg(feature = "signing")]
+    const SECRET: &str = "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+    const PUBLIC: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const SIGNATURE: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+                             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+    #[test]
+    fn verifying_key() {
+        let key = VerifyingKey::from_bytes(&bytes(PUBLIC)).unwrap();
+        assert_eq!(hex::encode(key.to_bytes()), PUBLIC);
+        assert!(!key.is_weak());
+
+        let signature = Signature::from_bytes(&bytes(SIGNATURE));
+        assert_eq!(signature.r_bytes()[..], signature.to_bytes()[..32]);
+        assert_eq!(signature.s_bytes()[..], signature.to_bytes()[32..]);
+        assert_eq!(key.verify(&[0x72], &signature), Ok(()));
+        assert_eq!(key.verify_strict(&[0x72], &signature), Ok(()));
+        assert_eq!(
+            key.verify(&[0x73], &signature),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            Signature::from_slice(&[0; 63]),
+            Err(SignatureError::InvalidLength)
+        );
+
+        // S = 2^256 - 1 parses, but doesn't verify
+        let mut high = signature.to_bytes();
+        high[32..].copy_from_slice(&[0xff; 32]);
+        assert_eq!(
+            key.verify(&[0x72], &Signature::from_bytes(&high)),
+            Err(SignatureError::NonCanonicalScalar)
+        );
+
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        assert!(VerifyingKey::from_bytes(&identity).unwrap().is_weak());
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn signing_key() {
+        let key = SigningKey::from_bytes(&bytes(SECRET));
+        assert_eq!(hex::encode(key.to_bytes()), SECRET);
+        assert_eq!(hex::encode(key.verifying_key().as_bytes()), PUBLIC);
+        let signature = key.sign(&[0x72]);
+        assert_eq!(hex::encode(signature.to_bytes()), SIGNATURE);
+        assert_eq!(key.try_sign(&[0x72]), Ok(signature));
+        assert_eq!(key.verify(&[0x72], &signature), Ok(()));
+
+        let keypair_bytes = key.to_keypair_bytes();
+        assert_eq!(hex::encode(keypair_bytes), format!("{SECRET}{PUBLIC}"));
+        let restored = SigningKey::from_keypair_bytes(&keypair_bytes).unwrap();
+        assert_eq!(restored.to_bytes(), key.to_bytes());
+        let mut mismatched = keypair_bytes;
+        mismatched[40] ^= 1;
+        assert!(matches!(
+            SigningKey::from_keypair_bytes(&mismatched),
+            Err(SignatureError::KeyMismatch)
+        ));
+    }
+
+    // RFC 8032 section 7.3, through the hasher-taking prehash API.
+    #[cfg(feature = "signing")]
+    #[test]
+    fn prehashed() {
+        let key = SigningKey::from_bytes(&bytes(
+            "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42",
+        ));
+        let hasher = sha2::Sha512::new().chain_update(b"abc");
+        let signature = key.sign_prehashed(hasher.clone(), None).unwrap();
+        assert_eq!(
+            hex::encode(signature.to_bytes()),
+            "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae41\
+             31f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406"
+        );
+        assert_eq!(
+            key.verifying_key()
+                .verify_prehashed(hasher, None, &signature),
+            Ok(())
+        );
+    }
+}
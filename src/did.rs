@@ -0,0 +1,153 @@
+use std::fmt;
+
+use crate::base58;
+use crate::signing::{PublicKey, SignatureError};
+
+// did:key identifiers (W3C CCG did:key method) for Ed25519 keys. The
+// identifier carries the key itself:
+//
+//   did:key:z6Mk...
+//
+// where "z6Mk..." is the key in multibase form: 'z' for base58btc, then
+// the base58 of the multicodec varint for ed25519-pub (0xed, as the two
+// bytes ed 01) followed by the 32-byte key. The prefix is why every
+// Ed25519 did:key starts with z6Mk. The same multibase string is what DID
+// documents put in publicKeyMultibase, and what a did:key's verification
+// method is named after:
+//
+//   did:key:z6Mk...#z6Mk...
+//
+// Other key types (X25519, P-256, ...) have other multicodec prefixes and
+// are refused.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DidError {
+    // A did:key identifier or multibase key that can't be read: another
+    // DID method or multibase encoding, bad base58, or a key of the wrong
+    // length.
+    Malformed,
+    // A multibase key of another type (X25519, P-256, ...).
+    UnsupportedAlgorithm,
+    // A key that isn't a point.
+    Signature(SignatureError),
+}
+
+impl fmt::Display for DidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DidError::Malformed => f.write_str("malformed did:key identifier"),
+            DidError::UnsupportedAlgorithm => f.write_str("did:key is not an Ed25519 key"),
+            DidError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DidError {}
+
+impl From<SignatureError> for DidError {
+    fn from(e: SignatureError) -> Self {
+        DidError::Signature(e)
+    }
+}
+
+const DID_KEY: &str = "did:key:";
+const BASE58BTC: char = 'z';
+// 0xed as an unsigned varint.
+const ED25519_PUB: [u8; 2] = [0xed, 0x01];
+
+pub fn to_did_key(key: &PublicKey) -> String {
+    format!("{DID_KEY}{}", to_multibase(key))
+}
+
+// Reads a did:key, or the DID URL of its verification method (the DID
+// with its own multibase key as the fragment).
+pub fn from_did_key(did: &str) -> Result<PublicKey, DidError> {
+    let id = did.strip_prefix(DID_KEY).ok_or(DidError::Malformed)?;
+    let id = match id.split_once('#') {
+        Some((id, fragment)) if fragment == id => id,
+        Some(_) => return Err(DidError::Malformed),
+        None => id,
+    };
+    from_multibase(id)
+}
+
+// The key as "z6Mk...", without the "did:key:".
+pub fn to_multibase(key: &PublicKey) -> String {
+    let mut bytes = ED25519_PUB.to_vec();
+    bytes.extend_from_slice(key.as_bytes());
+    format!("{BASE58BTC}{}", base58::encode(&bytes))
+}
+
+pub fn from_multibase(text: &str) -> Result<PublicKey, DidError> {
+    let bytes = text
+        .strip_prefix(BASE58BTC)
+        .and_then(base58::decode)
+        .ok_or(DidError::Malformed)?;
+    let key = bytes
+        .strip_prefix(&ED25519_PUB)
+        .ok_or(DidError::UnsupportedAlgorithm)?;
+    Ok(PublicKey::from_bytes(
+        key.try_into().map_err(|_| DidError::Malformed)?,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The did:key test vector for the all-zero seed.
+    const ZERO_SEED_KEY: &str = "3b6a27bcceb6a42d62a3a8d02a6f0d73653215771de243a63ac048a18b59da29";
+    const ZERO_SEED_DID: &str = "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+
+    fn key() -> PublicKey {
+        PublicKey::from_bytes(hex::decode(ZERO_SEED_KEY).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn did_key() {
+        assert_eq!(to_did_key(&key()), ZERO_SEED_DID);
+        assert_eq!(from_did_key(ZERO_SEED_DID), Ok(key()));
+        let multibase = &ZERO_SEED_DID[DID_KEY.len()..];
+        assert_eq!(to_multibase(&key()), multibase);
+        assert_eq!(from_multibase(multibase), Ok(key()));
+
+        let method = format!("{ZERO_SEED_DID}#{multibase}");
+        assert_eq!(from_did_key(&method), Ok(key()));
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn from_seed() {
+        let keypair = crate::signing::Keypair::from_seed([0; 32]);
+        assert_eq!(to_did_key(keypair.public()), ZERO_SEED_DID);
+    }
+
+    #[test]
+    fn malformed() {
+        let multibase = &ZERO_SEED_DID[DID_KEY.len()..];
+        let other_fragment = format!("{ZERO_SEED_DID}#keys-1");
+        let other_method = format!("did:web:{multibase}");
+        // a multibase prefix other than base58btc ('u' is base64url)
+        let base64 = format!("{DID_KEY}u{}", &multibase[1..]);
+        let not_base58 = ZERO_SEED_DID.replace("mu", "m0");
+        let mut short = ED25519_PUB.to_vec();
+        short.extend_from_slice(&key().as_bytes()[..31]);
+        let short = format!("{DID_KEY}z{}", base58::encode(&short));
+        for bad in [
+            &other_fragment,
+            &other_method,
+            &base64,
+            &not_base58,
+            &short,
+            "did:key:",
+        ] {
+            assert_eq!(from_did_key(bad), Err(DidError::Malformed), "{bad}");
+        }
+
+        // An X25519 did:key (multicodec 0xec), from the did:key test vectors.
+        assert_eq!(
+            from_did_key("did:key:z6LSeu9HkTHSfLLeUs2nnzUSNedgDUevfNQgQjQC23ZCit6F"),
+            Err(DidError::UnsupportedAlgorithm)
+        );
+    }
+}
@@ -0,0 +1,629 @@
+use std::fmt;
+
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::ristretto::{CompressedRistretto, RistrettoPoint, RISTRETTO_BASEPOINT_TABLE};
+use crate::scalar::Scalar;
+use crate::signing::SignatureError;
+use crate::transcript::DlogProof;
+
+// Pedersen's distributed key generation, in the form FROST uses: n
+// participants end up with shares x_1 .. x_n of a secret key x that nobody
+// ever holds, any t of which determine it, and a public key X = x * B
+// that all agree on. It is n Feldman VSS dealings run side by side:
+//
+//   round 1  each participant i picks a random polynomial f_i of degree
+//            t - 1 and broadcasts commitments C_ik = a_ik * B to its
+//            coefficients, with a proof of knowledge of a_i0. It sends
+//            f_i(j) privately to each other participant j.
+//
+//   finish   participant j checks each f_i(j) it got against i's
+//            commitments,
+//
+//              f_i(j) * B = sum_k j^k * C_ik
+//
+//            and its share is x_j = sum_i f_i(j). The secret is
+//            x = sum_i f_i(0), the public key X = sum_i C_i0, and the
+//            verification share X_j = x_j * B of every participant can
+//            be computed from the commitments alone.
+//
+// The proof of knowledge (an RFC 8235 DlogProof bound to the session
+// context and the sender's index) stops a participant from choosing its
+// C_i0 after seeing the others' to cancel them out and fix X. A failed
+// check on a private share means its sender cheated (or the share was
+// corrupted on the way): check_share tells the caller whom to complain
+// about, and the session has to be restarted without them. Complaints,
+// retries and the transport (private shares need an encrypted,
+// authenticated channel) are left to the caller.
+//
+// This tree has no separate VSS module, so the Feldman dealing and its
+// share check live here.
+//
+// The group is ristretto255, and the public key is a ristretto255 key,
+// not an Ed25519 one. The commitments arrive as ristretto encodings,
+// which only fix each point up to 4-torsion, so RistrettoPoint::to_edwards
+// of the group key isn't x * B on the Edwards curve; a DKG for Ed25519
+// keys would have to exchange exact Edwards commitments instead.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DkgError {
+    // A threshold of 0 or above the number of participants, or a
+    // broadcast committing to a polynomial of another degree.
+    InvalidThreshold,
+    // An index of 0 or above the number of participants, a broadcast from
+    // ourselves, or a share sent by or to someone other than expected.
+    UnknownParticipant,
+    // finish without exactly one broadcast and share from each of the
+    // other participants.
+    MissingParticipant,
+    // A message that doesn't decode, or a proof or share that doesn't
+    // verify (VerificationFailed), which means its sender cheated.
+    Signature(SignatureError),
+}
+
+impl fmt::Display for DkgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DkgError::InvalidThreshold => f.write_str("invalid DKG threshold"),
+            DkgError::UnknownParticipant => f.write_str("unknown DKG participant"),
+            DkgError::MissingParticipant => f.write_str("DKG participant missing or there twice"),
+            DkgError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DkgError {}
+
+impl From<SignatureError> for DkgError {
+    fn from(e: SignatureError) -> Self {
+        DkgError::Signature(e)
+    }
+}
+
+// The messages of one participant: its index, the commitments to its
+// polynomial and the proof for the constant term. Sent to everyone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Round1Broadcast {
+    sender: u16,
+    commitments: Vec<RistrettoPoint>,
+    proof: DlogProof,
+}
+
+impl Round1Broadcast {
+    pub fn sender(&self) -> u16 {
+        self.sender
+    }
+
+    // sender (2 bytes, big-endian) || t (2 bytes) || t commitments ||
+    // proof.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(4 + 32 * self.commitments.len() + 64);
+        bytes.extend_from_slice(&self.sender.to_be_bytes());
+        bytes.extend_from_slice(&(self.commitments.len() as u16).to_be_bytes());
+        for c in &self.commitments {
+            bytes.extend_from_slice(c.compress().as_bytes());
+        }
+        bytes.extend_from_slice(&self.proof.to_bytes());
+        bytes
+    }
+
+    // Fails with InvalidLength if the length doesn't match t, with
+    // InvalidPoint if a commitment doesn't decode and with
+    // NonCanonicalScalar if the proof's scalar isn't reduced.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DkgError> {
+        if bytes.len() < 4 {
+            return Err(SignatureError::InvalidLength.into());
+        }
+        let sender = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let t = u16::from_be_bytes([bytes[2], bytes[3]]) as usize;
+        if t == 0 || bytes.len() != 4 + 32 * t + 64 {
+            return Err(SignatureError::InvalidLength.into());
+        }
+        let commitments = bytes[4..4 + 32 * t]
+            .chunks(32)
+            .map(|c| {
+                CompressedRistretto(c.try_into().unwrap())
+                    .decompress()
+                    .ok_or(SignatureError::InvalidPoint)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            sender,
+            commitments,
+            proof: DlogProof::from_bytes(bytes[4 + 32 * t..].try_into().unwrap())?,
+        })
+    }
+}
+
+// f_i(j), from sender i to recipient j. Secret: send it encrypted, to j
+// only. Zeroized when dropped.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Round1Share {
+    sender: u16,
+    recipient: u16,
+    share: Scalar,
+}
+
+impl Round1Share {
+    pub fn sender(&self) -> u16 {
+        self.sender
+    }
+
+    pub fn recipient(&self) -> u16 {
+        self.recipient
+    }
+
+    // sender || recipient || f_i(j).
+    pub fn to_bytes(&self) -> [u8; 36] {
+        let mut bytes = [0; 36];
+        bytes[..2].copy_from_slice(&self.sender.to_be_bytes());
+        bytes[2..4].copy_from_slice(&self.recipient.to_be_bytes());
+        bytes[4..].copy_from_slice(self.share.as_bytes());
+        bytes
+    }
+
+    // Fails with NonCanonicalScalar if the share isn't reduced.
+    pub fn from_bytes(bytes: &[u8; 36]) -> Result<Self, DkgError> {
+        Ok(Self {
+            sender: u16::from_be_bytes([bytes[0], bytes[1]]),
+            recipient: u16::from_be_bytes([bytes[2], bytes[3]]),
+            share: Scalar::from_canonical_bytes(bytes[4..].try_into().unwrap())
+                .ok_or(SignatureError::NonCanonicalScalar)?,
+        })
+    }
+}
+
+impl Drop for Round1Share {
+    fn drop(&mut self) {
+        self.share.zeroize();
+    }
+}
+
+impl fmt::Debug for Round1Share {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Round1Share")
+            .field("sender", &self.sender)
+            .field("recipient", &self.recipient)
+            .field("share", &"<redacted>")
+            .finish()
+    }
+}
+
+// One participant between round 1 and finish: its polynomial, which is
+// secret. Zeroized when dropped.
+pub struct DkgParticipant {
+    context: Vec<u8>,
+    index: u16,
+    participants: u16,
+    coefficients: Vec<Scalar>,
+}
+
+impl DkgParticipant {
+    // Participant index (1 to participants) of a session in which any
+    // threshold of the participants can use the key. context names the
+    // session, and must be the same for everyone and unique to this run.
+    //
+    // Fails with InvalidThreshold unless 1 <= threshold <= participants,
+    // and with UnknownParticipant unless 1 <= index <= participants.
+    pub fn new<R: RngCore + CryptoRng>(
+        context: &[u8],
+        index: u16,
+        threshold: u16,
+        participants: u16,
+        rng: &mut R,
+    ) -> Result<(Self, Round1Broadcast), DkgError> {
+        if threshold == 0 || threshold > participants {
+            return Err(DkgError::InvalidThreshold);
+        }
+        if index == 0 || index > participants {
+            return Err(DkgError::UnknownParticipant);
+        }
+        let coefficients: Vec<_> = (0..threshold).map(|_| Scalar::random(rng)).collect();
+        let commitments = coefficients
+            .iter()
+            .map(|a| a * &*RISTRETTO_BASEPOINT_TABLE)
+            .collect();
+        let proof = DlogProof::prove(
+            &mut transcript(context),
+            &index.to_be_bytes(),
+            &coefficients[0],
+            rng,
+        );
+        let participant = Self {
+            context: context.to_vec(),
+            index,
+            participants,
+            coefficients,
+        };
+        let broadcast = Round1Broadcast {
+            sender: index,
+            commitments,
+            proof,
+        };
+        Ok((participant, broadcast))
+    }
+
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    // f(recipient), to send to participant recipient.
+    pub fn share_for(&self, recipient: u16) -> Round1Share {
+        Round1Share {
+            sender: self.index,
+            recipient,
+            share: evaluate(&self.coefficients, recipient),
+        }
+    }
+
+    // Checks one sender's broadcast (its proof and number of commitments)
+    // and the share it sent us. Fails with VerificationFailed if either is
+    // wrong, which means the sender misbehaved, and with UnknownParticipant
+    // or InvalidThreshold if they don't belong to this session.
+    pub fn check_share(
+        &self,
+        broadcast: &Round1Broadcast,
+        share: &Round1Share,
+    ) -> Result<(), DkgError> {
+        if share.sender != broadcast.sender || share.recipient != self.index {
+            return Err(DkgError::UnknownParticipant);
+        }
+        if broadcast.commitments.len() != self.coefficients.len() {
+            return Err(DkgError::InvalidThreshold);
+        }
+        broadcast.proof.verify(
+            &mut transcript(&self.context),
+            &broadcast.sender.to_be_bytes(),
+            &broadcast.commitments[0],
+        )?;
+        if &share.share * &*RISTRETTO_BASEPOINT_TABLE
+            != evaluate_commitments(&broadcast.commitments, self.index)
+        {
+            return Err(SignatureError::VerificationFailed.into());
+        }
+        Ok(())
+    }
+
+    // Finishes the DKG with every other participant's broadcast and the
+    // share each sent us (in any order, but one each). Fails with
+    // UnknownParticipant if a sender isn't one of the others, with
+    // MissingParticipant if someone is missing or there twice, and
+    // otherwise as check_share.
+    pub fn finish(
+        self,
+        broadcasts: &[Round1Broadcast],
+        shares: &[Round1Share],
+    ) -> Result<KeyShare, DkgError> {
+        let others = self.participants as usize - 1;
+        let mut senders: Vec<_> = broadcasts.iter().map(|b| b.sender).collect();
+        senders.sort_unstable();
+        senders.dedup();
+        if senders
+            .iter()
+            .any(|&i| i == 0 || i > self.participants || i == self.index)
+        {
+            return Err(DkgError::UnknownParticipant);
+        }
+        if broadcasts.len() != others || shares.len() != others || senders.len() != others {
+            return Err(DkgError::MissingParticipant);
+        }
+
+        let mut secret = evaluate(&self.coefficients, self.index);
+        let mut commitments = vec![self
+            .coefficients
+            .iter()
+            .map(|a| a * &*RISTRETTO_BASEPOINT_TABLE)
+            .collect::<Vec<_>>()];
+        for broadcast in broadcasts {
+            let share = shares
+                .iter()
+                .find(|s| s.sender == broadcast.sender)
+                .ok_or(DkgError::MissingParticipant)?;
+            self.check_share(broadcast, share)?;
+            secret += share.share;
+            commitments.push(broadcast.commitments.clone());
+        }
+
+        // the commitments to the sum of the polynomials
+        let summed: Vec<RistrettoPoint> = (0..self.coefficients.len())
+            .map(|k| commitments.iter().map(|c| c[k]).sum())
+            .collect();
+        Ok(KeyShare {
+            index: self.index,
+            secret,
+            public_key: summed[0],
+            verification_shares: (1..=self.participants)
+                .map(|j| evaluate_commitments(&summed, j))
+                .collect(),
+        })
+    }
+}
+
+impl Drop for DkgParticipant {
+    fn drop(&mut self) {
+        self.coefficients.zeroize();
+    }
+}
+
+// A participant's result: its share x_j of the secret, the group's
+// public key and everyone's verification shares. Zeroized when dropped.
+#[derive(Clone)]
+pub struct KeyShare {
+    index: u16,
+    secret: Scalar,
+    public_key: RistrettoPoint,
+    verification_shares: Vec<RistrettoPoint>,
+}
+
+impl KeyShare {
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    pub fn secret(&self) -> &Scalar {
+        &self.secret
+    }
+
+    // X = x * B in ristretto255 (see the note above on Ed25519).
+    pub fn public_key(&self) -> &RistrettoPoint {
+        &self.public_key
+    }
+
+    // X_j = x_j * B for participant j, or None if there is no such
+    // participant.
+    pub fn verification_share(&self, index: u16) -> Option<&RistrettoPoint> {
+        self.verification_shares
+            .get((index as usize).checked_sub(1)?)
+    }
+}
+
+impl Drop for KeyShare {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("index", &self.index)
+            .field("secret", &"<redacted>")
+            .field("public_key", &self.public_key)
+            .finish()
+    }
+}
+
+// The Lagrange coefficient for participant i when the secret is
+// reconstructed (or a threshold signature made) by the participants in
+// indices: the weight of x_i in x = sum_i lambda_i x_i.
+pub fn lagrange_coefficient(i: u16, indices: &[u16]) -> Scalar {
+    let x_i = Scalar::from(i as u64);
+    let (mut numerator, mut denominator) = (Scalar::ONE, Scalar::ONE);
+    for &j in indices.iter().filter(|&&j| j != i) {
+        let x_j = Scalar::from(j as u64);
+        numerator *= x_j;
+        denominator *= x_j - x_i;
+    }
+    numerator * denominator.invert()
+}
+
+fn transcript(context: &[u8]) -> Transcript {
+    let mut t = Transcript::new(b"pedersen-dkg");
+    t.append_message(b"context", context);
+    t
+}
+
+// f(x) by Horner's rule.
+fn evaluate(coefficients: &[Scalar], x: u16) -> Scalar {
+    let x = Scalar::from(x as u64);
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::ZERO, |acc, a| acc * x + a)
+}
+
+// sum_k x^k * C_k, which is f(x) * B for the committed f.
+fn evaluate_commitments(commitments: &[RistrettoPoint], x: u16) -> RistrettoPoint {
+    let x = Scalar::from(x as u64);
+    let mut power = Scalar::ONE;
+    let powers: Vec<_> = commitments
+        .iter()
+        .map(|_| {
+            let p = power;
+            power *= x;
+            p
+        })
+        .collect();
+    RistrettoPoint::vartime_multiscalar_mul(&powers, commitments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // Runs round 1 for n participants, returning them, their broadcasts
+    // and shares[i][j], from i + 1 to j + 1.
+    fn round1(
+        t: u16,
+        n: u16,
+        rng: &mut StdRng,
+    ) -> (
+        Vec<DkgParticipant>,
+        Vec<Round1Broadcast>,
+        Vec<Vec<Round1Share>>,
+    ) {
+        let (participants, broadcasts): (Vec<_>, Vec<_>) = (1..=n)
+            .map(|i| DkgParticipant::new(b"test", i, t, n, rng).unwrap())
+            .unzip();
+        let shares = participants
+            .iter()
+            .map(|p| (1..=n).map(|j| p.share_for(j)).collect())
+            .collect();
+        (participants, broadcasts, shares)
+    }
+
+    // What participant j receives from the others.
+    fn received(
+        j: usize,
+        broadcasts: &[Round1Broadcast],
+        shares: &[Vec<Round1Share>],
+    ) -> (Vec<Round1Broadcast>, Vec<Round1Share>) {
+        let others = (0..broadcasts.len()).filter(|&i| i != j);
+        (
+            others.clone().map(|i| broadcasts[i].clone()).collect(),
+            others.map(|i| shares[i][j].clone()).collect(),
+        )
+    }
+
+    #[test]
+    fn dkg() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let (t, n) = (3, 5);
+        let (participants, broadcasts, shares) = round1(t, n, &mut rng);
+        let keys: Vec<KeyShare> = participants
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| {
+                let (b, s) = received(j, &broadcasts, &shares);
+                p.finish(&b, &s).unwrap()
+            })
+            .collect();
+
+        // everyone agrees on the public key and the verification shares
+        for key in &keys {
+            assert_eq!(key.public_key(), keys[0].public_key());
+            for other in &keys {
+                assert_eq!(
+                    key.verification_share(other.index()),
+                    Some(&(other.secret() * &*RISTRETTO_BASEPOINT_TABLE))
+                );
+            }
+        }
+        assert_eq!(keys[0].verification_share(0), None);
+        assert_eq!(keys[0].verification_share(n + 1), None);
+
+        // any t shares give the secret behind the public key, fewer don't
+        for subset in [[1, 2, 3], [1, 3, 5], [2, 4, 5]] {
+            let x: Scalar = subset
+                .iter()
+                .map(|&i| lagrange_coefficient(i, &subset) * keys[i as usize - 1].secret())
+                .sum();
+            assert_eq!(&(&x * &*RISTRETTO_BASEPOINT_TABLE), keys[0].public_key());
+        }
+        let x: Scalar = [1, 2]
+            .iter()
+            .map(|&i| lagrange_coefficient(i, &[1, 2]) * keys[i as usize - 1].secret())
+            .sum();
+        assert_ne!(&(&x * &*RISTRETTO_BASEPOINT_TABLE), keys[0].public_key());
+    }
+
+    #[test]
+    fn parameters() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for (index, threshold, n) in [(1, 0, 3), (1, 4, 3)] {
+            assert_eq!(
+                DkgParticipant::new(b"test", index, threshold, n, &mut rng).err(),
+                Some(DkgError::InvalidThreshold)
+            );
+        }
+        for (index, threshold, n) in [(0, 2, 3), (4, 2, 3)] {
+            assert_eq!(
+                DkgParticipant::new(b"test", index, threshold, n, &mut rng).err(),
+                Some(DkgError::UnknownParticipant)
+            );
+        }
+
+        // a broadcast for another threshold
+        let (participants, _, _) = round1(2, 3, &mut rng);
+        let (_, broadcasts, shares) = round1(3, 3, &mut rng);
+        assert_eq!(
+            participants[0].check_share(&broadcasts[1], &shares[1][0]),
+            Err(DkgError::InvalidThreshold)
+        );
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let (_, broadcasts, shares) = round1(2, 3, &mut rng);
+        let bytes = broadcasts[0].to_bytes();
+        assert_eq!(bytes.len(), 4 + 2 * 32 + 64);
+        assert_eq!(
+            Round1Broadcast::from_bytes(&bytes),
+            Ok(broadcasts[0].clone())
+        );
+        assert_eq!(
+            Round1Broadcast::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DkgError::Signature(SignatureError::InvalidLength))
+        );
+        let share = &shares[0][1];
+        assert!(Round1Share::from_bytes(&share.to_bytes()).unwrap() == *share);
+        assert!(!format!("{share:?}").contains(&format!("{:?}", share.share)));
+    }
+
+    #[test]
+    fn cheaters_are_caught() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let (participants, broadcasts, mut shares) = round1(2, 3, &mut rng);
+
+        // a share off the committed polynomial
+        shares[1][0].share += Scalar::ONE;
+        assert_eq!(
+            participants[0].check_share(&broadcasts[1], &shares[1][0]),
+            Err(DkgError::Signature(SignatureError::VerificationFailed))
+        );
+        assert_eq!(
+            participants[0].check_share(&broadcasts[2], &shares[2][0]),
+            Ok(())
+        );
+
+        // a constant term copied from someone else, without its proof
+        let mut copied = broadcasts[1].clone();
+        copied.commitments[0] = broadcasts[2].commitments[0];
+        assert_eq!(
+            participants[0].check_share(&copied, &shares[2][0]),
+            Err(DkgError::UnknownParticipant)
+        );
+        copied.sender = 3;
+        assert_eq!(
+            participants[0].check_share(&copied, &shares[2][0]),
+            Err(DkgError::Signature(SignatureError::VerificationFailed))
+        );
+
+        // another session's messages
+        let (other, _) = DkgParticipant::new(b"other", 1, 2, 3, &mut rng).unwrap();
+        assert_eq!(
+            other.check_share(&broadcasts[2], &shares[2][0]),
+            Err(DkgError::Signature(SignatureError::VerificationFailed))
+        );
+
+        // missing and duplicated participants
+        let mut participants = participants.into_iter();
+        let first = participants.next().unwrap();
+        let (b, s) = received(0, &broadcasts, &shares);
+        assert_eq!(
+            first.finish(&[b[0].clone(), b[0].clone()], &s).unwrap_err(),
+            DkgError::MissingParticipant
+        );
+        let (b, s) = received(1, &broadcasts, &shares);
+        let second = participants.next().unwrap();
+        assert_eq!(
+            second
+                .finish(&[broadcasts[1].clone(), b[1].clone()], &s)
+                .unwrap_err(),
+            DkgError::UnknownParticipant
+        );
+        let (b, s) = received(2, &broadcasts, &shares);
+        assert_eq!(
+            participants
+                .next()
+                .unwrap()
+                .finish(&b[..1], &s[..1])
+                .unwrap_err(),
+            DkgError::MissingParticipant
+        );
+    }
+}
@@ -0,0 +1,706 @@
+use std::fmt;
+
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, Signature, SignatureError};
+
+// DNSSEC with Ed25519, algorithm 15 (RFC 8080), on top of the record
+// formats of RFC 4034.
+//
+// A zone's key is published as a DNSKEY record, whose RDATA is
+//
+//   flags (2 bytes) || protocol (3) || algorithm (15) || A (32 bytes)
+//
+// and is referred to from RRSIGs and DS records by its key tag, a 16-bit
+// checksum of that RDATA (RFC 4034 appendix B).
+//
+// An RRSIG signs one RRset (all the records with the same owner, class
+// and type). What gets signed is the RRSIG's own RDATA without the
+// signature, followed by every record of the RRset in canonical form
+// (section 6): the owner name in lower case and uncompressed, the type,
+// class and the RRSIG's original TTL, and the RDATA, with the records
+// sorted by RDATA and duplicates dropped. For a record synthesized from a
+// wildcard the owner is put back as the wildcard name, which the RRSIG's
+// label count makes possible. Ed25519 signs that directly; there is no
+// separate hash.
+//
+// RDATA is taken as given, so for types whose RDATA holds names (NS, MX,
+// SOA and the others listed in RFC 4034 section 6.2, as amended by RFC
+// 6840) it has to be in canonical form already: uncompressed, with those
+// names in lower case. Names here are in presentation form
+// ("www.example.com.", the final dot optional), without escapes.
+//
+// Verification checks the signature, that the key tag and the key agree,
+// that the key is a zone key, and the validity period. Times are 32-bit
+// serial numbers (RFC 1982) of Unix time, so they wrap in 2106.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnssecError {
+    // DNS data that can't be used: a name that isn't a valid domain name,
+    // DNSKEY or RRSIG RDATA of the wrong size or for another protocol, or
+    // an RRSIG whose labels, signer name or type don't fit the RRset.
+    Malformed,
+    // A DNSKEY or RRSIG for an algorithm other than 15.
+    UnsupportedAlgorithm,
+    // An RRSIG checked before its inception or after its expiration.
+    NotValidNow,
+    // A key that isn't a point, a signature that doesn't verify, or an
+    // RRSIG checked with a key that isn't a zone key or has another key
+    // tag (KeyMismatch).
+    Signature(SignatureError),
+}
+
+impl fmt::Display for DnssecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnssecError::Malformed => f.write_str("malformed DNS data"),
+            DnssecError::UnsupportedAlgorithm => f.write_str("DNSSEC algorithm is not Ed25519"),
+            DnssecError::NotValidNow => f.write_str("RRSIG is outside its validity period"),
+            DnssecError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DnssecError {}
+
+impl From<SignatureError> for DnssecError {
+    fn from(e: SignatureError) -> Self {
+        DnssecError::Signature(e)
+    }
+}
+
+const PROTOCOL: u8 = 3;
+const ED25519: u8 = 15;
+// RRSIG RDATA without the signer name and signature.
+const RRSIG_FIXED_LEN: usize = 18;
+
+// DNSKEY flags: a zone key, which may sign RRSIGs, and the secure entry
+// point that a key-signing key (KSK) usually sets.
+pub const ZONE_KEY: u16 = 0x0100;
+pub const SECURE_ENTRY_POINT: u16 = 0x0001;
+
+// The DNSKEY of an Ed25519 key: 256 (ZONE_KEY) for a zone-signing key,
+// 257 (ZONE_KEY | SECURE_ENTRY_POINT) for a key-signing key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dnskey {
+    pub flags: u16,
+    pub public: PublicKey,
+}
+
+impl Dnskey {
+    pub fn to_rdata(&self) -> Vec<u8> {
+        let mut rdata = Vec::with_capacity(36);
+        rdata.extend_from_slice(&self.flags.to_be_bytes());
+        rdata.push(PROTOCOL);
+        rdata.push(ED25519);
+        rdata.extend_from_slice(self.public.as_bytes());
+        rdata
+    }
+
+    pub fn from_rdata(rdata: &[u8]) -> Result<Self, DnssecError> {
+        if rdata.len() < 4 || rdata[2] != PROTOCOL {
+            return Err(DnssecError::Malformed);
+        }
+        if rdata[3] != ED25519 {
+            return Err(DnssecError::UnsupportedAlgorithm);
+        }
+        let public: [u8; 32] = rdata[4..].try_into().map_err(|_| DnssecError::Malformed)?;
+        Ok(Self {
+            flags: u16::from_be_bytes([rdata[0], rdata[1]]),
+            public: PublicKey::from_bytes(public)?,
+        })
+    }
+
+    // RFC 4034 appendix B: the RDATA summed as big-endian 16-bit words,
+    // with the carries folded back in once.
+    pub fn key_tag(&self) -> u16 {
+        let mut sum = 0u32;
+        for (i, &byte) in self.to_rdata().iter().enumerate() {
+            sum += if i % 2 == 0 {
+                u32::from(byte) << 8
+            } else {
+                u32::from(byte)
+            };
+        }
+        sum += sum >> 16;
+        sum as u16
+    }
+}
+
+// The records a signature covers. Each RDATA is in wire form, canonical
+// for its type (see above).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RrSet {
+    pub owner: String,
+    pub rr_type: u16,
+    pub class: u16,
+    pub ttl: u32,
+    pub rdata: Vec<Vec<u8>>,
+}
+
+// An RRSIG made with an Ed25519 key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rrsig {
+    fields: RrsigFields,
+    signature: Signature,
+}
+
+// Everything in an RRSIG but the signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RrsigFields {
+    type_covered: u16,
+    labels: u8,
+    original_ttl: u32,
+    expiration: u32,
+    inception: u32,
+    key_tag: u16,
+    // Canonical wire form.
+    signer_name: Vec<u8>,
+}
+
+impl Rrsig {
+    // Signs rrset with the zone key whose DNSKEY has the given flags. The
+    // signer name is the zone's name, which must be the owner or one of
+    // its ancestors. A leading "*" label in the owner marks a wildcard.
+    #[cfg(feature = "signing")]
+    pub fn sign(
+        keypair: &Keypair,
+        key_flags: u16,
+        signer_name: &str,
+        rrset: &RrSet,
+        inception: u32,
+        expiration: u32,
+    ) -> Result<Self, DnssecError> {
+        let key = Dnskey {
+            flags: key_flags,
+            public: *keypair.public(),
+        };
+        let owner = parse_name(&rrset.owner)?;
+        let wildcard = owner.first().is_some_and(|label| label == b"*");
+        let fields = RrsigFields {
+            type_covered: rrset.rr_type,
+            labels: (owner.len() - usize::from(wildcard)) as u8,
+            original_ttl: rrset.ttl,
+            expiration,
+            inception,
+            key_tag: key.key_tag(),
+            signer_name: put_name(&parse_name(signer_name)?),
+        };
+        let signature = keypair.sign(&fields.signed_data(rrset)?);
+        Ok(Self { fields, signature })
+    }
+
+    // Checks that key made this signature over rrset, and that now (a Unix
+    // time) is between the inception and expiration times. The key must be
+    // the zone's own (that its owner name is the signer name is up to the
+    // caller), and the RRset the one the RRSIG was found with: for a
+    // wildcard match, the records as they were served.
+    pub fn verify(&self, key: &Dnskey, rrset: &RrSet, now: u64) -> Result<(), DnssecError> {
+        let fields = &self.fields;
+        if key.flags & ZONE_KEY == 0 || key.key_tag() != fields.key_tag {
+            return Err(SignatureError::KeyMismatch.into());
+        }
+        if rrset.rr_type != fields.type_covered {
+            return Err(DnssecError::Malformed);
+        }
+        key.public
+            .verify(&fields.signed_data(rrset)?, &self.signature)?;
+
+        let now = now as u32;
+        if !serial_le(fields.inception, now) || !serial_le(now, fields.expiration) {
+            return Err(DnssecError::NotValidNow);
+        }
+        Ok(())
+    }
+
+    // The bytes the signature is over: this RDATA without the signature,
+    // then rrset's records in canonical form and order.
+    pub fn signed_data(&self, rrset: &RrSet) -> Result<Vec<u8>, DnssecError> {
+        self.fields.signed_data(rrset)
+    }
+
+    pub fn type_covered(&self) -> u16 {
+        self.fields.type_covered
+    }
+
+    pub fn labels(&self) -> u8 {
+        self.fields.labels
+    }
+
+    pub fn original_ttl(&self) -> u32 {
+        self.fields.original_ttl
+    }
+
+    pub fn expiration(&self) -> u32 {
+        self.fields.expiration
+    }
+
+    pub fn inception(&self) -> u32 {
+        self.fields.inception
+    }
+
+    pub fn key_tag(&self) -> u16 {
+        self.fields.key_tag
+    }
+
+    // In presentation form, with the final dot.
+    pub fn signer_name(&self) -> String {
+        let (labels, _) = read_name(&self.fields.signer_name).expect("checked when made");
+        if labels.is_empty() {
+            return ".".to_owned();
+        }
+        let mut name = String::new();
+        for label in labels {
+            // read_name only lets printable ASCII through.
+            name.push_str(std::str::from_utf8(&label).unwrap());
+            name.push('.');
+        }
+        name
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    pub fn to_rdata(&self) -> Vec<u8> {
+        let mut rdata = self.fields.to_rdata();
+        rdata.extend_from_slice(&self.signature.to_bytes());
+        rdata
+    }
+
+    // The algorithm must be 15; the signer name is lower-cased.
+    pub fn from_rdata(rdata: &[u8]) -> Result<Self, DnssecError> {
+        if rdata.len() < RRSIG_FIXED_LEN {
+            return Err(DnssecError::Malformed);
+        }
+        let (fixed, rest) = rdata.split_at(RRSIG_FIXED_LEN);
+        if fixed[2] != ED25519 {
+            return Err(DnssecError::UnsupportedAlgorithm);
+        }
+        let (signer, signature) = read_name(rest)?;
+        let signature: &[u8; 64] = signature.try_into().map_err(|_| DnssecError::Malformed)?;
+        let u32_at = |at: usize| u32::from_be_bytes(fixed[at..at + 4].try_into().unwrap());
+        Ok(Self {
+            fields: RrsigFields {
+                type_covered: u16::from_be_bytes([fixed[0], fixed[1]]),
+                labels: fixed[3],
+                original_ttl: u32_at(4),
+                expiration: u32_at(8),
+                inception: u32_at(12),
+                key_tag: u16::from_be_bytes([fixed[16], fixed[17]]),
+                signer_name: put_name(&signer),
+            },
+            signature: Signature::from_bytes(signature)?,
+        })
+    }
+}
+
+impl RrsigFields {
+    // The RRSIG's RDATA up to the signature.
+    fn to_rdata(&self) -> Vec<u8> {
+        let mut rdata = Vec::with_capacity(RRSIG_FIXED_LEN + self.signer_name.len() + 64);
+        rdata.extend_from_slice(&self.type_covered.to_be_bytes());
+        rdata.push(ED25519);
+        rdata.push(self.labels);
+        rdata.extend_from_slice(&self.original_ttl.to_be_bytes());
+        rdata.extend_from_slice(&self.expiration.to_be_bytes());
+        rdata.extend_from_slice(&self.inception.to_be_bytes());
+        rdata.extend_from_slice(&self.key_tag.to_be_bytes());
+        rdata.extend_from_slice(&self.signer_name);
+        rdata
+    }
+
+    fn signed_data(&self, rrset: &RrSet) -> Result<Vec<u8>, DnssecError> {
+        let mut owner = parse_name(&rrset.owner)?;
+        let signer = read_name(&self.signer_name)?.0;
+        if !owner.ends_with(&signer) {
+            return Err(DnssecError::Malformed);
+        }
+        let labels = usize::from(self.labels);
+        let wildcard = owner.first().is_some_and(|label| label == b"*");
+        let owner_labels = owner.len() - usize::from(wildcard);
+        if labels > owner_labels {
+            return Err(DnssecError::Malformed);
+        }
+        if labels < owner_labels {
+            owner.drain(..owner.len() - labels);
+            owner.insert(0, b"*".to_vec());
+        }
+        let owner = put_name(&owner);
+
+        let mut rdata: Vec<&Vec<u8>> = rrset.rdata.iter().collect();
+        rdata.sort();
+        rdata.dedup();
+
+        let mut data = self.to_rdata();
+        for rdata in rdata {
+            let len = u16::try_from(rdata.len()).map_err(|_| DnssecError::Malformed)?;
+            data.extend_from_slice(&owner);
+            data.extend_from_slice(&rrset.rr_type.to_be_bytes());
+            data.extend_from_slice(&rrset.class.to_be_bytes());
+            data.extend_from_slice(&self.original_ttl.to_be_bytes());
+            data.extend_from_slice(&len.to_be_bytes());
+            data.extend_from_slice(rdata);
+        }
+        Ok(data)
+    }
+}
+
+// a <= b in serial number arithmetic: b is at most 2^31 - 1 ahead of a.
+fn serial_le(a: u32, b: u32) -> bool {
+    b.wrapping_sub(a) < 1 << 31
+}
+
+// The labels of a name in presentation form, lower-cased, without the
+// root's empty label.
+fn parse_name(name: &str) -> Result<Vec<Vec<u8>>, DnssecError> {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    if name.is_empty() {
+        return Ok(Vec::new());
+    }
+    let labels: Vec<Vec<u8>> = name
+        .split('.')
+        .map(|label| label.to_ascii_lowercase().into_bytes())
+        .collect();
+    check_labels(&labels)?;
+    Ok(labels)
+}
+
+// Labels of 1 to 63 printable ASCII characters other than '.' and '\\'
+// (which would need escaping), at most 255 bytes in wire form.
+fn check_labels(labels: &[Vec<u8>]) -> Result<(), DnssecError> {
+    let printable = |b: &u8| b.is_ascii_graphic() && *b != b'.' && *b != b'\\';
+    let wire_len: usize = labels.iter().map(|label| label.len() + 1).sum::<usize>() + 1;
+    if wire_len > 255
+        || labels
+            .iter()
+            .any(|label| label.is_empty() || label.len() > 63 || !label.iter().all(printable))
+    {
+        return Err(DnssecError::Malformed);
+    }
+    Ok(())
+}
+
+// The wire form: each label after its length, then the root's zero.
+fn put_name(labels: &[Vec<u8>]) -> Vec<u8> {
+    let mut wire = Vec::new();
+    for label in labels {
+        wire.push(label.len() as u8);
+        wire.extend_from_slice(label);
+    }
+    wire.push(0);
+    wire
+}
+
+// An uncompressed name in wire form at the start of bytes, lower-cased,
+// and what follows it.
+fn read_name(mut bytes: &[u8]) -> Result<(Vec<Vec<u8>>, &[u8]), DnssecError> {
+    let mut labels = Vec::new();
+    loop {
+        match bytes {
+            [0, rest @ ..] => {
+                check_labels(&labels)?;
+                return Ok((labels, rest));
+            }
+            // 0x40 and up are compression pointers and extended label types.
+            [len, rest @ ..] if *len < 0x40 && rest.len() >= usize::from(*len) => {
+                let (label, rest) = rest.split_at(usize::from(*len));
+                labels.push(label.to_ascii_lowercase());
+                bytes = rest;
+            }
+            _ => return Err(DnssecError::Malformed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base64;
+    use sha2::{Digest, Sha256};
+
+    const MX: u16 = 15;
+    const IN: u16 = 1;
+
+    // RFC 8080 section 6: the seed, DNSKEY public key, DS digest and key
+    // tag, and the RRSIG over example.com.'s MX record, for each of the
+    // two examples. Both RRSIGs are valid from 1438207200 to 1440021600.
+    const EXAMPLES: [(&str, &str, &str, u16, &str); 2] = [
+        (
+            "ODIyNjAzODQ2MjgwODAxMjI2NDUxOTAyMDQxNDIyNjI=",
+            "l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=",
+            "3aa5ab37efce57f737fc1627013fee07bdf241bd10f3b1964ab55c78e79a304b",
+            3613,
+            "oL9krJun7xfBOIWcGHi7mag5/hdZrKWw15jPGrHpjQeRAvTdszaPD+QLs3fx8A4M\
+             3e23mRZ9VrbpMngwcrqNAg==",
+        ),
+        (
+            "DSSF3o0s0f+ElWzj9E/Osxw8hLpk55chkmx0LYN5WiY=",
+            "zPnZ/QwEe7S8C5SPz2OfS5RR40ATk2/rYnE9xHIEijs=",
+            "401781b934e392de492ec77ae2e15d70f6575a1c0bc59c5275c04ebe80c6614c",
+            35217,
+            "zXQ0bkYgQTEFyfLyi9QoiY6D8ZdYo4wyUhVioYZXFdT410QPRITQSqJSnzQoSm5p\
+             oJ7gD7AQR0O7KuI5k2pcBg==",
+        ),
+    ];
+    const INCEPTION: u32 = 1438207200;
+    const EXPIRATION: u32 = 1440021600;
+
+    // example.com. 3600 IN MX 10 mail.example.com.
+    fn mx() -> RrSet {
+        let mut rdata = vec![0, 10];
+        rdata.extend_from_slice(b"\x04mail\x07example\x03com\x00");
+        RrSet {
+            owner: "example.com.".to_owned(),
+            rr_type: MX,
+            class: IN,
+            ttl: 3600,
+            rdata: vec![rdata],
+        }
+    }
+
+    fn rrsig_rdata(key_tag: u16, signature: &str) -> Vec<u8> {
+        let mut rdata = vec![0, MX as u8, ED25519, 2];
+        rdata.extend_from_slice(&3600u32.to_be_bytes());
+        rdata.extend_from_slice(&EXPIRATION.to_be_bytes());
+        rdata.extend_from_slice(&INCEPTION.to_be_bytes());
+        rdata.extend_from_slice(&key_tag.to_be_bytes());
+        rdata.extend_from_slice(b"\x07example\x03com\x00");
+        rdata.extend_from_slice(&base64::decode(signature).unwrap());
+        rdata
+    }
+
+    #[test]
+    fn rfc8080_examples() {
+        for (_, public, ds, key_tag, signature) in EXAMPLES {
+            let mut rdata = vec![1, 1, PROTOCOL, ED25519];
+            rdata.extend_from_slice(&base64::decode(public).unwrap());
+            let key = Dnskey::from_rdata(&rdata).unwrap();
+            assert_eq!(key.flags, ZONE_KEY | SECURE_ENTRY_POINT);
+            assert_eq!(key.to_rdata(), rdata);
+            assert_eq!(key.key_tag(), key_tag);
+            // DS digest type 2: SHA-256 of the owner and the RDATA.
+            let digest = Sha256::new()
+                .chain_update(b"\x07example\x03com\x00")
+                .chain_update(&rdata)
+                .finalize();
+            assert_eq!(hex::encode(digest), ds);
+
+            let rrsig_rdata = rrsig_rdata(key_tag, signature);
+            let rrsig = Rrsig::from_rdata(&rrsig_rdata).unwrap();
+            assert_eq!(rrsig.to_rdata(), rrsig_rdata);
+            assert_eq!(rrsig.signer_name(), "example.com.");
+            assert_eq!(rrsig.key_tag(), key_tag);
+            assert_eq!(rrsig.verify(&key, &mx(), u64::from(INCEPTION)), Ok(()));
+            assert_eq!(rrsig.verify(&key, &mx(), u64::from(EXPIRATION)), Ok(()));
+            assert_eq!(
+                rrsig.verify(&key, &mx(), u64::from(EXPIRATION) + 1),
+                Err(DnssecError::NotValidNow)
+            );
+            assert_eq!(
+                rrsig.verify(&key, &mx(), u64::from(INCEPTION) - 1),
+                Err(DnssecError::NotValidNow)
+            );
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn rfc8080_signatures() {
+        for (seed, _, _, key_tag, signature) in EXAMPLES {
+            let seed = base64::decode(seed).unwrap().try_into().unwrap();
+            let keypair = Keypair::from_seed(seed);
+            let rrsig = Rrsig::sign(
+                &keypair,
+                ZONE_KEY | SECURE_ENTRY_POINT,
+                "example.com",
+                &mx(),
+                INCEPTION,
+                EXPIRATION,
+            )
+            .unwrap();
+            assert_eq!(rrsig.to_rdata(), rrsig_rdata(key_tag, signature));
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn canonical_form() {
+        let keypair = Keypair::from_seed([15; 32]);
+        let key = Dnskey {
+            flags: ZONE_KEY,
+            public: *keypair.public(),
+        };
+        let a = |ip: [u8; 4]| ip.to_vec();
+        let rrset = RrSet {
+            owner: "WWW.Example.COM".to_owned(),
+            rr_type: 1,
+            class: IN,
+            ttl: 300,
+            rdata: vec![a([192, 0, 2, 2]), a([192, 0, 2, 1]), a([192, 0, 2, 2])],
+        };
+        let rrsig =
+            Rrsig::sign(&keypair, ZONE_KEY, "Example.com.", &rrset, 0, 0x7fff_ffff).unwrap();
+        assert_eq!(rrsig.labels(), 3);
+        assert_eq!(rrsig.signer_name(), "example.com.");
+
+        // Lower-cased, sorted and without the duplicate.
+        let data = rrsig.signed_data(&rrset).unwrap();
+        let fixed = rrsig.to_rdata().len() - 64;
+        let record = |ip: [u8; 4]| {
+            let mut record = b"\x03www\x07example\x03com\x00\x00\x01\x00\x01".to_vec();
+            record.extend_from_slice(&300u32.to_be_bytes());
+            record.extend_from_slice(&[0, 4]);
+            record.extend_from_slice(&ip);
+            record
+        };
+        assert_eq!(
+            data[fixed..],
+            [record([192, 0, 2, 1]), record([192, 0, 2, 2])].concat()
+        );
+
+        // The records in another order, case and TTL (as a cache would
+        // serve them) still verify; the time check wraps around.
+        let served = RrSet {
+            owner: "www.example.com.".to_owned(),
+            ttl: 17,
+            rdata: vec![a([192, 0, 2, 1]), a([192, 0, 2, 2])],
+            ..rrset.clone()
+        };
+        assert_eq!(rrsig.verify(&key, &served, 1 << 32), Ok(()));
+
+        // A changed record, a missing one, another type.
+        let changed = RrSet {
+            rdata: vec![a([192, 0, 2, 1]), a([192, 0, 2, 3])],
+            ..rrset.clone()
+        };
+        let missing = RrSet {
+            rdata: vec![a([192, 0, 2, 1])],
+            ..rrset.clone()
+        };
+        for bad in [changed, missing] {
+            assert_eq!(
+                rrsig.verify(&key, &bad, 0),
+                Err(DnssecError::Signature(SignatureError::VerificationFailed))
+            );
+        }
+        let aaaa = RrSet {
+            rr_type: 28,
+            ..rrset.clone()
+        };
+        assert_eq!(rrsig.verify(&key, &aaaa, 0), Err(DnssecError::Malformed));
+
+        // Not a zone key, or another key.
+        let not_zone = Dnskey { flags: 0, ..key };
+        let other = Dnskey {
+            public: *Keypair::from_seed([16; 32]).public(),
+            ..key
+        };
+        for bad in [not_zone, other] {
+            assert_eq!(
+                rrsig.verify(&bad, &rrset, 0),
+                Err(DnssecError::Signature(SignatureError::KeyMismatch))
+            );
+        }
+
+        // A signer that isn't the owner or above it.
+        assert_eq!(
+            Rrsig::sign(&keypair, ZONE_KEY, "example.org", &rrset, 0, 1),
+            Err(DnssecError::Malformed)
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn wildcards() {
+        let keypair = Keypair::from_seed([15; 32]);
+        let key = Dnskey {
+            flags: ZONE_KEY,
+            public: *keypair.public(),
+        };
+        let wildcard = RrSet {
+            owner: "*.example.com.".to_owned(),
+            rr_type: 16,
+            class: IN,
+            ttl: 60,
+            rdata: vec![b"\x02hi".to_vec()],
+        };
+        let rrsig = Rrsig::sign(&keypair, ZONE_KEY, "example.com", &wildcard, 0, 1).unwrap();
+        assert_eq!(rrsig.labels(), 2);
+
+        // An answer synthesized from the wildcard verifies with the same
+        // RRSIG, as long as it's under the wildcard's parent.
+        let synthesized = RrSet {
+            owner: "a.b.example.com.".to_owned(),
+            ..wildcard.clone()
+        };
+        assert_eq!(rrsig.verify(&key, &synthesized, 0), Ok(()));
+        let elsewhere = RrSet {
+            owner: "a.example.org.".to_owned(),
+            ..wildcard.clone()
+        };
+        assert_eq!(
+            rrsig.verify(&key, &elsewhere, 0),
+            Err(DnssecError::Malformed)
+        );
+        // At the wildcard's parent it's just the wrong name.
+        let parent = RrSet {
+            owner: "example.com.".to_owned(),
+            ..wildcard.clone()
+        };
+        assert_eq!(
+            rrsig.verify(&key, &parent, 0),
+            Err(DnssecError::Signature(SignatureError::VerificationFailed))
+        );
+        // An RRSIG with more labels than the owner has.
+        let deeper = RrSet {
+            owner: "a.example.com.".to_owned(),
+            ..wildcard
+        };
+        let rrsig = Rrsig::sign(&keypair, ZONE_KEY, "example.com", &deeper, 0, 1).unwrap();
+        assert_eq!(rrsig.verify(&key, &parent, 0), Err(DnssecError::Malformed));
+    }
+
+    #[test]
+    fn malformed() {
+        let (_, public, _, key_tag, signature) = EXAMPLES[0];
+        let mut dnskey = vec![1, 0, PROTOCOL, ED25519];
+        dnskey.extend_from_slice(&base64::decode(public).unwrap());
+
+        let mut protocol = dnskey.clone();
+        protocol[2] = 4;
+        let mut ed448 = dnskey.clone();
+        ed448[3] = 16;
+        let mut short = dnskey.clone();
+        short.pop();
+        assert_eq!(Dnskey::from_rdata(&protocol), Err(DnssecError::Malformed));
+        assert_eq!(
+            Dnskey::from_rdata(&ed448),
+            Err(DnssecError::UnsupportedAlgorithm)
+        );
+        assert_eq!(Dnskey::from_rdata(&short), Err(DnssecError::Malformed));
+
+        let rrsig = rrsig_rdata(key_tag, signature);
+        let mut algorithm = rrsig.clone();
+        algorithm[2] = 13;
+        assert_eq!(
+            Rrsig::from_rdata(&algorithm),
+            Err(DnssecError::UnsupportedAlgorithm)
+        );
+        // a compressed signer name, a short signature
+        let mut compressed = rrsig[..18].to_vec();
+        compressed.extend_from_slice(&[0xc0, 0x0c]);
+        compressed.extend_from_slice(&rrsig[rrsig.len() - 64..]);
+        let short = &rrsig[..rrsig.len() - 1];
+        for bad in [&compressed[..], short, &rrsig[..10]] {
+            assert_eq!(Rrsig::from_rdata(bad), Err(DnssecError::Malformed));
+        }
+
+        // names: an empty label, a label too long, an escape
+        let long = format!("{}.com", "a".repeat(64));
+        for name in ["a..com", &long, "a\\.b.com"] {
+            assert_eq!(parse_name(name), Err(DnssecError::Malformed));
+        }
+        assert_eq!(parse_name("."), Ok(Vec::new()));
+    }
+}
@@ -0,0 +1,1604 @@
+use std::sync::LazyLock;
+
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use crate::field::FieldElement;
+use crate::montgomery::MontgomeryPoint;
+use crate::scalar::Scalar;
+
+// Points on the twisted Edwards curve used by ed25519,
+//
+//     -x^2 + y^2 = 1 + d * x^2 * y^2,  d = -121665 / 121666
+//
+// which is birationally equivalent to curve25519. The group law on this
+// curve is complete: the same addition formula works for any two points,
+// including doubling and the identity (0, 1), with no special cases.
+
+// d = -121665 / 121666
+pub(crate) const EDWARDS_D: FieldElement = FieldElement::from_limbs_unchecked([
+    0x78a3, 0x1359, 0x4dca, 0x75eb, 0xd8ab, 0x4141, 0x0a4d, 0x0070, 0xe898, 0x7779, 0x4079, 0x8cc7,
+    0xfe73, 0x2b6f, 0x6cee, 0x5203,
+]);
+
+// 2 * d
+pub(crate) const EDWARDS_D2: FieldElement = FieldElement::from_limbs_unchecked([
+    0xf159, 0x26b2, 0x9b94, 0xebd6, 0xb156, 0x8283, 0x149a, 0x00e0, 0xd130, 0xeef3, 0x80f2, 0x198e,
+    0xfce7, 0x56df, 0xd9dc, 0x2406,
+]);
+
+// The ed25519 basepoint B, the point with y = 4/5 and positive (even) x.
+// It generates the subgroup of prime order L.
+pub const ED25519_BASEPOINT: EdwardsPoint = EdwardsPoint {
+    x: FieldElement::from_limbs_unchecked([
+        0xd51a, 0x8f25, 0x2d60, 0xc956, 0xa7b2, 0x9525, 0xc760, 0x692c, 0xdc5c, 0xfdd6, 0xe231,
+        0xc0a4, 0x53fe, 0xcd6e, 0x36d3, 0x2169,
+    ]),
+    y: FieldElement::from_limbs_unchecked([
+        0x6658, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666,
+        0x6666, 0x6666, 0x6666, 0x6666, 0x6666,
+    ]),
+    z: FieldElement::ONE,
+    t: FieldElement::from_limbs_unchecked([
+        0xdda3, 0xa5b7, 0x8ab3, 0x6dde, 0x52f5, 0x7751, 0x9f80, 0x20f0, 0xe37d, 0x64ab, 0x4e8e,
+        0x66ea, 0x7665, 0xd78b, 0x5f0f, 0x6787,
+    ]),
+};
+
+// The eight points of order dividing 8, EIGHT_TORSION[k] = k * T for a
+// point T of order 8. Adding one of them to a point P leaves 8P alone but
+// changes P and its encoding, which is how tests tell cofactored and
+// cofactorless verification apart.
+pub const EIGHT_TORSION: [EdwardsPoint; 8] = [
+    EdwardsPoint {
+        x: FieldElement::ZERO,
+        y: FieldElement::ONE,
+        z: FieldElement::ONE,
+        t: FieldElement::ZERO,
+    },
+    EdwardsPoint {
+        x: FieldElement::from_limbs_unchecked([
+            0xd14a, 0xc545, 0x4646, 0xdea1, 0xe238, 0x13e5, 0x3c70, 0x5c19, 0x4abb, 0x38de, 0x9932,
+            0xe933, 0x4a28, 0x0639, 0xb9a0, 0x1fd5,
+        ]),
+        y: FieldElement::from_limbs_unchecked([
+            0x17c7, 0x706a, 0x4d3d, 0x4fd8, 0x3cba, 0x760b, 0x100d, 0x0f67, 0x202a, 0xfa53, 0x392c,
+            0xc6cc, 0xc74e, 0x77fd, 0xac92, 0x7a03,
+        ]),
+        z: FieldElement::ONE,
+        t: FieldElement::from_limbs_unchecked([
+            0x8781, 0xde7e, 0x253e, 0x2d0d, 0x8596, 0xcdfa, 0x3d74, 0xba99, 0xaf0a, 0x92f8, 0x76d1,
+            0x3032, 0x6beb, 0x60a2, 0x44c3, 0x6ce2,
+        ]),
+    },
+    EdwardsPoint {
+        x: FieldElement::from_limbs_unchecked([
+            0x5f3d, 0xb5f1, 0xe4d8, 0x3b11, 0x1b87, 0x52d0, 0xe7f9, 0xd0bc, 0x2858, 0xc204, 0xff66,
+            0xd4b2, 0x20f4, 0xb03e, 0xdb7f, 0x547c,
+        ]),
+        y: FieldElement::ZERO,
+        z: FieldElement::ONE,
+        t: FieldElement::ZERO,
+    },
+    EdwardsPoint {
+        x: FieldElement::from_limbs_unchecked([
+            0xd14a, 0xc545, 0x4646, 0xdea1, 0xe238, 0x13e5, 0x3c70, 0x5c19, 0x4abb, 0x38de, 0x9932,
+            0xe933, 0x4a28, 0x0639, 0xb9a0, 0x1fd5,
+        ]),
+        y: FieldElement::from_limbs_unchecked([
+            0xe826, 0x8f95, 0xb2c2, 0xb027, 0xc345, 0x89f4, 0xeff2, 0xf098, 0xdfd5, 0x05ac, 0xc6d3,
+            0x3933, 0x38b1, 0x8802, 0x536d, 0x05fc,
+        ]),
+        z: FieldElement::ONE,
+        t: FieldElement::from_limbs_unchecked([
+            0x786c, 0x2181, 0xdac1, 0xd2f2, 0x7a69, 0x3205, 0xc28b, 0x4566, 0x50f5, 0x6d07, 0x892e,
+            0xcfcd, 0x9414, 0x9f5d, 0xbb3c, 0x131d,
+        ]),
+    },
+    EdwardsPoint {
+        x: FieldElement::ZERO,
+        y: FieldElement::from_limbs_unchecked([
+            0xffec, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff,
+            0xffff, 0xffff, 0xffff, 0xffff, 0x7fff,
+        ]),
+        z: FieldElement::ONE,
+        t: FieldElement::ZERO,
+    },
+    EdwardsPoint {
+        x: FieldElement::from_limbs_unchecked([
+            0x2ea3, 0x3aba, 0xb9b9, 0x215e, 0x1dc7, 0xec1a, 0xc38f, 0xa3e6, 0xb544, 0xc721, 0x66cd,
+            0x16cc, 0xb5d7, 0xf9c6, 0x465f, 0x602a,
+        ]),
+        y: FieldElement::from_limbs_unchecked([
+            0xe826, 0x8f95, 0xb2c2, 0xb027, 0xc345, 0x89f4, 0xeff2, 0xf098, 0xdfd5, 0x05ac, 0xc6d3,
+            0x3933, 0x38b1, 0x8802, 0x536d, 0x05fc,
+        ]),
+        z: FieldElement::ONE,
+        t: FieldElement::from_limbs_unchecked([
+            0x8781, 0xde7e, 0x253e, 0x2d0d, 0x8596, 0xcdfa, 0x3d74, 0xba99, 0xaf0a, 0x92f8, 0x76d1,
+            0x3032, 0x6beb, 0x60a2, 0x44c3, 0x6ce2,
+        ]),
+    },
+    EdwardsPoint {
+        x: FieldElement::from_limbs_unchecked([
+            0xa0b0, 0x4a0e, 0x1b27, 0xc4ee, 0xe478, 0xad2f, 0x1806, 0x2f43, 0xd7a7, 0x3dfb, 0x0099,
+            0x2b4d, 0xdf0b, 0x4fc1, 0x2480, 0x2b83,
+        ]),
+        y: FieldElement::ZERO,
+        z: FieldElement::ONE,
+        t: FieldElement::ZERO,
+    },
+    EdwardsPoint {
+        x: FieldElement::from_limbs_unchecked([
+            0x2ea3, 0x3aba, 0xb9b9, 0x215e, 0x1dc7, 0xec1a, 0xc38f, 0xa3e6, 0xb544, 0xc721, 0x66cd,
+            0x16cc, 0xb5d7, 0xf9c6, 0x465f, 0x602a,
+        ]),
+        y: FieldElement::from_limbs_unchecked([
+            0x17c7, 0x706a, 0x4d3d, 0x4fd8, 0x3cba, 0x760b, 0x100d, 0x0f67, 0x202a, 0xfa53, 0x392c,
+            0xc6cc, 0xc74e, 0x77fd, 0xac92, 0x7a03,
+        ]),
+        z: FieldElement::ONE,
+        t: FieldElement::from_limbs_unchecked([
+            0x786c, 0x2181, 0xdac1, 0xd2f2, 0x7a69, 0x3205, 0xc28b, 0x4566, 0x50f5, 0x6d07, 0x892e,
+            0xcfcd, 0x9414, 0x9f5d, 0xbb3c, 0x131d,
+        ]),
+    },
+];
+
+// The standard 32-byte encoding of a point: y in little-endian, with the
+// top bit (unused, since y < 2^255) holding the sign of x.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressedEdwardsY(pub [u8; 32]);
+
+impl CompressedEdwardsY {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    // Recovers x from y using the curve equation:
+    //
+    //   -x^2 + y^2 = 1 + d x^2 y^2  =>  x^2 = (y^2 - 1) / (d y^2 + 1)
+    //
+    // sqrt_ratio_i takes that square root without an inversion. If the
+    // ratio is not a square, y is not the y-coordinate of any point and the
+    // encoding is invalid. Of the two roots x and -x, the sign bit picks
+    // the one whose "negative" bit (lowest bit) matches.
+    //
+    // Like most implementations this accepts y >= p (it is reduced) and a
+    // set sign bit with x = 0; strict verification checks for those
+    // separately.
+    pub fn decompress(&self) -> Option<EdwardsPoint> {
+        let y = FieldElement::from_bytes(self.0);
+        let yy = y * y;
+        let u = yy - FieldElement::ONE;
+        let v = yy * EDWARDS_D + FieldElement::ONE;
+        let (is_square, mut x) = FieldElement::sqrt_ratio_i(&u, &v);
+        if !is_square {
+            return None;
+        }
+
+        // sqrt_ratio_i returns the non-negative root
+        let sign = Choice::from(self.0[31] >> 7);
+        x.conditional_assign(&-x, sign);
+
+        Some(EdwardsPoint {
+            x,
+            y,
+            z: FieldElement::ONE,
+            t: x * y,
+        })
+    }
+}
+
+// A point in extended twisted Edwards coordinates (X : Y : Z : T), which
+// stands for the affine point (X/Z, Y/Z) with the extra coordinate
+// T = XY/Z. Keeping a denominator Z means additions need no inversions,
+// and T is what makes the addition formula below cheap.
+#[derive(Debug, Clone, Copy)]
+pub struct EdwardsPoint {
+    pub(crate) x: FieldElement,
+    pub(crate) y: FieldElement,
+    pub(crate) z: FieldElement,
+    pub(crate) t: FieldElement,
+}
+
+impl EdwardsPoint {
+    // The neutral element (0, 1).
+    pub const IDENTITY: EdwardsPoint = EdwardsPoint {
+        x: FieldElement::ZERO,
+        y: FieldElement::ONE,
+        z: FieldElement::ONE,
+        t: FieldElement::ZERO,
+    };
+
+    // Builds a point from affine coordinates, or None if (x, y) is not on
+    // the curve.
+    pub fn from_affine(x: FieldElement, y: FieldElement) -> Option<Self> {
+        let point = Self {
+            x,
+            y,
+            z: FieldElement::ONE,
+            t: x * y,
+        };
+        if point.is_on_curve() {
+            Some(point)
+        } else {
+            None
+        }
+    }
+
+    // A uniformly random point of the prime-order subgroup, computed as
+    // s * B for a random scalar s. This is fine for tests and blinding,
+    // but the discrete log s is known while it is computed, so don't use
+    // it for generators whose logs must stay unknown (e.g. the second
+    // Pedersen generator); hash to the curve for those instead.
+    #[cfg(feature = "rand")]
+    pub fn random<R: rand_core::RngCore + rand_core::CryptoRng>(rng: &mut R) -> Self {
+        &Scalar::random(rng) * &*ED25519_BASEPOINT_TABLE
+    }
+
+    // Checks the curve equation in projective form,
+    // -X^2 Z^2 + Y^2 Z^2 = Z^4 + d X^2 Y^2, and that T is consistent
+    // with the other coordinates (XY = ZT) and Z != 0.
+    pub fn is_on_curve(&self) -> bool {
+        let xx = self.x * self.x;
+        let yy = self.y * self.y;
+        let zz = self.z * self.z;
+        let lhs = (yy - xx) * zz;
+        let rhs = zz * zz + EDWARDS_D * xx * yy;
+
+        lhs == rhs && self.x * self.y == self.z * self.t && !self.z.is_zero()
+    }
+
+    // (X : Y : Z : T) is the identity when X = 0 and Y = Z; both
+    // comparisons are evaluated in constant time and combined as Choices.
+    pub fn is_identity(&self) -> bool {
+        (self.x.ct_eq(&FieldElement::ZERO) & self.y.ct_eq(&self.z)).into()
+    }
+
+    // Multiplies by the cofactor 8 with three doublings. The result is
+    // always in the prime-order subgroup, which is what cofactored
+    // verification and clearing torsion rely on.
+    pub fn mul_by_cofactor(&self) -> EdwardsPoint {
+        let mut p = *self;
+        p.double().double().double();
+        p
+    }
+
+    // The curve has 8 * L points, so a point has order dividing 8 exactly
+    // when multiplying by the cofactor takes it to the identity. There are
+    // eight such points; signatures and keys built from them say nothing
+    // about any secret, so strict verifiers reject them.
+    pub fn is_small_order(&self) -> bool {
+        self.mul_by_cofactor().is_identity()
+    }
+
+    // P + EIGHT_TORSION[k % 8], the same point with a small-order
+    // component added, for exercising verifiers on edge cases.
+    pub fn with_torsion(&self, k: usize) -> EdwardsPoint {
+        self + &EIGHT_TORSION[k % 8]
+    }
+
+    // A point is torsion-free, i.e. in the prime-order subgroup generated
+    // by the basepoint, when L * P is the identity. L itself isn't a valid
+    // Scalar, so we compute (L - 1) * P + P. The point is assumed public,
+    // so this uses the variable-time multiplication.
+    pub fn is_torsion_free(&self) -> bool {
+        let mut p = *self;
+        p.mul_vartime(&-Scalar::ONE);
+        p += self;
+        p.is_identity()
+    }
+
+    // The inverse of CompressedEdwardsY::decompress. One inversion brings
+    // the point back to affine (x, y) = (X/Z, Y/Z); y is encoded
+    // little-endian and the "negative" bit of x goes in the top bit.
+    pub fn compress(&self) -> CompressedEdwardsY {
+        self.to_affine().compress()
+    }
+
+    // The birational map to the Montgomery curve, u = (1 + y) / (1 - y),
+    // which is (Z + Y) / (Z - Y) in projective coordinates. It sends the
+    // basepoint to u = 9, and k * P to the u that the ladder computes from
+    // u(P), so an Ed25519 public key converts to an X25519 one. The
+    // identity (y = 1) has no image; it ends up at u = 0 because the
+    // inverse of 0 is 0.
+    pub fn to_montgomery(&self) -> MontgomeryPoint {
+        let mut denominator = self.z - self.y;
+        denominator.inverse();
+        MontgomeryPoint(((self.z + self.y) * denominator).to_bytes())
+    }
+
+    // (x, y) = (X/Z, Y/Z), at the cost of one inversion.
+    pub fn to_affine(&self) -> AffinePoint {
+        let mut z_inv = self.z;
+        z_inv.inverse();
+        AffinePoint {
+            x: self.x * z_inv,
+            y: self.y * z_inv,
+        }
+    }
+
+    // Converts many points to affine with a single inversion, using
+    // Montgomery's trick: with running products z_0, z_0 z_1, ...,
+    // z_0 ... z_{n-1}, one inversion of the full product gives every
+    // 1/z_i by walking back down, multiplying by the products on one side
+    // and the z's on the other. That is 3(n - 1) multiplications instead
+    // of n - 1 extra inversions.
+    pub fn batch_to_affine(points: &[EdwardsPoint]) -> Vec<AffinePoint> {
+        // products[i] = z_0 ... z_{i-1}
+        let mut products = Vec::with_capacity(points.len());
+        let mut acc = FieldElement::ONE;
+        for point in points {
+            products.push(acc);
+            acc *= point.z;
+        }
+
+        // acc = 1 / (z_0 ... z_{i}) on entry to iteration i
+        acc.inverse();
+        let mut affine = vec![AffinePoint::IDENTITY; points.len()];
+        for i in (0..points.len()).rev() {
+            let z_inv = acc * products[i];
+            acc *= points[i].z;
+            affine[i] = AffinePoint {
+                x: points[i].x * z_inv,
+                y: points[i].y * z_inv,
+            };
+        }
+        affine
+    }
+
+    // Unified addition from Hisil, Wong, Carter and Dawson, "Twisted
+    // Edwards Curves Revisited" (add-2008-hwcd-3), specialised to a = -1:
+    //
+    //   A = (Y1 - X1)(Y2 - X2)    B = (Y1 + X1)(Y2 + X2)
+    //   C = 2d T1 T2              D = 2 Z1 Z2
+    //   E = B - A   F = D - C   G = D + C   H = B + A
+    //   X3 = EF     Y3 = GH     T3 = EH     Z3 = FG
+    //
+    // It is complete on this curve (d is not a square), so it also works
+    // for doubling and for the identity.
+    pub fn add(&mut self, other: &Self) -> &mut Self {
+        let a = (self.y - self.x) * (other.y - other.x);
+        let b = (self.y + self.x) * (other.y + other.x);
+        let c = self.t * EDWARDS_D2 * other.t;
+        let d = self.z * other.z;
+        let d = d + d;
+
+        let e = b - a;
+        let f = d - c;
+        let g = d + c;
+        let h = b + a;
+
+        self.x = e * f;
+        self.y = g * h;
+        self.t = e * h;
+        self.z = f * g;
+        self
+    }
+
+    // Dedicated doubling (dbl-2008-hwcd) with a = -1, which doesn't need T
+    // and saves a few multiplications over add(self, self):
+    //
+    //   A = X1^2   B = Y1^2   C = 2 Z1^2   D = -A
+    //   E = (X1 + Y1)^2 - A - B   G = D + B   F = G - C   H = D - B
+    //   X3 = EF    Y3 = GH    T3 = EH    Z3 = FG
+    pub fn double(&mut self) -> &mut Self {
+        let a = self.x * self.x;
+        let b = self.y * self.y;
+        let zz = self.z * self.z;
+        let c = zz + zz;
+        let d = -a;
+        let xy = self.x + self.y;
+        let e = xy * xy - a - b;
+        let g = d + b;
+        let f = g - c;
+        let h = d - b;
+
+        self.x = e * f;
+        self.y = g * h;
+        self.t = e * h;
+        self.z = f * g;
+        self
+    }
+
+    // -(x, y) = (-x, y), and T = XY/Z changes sign along with X.
+    fn negate(&self) -> Self {
+        Self {
+            x: -self.x,
+            y: self.y,
+            z: self.z,
+            t: -self.t,
+        }
+    }
+
+    // Constant-time scalar multiplication with a fixed 4-bit window.
+    //
+    // The scalar is written in signed radix 16, s = sum d_i 16^i with
+    // d_i in [-8, 8), and we precompute P, 2P, ..., 8P. Going from the top
+    // digit down, the accumulator is multiplied by 16 (four doublings) and
+    // d_i * P is added. To not leak the digits, the table entry is picked
+    // by looking at every entry and conditionally keeping the one that
+    // matches |d_i| (leaving the identity for d_i = 0), and negated
+    // conditionally on the sign of d_i. Since the addition formula is
+    // complete there are no special cases either, so the same operations
+    // run whatever the scalar is.
+    pub fn mul(&mut self, scalar: &Scalar) -> &mut Self {
+        let mut table = [*self; 8];
+        for i in 1..8 {
+            table[i] = table[i - 1] + *self;
+        }
+
+        let digits = scalar.to_radix_2w(4);
+        let mut acc = EdwardsPoint::IDENTITY;
+        for &digit in digits.iter().rev() {
+            for _ in 0..4 {
+                acc.double();
+            }
+            acc += select(&table, digit);
+        }
+
+        *self = acc;
+        self
+    }
+
+    // Multiplies X, Y, Z and T by a random non-zero r. (rX : rY : rZ : rT)
+    // is the same point, but the coordinates the multiplication starts
+    // from are now unpredictable, which is a cheap countermeasure against
+    // power and EM attacks that correlate intermediate values with the
+    // inputs.
+    #[cfg(feature = "rand")]
+    pub fn rerandomize<R: rand_core::RngCore + rand_core::CryptoRng>(
+        &mut self,
+        rng: &mut R,
+    ) -> &mut Self {
+        let mut r = FieldElement::ZERO;
+        while r.is_zero() {
+            let mut bytes = [0; 32];
+            rng.fill_bytes(&mut bytes);
+            r = FieldElement::from_bytes(bytes);
+        }
+        self.x *= r;
+        self.y *= r;
+        self.z *= r;
+        self.t *= r;
+        self
+    }
+
+    // mul on a rerandomized copy of the point, for embedded users worried
+    // about side channels; the table of multiples inherits the blinding.
+    #[cfg(feature = "rand")]
+    pub fn mul_blinded<R: rand_core::RngCore + rand_core::CryptoRng>(
+        &mut self,
+        scalar: &Scalar,
+        rng: &mut R,
+    ) -> &mut Self {
+        self.rerandomize(rng).mul(scalar)
+    }
+
+    // Variable-time scalar multiplication using the width-5 NAF of the
+    // scalar, for public scalars only (e.g. in verification): the
+    // sequence of additions depends on the scalar's digits.
+    //
+    // The NAF digits are odd and in (-16, 16), so the table only holds the
+    // odd multiples P, 3P, ..., 15P, and a negative digit subtracts the
+    // entry. On average only one digit in six is non-zero, so this needs
+    // about 43 additions instead of 64, and skips the constant-time
+    // lookups entirely.
+    pub fn mul_vartime(&mut self, scalar: &Scalar) -> &mut Self {
+        let table = odd_multiples::<8>(self);
+        let naf = scalar.non_adjacent_form(5);
+
+        let mut acc = EdwardsPoint::IDENTITY;
+        // skip the leading zero digits
+        let top = naf.iter().rposition(|&d| d != 0);
+        for i in (0..=top.unwrap_or(0)).rev() {
+            acc.double();
+            add_naf_digit(&mut acc, &table, naf[i]);
+        }
+
+        *self = acc;
+        self
+    }
+
+    // Computes a * A + b * B for the basepoint B in variable time, which is
+    // what verification needs (s * B - k * A). Both scalars must be public.
+    //
+    // The two multiplications share their doublings: we walk the NAFs of a
+    // and b together from the top, doubling once per position and adding
+    // whichever digits are non-zero. A gets a width-5 table built on the
+    // fly; B is fixed, so its table is built once with width 8 (odd
+    // multiples up to 127B), which makes b's digits sparser still.
+    pub fn vartime_double_scalar_mul_basepoint(
+        a: &Scalar,
+        point: &EdwardsPoint,
+        b: &Scalar,
+    ) -> EdwardsPoint {
+        double_naf_mul_basepoint(&a.non_adjacent_form(5), &odd_multiples::<8>(point), b)
+    }
+
+    // Computes sum a_i * P_i in variable time (Straus's method), the
+    // general form of vartime_double_scalar_mul_basepoint: each point gets
+    // a width-5 table of odd multiples, and all the NAFs are walked
+    // together so the 253 or so doublings are shared between every term.
+    // For n terms that is about 253 + 43n additions and doublings, against
+    // roughly 300n for n separate multiplications. The scalars must be
+    // public.
+    //
+    // Panics if the slices have different lengths.
+    pub fn vartime_multiscalar_mul(scalars: &[Scalar], points: &[EdwardsPoint]) -> EdwardsPoint {
+        assert_eq!(scalars.len(), points.len(), "need one scalar per point");
+        let nafs: Vec<_> = scalars.iter().map(|s| s.non_adjacent_form(5)).collect();
+        let tables: Vec<_> = points.iter().map(odd_multiples::<8>).collect();
+
+        let mut acc = EdwardsPoint::IDENTITY;
+        let top = (0..256).rposition(|i| nafs.iter().any(|naf| naf[i] != 0));
+        for i in (0..=top.unwrap_or(0)).rev() {
+            acc.double();
+            for (naf, table) in nafs.iter().zip(&tables) {
+                add_naf_digit(&mut acc, table, naf[i]);
+            }
+        }
+        acc
+    }
+}
+
+// Walks the NAF of a (whose odd multiples of A are in table) together with
+// the width-8 NAF of b against the basepoint, for a * A + b * B.
+fn double_naf_mul_basepoint<const N: usize>(
+    a_naf: &[i8; 256],
+    table: &[EdwardsPoint; N],
+    b: &Scalar,
+) -> EdwardsPoint {
+    let b_naf = b.non_adjacent_form(8);
+    let basepoint_table = &*ED25519_BASEPOINT_ODD_MULTIPLES;
+
+    let mut acc = EdwardsPoint::IDENTITY;
+    let top = (0..256).rposition(|i| a_naf[i] != 0 || b_naf[i] != 0);
+    for i in (0..=top.unwrap_or(0)).rev() {
+        acc.double();
+        add_naf_digit(&mut acc, table, a_naf[i]);
+        add_naf_digit(&mut acc, basepoint_table, b_naf[i]);
+    }
+    acc
+}
+
+// The odd multiples P, 3P, ..., 127P of a fixed point, for repeated
+// variable-time multiplications by it. vartime_double_scalar_mul_basepoint
+// builds a width-5 table for A on every call (7 additions and a doubling)
+// because a bigger one wouldn't pay for itself once; kept around, the
+// width-8 table costs 63 additions up front and then makes the NAF of a
+// sparser, about one non-zero digit in nine instead of one in six.
+#[derive(Clone)]
+pub struct NafLookupTable {
+    table: Box<[EdwardsPoint; 64]>,
+}
+
+impl NafLookupTable {
+    pub fn new(point: &EdwardsPoint) -> Self {
+        Self {
+            table: Box::new(odd_multiples(point)),
+        }
+    }
+
+    pub fn point(&self) -> EdwardsPoint {
+        self.table[0]
+    }
+
+    // a * P + b * B, as EdwardsPoint::vartime_double_scalar_mul_basepoint
+    // with this table's point P. Both scalars must be public.
+    pub fn vartime_double_scalar_mul_basepoint(&self, a: &Scalar, b: &Scalar) -> EdwardsPoint {
+        double_naf_mul_basepoint(&a.non_adjacent_form(8), &self.table, b)
+    }
+}
+
+// B, 3B, 5B, ..., 127B for the width-8 NAF of the basepoint scalar in
+// vartime_double_scalar_mul_basepoint.
+static ED25519_BASEPOINT_ODD_MULTIPLES: LazyLock<[EdwardsPoint; 64]> =
+    LazyLock::new(|| odd_multiples(&ED25519_BASEPOINT));
+
+// P, 3P, 5P, ..., (2N - 1)P
+fn odd_multiples<const N: usize>(point: &EdwardsPoint) -> [EdwardsPoint; N] {
+    let mut two_p = *point;
+    two_p.double();
+    let mut table = [*point; N];
+    for i in 1..N {
+        table[i] = table[i - 1] + two_p;
+    }
+    table
+}
+
+// acc += digit * P, where table holds the odd multiples of P and digit is
+// an odd NAF digit (or zero, which adds nothing).
+fn add_naf_digit<const N: usize>(acc: &mut EdwardsPoint, table: &[EdwardsPoint; N], digit: i8) {
+    if digit > 0 {
+        acc.add(&table[digit as usize / 2]);
+    } else if digit < 0 {
+        acc.add(&table[(-digit) as usize / 2].negate());
+    }
+}
+
+// A point in affine coordinates (x, y), as produced by
+// EdwardsPoint::to_affine. Unlike the extended representation, each point
+// has exactly one affine form, so equality and encoding are direct; but
+// adding affine points would need an inversion each time, so arithmetic
+// goes through EdwardsPoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AffinePoint {
+    x: FieldElement,
+    y: FieldElement,
+}
+
+impl AffinePoint {
+    pub const IDENTITY: AffinePoint = AffinePoint {
+        x: FieldElement::ZERO,
+        y: FieldElement::ONE,
+    };
+
+    pub fn x(&self) -> FieldElement {
+        self.x
+    }
+
+    pub fn y(&self) -> FieldElement {
+        self.y
+    }
+
+    pub fn to_edwards(&self) -> EdwardsPoint {
+        EdwardsPoint {
+            x: self.x,
+            y: self.y,
+            z: FieldElement::ONE,
+            t: self.x * self.y,
+        }
+    }
+
+    // y is encoded little-endian and the "negative" bit of x goes in the
+    // top bit.
+    pub fn compress(&self) -> CompressedEdwardsY {
+        let mut bytes = self.y.to_bytes();
+        bytes[31] ^= (self.x.is_negative() as u8) << 7;
+        CompressedEdwardsY(bytes)
+    }
+}
+
+impl From<AffinePoint> for EdwardsPoint {
+    fn from(point: AffinePoint) -> EdwardsPoint {
+        point.to_edwards()
+    }
+}
+
+// Precomputed multiples of a fixed point B for fast constant-time
+// multiplication by B: entry [i][j] is (j + 1) * 16^i * B.
+//
+// With the scalar in signed radix 16, s = sum d_i 16^i with d_i in [-8, 8),
+// s * B = sum d_i * (16^i * B), and each term is a (constant-time) lookup
+// in row i. So a multiplication is 64 additions and no doublings, against
+// 252 doublings and 64 additions for the variable-base method.
+//
+// B doesn't have to be the basepoint: building a table for a long-lived
+// public point (say, a public key used for many verifications or key
+// agreements) and saving it with to_bytes lets later runs skip the 512
+// additions of create.
+#[derive(Clone)]
+pub struct EdwardsBasepointTable {
+    // 256 KiB, too big to move around on the stack
+    rows: Box<[[EdwardsPoint; 8]; 64]>,
+}
+
+impl EdwardsBasepointTable {
+    pub fn create(basepoint: &EdwardsPoint) -> Self {
+        let mut rows = empty_rows();
+        // 16^i * B
+        let mut row_base = *basepoint;
+        for row in rows.iter_mut() {
+            row[0] = row_base;
+            for j in 1..8 {
+                row[j] = row[j - 1] + row_base;
+            }
+            for _ in 0..4 {
+                row_base.double();
+            }
+        }
+        Self { rows }
+    }
+
+    pub fn basepoint(&self) -> EdwardsPoint {
+        self.rows[0][0]
+    }
+
+    // The length of to_bytes: 512 entries of 64 bytes.
+    pub const TABLE_BYTES: usize = 64 * 8 * 64;
+
+    // Every entry in affine form, x then y, each as 32 little-endian
+    // bytes. Affine points need no Z or T, and converting all 512 of them
+    // costs a single inversion.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let points: Vec<EdwardsPoint> = self.rows.iter().flatten().copied().collect();
+        let mut bytes = Vec::with_capacity(Self::TABLE_BYTES);
+        for point in EdwardsPoint::batch_to_affine(&points) {
+            bytes.extend_from_slice(&point.x.to_bytes());
+            bytes.extend_from_slice(&point.y.to_bytes());
+        }
+        bytes
+    }
+
+    // The inverse of to_bytes. Returns None if the length is wrong or any
+    // coordinate is non-canonical or any entry is not on the curve. That
+    // doesn't prove the entries are the right multiples of one point,
+    // which would take as long as create, so only load tables from
+    // storage you trust as much as the point itself.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::TABLE_BYTES {
+            return None;
+        }
+
+        let mut rows = empty_rows();
+        let entries = rows.iter_mut().flatten();
+        for (entry, chunk) in entries.zip(bytes.chunks_exact(64)) {
+            let x_bytes: [u8; 32] = chunk[..32].try_into().unwrap();
+            let y_bytes: [u8; 32] = chunk[32..].try_into().unwrap();
+            let x = FieldElement::from_bytes(x_bytes);
+            let y = FieldElement::from_bytes(y_bytes);
+            if x.to_bytes() != x_bytes || y.to_bytes() != y_bytes {
+                return None;
+            }
+            *entry = EdwardsPoint::from_affine(x, y)?;
+        }
+        Some(Self { rows })
+    }
+
+    pub fn mul_base(&self, scalar: &Scalar) -> EdwardsPoint {
+        let digits = scalar.to_radix_2w(4);
+        let mut acc = EdwardsPoint::IDENTITY;
+        for (row, &digit) in self.rows.iter().zip(digits.iter()) {
+            acc += select(row, digit);
+        }
+        acc
+    }
+}
+
+fn empty_rows() -> Box<[[EdwardsPoint; 8]; 64]> {
+    vec![[EdwardsPoint::IDENTITY; 8]; 64]
+        .into_boxed_slice()
+        .try_into()
+        .unwrap()
+}
+
+// The table for the ed25519 basepoint, built the first time it is used.
+pub static ED25519_BASEPOINT_TABLE: LazyLock<EdwardsBasepointTable> =
+    LazyLock::new(|| EdwardsBasepointTable::create(&ED25519_BASEPOINT));
+
+impl std::ops::Mul<&Scalar> for &EdwardsBasepointTable {
+    type Output = EdwardsPoint;
+
+    fn mul(self, scalar: &Scalar) -> EdwardsPoint {
+        self.mul_base(scalar)
+    }
+}
+
+impl std::ops::Mul<&EdwardsBasepointTable> for &Scalar {
+    type Output = EdwardsPoint;
+
+    fn mul(self, table: &EdwardsBasepointTable) -> EdwardsPoint {
+        table.mul_base(self)
+    }
+}
+
+// Returns digit * P from table = [P, 2P, ..., 8P] for digit in [-8, 8),
+// without branching on or indexing by the digit.
+fn select(table: &[EdwardsPoint; 8], digit: i8) -> EdwardsPoint {
+    // |digit| and its sign, computed with masks
+    let negative = ((digit as u8) >> 7) & 1;
+    let abs = ((digit as i16) ^ -(negative as i16)) + negative as i16;
+
+    let mut selected = EdwardsPoint::IDENTITY;
+    for (j, point) in table.iter().enumerate() {
+        let matches = (abs as u8).ct_eq(&(j as u8 + 1));
+        selected.conditional_assign(point, matches);
+    }
+    let negated = selected.negate();
+    selected.conditional_assign(&negated, Choice::from(negative));
+    selected
+}
+
+// (X1 : Y1 : Z1) and (X2 : Y2 : Z2) are the same point when X1/Z1 = X2/Z2
+// and Y1/Z1 = Y2/Z2, i.e. X1 Z2 = X2 Z1 and Y1 Z2 = Y2 Z1. Comparing the
+// coordinates themselves would be wrong, since every point has many
+// representations.
+impl ConstantTimeEq for EdwardsPoint {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        (self.x * other.z).ct_eq(&(other.x * self.z))
+            & (self.y * other.z).ct_eq(&(other.y * self.z))
+    }
+}
+
+impl PartialEq for EdwardsPoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for EdwardsPoint {}
+
+impl ConditionallySelectable for EdwardsPoint {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Self {
+            x: FieldElement::conditional_select(&a.x, &b.x, choice),
+            y: FieldElement::conditional_select(&a.y, &b.y, choice),
+            z: FieldElement::conditional_select(&a.z, &b.z, choice),
+            t: FieldElement::conditional_select(&a.t, &b.t, choice),
+        }
+    }
+}
+
+impl std::ops::Mul<&Scalar> for &EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn mul(self, scalar: &Scalar) -> EdwardsPoint {
+        let mut result = *self;
+        EdwardsPoint::mul(&mut result, scalar);
+        result
+    }
+}
+
+impl std::ops::Mul<&EdwardsPoint> for &Scalar {
+    type Output = EdwardsPoint;
+
+    fn mul(self, point: &EdwardsPoint) -> EdwardsPoint {
+        point * self
+    }
+}
+
+impl std::ops::Mul<Scalar> for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn mul(self, scalar: Scalar) -> EdwardsPoint {
+        &self * &scalar
+    }
+}
+
+impl std::ops::Mul<EdwardsPoint> for Scalar {
+    type Output = EdwardsPoint;
+
+    fn mul(self, point: EdwardsPoint) -> EdwardsPoint {
+        &point * &self
+    }
+}
+
+impl std::ops::Mul<&Scalar> for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn mul(mut self, scalar: &Scalar) -> EdwardsPoint {
+        self *= scalar;
+        self
+    }
+}
+
+impl std::ops::MulAssign<&Scalar> for EdwardsPoint {
+    fn mul_assign(&mut self, scalar: &Scalar) {
+        EdwardsPoint::mul(self, scalar);
+    }
+}
+
+impl std::ops::MulAssign<Scalar> for EdwardsPoint {
+    fn mul_assign(&mut self, scalar: Scalar) {
+        EdwardsPoint::mul(self, &scalar);
+    }
+}
+
+impl std::ops::AddAssign<&EdwardsPoint> for EdwardsPoint {
+    fn add_assign(&mut self, rhs: &EdwardsPoint) {
+        EdwardsPoint::add(self, rhs);
+    }
+}
+
+impl std::ops::AddAssign<EdwardsPoint> for EdwardsPoint {
+    fn add_assign(&mut self, rhs: EdwardsPoint) {
+        EdwardsPoint::add(self, &rhs);
+    }
+}
+
+impl std::ops::Add<&EdwardsPoint> for &EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn add(self, rhs: &EdwardsPoint) -> EdwardsPoint {
+        let mut result = *self;
+        EdwardsPoint::add(&mut result, rhs);
+        result
+    }
+}
+
+impl std::ops::Add<EdwardsPoint> for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn add(self, rhs: EdwardsPoint) -> EdwardsPoint {
+        &self + &rhs
+    }
+}
+
+impl std::ops::Add<&EdwardsPoint> for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn add(mut self, rhs: &EdwardsPoint) -> EdwardsPoint {
+        self += rhs;
+        self
+    }
+}
+
+impl std::ops::Neg for &EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn neg(self) -> EdwardsPoint {
+        self.negate()
+    }
+}
+
+impl std::ops::Neg for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn neg(self) -> EdwardsPoint {
+        self.negate()
+    }
+}
+
+impl std::ops::SubAssign<&EdwardsPoint> for EdwardsPoint {
+    fn sub_assign(&mut self, rhs: &EdwardsPoint) {
+        EdwardsPoint::add(self, &rhs.negate());
+    }
+}
+
+impl std::ops::SubAssign<EdwardsPoint> for EdwardsPoint {
+    fn sub_assign(&mut self, rhs: EdwardsPoint) {
+        *self -= &rhs;
+    }
+}
+
+impl std::ops::Sub<&EdwardsPoint> for &EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn sub(self, rhs: &EdwardsPoint) -> EdwardsPoint {
+        let mut result = *self;
+        result -= rhs;
+        result
+    }
+}
+
+impl std::ops::Sub<EdwardsPoint> for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn sub(self, rhs: EdwardsPoint) -> EdwardsPoint {
+        &self - &rhs
+    }
+}
+
+impl std::ops::Sub<&EdwardsPoint> for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn sub(mut self, rhs: &EdwardsPoint) -> EdwardsPoint {
+        self -= rhs;
+        self
+    }
+}
+
+impl std::iter::Sum for EdwardsPoint {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(EdwardsPoint::IDENTITY, |acc, p| acc + p)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a EdwardsPoint> for EdwardsPoint {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(EdwardsPoint::IDENTITY, |acc, p| acc + p)
+    }
+}
+
+// The RustCrypto group traits, so that generic protocol code written
+// against group::Group can use EdwardsPoint. The full curve group has
+// cofactor 8, so EdwardsPoint is only a Group, not a PrimeGroup.
+#[cfg(feature = "group")]
+mod group_impl {
+    use super::*;
+    use ff::Field;
+    use group::{Group, GroupEncoding};
+    use rand_core::RngCore;
+    use subtle::CtOption;
+
+    impl Group for EdwardsPoint {
+        type Scalar = Scalar;
+
+        fn random(rng: impl RngCore) -> Self {
+            &<Scalar as Field>::random(rng) * &*ED25519_BASEPOINT_TABLE
+        }
+
+        fn identity() -> Self {
+            EdwardsPoint::IDENTITY
+        }
+
+        fn generator() -> Self {
+            ED25519_BASEPOINT
+        }
+
+        fn is_identity(&self) -> Choice {
+            Choice::from(EdwardsPoint::is_identity(self) as u8)
+        }
+
+        fn double(&self) -> Self {
+            let mut p = *self;
+            EdwardsPoint::double(&mut p);
+            p
+        }
+    }
+
+    impl GroupEncoding for EdwardsPoint {
+        type Repr = [u8; 32];
+
+        fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
+            let point = CompressedEdwardsY(*bytes).decompress();
+            CtOption::new(
+                point.unwrap_or(EdwardsPoint::IDENTITY),
+                Choice::from(point.is_some() as u8),
+            )
+        }
+
+        fn from_bytes_unchecked(bytes: &[u8; 32]) -> CtOption<Self> {
+            Self::from_bytes(bytes)
+        }
+
+        fn to_bytes(&self) -> [u8; 32] {
+            self.compress().to_bytes()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // A generic function that only knows about the traits.
+        fn sum_of_multiples<G: Group>(scalars: &[G::Scalar]) -> G {
+            scalars.iter().map(|s| G::generator() * s).sum()
+        }
+
+        #[test]
+        fn group_traits() {
+            let scalars = [Scalar::from(2u64), Scalar::from(3u64)];
+            let p: EdwardsPoint = sum_of_multiples(&scalars);
+            assert_eq!(p, ED25519_BASEPOINT * Scalar::from(5u64));
+            assert!(bool::from(Group::is_identity(&(p - p))));
+            assert_eq!(Group::double(&p), p + p);
+
+            let bytes = GroupEncoding::to_bytes(&p);
+            assert_eq!(EdwardsPoint::from_bytes(&bytes).unwrap(), p);
+            // y = 2 is not the y-coordinate of any point
+            let mut invalid = [0; 32];
+            invalid[0] = 2;
+            assert!(bool::from(EdwardsPoint::from_bytes(&invalid).is_none()));
+
+            let random = <EdwardsPoint as Group>::random(rand::thread_rng());
+            assert!(random.is_torsion_free());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::montgomery::X25519_BASEPOINT;
+    use proptest::prelude::*;
+
+    // 2B, as affine coordinates computed independently.
+    fn two_b() -> EdwardsPoint {
+        let x = FieldElement::from_bytes(
+            hex::decode("0ece43284ea1c5835fa4d715458e0d08ace733187d3b043d6c045a9f4c38ab36")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        );
+        let y = FieldElement::from_bytes(
+            hex::decode("c9a3f86aae465f0e56513864510f3997561fa2c9e85ea21dc2292309f3cd6022")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        );
+        EdwardsPoint::from_affine(x, y).unwrap()
+    }
+
+    // Same affine point, whatever the Z.
+    #[test]
+    fn basepoint_is_on_curve() {
+        assert!(ED25519_BASEPOINT.is_on_curve());
+        assert!(EdwardsPoint::from_affine(FieldElement::ONE, FieldElement::ONE).is_none());
+    }
+
+    #[test]
+    fn double_matches_add() {
+        let b = ED25519_BASEPOINT;
+        let mut doubled = b;
+        doubled.double();
+        assert!(doubled.is_on_curve());
+        assert_eq!(doubled, two_b());
+        assert_eq!(b + b, two_b());
+    }
+
+    #[test]
+    fn group_law() {
+        let b = ED25519_BASEPOINT;
+        let identity = EdwardsPoint::from_affine(FieldElement::ZERO, FieldElement::ONE).unwrap();
+
+        // B + 0 = B
+        assert_eq!(b + identity, b);
+
+        // (B + 2B) + 2B = B + (2B + 2B), and both equal 5B = 2(2B) + B
+        let lhs = (b + two_b()) + two_b();
+        let rhs = b + (two_b() + two_b());
+        assert_eq!(lhs, rhs);
+        let mut four_b = two_b();
+        four_b.double();
+        assert_eq!(four_b + b, lhs);
+        assert!(lhs.is_on_curve());
+    }
+
+    #[test]
+    fn decompress_basepoint() {
+        let mut encoding = [0x66; 32];
+        encoding[0] = 0x58;
+        let b = CompressedEdwardsY(encoding).decompress().unwrap();
+        assert_eq!(b, ED25519_BASEPOINT);
+        assert!(b.is_on_curve());
+
+        // same y with the sign bit set is -B
+        encoding[31] |= 0x80;
+        let minus_b = CompressedEdwardsY(encoding).decompress().unwrap();
+        assert_eq!(minus_b.x, -ED25519_BASEPOINT.x);
+        assert_eq!(minus_b.y, ED25519_BASEPOINT.y);
+    }
+
+    #[test]
+    fn decompress_two_b() {
+        let encoding =
+            hex::decode("c9a3f86aae465f0e56513864510f3997561fa2c9e85ea21dc2292309f3cd6022")
+                .unwrap();
+        let p = CompressedEdwardsY(encoding.try_into().unwrap())
+            .decompress()
+            .unwrap();
+        assert_eq!(p, two_b());
+    }
+
+    #[test]
+    fn decompress_rejects_invalid_y() {
+        // no point has y = 2 or y = 7
+        let mut encoding = [0; 32];
+        encoding[0] = 2;
+        assert!(CompressedEdwardsY(encoding).decompress().is_none());
+        encoding[0] = 7;
+        assert!(CompressedEdwardsY(encoding).decompress().is_none());
+        // but y = 1 is the identity
+        encoding[0] = 1;
+        let identity = CompressedEdwardsY(encoding).decompress().unwrap();
+        assert!(identity.x.is_zero());
+    }
+
+    #[test]
+    fn compress_roundtrip() {
+        let mut encoding = [0x66; 32];
+        encoding[0] = 0x58;
+        assert_eq!(ED25519_BASEPOINT.compress().to_bytes(), encoding);
+
+        // compressing doesn't depend on the representation
+        let mut p = ED25519_BASEPOINT;
+        for _ in 0..5 {
+            p.double();
+            p += ED25519_BASEPOINT;
+            let compressed = p.compress();
+            let q = compressed.decompress().unwrap();
+            assert_eq!(p, q);
+            assert_eq!(q.compress(), compressed);
+        }
+
+        assert_eq!(
+            hex::encode(two_b().compress().as_bytes()),
+            "c9a3f86aae465f0e56513864510f3997561fa2c9e85ea21dc2292309f3cd6022"
+        );
+    }
+
+    #[test]
+    fn scalar_mul_small() {
+        let b = ED25519_BASEPOINT;
+        let mut expected = EdwardsPoint::IDENTITY;
+        for k in 0..20u64 {
+            assert_eq!(b * Scalar::from(k), expected);
+            expected += b;
+        }
+        assert_eq!(
+            hex::encode((b * Scalar::from(7)).compress().as_bytes()),
+            "b862409fb5c4c4123df2abf7462b88f041ad36dd6864ce872fd5472be363c5b1"
+        );
+    }
+
+    #[test]
+    fn scalar_mul_vector() {
+        let bytes: Vec<u8> = (1..=32).collect();
+        let k = Scalar::from_bytes_mod_order(bytes.try_into().unwrap());
+        assert_eq!(
+            hex::encode((k * ED25519_BASEPOINT).compress().as_bytes()),
+            "ce92350b547b6cf028df0618bf9aba55f949930059308d83ebd727e13472ed99"
+        );
+        // L - 1 is -1
+        assert_eq!(ED25519_BASEPOINT * -Scalar::ONE, ED25519_BASEPOINT.negate());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn scalar_mul_prop(a in any::<[u8; 32]>(), b in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let b = Scalar::from_bytes_mod_order(b);
+            let p = ED25519_BASEPOINT * a;
+
+            prop_assert_eq!(p * b, ED25519_BASEPOINT * (a * b));
+            prop_assert_eq!(ED25519_BASEPOINT * (a + b), p + ED25519_BASEPOINT * b);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn basepoint_table_prop(a in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let table = &*ED25519_BASEPOINT_TABLE;
+
+            prop_assert_eq!(table * &a, ED25519_BASEPOINT * a);
+            prop_assert_eq!(&a * table, ED25519_BASEPOINT * a);
+        }
+    }
+
+    #[test]
+    fn basepoint_table_other_point() {
+        let table = EdwardsBasepointTable::create(&two_b());
+        assert_eq!(table.basepoint(), two_b());
+        let k = -Scalar::from(3);
+        assert_eq!(&table * &k, two_b() * k);
+        assert_eq!(&table * &Scalar::ZERO, EdwardsPoint::IDENTITY);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn mul_vartime_prop(a in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let mut p = two_b();
+            p.mul_vartime(&a);
+
+            prop_assert_eq!(p, two_b() * a);
+        }
+    }
+
+    #[test]
+    fn mul_vartime_edge_cases() {
+        let mut p = ED25519_BASEPOINT;
+        p.mul_vartime(&Scalar::ZERO);
+        assert_eq!(p, EdwardsPoint::IDENTITY);
+        let mut p = ED25519_BASEPOINT;
+        p.mul_vartime(&-Scalar::ONE);
+        assert_eq!(p, ED25519_BASEPOINT.negate());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn vartime_double_scalar_mul_basepoint_prop(
+            a in any::<[u8; 32]>(),
+            b in any::<[u8; 32]>(),
+        ) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let b = Scalar::from_bytes_mod_order(b);
+            let p = EdwardsPoint::vartime_double_scalar_mul_basepoint(&a, &two_b(), &b);
+
+            prop_assert_eq!(p, two_b() * a + ED25519_BASEPOINT * b);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn naf_lookup_table_prop(a in any::<[u8; 32]>(), b in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let b = Scalar::from_bytes_mod_order(b);
+            let table = NafLookupTable::new(&two_b());
+
+            prop_assert_eq!(
+                table.vartime_double_scalar_mul_basepoint(&a, &b),
+                EdwardsPoint::vartime_double_scalar_mul_basepoint(&a, &two_b(), &b)
+            );
+        }
+    }
+
+    #[test]
+    fn naf_lookup_table_edge_cases() {
+        let table = NafLookupTable::new(&two_b().with_torsion(5));
+        assert_eq!(table.point(), two_b().with_torsion(5));
+        assert_eq!(
+            table.vartime_double_scalar_mul_basepoint(&Scalar::ZERO, &Scalar::ZERO),
+            EdwardsPoint::IDENTITY
+        );
+        assert_eq!(
+            table.vartime_double_scalar_mul_basepoint(&-Scalar::ONE, &Scalar::ONE),
+            two_b().with_torsion(5) * -Scalar::ONE + ED25519_BASEPOINT
+        );
+    }
+
+    #[test]
+    fn vartime_double_scalar_mul_basepoint_edge_cases() {
+        // s * B - k * A with A = B and s = k is the identity
+        let k = Scalar::from_bytes_mod_order([7; 32]);
+        let p = EdwardsPoint::vartime_double_scalar_mul_basepoint(&-k, &ED25519_BASEPOINT, &k);
+        assert_eq!(p, EdwardsPoint::IDENTITY);
+
+        let p = EdwardsPoint::vartime_double_scalar_mul_basepoint(
+            &Scalar::ZERO,
+            &two_b(),
+            &Scalar::ZERO,
+        );
+        assert_eq!(p, EdwardsPoint::IDENTITY);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn vartime_multiscalar_mul_prop(
+            scalars in proptest::collection::vec(any::<[u8; 32]>(), 0..5),
+        ) {
+            let scalars: Vec<_> = scalars.into_iter().map(Scalar::from_bytes_mod_order).collect();
+            let points: Vec<_> = (0..scalars.len())
+                .map(|i| ED25519_BASEPOINT * Scalar::from(i as u64 + 2))
+                .collect();
+            let expected: EdwardsPoint = scalars.iter().zip(&points).map(|(s, p)| p * s).sum();
+
+            prop_assert_eq!(EdwardsPoint::vartime_multiscalar_mul(&scalars, &points), expected);
+        }
+    }
+
+    #[test]
+    fn vartime_multiscalar_mul_edge_cases() {
+        assert_eq!(
+            EdwardsPoint::vartime_multiscalar_mul(&[], &[]),
+            EdwardsPoint::IDENTITY
+        );
+        // points with torsion components are handled like any other
+        let k = Scalar::from(5);
+        let p =
+            EdwardsPoint::vartime_multiscalar_mul(&[k, -k], &[two_b().with_torsion(3), two_b()]);
+        assert_eq!(p, EIGHT_TORSION[3] * k);
+    }
+
+    #[test]
+    #[should_panic(expected = "need one scalar per point")]
+    fn vartime_multiscalar_mul_mismatched_lengths() {
+        EdwardsPoint::vartime_multiscalar_mul(&[Scalar::ONE], &[]);
+    }
+
+    #[test]
+    fn identity() {
+        assert!(EdwardsPoint::IDENTITY.is_identity());
+        assert!(EdwardsPoint::IDENTITY.is_on_curve());
+        assert!(!ED25519_BASEPOINT.is_identity());
+        // a projective representative with Z != 1
+        assert!((two_b() - two_b()).is_identity());
+        assert!(
+            (ED25519_BASEPOINT + EdwardsPoint::IDENTITY).compress() == ED25519_BASEPOINT.compress()
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn neg_sub_prop(a in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let p = ED25519_BASEPOINT * a;
+
+            prop_assert_eq!(-p, ED25519_BASEPOINT * -a);
+            prop_assert!((p + -p).is_identity());
+            prop_assert_eq!(p - two_b(), p + -two_b());
+            prop_assert_eq!(p - p - p, -p);
+        }
+    }
+
+    // The encodings of the eight points of order dividing 8.
+    const SMALL_ORDER_ENCODINGS: [&str; 8] = [
+        "0100000000000000000000000000000000000000000000000000000000000000",
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000080",
+        "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05",
+        "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc85",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac03fa",
+    ];
+
+    fn small_order_points() -> Vec<EdwardsPoint> {
+        EIGHT_TORSION.to_vec()
+    }
+
+    #[test]
+    fn eight_torsion() {
+        for (k, p) in EIGHT_TORSION.iter().enumerate() {
+            assert!(p.is_on_curve());
+            assert_eq!(*p, EIGHT_TORSION[1] * Scalar::from(k as u64));
+            let encoding = hex::encode(p.compress().as_bytes());
+            assert!(SMALL_ORDER_ENCODINGS.contains(&encoding.as_str()));
+            assert_eq!(p.compress().decompress(), Some(*p));
+        }
+        // T has order exactly 8
+        let mut t = EIGHT_TORSION[1];
+        assert!(!t.double().double().is_identity());
+        assert!(EIGHT_TORSION[1].mul_by_cofactor().is_identity());
+
+        let p = two_b().with_torsion(3);
+        assert_eq!(p, two_b() + EIGHT_TORSION[3]);
+        assert_eq!(p.mul_by_cofactor(), two_b().mul_by_cofactor());
+        assert_eq!(two_b().with_torsion(8), two_b());
+    }
+
+    #[test]
+    fn small_order() {
+        for p in small_order_points() {
+            assert!(p.is_small_order());
+            // only the identity is both small order and torsion-free
+            assert_eq!(p.is_torsion_free(), p.is_identity());
+            // adding torsion to a prime-order point leaves it neither
+            let q = ED25519_BASEPOINT + p;
+            assert!(!q.is_small_order());
+            assert_eq!(q.is_torsion_free(), p.is_identity());
+        }
+        assert!(ED25519_BASEPOINT.is_torsion_free());
+        assert!(!ED25519_BASEPOINT.is_small_order());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn torsion_free_prop(a in any::<[u8; 32]>()) {
+            let p = ED25519_BASEPOINT * Scalar::from_bytes_mod_order(a);
+            prop_assert!(p.is_torsion_free());
+        }
+    }
+
+    #[test]
+    fn mul_by_cofactor() {
+        let eight = Scalar::from(8u64);
+        assert_eq!(two_b().mul_by_cofactor(), two_b() * eight);
+        for p in small_order_points() {
+            let q = ED25519_BASEPOINT + p;
+            assert!(q.mul_by_cofactor().is_torsion_free());
+            assert_eq!(q.mul_by_cofactor(), ED25519_BASEPOINT * eight);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn batch_to_affine_prop(a in any::<[u8; 32]>(), n in 0..8usize) {
+            let a = Scalar::from_bytes_mod_order(a);
+            // points with Z != 1, from doubling
+            let points: Vec<_> = (0..n)
+                .map(|i| {
+                    let mut p = ED25519_BASEPOINT * (a + Scalar::from(i as u64));
+                    p.double();
+                    p
+                })
+                .collect();
+
+            let affine = EdwardsPoint::batch_to_affine(&points);
+            prop_assert_eq!(affine.len(), n);
+            for (p, q) in points.iter().zip(affine.iter()) {
+                prop_assert_eq!(*q, p.to_affine());
+                prop_assert_eq!(q.compress(), p.compress());
+                prop_assert_eq!(q.to_edwards(), *p);
+                prop_assert!(q.to_edwards().is_on_curve());
+            }
+        }
+    }
+
+    #[test]
+    fn affine_identity() {
+        assert_eq!(EdwardsPoint::IDENTITY.to_affine(), AffinePoint::IDENTITY);
+        assert!(EdwardsPoint::from(AffinePoint::IDENTITY).is_identity());
+        assert_eq!((two_b() - two_b()).to_affine(), AffinePoint::IDENTITY);
+    }
+
+    #[test]
+    fn projective_eq() {
+        // the same point with Z = 1 and with Z != 1
+        let mut p = ED25519_BASEPOINT;
+        p.double();
+        let q = p.to_affine().to_edwards();
+        assert_ne!(p.z, q.z);
+        assert_eq!(p, q);
+        assert_ne!(p, ED25519_BASEPOINT);
+        assert_ne!(p, -p);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn random() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let p = EdwardsPoint::random(&mut rng);
+        let q = EdwardsPoint::random(&mut rng);
+        assert_ne!(p, q);
+        assert!(p.is_on_curve());
+        assert!(p.is_torsion_free());
+
+        let mut rng = StdRng::seed_from_u64(42);
+        assert_eq!(EdwardsPoint::random(&mut rng), p);
+    }
+
+    #[test]
+    fn table_bytes_roundtrip() {
+        let point = two_b() * Scalar::from_bytes_mod_order([9; 32]);
+        let table = EdwardsBasepointTable::create(&point);
+        let bytes = table.to_bytes();
+        assert_eq!(bytes.len(), EdwardsBasepointTable::TABLE_BYTES);
+
+        let loaded = EdwardsBasepointTable::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.basepoint(), point);
+        let k = Scalar::from_bytes_mod_order([0x42; 32]);
+        assert_eq!(&loaded * &k, point * k);
+        assert_eq!(loaded.to_bytes(), bytes);
+
+        assert!(EdwardsBasepointTable::from_bytes(&bytes[1..]).is_none());
+        // an entry that is no longer on the curve
+        let mut corrupted = bytes.clone();
+        corrupted[100] ^= 1;
+        assert!(EdwardsBasepointTable::from_bytes(&corrupted).is_none());
+        // a non-canonical coordinate
+        let mut corrupted = bytes;
+        corrupted[..32].copy_from_slice(&[0xff; 32]);
+        assert!(EdwardsBasepointTable::from_bytes(&corrupted).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn blinded_mul() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut p = two_b();
+        p.rerandomize(&mut rng);
+        assert_eq!(p, two_b());
+        assert_ne!(p.z, two_b().z);
+        assert!(p.is_on_curve());
+
+        let k = Scalar::from_bytes_mod_order([0x33; 32]);
+        let mut q = two_b();
+        q.mul_blinded(&k, &mut rng);
+        assert_eq!(q, two_b() * k);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn to_montgomery_prop(a in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let p = ED25519_BASEPOINT * a;
+
+            let u = p.to_montgomery();
+            prop_assert_eq!(u, X25519_BASEPOINT * a);
+            // -P has the same u, and the sign of x picks between them
+            prop_assert_eq!((-p).to_montgomery(), u);
+            let sign = p.compress().as_bytes()[31] >> 7;
+            prop_assert_eq!(u.to_edwards(sign), Some(p));
+            prop_assert_eq!(u.to_edwards(sign ^ 1), Some(-p));
+        }
+    }
+
+    #[test]
+    fn montgomery_basepoint() {
+        assert_eq!(ED25519_BASEPOINT.to_montgomery(), X25519_BASEPOINT);
+        assert_eq!(X25519_BASEPOINT.to_edwards(0), Some(ED25519_BASEPOINT));
+        assert_eq!(EdwardsPoint::IDENTITY.to_montgomery().to_bytes(), [0; 32]);
+    }
+}
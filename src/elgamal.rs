@@ -0,0 +1,431 @@
+#[cfg(feature = "signing")]
+use std::collections::HashMap;
+#[cfg(feature = "signing")]
+use std::fmt;
+
+#[cfg(any(feature = "signing", feature = "rand"))]
+use zeroize::Zeroize;
+
+#[cfg(feature = "signing")]
+use crate::ristretto::RISTRETTO_BASEPOINT;
+use crate::ristretto::{CompressedRistretto, RistrettoPoint, RISTRETTO_BASEPOINT_TABLE};
+use crate::scalar::Scalar;
+use crate::signing::SignatureError;
+
+// Exponential ("lifted") ElGamal in ristretto255. The secret key is a
+// scalar x and the public key Y = x * B. A message m is a scalar, put in
+// the exponent, and encrypted with a fresh random r as
+//
+//   (C1, C2) = (r * B, m * B + r * Y)
+//
+// Decryption computes C2 - x * C1 = m * B, which gives back m only as a
+// point: finding m takes a discrete log, feasible only when m is known to
+// be small (a vote, a count, an amount). decrypt searches 0..=max with
+// baby-step giant-step, about 2 * sqrt(max) point operations, and in
+// variable time, so it shouldn't be used where the timing of decrypting
+// m would give m away.
+//
+// Putting m in the exponent is what makes the scheme additively
+// homomorphic: adding two ciphertexts componentwise gives an encryption of
+// the sum of the messages, under the sum of the randomness, and
+// multiplying one by a scalar k gives an encryption of k * m. A tally of
+// encrypted votes is a sum of ciphertexts, decrypted once.
+//
+// Anyone holding the public key can also rerandomize a ciphertext, adding
+// an encryption of zero: the result decrypts to the same m but can't be
+// linked to the original without the secret key, which is the step each
+// server of a mixnet performs.
+//
+// Plain ElGamal is only secure against passive attackers. Ciphertexts are
+// malleable by design, so anything that accepts them from others needs
+// proofs alongside (that each vote is 0 or 1, that a mix is a
+// permutation), which this module doesn't provide.
+
+// An ElGamal ciphertext (C1, C2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ciphertext {
+    c1: RistrettoPoint,
+    c2: RistrettoPoint,
+}
+
+impl Ciphertext {
+    // The encryption of zero with no randomness, (0, 0): the identity of
+    // ciphertext addition, to start a sum from.
+    pub const ZERO: Ciphertext = Ciphertext {
+        c1: RistrettoPoint::IDENTITY,
+        c2: RistrettoPoint::IDENTITY,
+    };
+
+    // C1 || C2, compressed.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(self.c1.compress().as_bytes());
+        bytes[32..].copy_from_slice(self.c2.compress().as_bytes());
+        bytes
+    }
+
+    // Fails with InvalidPoint if either half isn't a canonical ristretto255
+    // encoding.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, SignatureError> {
+        let point = |b: &[u8]| {
+            CompressedRistretto(b.try_into().unwrap())
+                .decompress()
+                .ok_or(SignatureError::InvalidPoint)
+        };
+        Ok(Ciphertext {
+            c1: point(&bytes[..32])?,
+            c2: point(&bytes[32..])?,
+        })
+    }
+}
+
+impl std::ops::Add<&Ciphertext> for &Ciphertext {
+    type Output = Ciphertext;
+
+    fn add(self, rhs: &Ciphertext) -> Ciphertext {
+        Ciphertext {
+            c1: self.c1 + rhs.c1,
+            c2: self.c2 + rhs.c2,
+        }
+    }
+}
+
+impl std::ops::Add<Ciphertext> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn add(self, rhs: Ciphertext) -> Ciphertext {
+        &self + &rhs
+    }
+}
+
+impl std::ops::Sub<&Ciphertext> for &Ciphertext {
+    type Output = Ciphertext;
+
+    fn sub(self, rhs: &Ciphertext) -> Ciphertext {
+        Ciphertext {
+            c1: self.c1 - rhs.c1,
+            c2: self.c2 - rhs.c2,
+        }
+    }
+}
+
+impl std::ops::Sub<Ciphertext> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn sub(self, rhs: Ciphertext) -> Ciphertext {
+        &self - &rhs
+    }
+}
+
+impl std::ops::Mul<&Scalar> for &Ciphertext {
+    type Output = Ciphertext;
+
+    fn mul(self, k: &Scalar) -> Ciphertext {
+        Ciphertext {
+            c1: self.c1 * k,
+            c2: self.c2 * k,
+        }
+    }
+}
+
+impl std::ops::Mul<Scalar> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn mul(self, k: Scalar) -> Ciphertext {
+        &self * &k
+    }
+}
+
+impl std::iter::Sum for Ciphertext {
+    fn sum<I: Iterator<Item = Ciphertext>>(iter: I) -> Self {
+        iter.fold(Ciphertext::ZERO, |acc, c| acc + c)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a Ciphertext> for Ciphertext {
+    fn sum<I: Iterator<Item = &'a Ciphertext>>(iter: I) -> Self {
+        iter.fold(Ciphertext::ZERO, |acc, c| &acc + c)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElGamalPublicKey(RistrettoPoint);
+
+impl ElGamalPublicKey {
+    // Fails with InvalidPoint if bytes isn't a canonical encoding, or is
+    // the identity: encrypting to the identity would put m * B in C2 in
+    // the clear.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, SignatureError> {
+        match CompressedRistretto(*bytes).decompress() {
+            Some(point) if point != RistrettoPoint::IDENTITY => Ok(Self(point)),
+            _ => Err(SignatureError::InvalidPoint),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.compress().to_bytes()
+    }
+
+    pub fn as_point(&self) -> &RistrettoPoint {
+        &self.0
+    }
+
+    // Encrypts message with the given randomness, which must be uniform,
+    // secret and never reused: anyone who learns r can decrypt, and two
+    // ciphertexts sharing r reveal the difference of their messages.
+    pub fn encrypt_with_randomness(&self, message: &Scalar, randomness: &Scalar) -> Ciphertext {
+        Ciphertext {
+            c1: randomness * &*RISTRETTO_BASEPOINT_TABLE,
+            c2: message * &*RISTRETTO_BASEPOINT_TABLE + self.0 * randomness,
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    pub fn encrypt<R: rand_core::RngCore + rand_core::CryptoRng>(
+        &self,
+        message: &Scalar,
+        rng: &mut R,
+    ) -> Ciphertext {
+        let mut r = Scalar::random(rng);
+        let ciphertext = self.encrypt_with_randomness(message, &r);
+        r.zeroize();
+        ciphertext
+    }
+
+    // The same message under fresh randomness: ciphertext plus an
+    // encryption of zero with randomness.
+    pub fn rerandomize_with_randomness(
+        &self,
+        ciphertext: &Ciphertext,
+        randomness: &Scalar,
+    ) -> Ciphertext {
+        ciphertext + &self.encrypt_with_randomness(&Scalar::ZERO, randomness)
+    }
+
+    #[cfg(feature = "rand")]
+    pub fn rerandomize<R: rand_core::RngCore + rand_core::CryptoRng>(
+        &self,
+        ciphertext: &Ciphertext,
+        rng: &mut R,
+    ) -> Ciphertext {
+        let mut r = Scalar::random(rng);
+        let rerandomized = self.rerandomize_with_randomness(ciphertext, &r);
+        r.zeroize();
+        rerandomized
+    }
+}
+
+// Zeroized when dropped; Debug redacts it.
+#[cfg(feature = "signing")]
+#[derive(Clone)]
+pub struct ElGamalSecretKey {
+    x: Scalar,
+    public: ElGamalPublicKey,
+}
+
+#[cfg(feature = "signing")]
+impl ElGamalSecretKey {
+    // Fails with NonCanonicalScalar if the scalar isn't reduced, and with
+    // InvalidPoint if it is zero, whose public key would be the identity.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, SignatureError> {
+        let x = Scalar::from_canonical_bytes(*bytes).ok_or(SignatureError::NonCanonicalScalar)?;
+        if x == Scalar::ZERO {
+            return Err(SignatureError::InvalidPoint);
+        }
+        Ok(Self {
+            public: ElGamalPublicKey(&x * &*RISTRETTO_BASEPOINT_TABLE),
+            x,
+        })
+    }
+
+    #[cfg(feature = "rand")]
+    pub fn random<R: rand_core::RngCore + rand_core::CryptoRng>(rng: &mut R) -> Self {
+        let x = Scalar::random(rng);
+        Self {
+            public: ElGamalPublicKey(&x * &*RISTRETTO_BASEPOINT_TABLE),
+            x,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.x.to_bytes()
+    }
+
+    pub fn public(&self) -> &ElGamalPublicKey {
+        &self.public
+    }
+
+    // m * B for the encrypted m. Constant time.
+    pub fn decrypt_point(&self, ciphertext: &Ciphertext) -> RistrettoPoint {
+        ciphertext.c2 - ciphertext.c1 * self.x
+    }
+
+    // The encrypted m, if it is at most max, by baby-step giant-step in
+    // variable time.
+    pub fn decrypt(&self, ciphertext: &Ciphertext, max: u64) -> Option<u64> {
+        discrete_log(&self.decrypt_point(ciphertext), max)
+    }
+}
+
+#[cfg(feature = "signing")]
+impl Zeroize for ElGamalSecretKey {
+    fn zeroize(&mut self) {
+        self.x.zeroize();
+    }
+}
+
+#[cfg(feature = "signing")]
+impl Drop for ElGamalSecretKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "signing")]
+impl fmt::Debug for ElGamalSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElGamalSecretKey")
+            .field("secret", &"<redacted>")
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
+// The m in 0..=max with m * B = point, if there is one. With n = ceil(sqrt(max
+// + 1)), write m = i * n + j with j < n: a table holds j * B for every j
+// (the baby steps), and point - i * (n * B) is looked up in it for
+// i = 0, 1, ... (the giant steps).
+#[cfg(feature = "signing")]
+fn discrete_log(point: &RistrettoPoint, max: u64) -> Option<u64> {
+    let n = (max as f64 + 1.0).sqrt().ceil() as u64;
+    let mut baby_steps = HashMap::with_capacity(n as usize);
+    let mut step = RistrettoPoint::IDENTITY;
+    for j in 0..n {
+        baby_steps.insert(step.compress().to_bytes(), j);
+        step += RISTRETTO_BASEPOINT;
+    }
+
+    // step is now n * B
+    let mut target = *point;
+    for i in 0..n {
+        if let Some(j) = baby_steps.get(target.compress().as_bytes()) {
+            let m = i * n + j;
+            return (m <= max).then_some(m);
+        }
+        target -= step;
+    }
+    None
+}
+
+#[cfg(all(test, feature = "signing"))]
+mod tests {
+    use super::*;
+
+    fn secret() -> ElGamalSecretKey {
+        ElGamalSecretKey::from_bytes(&Scalar::from(0x5eed_u64).to_bytes()).unwrap()
+    }
+
+    fn encrypt(message: u64, randomness: u64) -> Ciphertext {
+        secret()
+            .public()
+            .encrypt_with_randomness(&Scalar::from(message), &Scalar::from(randomness))
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let key = secret();
+        for m in [0, 1, 2, 99, 100, 1000, 65535] {
+            let c = encrypt(m, 1 + m);
+            assert_eq!(
+                key.decrypt_point(&c),
+                &Scalar::from(m) * &*RISTRETTO_BASEPOINT_TABLE
+            );
+            assert_eq!(key.decrypt(&c, 65535), Some(m));
+        }
+        // out of range, and exactly at the bound
+        assert_eq!(key.decrypt(&encrypt(101, 7), 100), None);
+        assert_eq!(key.decrypt(&encrypt(100, 7), 100), Some(100));
+        assert_eq!(key.decrypt(&encrypt(0, 7), 0), Some(0));
+
+        // another key gets garbage
+        let other = ElGamalSecretKey::from_bytes(&Scalar::from(2u64).to_bytes()).unwrap();
+        assert_eq!(other.decrypt(&encrypt(5, 7), 1000), None);
+
+        // byte encodings
+        let c = encrypt(5, 7);
+        assert_eq!(Ciphertext::from_bytes(&c.to_bytes()), Ok(c));
+        assert_eq!(
+            ElGamalPublicKey::from_bytes(&key.public().to_bytes()),
+            Ok(*key.public())
+        );
+        let restored = ElGamalSecretKey::from_bytes(&key.to_bytes()).unwrap();
+        assert_eq!(restored.decrypt(&c, 10), Some(5));
+    }
+
+    #[test]
+    fn invalid_encodings() {
+        assert_eq!(
+            ElGamalPublicKey::from_bytes(&[0; 32]),
+            Err(SignatureError::InvalidPoint)
+        );
+        let mut bytes = encrypt(5, 7).to_bytes();
+        bytes[63] ^= 0x80;
+        assert_eq!(
+            Ciphertext::from_bytes(&bytes),
+            Err(SignatureError::InvalidPoint)
+        );
+        assert_eq!(
+            ElGamalSecretKey::from_bytes(&[0; 32]).unwrap_err(),
+            SignatureError::InvalidPoint
+        );
+        assert_eq!(
+            ElGamalSecretKey::from_bytes(&[0xff; 32]).unwrap_err(),
+            SignatureError::NonCanonicalScalar
+        );
+    }
+
+    #[test]
+    fn homomorphic() {
+        let key = secret();
+        let (a, b) = (encrypt(30, 11), encrypt(12, 22));
+        assert_eq!(a + b, encrypt(42, 33));
+        assert_eq!(key.decrypt(&(a - b), 100), Some(18));
+        assert_eq!(key.decrypt(&(a * Scalar::from(3u64)), 100), Some(90));
+
+        // a tally of 0/1 votes
+        let votes = [1, 0, 1, 1, 0, 1, 1];
+        let tally: Ciphertext = votes
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| encrypt(v, 100 + i as u64))
+            .sum();
+        assert_eq!(key.decrypt(&tally, votes.len() as u64), Some(5));
+        assert_eq!(key.decrypt(&Ciphertext::ZERO, 0), Some(0));
+    }
+
+    #[test]
+    fn rerandomize() {
+        let key = secret();
+        let c = encrypt(7, 3);
+        let d = key
+            .public()
+            .rerandomize_with_randomness(&c, &Scalar::from(4u64));
+        assert_ne!(c, d);
+        assert_eq!(d, encrypt(7, 7));
+        assert_eq!(key.decrypt(&d, 10), Some(7));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_keys_and_randomness() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let key = ElGamalSecretKey::random(&mut rng);
+        let c = key.public().encrypt(&Scalar::from(12u64), &mut rng);
+        let d = key.public().rerandomize(&c, &mut rng);
+        assert_ne!(c, d);
+        assert_eq!(key.decrypt(&c, 20), Some(12));
+        assert_eq!(key.decrypt(&d, 20), Some(12));
+    }
+}
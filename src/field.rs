@@ -1,8 +1,73 @@
-#[derive(Debug, Clone)]
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use crate::limbs::{self, Modulus};
+
+// Limbs are kept in i64 and are only brought back into 16 bits by carry,
+// so they can grow between carries. These are the largest magnitudes (in
+// bits) each operation can take without its intermediate sums overflowing:
+//
+// mul takes |limb| < 2^26, so each product of two limbs is below 2^52.
+// reduce_wide adds 38 times the high column i into the low column i, so
+// column i sums i + 1 + 38 * (15 - i) products, at most 571 (column 0),
+// and every column handed to carry is below 571 * 2^52 < 2^62.
+// carry takes |limb| < 2^62: a limb's carry is then below 2^46, so the
+// next limb, which gains it before its own carry is taken, still fits,
+// and the top carry times 38, added into limb 0, is below 2^52. The 38
+// multiplies the carry, not a limb, so it costs no headroom.
+// add and sub only need the sum of two limbs to fit.
+const MUL_LIMB_BITS: u32 = 26;
+const CARRY_LIMB_BITS: u32 = 62;
+const ADD_LIMB_BITS: u32 = 62;
+
+// Checked only in debug builds: panics if any limb has grown past 2^bits,
+// which means the caller skipped a carry somewhere and the next operation
+// would silently wrap around.
+fn debug_assert_limbs(limbs: &[i64; 16], bits: u32, op: &str) {
+    debug_assert!(
+        limbs.iter().all(|l| l.unsigned_abs() < 1 << bits),
+        "{op}: limb exceeds 2^{bits}, carry before calling {op}: {limbs:?}"
+    );
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct Field25519Element<T, const SIZE: usize> {
     items: [T; SIZE],
 }
 
+// The unpacked form that all the arithmetic is done on.
+pub type FieldElement = Field25519Element<i64, 16>;
+
+// p = 2^255 - 19 in 16-bit limbs. 2^256 = 2 * 19 = 38 mod p, so anything
+// that overflows the 16 limbs is multiplied by 38 and added back at the
+// bottom.
+pub(crate) struct P25519;
+
+impl Modulus<16> for P25519 {
+    const LIMB_BITS: u32 = 16;
+    // except for the first 16 and last 16 bits all the bits of p are 1
+    const MODULUS: [i64; 16] = [
+        0xffed, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff,
+        0xffff, 0xffff, 0xffff, 0xffff, 0x7fff,
+    ];
+
+    fn fold_carry(limbs: &mut [i64; 16], carry: i64) {
+        limbs[0] += 38 * carry;
+    }
+
+    // The product of two 16-limb numbers has 31 limbs, limb i + 16 being
+    // worth 2^256 * 2^(16 * i) = 38 * 2^(16 * i), so it is folded into
+    // limb i. Two carries bring the limbs back to (about) 16 bits.
+    fn reduce_wide(mut lo: [i64; 16], hi: [i64; 16]) -> [i64; 16] {
+        for i in 0..15 {
+            lo[i] += 38 * hi[i];
+        }
+        debug_assert_limbs(&lo, CARRY_LIMB_BITS, "carry");
+        limbs::carry::<P25519, 16>(&mut lo);
+        limbs::carry::<P25519, 16>(&mut lo);
+        lo
+    }
+}
+
 impl<T: Default + Copy, const SIZE: usize> Default for Field25519Element<T, SIZE> {
     fn default() -> Self {
         Self {
@@ -11,6 +76,34 @@ impl<T: Default + Copy, const SIZE: usize> Default for Field25519Element<T, SIZE
     }
 }
 
+impl<T, const SIZE: usize> std::ops::Index<usize> for Field25519Element<T, SIZE> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.items[index]
+    }
+}
+
+impl PartialEq for Field25519Element<u8, 32> {
+    fn eq(&self, other: &Self) -> bool {
+        bytes_eq(&self.items, &other.items)
+    }
+}
+
+impl Eq for Field25519Element<u8, 32> {}
+
+// The same number can be spread over the limbs in many ways (e.g. a limb
+// holding 2^16 instead of a carry into the next limb, or a value that is
+// off by a multiple of p), so comparing the limbs directly is wrong.
+// Both sides are packed first, which fully reduces them mod p.
+impl PartialEq for Field25519Element<i64, 16> {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for Field25519Element<i64, 16> {}
+
 impl Field25519Element<u8, 32> {
     pub fn new(items: [u8; 32]) -> Self {
         Self { items }
@@ -24,50 +117,73 @@ impl Field25519Element<u8, 32> {
     // We could have used u16 instead of i64 theorectically, i64 prevents
     // any possible overflow/underflow.
     pub fn unpack(&self) -> Field25519Element<i64, 16> {
-        let mut unpacked = Field25519Element::default();
-        self.items.chunks(2).enumerate().for_each(|(i, chunk)| {
-            unpacked.items[i] = ((chunk[1] as i64) << 8) + chunk[0] as i64;
-        });
-        unpacked.items[15] = unpacked.items[15] & 0x7fff;
+        let mut unpacked = Field25519Element {
+            items: limbs::from_bytes::<P25519, 16>(&self.items),
+        };
+        unpacked.items[15] &= 0x7fff;
         unpacked
     }
 }
 
 impl Field25519Element<i64, 16> {
-    pub fn add(&mut self, other: &Self) -> &mut Self {
-        for i in 0..16 {
-            self.items[i] = self.items[i] + other.items[i];
+    pub const ZERO: Self = Self { items: [0; 16] };
+    pub const ONE: Self = Self {
+        items: [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    };
+    // sqrt(-1) = 2^((p-1)/4)
+    #[rustfmt::skip]
+    pub const SQRT_M1: Self = Self {
+        items: [
+            0xa0b0, 0x4a0e, 0x1b27, 0xc4ee, 0xe478, 0xad2f, 0x1806, 0x2f43, 0xd7a7, 0x3dfb,
+            0x0099, 0x2b4d, 0xdf0b, 0x4fc1, 0x2480, 0x2b83,
+        ],
+    };
+
+    fn debug_assert_limbs(&self, bits: u32, op: &str) {
+        debug_assert_limbs(&self.items, bits, op);
+    }
+
+    // Unchecked version of from_limbs usable in constants. The limbs must
+    // already be carried.
+    pub(crate) const fn from_limbs_unchecked(limbs: [i64; 16]) -> Self {
+        Self { items: limbs }
+    }
+
+    // Builds an element directly from its limbs, least significant first.
+    // Each limb is weighted by 2^(16 * i) and may be negative or exceed
+    // 16 bits, but limbs too large for mul are rejected (see MUL_LIMB_BITS)
+    // so any element built here is safe to pass to every operation.
+    pub fn from_limbs(limbs: [i64; 16]) -> Option<Self> {
+        if limbs.iter().all(|l| l.unsigned_abs() < 1 << MUL_LIMB_BITS) {
+            Some(Self { items: limbs })
+        } else {
+            None
         }
+    }
+
+    // Read-only view of the limbs as they are, without carrying or reducing.
+    pub fn limbs(&self) -> &[i64; 16] {
+        &self.items
+    }
 
+    pub fn add(&mut self, other: &Self) -> &mut Self {
+        self.debug_assert_limbs(ADD_LIMB_BITS, "add");
+        other.debug_assert_limbs(ADD_LIMB_BITS, "add");
+        limbs::add(&mut self.items, &other.items);
         self
     }
 
     pub fn sub(&mut self, other: &Self) -> &mut Self {
-        for i in 0..16 {
-            self.items[i] = self.items[i] - other.items[i];
-        }
-
+        self.debug_assert_limbs(ADD_LIMB_BITS, "sub");
+        other.debug_assert_limbs(ADD_LIMB_BITS, "sub");
+        limbs::sub(&mut self.items, &other.items);
         self
     }
 
     pub fn mul(&mut self, other: &Self) -> &mut Self {
-        let mut product = [0; 32];
-        for i in 0..16 {
-            for j in 0..16 {
-                product[i + j] += self.items[i] * other.items[j];
-            }
-        }
-
-        for i in 0..15 {
-            product[i] += 38 * product[i + 16];
-        }
-
-        for i in 0..16 {
-            self.items[i] = product[i];
-        }
-
-        self.carry();
-        self.carry();
+        self.debug_assert_limbs(MUL_LIMB_BITS, "mul");
+        other.debug_assert_limbs(MUL_LIMB_BITS, "mul");
+        self.items = limbs::mul::<P25519, 16>(&self.items, &other.items);
         self
     }
 
@@ -87,8 +203,7 @@ impl Field25519Element<i64, 16> {
     // start at bit 253 and save one iteration by initialising
     // the result to in instead of 1.
     pub fn inverse(&mut self) -> &mut Self {
-        let initial = self.clone();
-        // let mut result = self.clone();
+        let initial = *self;
         for i in (0..=253).rev() {
             self.mul(&self.clone());
             if i != 2 && i != 4 {
@@ -102,12 +217,7 @@ impl Field25519Element<i64, 16> {
     // If b is 1 and bits in p and q differ, swap the bits in p and q.
     // If b is 0, do nothing. If the bits are the same, do nothing.
     pub fn swap(&mut self, other: &mut Self, b: i64) {
-        let c = !(b - 1);
-        for i in 0..16 {
-            let t = c & (self.items[i] ^ other.items[i]);
-            self.items[i] ^= t;
-            other.items[i] ^= t;
-        }
+        limbs::swap(&mut self.items, &mut other.items, b);
     }
 
     // Inspect the field element by examining each element in the array.
@@ -116,54 +226,422 @@ impl Field25519Element<i64, 16> {
     // and added to the next element. If the current element is the last element,
     // the carry is multiplied by 38 (19 * 2) and added to the first element.
     pub fn carry(&mut self) {
-        for i in 0..16 {
-            // 1. divide by 2^16
-            let carry = self.items[i] >> 16;
-            // 2. multiply by 2^16 and subtract
-            self.items[i] -= carry << 16;
-            if i < 15 {
-                self.items[i + 1] += carry;
-            } else {
-                self.items[0] += 38 * carry;
-            }
-        }
+        self.debug_assert_limbs(CARRY_LIMB_BITS, "carry");
+        limbs::carry::<P25519, 16>(&mut self.items);
     }
 
+    // Three carries leave every limb in [0, 2^16). Subtracting p twice,
+    // each time only if the value is at least p, then gives the canonical
+    // representative, which is split into bytes.
     pub fn pack(&mut self) -> Field25519Element<u8, 32> {
-        let mut temp = Field25519Element::default();
         self.carry();
         self.carry();
         self.carry();
         for _ in 0..2 {
-            // except for the first 16 and last 16 bits all the bits are 1
-            // 0xffed are the least significant 16 bits of 2^255-19.
-            // subtract them from first item of the field element array
-            temp.items[0] = self.items[0] - 0xffed;
-            for i in 1..15 {
-                // subtract 0xffff from intermediate items and also check if there is a carry
-                // by checking if i-1th item exceeds 2^16, if it does subtract the carry bits too
-                // and adjust the ith item accordingly by taking mod 2^16
-                temp.items[i] = self.items[i] - 0xffff - ((temp.items[i - 1] >> 16) & 1);
-                temp.items[i - 1] &= 0xffff;
+            limbs::subtract_modulus::<P25519, 16>(&mut self.items);
+        }
+
+        Field25519Element::new(limbs::to_bytes::<P25519, 16>(&self.items))
+    }
+
+    // Little-endian encoding of the fully reduced element. Same as pack
+    // but leaves self untouched.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut copy = *self;
+        copy.pack().items
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Field25519Element::new(bytes).unpack()
+    }
+
+    // Computes self^((p-5)/8) = self^(2^252-3), the exponent used to take
+    // square roots since p = 5 mod 8. Works just like inverse: all bits of
+    // 2^252-3 are 1 except for bit 1, so we square for each bit from the
+    // top and multiply by the input for every bit that is set.
+    pub fn pow22523(&mut self) -> &mut Self {
+        let initial = *self;
+        for i in (0..=250).rev() {
+            self.mul(&self.clone());
+            if i != 1 {
+                self.mul(&initial);
+            }
+        }
+
+        self
+    }
+
+    // An element is "negative" if the least significant bit of its
+    // canonical encoding is set. This is the sign bit used by point
+    // compression.
+    pub fn is_negative(&self) -> bool {
+        self.to_bytes()[0] & 1 == 1
+    }
+
+    // Computes the square root of u/v without an inversion, returning
+    // (true, +sqrt(u/v)) when u/v is a square. If u/v is not a square the
+    // result is (false, +sqrt(i * u/v)) where i = sqrt(-1). For u = 0 the
+    // result is (true, 0) and for v = 0, u != 0 it is (false, 0).
+    // The returned root is always the non-negative one.
+    //
+    // Since p = 5 mod 8, a candidate root of u/v is
+    //   r = (u/v)^((p+3)/8) = u * v^3 * (u * v^7)^((p-5)/8)
+    // and v * r^2 is then one of u, -u, i*u or -i*u. If it is -u (or
+    // -i*u), multiplying r by i fixes the sign.
+    pub fn sqrt_ratio_i(u: &Self, v: &Self) -> (bool, Self) {
+        let v3 = v * v * v;
+        let v7 = v3 * v3 * v;
+        let mut r = u * v7;
+        r.pow22523();
+        r = r * u * v3;
+
+        let check = v * r * r;
+        let neg_u = -u;
+        let correct_sign = check.ct_eq(u);
+        let flipped_sign = check.ct_eq(&neg_u);
+        let flipped_sign_i = check.ct_eq(&(neg_u * Self::SQRT_M1));
+
+        let r_prime = Self::SQRT_M1 * r;
+        r.conditional_assign(&r_prime, flipped_sign | flipped_sign_i);
+
+        let r_is_negative = Choice::from(r.is_negative() as u8);
+        r.conditional_assign(&-r, r_is_negative);
+
+        ((correct_sign | flipped_sign).into(), r)
+    }
+
+    // Reduces the element and compares its canonical encoding against
+    // that of 0. The bytes are folded together with OR, so no branch or
+    // early exit depends on the value being checked.
+    pub fn is_zero(&self) -> bool {
+        bytes_eq(&self.to_bytes(), &[0; 32])
+    }
+
+    // Same as is_zero, but against the canonical encoding of 1.
+    pub fn is_one(&self) -> bool {
+        bytes_eq(&self.to_bytes(), &Self::ONE.to_bytes())
+    }
+
+    // Big-endian counterpart of unpack. Many test vectors and other
+    // libraries write field elements most-significant byte first, so we
+    // reverse the bytes and hand them to the little-endian unpack.
+    pub fn from_bytes_be(bytes: [u8; 32]) -> Self {
+        let mut items = bytes;
+        items.reverse();
+        Field25519Element::new(items).unpack()
+    }
+
+    // Big-endian counterpart of pack. The element is fully reduced first,
+    // so the output is the canonical encoding with the bytes reversed.
+    pub fn to_bytes_be(&self) -> [u8; 32] {
+        let mut items = self.to_bytes();
+        items.reverse();
+        items
+    }
+}
+
+impl ConstantTimeEq for Field25519Element<i64, 16> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.to_bytes().ct_eq(&other.to_bytes())
+    }
+}
+
+impl ConditionallySelectable for Field25519Element<i64, 16> {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        let mut result = *a;
+        let mut other = *b;
+        result.swap(&mut other, choice.unwrap_u8() as i64);
+        result
+    }
+}
+
+// Operator versions of add, sub and mul. Unlike the in-place methods
+// these carry after add and sub, so arbitrarily long chains of operators
+// keep the limbs within the bounds mul expects.
+macro_rules! impl_field_op {
+    ($op:ident, $op_fn:ident, $assign:ident, $assign_fn:ident, $inherent:ident, $carry:expr) => {
+        impl<'a> std::ops::$assign<&'a FieldElement> for FieldElement {
+            fn $assign_fn(&mut self, rhs: &'a FieldElement) {
+                FieldElement::$inherent(self, rhs);
+                if $carry {
+                    self.carry();
+                }
+            }
+        }
+
+        impl std::ops::$assign<FieldElement> for FieldElement {
+            fn $assign_fn(&mut self, rhs: FieldElement) {
+                std::ops::$assign::$assign_fn(self, &rhs);
+            }
+        }
+
+        impl<'a, 'b> std::ops::$op<&'b FieldElement> for &'a FieldElement {
+            type Output = FieldElement;
+
+            fn $op_fn(self, rhs: &'b FieldElement) -> FieldElement {
+                let mut result = *self;
+                std::ops::$assign::$assign_fn(&mut result, rhs);
+                result
+            }
+        }
+
+        impl<'b> std::ops::$op<&'b FieldElement> for FieldElement {
+            type Output = FieldElement;
+
+            fn $op_fn(self, rhs: &'b FieldElement) -> FieldElement {
+                std::ops::$op::$op_fn(&self, rhs)
+            }
+        }
+
+        impl<'a> std::ops::$op<FieldElement> for &'a FieldElement {
+            type Output = FieldElement;
+
+            fn $op_fn(self, rhs: FieldElement) -> FieldElement {
+                std::ops::$op::$op_fn(self, &rhs)
             }
-            // 0x7fff are the most significant 16 bits of 2^255-19, subtract them from the last item
-            // of the field element array and also check if there is a carry by checking if the
-            // 14th item exceeds 2^16, if it does subtract the carry bits too and adjust the 15th
-            temp.items[15] = self.items[15] - 0x7fff - ((temp.items[14] >> 16) & 1);
-            let carry = (temp.items[15] >> 16) & 1;
-            temp.items[14] &= 0xffff;
-            self.swap(&mut temp, 1 - carry);
         }
 
-        let mut result = Field25519Element::default();
-        for i in 0..16 {
-            result.items[2 * i] = (self.items[i] & 0xff) as u8;
-            result.items[(2 * i) + 1] = (self.items[i] >> 8) as u8;
+        impl std::ops::$op<FieldElement> for FieldElement {
+            type Output = FieldElement;
+
+            fn $op_fn(self, rhs: FieldElement) -> FieldElement {
+                std::ops::$op::$op_fn(&self, &rhs)
+            }
+        }
+    };
+}
+
+impl_field_op!(Add, add, AddAssign, add_assign, add, true);
+impl_field_op!(Sub, sub, SubAssign, sub_assign, sub, true);
+impl_field_op!(Mul, mul, MulAssign, mul_assign, mul, false);
+
+impl std::ops::Neg for &FieldElement {
+    type Output = FieldElement;
+
+    fn neg(self) -> FieldElement {
+        FieldElement::ZERO - self
+    }
+}
+
+impl std::ops::Neg for FieldElement {
+    type Output = FieldElement;
+
+    fn neg(self) -> FieldElement {
+        -&self
+    }
+}
+
+impl std::iter::Sum for FieldElement {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(FieldElement::ZERO, |acc, x| acc + x)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a FieldElement> for FieldElement {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(FieldElement::ZERO, |acc, x| acc + x)
+    }
+}
+
+impl std::iter::Product for FieldElement {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(FieldElement::ONE, |acc, x| acc * x)
+    }
+}
+
+impl<'a> std::iter::Product<&'a FieldElement> for FieldElement {
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(FieldElement::ONE, |acc, x| acc * x)
+    }
+}
+
+impl From<u64> for FieldElement {
+    fn from(value: u64) -> Self {
+        let mut result = FieldElement::ZERO;
+        for i in 0..4 {
+            result.items[i] = ((value >> (16 * i)) & 0xffff) as i64;
         }
         result
     }
 }
 
+// Compares two byte arrays without branching on their contents.
+// All differing bits are accumulated into d, which is 0 only if the
+// arrays are equal. (d - 1) >> 8 is then 0x..ff when d == 0 and 0
+// otherwise, so the lowest bit is the answer.
+fn bytes_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    let d = a.iter().zip(b.iter()).fold(0u8, |d, (x, y)| d | (x ^ y));
+    ((d as u16).wrapping_sub(1) >> 8) & 1 == 1
+}
+
+// Implementations of the RustCrypto ff traits, so the field can be used
+// from generic code written against ff::Field / ff::PrimeField.
+#[cfg(feature = "ff")]
+mod ff_impl {
+    use super::*;
+    use ff::{Field, PrimeField};
+    use rand_core::RngCore;
+    use subtle::CtOption;
+
+    impl Field for FieldElement {
+        const ZERO: Self = FieldElement::ZERO;
+        const ONE: Self = FieldElement::ONE;
+
+        // 255 random bits interpreted as an integer and reduced. Values
+        // in [p, 2^255) wrap around to [0, 19), a bias of 19 / 2^255.
+        fn random(mut rng: impl RngCore) -> Self {
+            let mut bytes = [0; 32];
+            rng.fill_bytes(&mut bytes);
+            FieldElement::from_bytes(bytes)
+        }
+
+        fn is_zero(&self) -> Choice {
+            self.ct_eq(&FieldElement::ZERO)
+        }
+
+        fn square(&self) -> Self {
+            self * self
+        }
+
+        fn double(&self) -> Self {
+            self + self
+        }
+
+        fn invert(&self) -> CtOption<Self> {
+            let mut inverse = *self;
+            inverse.inverse();
+            CtOption::new(inverse, !Field::is_zero(self))
+        }
+
+        // sqrt_ratio_i already follows ff's conventions: for a non-square
+        // it returns sqrt(i * num/div), and i is ROOT_OF_UNITY below.
+        fn sqrt_ratio(num: &Self, div: &Self) -> (Choice, Self) {
+            let (was_square, root) = FieldElement::sqrt_ratio_i(num, div);
+            (Choice::from(was_square as u8), root)
+        }
+    }
+
+    impl PrimeField for FieldElement {
+        type Repr = [u8; 32];
+
+        // Only canonical encodings (less than p, top bit clear) are
+        // accepted: unpacking and packing again must give the same bytes.
+        fn from_repr(repr: [u8; 32]) -> CtOption<Self> {
+            let element = FieldElement::from_bytes(repr);
+            let canonical = element.to_bytes().ct_eq(&repr);
+            CtOption::new(element, canonical)
+        }
+
+        fn to_repr(&self) -> [u8; 32] {
+            self.to_bytes()
+        }
+
+        fn is_odd(&self) -> Choice {
+            Choice::from(self.to_bytes()[0] & 1)
+        }
+
+        const MODULUS: &'static str =
+            "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed";
+        const NUM_BITS: u32 = 255;
+        const CAPACITY: u32 = 254;
+        // (p + 1) / 2
+        const TWO_INV: Self = Field25519Element {
+            items: [
+                0xfff7, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff,
+                0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0x3fff,
+            ],
+        };
+        const MULTIPLICATIVE_GENERATOR: Self = Field25519Element {
+            items: [2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        };
+        // p - 1 = 2^2 * t with t odd
+        const S: u32 = 2;
+        // 2^t, which is sqrt(-1)
+        const ROOT_OF_UNITY: Self = FieldElement::SQRT_M1;
+        // -sqrt(-1)
+        const ROOT_OF_UNITY_INV: Self = Field25519Element {
+            items: [
+                0x5f3d, 0xb5f1, 0xe4d8, 0x3b11, 0x1b87, 0x52d0, 0xe7f9, 0xd0bc, 0x2858, 0xc204,
+                0xff66, 0xd4b2, 0x20f4, 0xb03e, 0xdb7f, 0x547c,
+            ],
+        };
+        // 2^(2^S)
+        const DELTA: Self = Field25519Element {
+            items: [16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        };
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use proptest::prelude::*;
+
+        #[test]
+        fn constants() {
+            assert_eq!(FieldElement::TWO_INV.double(), FieldElement::ONE);
+            assert_eq!(
+                FieldElement::ROOT_OF_UNITY * FieldElement::ROOT_OF_UNITY_INV,
+                FieldElement::ONE
+            );
+            assert_eq!(
+                FieldElement::ROOT_OF_UNITY.pow_vartime([1 << FieldElement::S]),
+                FieldElement::ONE
+            );
+            assert_eq!(
+                FieldElement::MULTIPLICATIVE_GENERATOR.pow_vartime([1 << FieldElement::S]),
+                FieldElement::DELTA
+            );
+            // the generator is a non-square
+            assert!(bool::from(
+                FieldElement::MULTIPLICATIVE_GENERATOR.sqrt().is_none()
+            ));
+        }
+
+        #[test]
+        fn repr() {
+            let mut p = [0xff; 32];
+            p[0] = 0xed;
+            p[31] = 0x7f;
+            assert!(bool::from(FieldElement::from_repr(p).is_none()));
+            p[0] = 0xec;
+            assert_eq!(FieldElement::from_repr(p).unwrap(), -FieldElement::ONE);
+            assert!(bool::from(FieldElement::from_repr([0xff; 32]).is_none()));
+            assert_eq!(FieldElement::from_u128(1 << 64).to_repr()[8], 1);
+            assert!(bool::from(FieldElement::from(3).is_odd()));
+        }
+
+        #[test]
+        fn sqrt_ratio_edge_cases() {
+            let (square, root) = FieldElement::sqrt_ratio(&FieldElement::ZERO, &FieldElement::ONE);
+            assert!(bool::from(square) && root == FieldElement::ZERO);
+            let (square, root) = FieldElement::sqrt_ratio(&FieldElement::ONE, &FieldElement::ZERO);
+            assert!(!bool::from(square) && root == FieldElement::ZERO);
+            let two = FieldElement::from(2);
+            let (square, root) = FieldElement::sqrt_ratio(&two, &FieldElement::ONE);
+            assert!(!bool::from(square));
+            assert_eq!(root.square(), two * FieldElement::ROOT_OF_UNITY);
+        }
+
+        proptest! {
+            #[test]
+            fn field_prop(a in any::<[u8; 32]>(), b in any::<[u8; 32]>()) {
+                let a = FieldElement::from_bytes(a);
+                let b = FieldElement::from_bytes(b);
+
+                prop_assert_eq!(a.square() - b.square(), (a + b) * (a - b));
+                prop_assert_eq!([a, b].iter().sum::<FieldElement>(), a + b);
+                prop_assert_eq!([a, b].into_iter().product::<FieldElement>(), a * b);
+                if !a.is_zero() {
+                    prop_assert_eq!(a * a.invert().unwrap(), FieldElement::ONE);
+                }
+                let square = a.square();
+                let root = square.sqrt().unwrap();
+                prop_assert_eq!(root.square(), square);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,7 +683,7 @@ mod tests {
 
             let mut unpacked_a = packed_a.unpack();
             let unpacked_b = packed_b.unpack();
-            let expected = unpacked_a.clone();
+            let expected = unpacked_a;
 
             unpacked_a.add(&unpacked_b);
             unpacked_a.sub(&unpacked_b);
@@ -226,7 +704,7 @@ mod tests {
             let packed_a = Field25519Element { items: a_items };
             let mut unpacked_a = packed_a.unpack();
 
-            let a_before_inverse = unpacked_a.clone();
+            let a_before_inverse = unpacked_a;
             // b is a inverse
             unpacked_a.inverse();
 
@@ -235,9 +713,199 @@ mod tests {
             let packed_a = unpacked_a.pack();
 
             let mut expected = Field25519Element::new([0; 32]);
-            expected.items[0] = 1 as u8;
+            expected.items[0] = 1;
 
             assert_eq!(expected.items, packed_a.items);
         }
     }
+
+    proptest! {
+        #[test]
+        fn bytes_be_prop(items in any::<[u8; 32]>(), l in 0u8..128) {
+            let mut items = items;
+            // force the most significant byte to be less than 128
+            // (see packunpack_prop)
+            items[0] = l;
+            let unpacked = Field25519Element::from_bytes_be(items);
+
+            assert_eq!(unpacked.to_bytes_be(), items);
+
+            // the big-endian encoding is the little-endian one reversed
+            let mut le = items;
+            le.reverse();
+            assert_eq!(unpacked.to_bytes(), le);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "mul: limb exceeds 2^26")]
+    fn mul_rejects_uncarried_limbs() {
+        let mut items = [0xff; 32];
+        items[31] = 0x7f;
+        let one_less_than_p = Field25519Element::new(items).unpack();
+
+        // every addition adds almost 2^16 to each limb and nothing
+        // carries in between, so after 2^11 rounds they no longer fit
+        // in the 26 bits mul relies on
+        let mut acc = one_less_than_p;
+        for _ in 0..2048 {
+            acc.add(&one_less_than_p);
+        }
+        acc.mul(&one_less_than_p);
+    }
+
+    // The largest limbs mul accepts, in both signs: the columns reach
+    // about 2^61 on their way into carry and must neither overflow nor
+    // trip the carry bound. Carrying the inputs first gives the same
+    // values in small limbs, for a product to compare against.
+    #[test]
+    fn mul_at_limb_bound() {
+        let max = (1 << MUL_LIMB_BITS) - 1;
+        let mut alternating = [max; 16];
+        for limb in alternating.iter_mut().skip(1).step_by(2) {
+            *limb = -max;
+        }
+        for (a, b) in [
+            ([max; 16], [max; 16]),
+            ([-max; 16], [max; 16]),
+            ([-max; 16], [-max; 16]),
+            (alternating, [max; 16]),
+        ] {
+            let a = FieldElement::from_limbs(a).unwrap();
+            let b = FieldElement::from_limbs(b).unwrap();
+            let (mut small_a, mut small_b) = (a, b);
+            small_a.carry();
+            small_b.carry();
+
+            let mut product = a;
+            product.mul(&b);
+            let mut expected = small_a;
+            expected.mul(&small_b);
+            assert_eq!(product, expected);
+        }
+    }
+
+    #[test]
+    fn is_zero_is_one() {
+        let mut p = [0xff; 32];
+        p[0] = 0xed;
+        p[31] = 0x7f;
+        // p itself is not canonical, but it is congruent to 0
+        let mut items = p;
+        assert!(Field25519Element::new(items).unpack().is_zero());
+        // and p + 1 is congruent to 1
+        items[0] = 0xee;
+        assert!(Field25519Element::new(items).unpack().is_one());
+
+        let mut one = [0; 32];
+        one[0] = 1;
+        let one = Field25519Element::new(one).unpack();
+        assert!(one.is_one());
+        assert!(!one.is_zero());
+
+        let zero = Field25519Element::default();
+        assert!(zero.is_zero());
+        assert!(!zero.is_one());
+
+        // 1 - 1 = 0 and (1 + 1) - 1 = 1
+        let mut diff = one;
+        diff.sub(&one);
+        assert!(diff.is_zero());
+        let mut two = one;
+        two.add(&one);
+        assert!(!two.is_zero());
+        assert!(!two.is_one());
+        two.sub(&one);
+        assert!(two.is_one());
+    }
+
+    #[test]
+    fn eq_aliased_limbs() {
+        let mut one = [0; 32];
+        one[0] = 1;
+        let one = Field25519Element::new(one).unpack();
+
+        // 2^16 held in the lowest limb vs. carried into the next one
+        let mut a = Field25519Element::<i64, 16>::default();
+        a.items[0] = 1 << 16;
+        let mut b = Field25519Element::<i64, 16>::default();
+        b.items[1] = 1;
+        assert_ne!(a.items, b.items);
+        assert_eq!(a, b);
+
+        // 2^255 wraps around to 19
+        let mut c = Field25519Element::<i64, 16>::default();
+        c.items[15] = 1 << 15;
+        let mut d = Field25519Element::<i64, 16>::default();
+        d.items[0] = 19;
+        assert_eq!(c, d);
+
+        // negative limbs: 2^16 - 1 written as (1, -1)
+        let mut e = Field25519Element::<i64, 16>::default();
+        e.items[0] = -1;
+        e.items[1] = 1;
+        let mut f = Field25519Element::<i64, 16>::default();
+        f.items[0] = 0xffff;
+        assert_eq!(e, f);
+
+        // 0 - 1 is p - 1
+        let mut minus_one = Field25519Element::<i64, 16>::default();
+        minus_one.sub(&one);
+        let mut p_minus_one = [0xff; 32];
+        p_minus_one[0] = 0xec;
+        p_minus_one[31] = 0x7f;
+        assert_eq!(minus_one, Field25519Element::new(p_minus_one).unpack());
+
+        assert_ne!(one, Field25519Element::<i64, 16>::default());
+        assert_ne!(a, d);
+    }
+
+    proptest! {
+        #[test]
+        fn eq_after_addsub_prop(
+            a in any::<[u8; 32]>(),
+            b in any::<[u8; 32]>(),
+            l in 0u8..128,
+            m in 0u8..128
+        ) {
+            let mut a_items = a;
+            a_items[31] = l;
+            let mut b_items = b;
+            b_items[31] = m;
+            let a = Field25519Element::new(a_items).unpack();
+            let b = Field25519Element::new(b_items).unpack();
+
+            // (a + b) * b - b * b has different limbs than a * b
+            let mut lhs = a;
+            lhs.add(&b).mul(&b);
+            let mut bb = b;
+            bb.mul(&b);
+            lhs.sub(&bb);
+            let mut rhs = a;
+            rhs.mul(&b);
+
+            prop_assert_eq!(lhs, rhs);
+        }
+    }
+
+    #[test]
+    fn limbs_roundtrip() {
+        let mut limbs = [0; 16];
+        limbs[0] = -5;
+        limbs[3] = 1 << 20;
+        limbs[15] = 0x7fff;
+        let a = Field25519Element::from_limbs(limbs).unwrap();
+        assert_eq!(a.limbs(), &limbs);
+        assert_eq!(a[3], 1 << 20);
+        assert_eq!(a[0], -5);
+
+        let packed = Field25519Element::new([7; 32]);
+        assert_eq!(packed[31], 7);
+
+        limbs[7] = 1 << MUL_LIMB_BITS;
+        assert!(Field25519Element::from_limbs(limbs).is_none());
+        limbs[7] = -(1 << MUL_LIMB_BITS);
+        assert!(Field25519Element::from_limbs(limbs).is_none());
+    }
 }
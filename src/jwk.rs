@@ -0,0 +1,211 @@
+use std::fmt;
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+#[cfg(feature = "signing")]
+use zeroize::Zeroizing;
+
+use crate::base64;
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, SignatureError};
+
+// Ed25519 keys as JSON Web Keys (RFC 8037), for JOSE-based systems:
+//
+//   {"kty":"OKP","crv":"Ed25519","x":"<A>"}
+//
+// for a public key, with "d":"<seed>" added for a private one, both in
+// unpadded base64url. Reading accepts and ignores other members ("kid",
+// "use" and so on); "kty" and "crv" must name Ed25519, and a private key
+// must still carry "x", which has to match "d".
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JwkError {
+    // JSON that isn't an Ed25519 JWK: not an object, or a required member
+    // missing or of the wrong size.
+    Malformed,
+    // A JWK for another key type or curve.
+    UnsupportedAlgorithm,
+    // A member that isn't unpadded base64url.
+    InvalidBase64,
+    // An "x" that isn't a point, or that doesn't match "d" (KeyMismatch).
+    Signature(SignatureError),
+}
+
+impl fmt::Display for JwkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwkError::Malformed => f.write_str("malformed JWK"),
+            JwkError::UnsupportedAlgorithm => f.write_str("JWK is not an Ed25519 key"),
+            JwkError::InvalidBase64 => f.write_str("invalid base64url in JWK"),
+            JwkError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for JwkError {}
+
+impl From<SignatureError> for JwkError {
+    fn from(e: SignatureError) -> Self {
+        JwkError::Signature(e)
+    }
+}
+
+// The members every Ed25519 JWK has, in the order written.
+fn public_members(key: &PublicKey) -> String {
+    format!(
+        r#""kty":"OKP","crv":"Ed25519","x":"{}""#,
+        base64::encode_url(key.as_bytes())
+    )
+}
+
+pub fn encode_public_key(key: &PublicKey) -> String {
+    format!("{{{}}}", public_members(key))
+}
+
+pub fn decode_public_key(json: &str) -> Result<PublicKey, JwkError> {
+    let jwk: Value = serde_json::from_str(json).map_err(|_| JwkError::Malformed)?;
+    read_public_key(&jwk)
+}
+
+// A private JWK. The JSON holds the seed, so it is wiped when dropped.
+#[cfg(feature = "signing")]
+pub fn encode_keypair(keypair: &Keypair) -> Zeroizing<String> {
+    let seed = Zeroizing::new(keypair.secret().to_bytes());
+    let d = Zeroizing::new(base64::encode_url(&seed[..]));
+    Zeroizing::new(format!(
+        r#"{{{},"d":"{}"}}"#,
+        public_members(keypair.public()),
+        d.as_str()
+    ))
+}
+
+#[cfg(feature = "signing")]
+pub fn decode_keypair(json: &str) -> Result<Keypair, JwkError> {
+    let jwk: Value = serde_json::from_str(json).map_err(|_| JwkError::Malformed)?;
+    let public = read_public_key(&jwk)?;
+    let d = jwk["d"].as_str().ok_or(JwkError::Malformed)?;
+    let seed = Zeroizing::new(base64::decode_url(d).ok_or(JwkError::InvalidBase64)?);
+    let seed: [u8; 32] = seed[..].try_into().map_err(|_| JwkError::Malformed)?;
+    let keypair = Keypair::from_seed(seed);
+    if keypair.public() != &public {
+        return Err(SignatureError::KeyMismatch.into());
+    }
+    Ok(keypair)
+}
+
+// The JWK thumbprint (RFC 7638): SHA-256 of the required members in
+// lexicographic order with no whitespace, in base64url. It is a stable
+// name for the key, often used as its "kid".
+pub fn thumbprint(key: &PublicKey) -> String {
+    let canonical = format!(
+        r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#,
+        base64::encode_url(key.as_bytes())
+    );
+    base64::encode_url(&Sha256::digest(canonical))
+}
+
+fn read_public_key(jwk: &Value) -> Result<PublicKey, JwkError> {
+    let kty = jwk["kty"].as_str().ok_or(JwkError::Malformed)?;
+    let crv = jwk["crv"].as_str().ok_or(JwkError::Malformed)?;
+    if kty != "OKP" || crv != "Ed25519" {
+        return Err(JwkError::UnsupportedAlgorithm);
+    }
+    let x = jwk["x"].as_str().ok_or(JwkError::Malformed)?;
+    let x = base64::decode_url(x).ok_or(JwkError::InvalidBase64)?;
+    Ok(PublicKey::from_bytes(
+        x.try_into().map_err(|_| JwkError::Malformed)?,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 8037 appendix A.1 and A.2.
+    const PUBLIC_JWK: &str =
+        r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#;
+    #[cfg(feature = "signing")]
+    const PRIVATE_JWK: &str = r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo","d":"nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A"}"#;
+
+    #[test]
+    fn public_key() {
+        let key = decode_public_key(PUBLIC_JWK).unwrap();
+        // the public key of RFC 8032 test 1
+        assert_eq!(
+            hex::encode(key.as_bytes()),
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+        );
+        assert_eq!(encode_public_key(&key), PUBLIC_JWK);
+
+        // other members and whitespace are fine
+        let with_kid = r#"{ "kid": "k1", "x": "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo",
+            "crv": "Ed25519", "kty": "OKP" }"#;
+        assert_eq!(decode_public_key(with_kid), Ok(key));
+    }
+
+    // RFC 8037 appendix A.3.
+    #[test]
+    fn rfc8037_thumbprint() {
+        let key = decode_public_key(PUBLIC_JWK).unwrap();
+        assert_eq!(
+            thumbprint(&key),
+            "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k"
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn private_key() {
+        let keypair = decode_keypair(PRIVATE_JWK).unwrap();
+        assert_eq!(
+            hex::encode(keypair.secret().to_bytes()),
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
+        );
+        assert_eq!(*encode_keypair(&keypair), PRIVATE_JWK);
+        // a private key is also a public key
+        assert_eq!(
+            decode_public_key(PRIVATE_JWK).as_ref(),
+            Ok(keypair.public())
+        );
+
+        // x from RFC 8032 test 2
+        let mismatched = PRIVATE_JWK.replace(
+            "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo",
+            "PUAXw-hDiVqStwqnTRt-vJyYLM8uxJaMwM1V8Sr0Zgw",
+        );
+        assert!(matches!(
+            decode_keypair(&mismatched),
+            Err(JwkError::Signature(SignatureError::KeyMismatch))
+        ));
+        assert!(matches!(
+            decode_keypair(PUBLIC_JWK),
+            Err(JwkError::Malformed)
+        ));
+    }
+
+    #[test]
+    fn invalid() {
+        let cases = [
+            ("not json", JwkError::Malformed),
+            ("[]", JwkError::Malformed),
+            (r#"{"kty":"OKP","crv":"Ed25519"}"#, JwkError::Malformed),
+            (
+                &PUBLIC_JWK.replace("Ed25519", "X25519"),
+                JwkError::UnsupportedAlgorithm,
+            ),
+            (
+                &PUBLIC_JWK.replace("OKP", "EC"),
+                JwkError::UnsupportedAlgorithm,
+            ),
+            // padded, and in the standard alphabet
+            (&PUBLIC_JWK.replace("URo", "URo="), JwkError::InvalidBase64),
+            (&PUBLIC_JWK.replace('_', "/"), JwkError::InvalidBase64),
+            // too short
+            (&PUBLIC_JWK.replace("11qY", ""), JwkError::Malformed),
+        ];
+        for (json, error) in cases {
+            assert_eq!(decode_public_key(json), Err(error), "{json}");
+        }
+    }
+}
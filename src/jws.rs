@@ -0,0 +1,266 @@
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::base64;
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, Signature, SignatureError};
+
+// Compact JWS (RFC 7515) and JWTs (RFC 7519) signed with Ed25519, which
+// JOSE calls "EdDSA" (RFC 8037). A token is three base64url parts,
+//
+//   header . payload . signature
+//
+// where the header is a JSON object naming the algorithm, and the
+// signature is over the ASCII of the first two parts with their dot. For a
+// JWT the payload is a JSON object of claims.
+//
+// Verification only accepts alg = EdDSA: the algorithm comes from the
+// token, so a verifier that let it choose could be talked into checking an
+// HMAC with the public key as the secret, or "none" at all. Tokens with a
+// "crit" header are refused too, since none of the extensions it could
+// name are implemented.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JwsError {
+    // A token that isn't a compact JWS this crate can check: not three
+    // parts, a header that isn't a JSON object or has "crit", or (for a
+    // JWT) claims that aren't an object or time claims that aren't numbers.
+    Malformed,
+    // A header whose "alg" isn't EdDSA.
+    UnsupportedAlgorithm,
+    // A part that isn't unpadded base64url.
+    InvalidBase64,
+    // A JWT checked before its "nbf" time, or from its "exp" time on.
+    NotValidNow,
+    // A signature of the wrong size, or one that doesn't verify.
+    Signature(SignatureError),
+}
+
+impl fmt::Display for JwsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwsError::Malformed => f.write_str("malformed or unsupported JWS"),
+            JwsError::UnsupportedAlgorithm => f.write_str("JWS algorithm is not EdDSA"),
+            JwsError::InvalidBase64 => f.write_str("invalid base64url in JWS"),
+            JwsError::NotValidNow => f.write_str("JWT is outside its validity period"),
+            JwsError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for JwsError {}
+
+impl From<SignatureError> for JwsError {
+    fn from(e: SignatureError) -> Self {
+        JwsError::Signature(e)
+    }
+}
+
+// The header and payload of a token whose signature checked out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jws {
+    pub header: Map<String, Value>,
+    pub payload: Vec<u8>,
+}
+
+// Signs payload with the given header members, setting "alg" to EdDSA.
+// Members are written in sorted order, so the same inputs always give the
+// same token.
+#[cfg(feature = "signing")]
+pub fn sign(keypair: &Keypair, mut header: Map<String, Value>, payload: &[u8]) -> String {
+    header.insert("alg".to_owned(), Value::from("EdDSA"));
+    let header = serde_json::to_vec(&header).expect("a JSON object always serializes");
+    let signing_input = format!(
+        "{}.{}",
+        base64::encode_url(&header),
+        base64::encode_url(payload)
+    );
+    let signature = keypair.sign(signing_input.as_bytes());
+    format!(
+        "{signing_input}.{}",
+        base64::encode_url(&signature.to_bytes())
+    )
+}
+
+// Checks a compact JWS against public and returns its header and payload.
+pub fn verify(public: &PublicKey, token: &str) -> Result<Jws, JwsError> {
+    let malformed = JwsError::Malformed;
+    let mut parts = token.split('.');
+    let (Some(header_b64), Some(payload_b64), Some(signature_b64), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(malformed);
+    };
+
+    let header = base64::decode_url(header_b64).ok_or(JwsError::InvalidBase64)?;
+    let header: Map<String, Value> = serde_json::from_slice(&header).map_err(|_| malformed)?;
+    if header.get("alg") != Some(&Value::from("EdDSA")) {
+        return Err(JwsError::UnsupportedAlgorithm);
+    }
+    if header.contains_key("crit") {
+        return Err(malformed);
+    }
+    let signature = base64::decode_url(signature_b64).ok_or(JwsError::InvalidBase64)?;
+    let signature = Signature::try_from(&signature[..])?;
+
+    let signing_input = &token[..header_b64.len() + 1 + payload_b64.len()];
+    public.verify(signing_input.as_bytes(), &signature)?;
+    Ok(Jws {
+        header,
+        payload: base64::decode_url(payload_b64).ok_or(JwsError::InvalidBase64)?,
+    })
+}
+
+// A JWT: claims signed with the header {"alg":"EdDSA","typ":"JWT"}.
+#[cfg(feature = "signing")]
+pub fn sign_jwt(keypair: &Keypair, claims: &Map<String, Value>) -> String {
+    let mut header = Map::new();
+    header.insert("typ".to_owned(), Value::from("JWT"));
+    let claims = serde_json::to_vec(claims).expect("a JSON object always serializes");
+    sign(keypair, header, &claims)
+}
+
+// Checks a JWT's signature and its time claims, and returns the claims.
+// now is the current Unix time in seconds; a token with an "exp" claim is
+// rejected from that time on, and one with "nbf" before it. The other
+// registered claims (iss, aud and so on) mean whatever the application
+// says, so checking them is left to the caller.
+pub fn verify_jwt(
+    public: &PublicKey,
+    token: &str,
+    now: u64,
+) -> Result<Map<String, Value>, JwsError> {
+    let jws = verify(public, token)?;
+    let claims: Map<String, Value> =
+        serde_json::from_slice(&jws.payload).map_err(|_| JwsError::Malformed)?;
+    let time = |name| match claims.get(name) {
+        None => Ok(None),
+        Some(value) => value.as_f64().map(Some).ok_or(JwsError::Malformed),
+    };
+    let now = now as f64;
+    if time("exp")?.is_some_and(|exp| now >= exp) || time("nbf")?.is_some_and(|nbf| now < nbf) {
+        return Err(JwsError::NotValidNow);
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(json: &str) -> Map<String, Value> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    // The key from RFC 8037 appendix A.1 (RFC 8032 test 1).
+    fn public() -> PublicKey {
+        PublicKey::from_bytes(
+            hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[cfg(feature = "signing")]
+    fn keypair() -> Keypair {
+        Keypair::from_seed(
+            hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        )
+    }
+
+    // RFC 8037 appendix A.4.
+    const TOKEN: &str = "eyJhbGciOiJFZERTQSJ9.RXhhbXBsZSBvZiBFZDI1NTE5IHNpZ25pbmc.\
+        hgyY0il_MGCjP0JzlnLWG1PPOt7-09PGcvMg3AIbQR6dWbhijcNR4ki4iylGjg5BhVsPt9g7sVvpAr_MuM0KAg";
+
+    #[test]
+    fn rfc8037_verify() {
+        let jws = verify(&public(), TOKEN).unwrap();
+        assert_eq!(jws.header, claims(r#"{"alg":"EdDSA"}"#));
+        assert_eq!(jws.payload, b"Example of Ed25519 signing");
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn rfc8037_sign() {
+        assert_eq!(
+            sign(&keypair(), Map::new(), b"Example of Ed25519 signing"),
+            TOKEN
+        );
+    }
+
+    #[test]
+    fn rejected() {
+        // the signature of another payload
+        let (signing_input, signature) = TOKEN.rsplit_once('.').unwrap();
+        let other = format!("eyJhbGciOiJFZERTQSJ9.b3RoZXI.{signature}");
+        assert_eq!(
+            verify(&public(), &other),
+            Err(JwsError::Signature(SignatureError::VerificationFailed))
+        );
+        // {"alg":"none"} and {"alg":"HS256"}
+        for header in ["eyJhbGciOiJub25lIn0", "eyJhbGciOiJIUzI1NiJ9"] {
+            let token = TOKEN.replacen("eyJhbGciOiJFZERTQSJ9", header, 1);
+            assert_eq!(
+                verify(&public(), &token),
+                Err(JwsError::UnsupportedAlgorithm)
+            );
+        }
+        // {"alg":"EdDSA","crit":["exp"]}
+        let crit = TOKEN.replacen(
+            "eyJhbGciOiJFZERTQSJ9",
+            "eyJhbGciOiJFZERTQSIsImNyaXQiOlsiZXhwIl19",
+            1,
+        );
+        assert_eq!(verify(&public(), &crit), Err(JwsError::Malformed));
+        // two parts, four parts, padding
+        for token in [
+            signing_input.to_owned(),
+            format!("{TOKEN}.x"),
+            format!("{TOKEN}=="),
+        ] {
+            assert!(verify(&public(), &token).is_err(), "{token}");
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn jwt() {
+        let token = sign_jwt(
+            &keypair(),
+            &claims(r#"{"sub":"alice","nbf":1000,"exp":2000}"#),
+        );
+        let (header, _) = token.split_once('.').unwrap();
+        assert_eq!(
+            base64::decode_url(header).unwrap(),
+            br#"{"alg":"EdDSA","typ":"JWT"}"#
+        );
+
+        let verified = verify_jwt(&public(), &token, 1000).unwrap();
+        assert_eq!(verified["sub"], "alice");
+        assert!(verify_jwt(&public(), &token, 1999).is_ok());
+        for now in [999, 2000] {
+            assert_eq!(
+                verify_jwt(&public(), &token, now),
+                Err(JwsError::NotValidNow)
+            );
+        }
+
+        // no time claims; a time claim that isn't a number; not an object
+        let forever = sign_jwt(&keypair(), &claims(r#"{"sub":"alice"}"#));
+        assert!(verify_jwt(&public(), &forever, u64::MAX).is_ok());
+        let bad_exp = sign_jwt(&keypair(), &claims(r#"{"exp":"soon"}"#));
+        assert_eq!(verify_jwt(&public(), &bad_exp, 0), Err(JwsError::Malformed));
+        let not_claims = sign(&keypair(), Map::new(), b"[1, 2]");
+        assert_eq!(
+            verify_jwt(&public(), &not_claims, 0),
+            Err(JwsError::Malformed)
+        );
+    }
+}
@@ -1 +1,82 @@
+// Most of the arithmetic is written with index loops, mirroring the
+// TweetNaCl code it is based on.
+#![allow(clippy::needless_range_loop)]
+
+#[cfg(any(feature = "did", feature = "solana"))]
+mod base58;
+mod base64;
+#[cfg(feature = "bincode")]
+mod bincode;
+#[cfg(feature = "borsh")]
+mod borsh;
+#[cfg(feature = "cbor")]
+mod cbor;
+pub mod compat;
+#[cfg(feature = "did")]
+pub mod did;
+#[cfg(feature = "dkg")]
+pub mod dkg;
+#[cfg(feature = "dnssec")]
+pub mod dnssec;
+pub mod edwards;
+#[cfg(feature = "elgamal")]
+pub mod elgamal;
 pub mod field;
+#[cfg(feature = "jose")]
+pub mod jwk;
+#[cfg(feature = "jose")]
+pub mod jws;
+mod limbs;
+#[cfg(feature = "minisign")]
+pub mod minisign;
+pub mod montgomery;
+#[cfg(feature = "musig")]
+pub mod musig;
+#[cfg(feature = "opaque")]
+pub mod opaque;
+#[cfg(feature = "openpgp")]
+pub mod openpgp;
+#[cfg(feature = "oprf")]
+pub mod oprf;
+#[cfg(feature = "pedersen")]
+pub mod pedersen;
+#[cfg(any(feature = "pkcs8", feature = "ssh"))]
+pub mod pem;
+#[cfg(feature = "pkcs8")]
+pub mod pkcs8;
+#[cfg(feature = "ring")]
+pub mod ring;
+pub mod ristretto;
+pub mod scalar;
+#[cfg(feature = "montgomery-scalar")]
+pub mod scalar_montgomery;
+#[cfg(feature = "shamir")]
+pub mod shamir;
+#[cfg(feature = "signify")]
+pub mod signify;
+pub mod signing;
+#[cfg(feature = "sodium")]
+pub mod sodium;
+#[cfg(feature = "solana")]
+pub mod solana;
+#[cfg(feature = "sr25519")]
+pub mod sr25519;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+#[cfg(feature = "stellar")]
+pub mod stellar;
+#[cfg(feature = "rustls")]
+pub mod tls;
+#[cfg(feature = "transcripts")]
+pub mod transcript;
+#[cfg(feature = "tweetnacl")]
+pub mod tweetnacl;
+#[cfg(feature = "vrf")]
+pub mod vrf;
+#[cfg(feature = "webauthn")]
+pub mod webauthn;
+pub mod x25519;
+#[cfg(feature = "x3dh")]
+pub mod x3dh;
+#[cfg(feature = "x509")]
+pub mod x509;
@@ -0,0 +1,194 @@
+// The arithmetic shared by the two prime fields in this crate, integers
+// mod p = 2^255 - 19 (field.rs) and integers mod L (scalar.rs).
+//
+// Both store a 256-bit number as N signed limbs of LIMB_BITS bits each,
+// least significant first, in i64 so that limbs can grow (or go negative)
+// between carries. Adding, subtracting, multiplying out and carrying work
+// the same way whatever the modulus is; the only modulus-specific parts
+// are how a carry out of the top limb and the upper half of a product are
+// folded back in, which each field provides through Modulus.
+
+pub(crate) trait Modulus<const N: usize> {
+    const LIMB_BITS: u32;
+
+    // The modulus in limbs of LIMB_BITS bits.
+    const MODULUS: [i64; N];
+
+    // Adds carry * 2^(N * LIMB_BITS) back into the limbs, reduced mod m.
+    fn fold_carry(limbs: &mut [i64; N], carry: i64);
+
+    // Reduces lo + hi * 2^(N * LIMB_BITS), e.g. the two halves of a
+    // product from mul_wide, to N limbs congruent to it mod m.
+    fn reduce_wide(lo: [i64; N], hi: [i64; N]) -> [i64; N];
+}
+
+pub(crate) fn add<const N: usize>(a: &mut [i64; N], b: &[i64; N]) {
+    for i in 0..N {
+        a[i] += b[i];
+    }
+}
+
+pub(crate) fn sub<const N: usize>(a: &mut [i64; N], b: &[i64; N]) {
+    for i in 0..N {
+        a[i] -= b[i];
+    }
+}
+
+// Schoolbook multiplication without any reduction. Limb i of a times limb
+// j of b is worth 2^(LIMB_BITS * (i + j)), so it lands in limb i + j of the
+// 2N-limb product, returned as its low and high halves.
+pub(crate) fn mul_wide<const N: usize>(a: &[i64; N], b: &[i64; N]) -> ([i64; N], [i64; N]) {
+    let mut lo = [0; N];
+    let mut hi = [0; N];
+    for i in 0..N {
+        for j in 0..N {
+            if i + j < N {
+                lo[i + j] += a[i] * b[j];
+            } else {
+                hi[i + j - N] += a[i] * b[j];
+            }
+        }
+    }
+    (lo, hi)
+}
+
+pub(crate) fn mul<M: Modulus<N>, const N: usize>(a: &[i64; N], b: &[i64; N]) -> [i64; N] {
+    let (lo, hi) = mul_wide(a, b);
+    M::reduce_wide(lo, hi)
+}
+
+// Goes through the limbs from the bottom, shifting each one right by
+// LIMB_BITS to find its carry. The carry is subtracted from the current
+// limb and added to the next one; the carry out of the top limb is handed
+// to the modulus to fold back in. The shift is arithmetic, so negative
+// limbs borrow from the next limb and end up in [0, 2^LIMB_BITS) too.
+pub(crate) fn carry<M: Modulus<N>, const N: usize>(limbs: &mut [i64; N]) {
+    for i in 0..N {
+        let carry = limbs[i] >> M::LIMB_BITS;
+        limbs[i] -= carry << M::LIMB_BITS;
+        if i < N - 1 {
+            limbs[i + 1] += carry;
+        } else {
+            M::fold_carry(limbs, carry);
+        }
+    }
+}
+
+// If b is 1 swap a and b limb by limb, if b is 0 leave them alone, using
+// masks rather than a branch.
+pub(crate) fn swap<const N: usize>(a: &mut [i64; N], b: &mut [i64; N], bit: i64) {
+    let c = !(bit - 1);
+    for i in 0..N {
+        let t = c & (a[i] ^ b[i]);
+        a[i] ^= t;
+        b[i] ^= t;
+    }
+}
+
+// Takes limbs that have been carried (all in [0, 2^LIMB_BITS)) holding a
+// value below 2m and subtracts m if the value is at least m.
+//
+// m is subtracted limb by limb into a temporary, propagating the borrow
+// (bit LIMB_BITS of a limb that went negative) to the next limb. If the
+// top limb still borrows, the value was below m and we keep it; otherwise
+// we swap in the difference. Either way both are computed, so there is no
+// branch on the value.
+pub(crate) fn subtract_modulus<M: Modulus<N>, const N: usize>(limbs: &mut [i64; N]) {
+    let mask = (1 << M::LIMB_BITS) - 1;
+    let mut difference = [0; N];
+    let mut borrow = 0;
+    for i in 0..N {
+        difference[i] = limbs[i] - M::MODULUS[i] - borrow;
+        borrow = (difference[i] >> M::LIMB_BITS) & 1;
+        difference[i] &= mask;
+    }
+    swap(limbs, &mut difference, 1 - borrow);
+}
+
+// Little-endian bytes of carried limbs; N * LIMB_BITS must be 256.
+pub(crate) fn to_bytes<M: Modulus<N>, const N: usize>(limbs: &[i64; N]) -> [u8; 32] {
+    let bytes_per_limb = M::LIMB_BITS as usize / 8;
+    let mut bytes = [0; 32];
+    for i in 0..N {
+        for k in 0..bytes_per_limb {
+            bytes[i * bytes_per_limb + k] = (limbs[i] >> (8 * k)) as u8;
+        }
+    }
+    bytes
+}
+
+// The inverse of to_bytes, without any reduction.
+pub(crate) fn from_bytes<M: Modulus<N>, const N: usize>(bytes: &[u8; 32]) -> [i64; N] {
+    let bytes_per_limb = M::LIMB_BITS as usize / 8;
+    let mut limbs = [0; N];
+    for i in 0..N {
+        for k in 0..bytes_per_limb {
+            limbs[i] += (bytes[i * bytes_per_limb + k] as i64) << (8 * k);
+        }
+    }
+    limbs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // Integers mod 2^32 - 5 in four 8-bit limbs, small enough to check
+    // against u64 arithmetic. 2^32 = 5 mod m.
+    struct Small;
+
+    impl Modulus<4> for Small {
+        const LIMB_BITS: u32 = 8;
+        const MODULUS: [i64; 4] = [0xfb, 0xff, 0xff, 0xff];
+
+        fn fold_carry(limbs: &mut [i64; 4], carry: i64) {
+            limbs[0] += 5 * carry;
+        }
+
+        fn reduce_wide(mut lo: [i64; 4], hi: [i64; 4]) -> [i64; 4] {
+            for i in 0..4 {
+                lo[i] += 5 * hi[i];
+            }
+            carry::<Small, 4>(&mut lo);
+            carry::<Small, 4>(&mut lo);
+            lo
+        }
+    }
+
+    const M: u64 = (1 << 32) - 5;
+
+    fn canonical(mut limbs: [i64; 4]) -> u64 {
+        carry::<Small, 4>(&mut limbs);
+        carry::<Small, 4>(&mut limbs);
+        subtract_modulus::<Small, 4>(&mut limbs);
+        subtract_modulus::<Small, 4>(&mut limbs);
+        limbs
+            .iter()
+            .enumerate()
+            .map(|(i, &l)| (l as u64) << (8 * i))
+            .sum()
+    }
+
+    fn limbs(x: u32) -> [i64; 4] {
+        [0, 1, 2, 3].map(|i| ((x >> (8 * i)) & 0xff) as i64)
+    }
+
+    proptest! {
+        #[test]
+        fn small_modulus_prop(a in 0..M as u32, b in 0..M as u32) {
+            let (a64, b64) = (a as u64, b as u64);
+
+            let mut sum = limbs(a);
+            add(&mut sum, &limbs(b));
+            prop_assert_eq!(canonical(sum), (a64 + b64) % M);
+
+            let mut difference = limbs(a);
+            sub(&mut difference, &limbs(b));
+            prop_assert_eq!(canonical(difference), (a64 + M - b64) % M);
+
+            let product = mul::<Small, 4>(&limbs(a), &limbs(b));
+            prop_assert_eq!(canonical(product), a64 * b64 % M);
+        }
+    }
+}
@@ -0,0 +1,400 @@
+#[cfg(feature = "signing")]
+use blake2::digest::consts::U32;
+#[cfg(feature = "signing")]
+use blake2::Blake2b;
+use blake2::{Blake2b512, Digest};
+#[cfg(feature = "signing")]
+use zeroize::Zeroizing;
+
+use crate::base64;
+use crate::signify::{read_box, write_box, SignifyError, SignifyPublicKey};
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{Signature, SignatureError};
+
+// minisign's signature and secret key files. minisign grew out of signify
+// (see the signify module) and its public keys are signify's, but a
+// signature file has two more lines:
+//
+//   untrusted comment: <text>
+//   base64(algorithm || keynum || signature)
+//   trusted comment: <text>
+//   base64(global signature)
+//
+// where the global signature is over signature || the trusted comment
+// (without its "trusted comment: " prefix), so the trusted comment, which
+// usually holds a timestamp and the file name, can't be changed without
+// the key. The algorithm is "ED" for the signature of the BLAKE2b-512
+// digest of the file, which minisign has made by default since 0.8, or
+// "Ed" for a legacy signature of the file itself, which is still
+// accepted.
+//
+// A secret key is
+//
+//   "Ed" || kdf || "B2" || salt(32) || opslimit(8) || memlimit(8)
+//        || keynum || seed || A || checksum(32)
+//
+// with the checksum BLAKE2b-256 of "Ed" || keynum || seed || A. kdf is
+// "Sc" when the last three fields are encrypted under a scrypt hash of a
+// passphrase, which isn't implemented here, or two zero bytes for a key
+// made with minisign -W, which is the only kind read or written.
+
+pub type MinisignPublicKey = SignifyPublicKey;
+
+const LEGACY: &[u8; 2] = b"Ed";
+const PREHASHED: &[u8; 2] = b"ED";
+const TRUSTED_COMMENT_PREFIX: &str = "trusted comment: ";
+
+const SIGNATURE_LEN: usize = 2 + 8 + 64;
+#[cfg(feature = "signing")]
+const SECRET_KEY_LEN: usize = 2 + 2 + 2 + 32 + 8 + 8 + 8 + 64 + 32;
+
+// An unencrypted secret key, with its key number.
+#[cfg(feature = "signing")]
+#[derive(Debug, Clone)]
+pub struct MinisignSecretKey {
+    pub key_number: [u8; 8],
+    pub keypair: Keypair,
+}
+
+#[cfg(feature = "signing")]
+impl MinisignSecretKey {
+    pub fn public_key(&self) -> MinisignPublicKey {
+        SignifyPublicKey {
+            key_number: self.key_number,
+            public: *self.keypair.public(),
+        }
+    }
+
+    // Signs message in the prehashed form, with the given trusted comment
+    // (minisign's default is "timestamp:<unix time>\tfile:<name>"). The
+    // comment is one line of the file, so a newline in it is
+    // Malformed.
+    pub fn sign(
+        &self,
+        message: &[u8],
+        trusted_comment: &str,
+    ) -> Result<MinisignSignature, SignifyError> {
+        if trusted_comment.contains(['\n', '\r']) {
+            return Err(SignifyError::Malformed);
+        }
+        let signature = self.keypair.sign(&Blake2b512::digest(message));
+        let global_signature = self
+            .keypair
+            .sign(&global_signed_data(&signature, trusted_comment));
+        Ok(MinisignSignature {
+            key_number: self.key_number,
+            prehashed: true,
+            signature,
+            trusted_comment: trusted_comment.to_owned(),
+            global_signature,
+        })
+    }
+
+    // An encrypted key fails with UnsupportedAlgorithm, and one whose
+    // checksum doesn't match with Malformed.
+    pub fn from_file(text: &str) -> Result<Self, SignifyError> {
+        let (_, bytes, rest) = read_box(text)?;
+        let bytes = Zeroizing::new(bytes);
+        if !rest.trim().is_empty() || bytes.len() != SECRET_KEY_LEN {
+            return Err(SignifyError::Malformed);
+        }
+        if &bytes[..2] != LEGACY || bytes[2..4] != [0; 2] || &bytes[4..6] != b"B2" {
+            return Err(SignifyError::UnsupportedAlgorithm);
+        }
+        let key_number = bytes[54..62].try_into().unwrap();
+        let secret = Zeroizing::new(<[u8; 64]>::try_from(&bytes[62..126]).unwrap());
+        if checksum(&key_number, &secret)[..] != bytes[126..] {
+            return Err(SignifyError::Malformed);
+        }
+        Ok(Self {
+            key_number,
+            keypair: Keypair::from_bytes(&secret)?,
+        })
+    }
+
+    // The scrypt salt and limits mean nothing without encryption, so they
+    // are left zero, as minisign does.
+    pub fn to_file(&self, comment: &str) -> Zeroizing<String> {
+        let secret = Zeroizing::new(self.keypair.to_bytes());
+        let mut bytes = Zeroizing::new(LEGACY.to_vec());
+        bytes.extend_from_slice(&[0; 2]);
+        bytes.extend_from_slice(b"B2");
+        bytes.extend_from_slice(&[0; 32 + 8 + 8]);
+        bytes.extend_from_slice(&self.key_number);
+        bytes.extend_from_slice(&secret[..]);
+        bytes.extend_from_slice(&checksum(&self.key_number, &secret));
+        let line = Zeroizing::new(base64::encode(&bytes, true));
+        Zeroizing::new(write_box(comment, &line))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinisignSignature {
+    key_number: [u8; 8],
+    prehashed: bool,
+    signature: Signature,
+    trusted_comment: String,
+    global_signature: Signature,
+}
+
+impl MinisignSignature {
+    // The number of the key that made the signature.
+    pub fn key_number(&self) -> &[u8; 8] {
+        &self.key_number
+    }
+
+    // Whether the signature is of the BLAKE2b-512 digest of the file
+    // rather than of the file itself.
+    pub fn is_prehashed(&self) -> bool {
+        self.prehashed
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    // Only meaningful once verify has succeeded.
+    pub fn trusted_comment(&self) -> &str {
+        &self.trusted_comment
+    }
+
+    pub fn global_signature(&self) -> &Signature {
+        &self.global_signature
+    }
+
+    // Checks both signatures against key, as minisign -V does. A signature
+    // from a key with a different number fails with KeyMismatch without
+    // any curve arithmetic.
+    pub fn verify(&self, key: &MinisignPublicKey, message: &[u8]) -> Result<(), SignifyError> {
+        if self.key_number != key.key_number {
+            return Err(SignatureError::KeyMismatch.into());
+        }
+        if self.prehashed {
+            key.public
+                .verify(&Blake2b512::digest(message), &self.signature)?;
+        } else {
+            key.public.verify(message, &self.signature)?;
+        }
+        key.public.verify(
+            &global_signed_data(&self.signature, &self.trusted_comment),
+            &self.global_signature,
+        )?;
+        Ok(())
+    }
+
+    pub fn from_file(text: &str) -> Result<Self, SignifyError> {
+        let malformed = SignifyError::Malformed;
+        let (_, bytes, rest) = read_box(text)?;
+        if bytes.len() != SIGNATURE_LEN {
+            return Err(malformed);
+        }
+        let prehashed = match &bytes[..2] {
+            algorithm if algorithm == PREHASHED => true,
+            algorithm if algorithm == LEGACY => false,
+            _ => return Err(SignifyError::UnsupportedAlgorithm),
+        };
+
+        let mut lines = rest.lines();
+        let trusted_comment = lines
+            .next()
+            .and_then(|line| line.strip_prefix(TRUSTED_COMMENT_PREFIX))
+            .ok_or(malformed)?;
+        let global_signature = lines.next().ok_or(malformed)?;
+        let global_signature =
+            base64::decode(global_signature).ok_or(SignifyError::InvalidBase64)?;
+        if lines.any(|line| !line.trim().is_empty()) {
+            return Err(malformed);
+        }
+        Ok(Self {
+            key_number: bytes[2..10].try_into().unwrap(),
+            prehashed,
+            signature: Signature::try_from(&bytes[10..])?,
+            trusted_comment: trusted_comment.to_owned(),
+            global_signature: Signature::try_from(&global_signature[..])?,
+        })
+    }
+
+    // minisign writes "signature from minisign secret key" as the
+    // untrusted comment.
+    pub fn to_file(&self, comment: &str) -> String {
+        let mut bytes = if self.prehashed { PREHASHED } else { LEGACY }.to_vec();
+        bytes.extend_from_slice(&self.key_number);
+        bytes.extend_from_slice(&self.signature.to_bytes());
+        format!(
+            "{}{TRUSTED_COMMENT_PREFIX}{}\n{}\n",
+            write_box(comment, &base64::encode(&bytes, true)),
+            self.trusted_comment,
+            base64::encode(&self.global_signature.to_bytes(), true)
+        )
+    }
+}
+
+// minisign shows a key number as a 64-bit little-endian integer in hex,
+// e.g. in the comment "minisign public key E7620F1842B4E81F".
+pub fn key_id(key_number: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_number))
+}
+
+fn global_signed_data(signature: &Signature, trusted_comment: &str) -> Vec<u8> {
+    let mut data = signature.to_bytes().to_vec();
+    data.extend_from_slice(trusted_comment.as_bytes());
+    data
+}
+
+#[cfg(feature = "signing")]
+fn checksum(key_number: &[u8; 8], secret: &[u8; 64]) -> [u8; 32] {
+    Blake2b::<U32>::new()
+        .chain_update(LEGACY)
+        .chain_update(key_number)
+        .chain_update(secret)
+        .finalize()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The key and signatures of "test" from the minisign-verify crate's
+    // tests.
+    const PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+
+    const LEGACY_SIGNATURE: &str = "untrusted comment: signature from minisign secret key\n\
+        RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=\n\
+        trusted comment: timestamp:1555779966\tfile:test\n\
+        QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==\n";
+
+    const PREHASHED_SIGNATURE: &str = "untrusted comment: signature from minisign secret key\n\
+        RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=\n\
+        trusted comment: timestamp:1556193335\tfile:test\n\
+        y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==\n";
+
+    fn public_key() -> MinisignPublicKey {
+        MinisignPublicKey::from_base64(PUBLIC_KEY).unwrap()
+    }
+
+    #[test]
+    fn verify() {
+        assert_eq!(key_id(&public_key().key_number), "E7620F1842B4E81F");
+
+        for (text, prehashed) in [(LEGACY_SIGNATURE, false), (PREHASHED_SIGNATURE, true)] {
+            let signature = MinisignSignature::from_file(text).unwrap();
+            assert_eq!(signature.is_prehashed(), prehashed);
+            assert_eq!(signature.verify(&public_key(), b"test"), Ok(()));
+            assert_eq!(
+                signature.verify(&public_key(), b"Test"),
+                Err(SignifyError::Signature(SignatureError::VerificationFailed))
+            );
+            assert_eq!(
+                signature.to_file("signature from minisign secret key"),
+                text
+            );
+        }
+        let signature = MinisignSignature::from_file(PREHASHED_SIGNATURE).unwrap();
+        assert_eq!(
+            signature.trusted_comment(),
+            "timestamp:1556193335\tfile:test"
+        );
+    }
+
+    #[test]
+    fn trusted_comment_is_signed() {
+        let forged = PREHASHED_SIGNATURE.replace("file:test", "file:other");
+        let signature = MinisignSignature::from_file(&forged).unwrap();
+        assert_eq!(
+            signature.verify(&public_key(), b"test"),
+            Err(SignifyError::Signature(SignatureError::VerificationFailed))
+        );
+
+        let other = MinisignPublicKey {
+            key_number: [0; 8],
+            ..public_key()
+        };
+        let signature = MinisignSignature::from_file(PREHASHED_SIGNATURE).unwrap();
+        assert_eq!(
+            signature.verify(&other, b"test"),
+            Err(SignifyError::Signature(SignatureError::KeyMismatch))
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let (signature, trusted) =
+            PREHASHED_SIGNATURE.split_at(PREHASHED_SIGNATURE.find("trusted").unwrap());
+        let (_, global) = trusted.split_once('\n').unwrap();
+        let cases = [
+            // no trusted comment or global signature, the prefix changed,
+            // no global signature, something after it
+            (signature.to_owned(), SignifyError::Malformed),
+            (
+                PREHASHED_SIGNATURE.replace("trusted comment: ", "trusted: "),
+                SignifyError::Malformed,
+            ),
+            (
+                PREHASHED_SIGNATURE.replace(global, ""),
+                SignifyError::Malformed,
+            ),
+            (
+                format!("{PREHASHED_SIGNATURE}extra\n"),
+                SignifyError::Malformed,
+            ),
+            (
+                PREHASHED_SIGNATURE.replace("EBg==", "EBg"),
+                SignifyError::InvalidBase64,
+            ),
+            (
+                PREHASHED_SIGNATURE.replace("RUQf", "RVQf"),
+                SignifyError::UnsupportedAlgorithm,
+            ),
+        ];
+        for (text, error) in cases {
+            assert_eq!(MinisignSignature::from_file(&text), Err(error), "{text}");
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn sign() {
+        let secret = MinisignSecretKey {
+            key_number: 0x0123456789abcdefu64.to_le_bytes(),
+            keypair: Keypair::from_seed([3; 32]),
+        };
+        let file = secret.to_file("minisign secret key");
+        let read = MinisignSecretKey::from_file(&file).unwrap();
+        assert_eq!(read.key_number, secret.key_number);
+        assert_eq!(read.keypair.to_bytes(), secret.keypair.to_bytes());
+        assert_eq!(key_id(&read.public_key().key_number), "0123456789ABCDEF");
+
+        let signature = read
+            .sign(
+                b"release.tar.gz contents",
+                "timestamp:1700000000\tfile:release.tar.gz",
+            )
+            .unwrap();
+        let text = signature.to_file("signature from minisign secret key");
+        let signature = MinisignSignature::from_file(&text).unwrap();
+        assert!(signature.is_prehashed());
+        assert_eq!(
+            signature.verify(&secret.public_key(), b"release.tar.gz contents"),
+            Ok(())
+        );
+        assert_eq!(read.sign(b"x", "two\nlines"), Err(SignifyError::Malformed));
+
+        // a corrupted seed; kdf "Sc"
+        let mut bytes = base64::decode(file.lines().nth(1).unwrap()).unwrap();
+        bytes[62] ^= 1;
+        let corrupt = write_box("c", &base64::encode(&bytes, true));
+        assert!(matches!(
+            MinisignSecretKey::from_file(&corrupt),
+            Err(SignifyError::Malformed)
+        ));
+        bytes[62] ^= 1;
+        bytes[2..4].copy_from_slice(b"Sc");
+        let encrypted = write_box("c", &base64::encode(&bytes, true));
+        assert!(matches!(
+            MinisignSecretKey::from_file(&encrypted),
+            Err(SignifyError::UnsupportedAlgorithm)
+        ));
+    }
+}
@@ -0,0 +1,416 @@
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use crate::edwards::{CompressedEdwardsY, EdwardsPoint};
+use crate::field::FieldElement;
+use crate::scalar::{ClampedScalar, Scalar};
+
+// Curve25519 in Montgomery form,
+//
+//     v^2 = u^3 + A u^2 + u,  A = 486662,
+//
+// the same curve as the Edwards one in edwards.rs under a change of
+// variables. Montgomery's trick is that the u-coordinate of k * P can be
+// computed from the u-coordinate of P alone, with a ladder that does the
+// same work for every bit of k. That makes it the natural fit for
+// Diffie-Hellman (X25519, RFC 7748), where only u is ever sent.
+
+const A: u64 = 486662;
+
+// (A - 2) / 4, the constant the ladder's doubling formula needs.
+const A24: u64 = 121665;
+
+// A point given by its u-coordinate, as 32 little-endian bytes. The top bit
+// is ignored when decoding, as RFC 7748 requires, and any 255-bit value is
+// accepted, even one that is not the u of a point on the curve (it is then
+// on the quadratic twist, which the ladder handles just the same).
+#[derive(Debug, Clone, Copy)]
+pub struct MontgomeryPoint(pub [u8; 32]);
+
+// The X25519 basepoint, u = 9.
+pub const X25519_BASEPOINT: MontgomeryPoint = MontgomeryPoint([
+    9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+]);
+
+impl MontgomeryPoint {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    // The inverse of EdwardsPoint::to_montgomery, y = (u - 1) / (u + 1).
+    // u only determines a point up to sign, so the caller says which x to
+    // take with sign (0 or 1, like the top bit of an Edwards encoding).
+    // None if u = -1, which has no image, or if u is on the twist rather
+    // than the curve.
+    pub fn to_edwards(&self, sign: u8) -> Option<EdwardsPoint> {
+        let u = FieldElement::from_bytes(self.0);
+        if u == -FieldElement::ONE {
+            return None;
+        }
+
+        let mut denominator = u + FieldElement::ONE;
+        denominator.inverse();
+        let mut y = ((u - FieldElement::ONE) * denominator).to_bytes();
+        y[31] ^= (sign & 1) << 7;
+        CompressedEdwardsY(y).decompress()
+    }
+
+    // Elligator2 (Bernstein, Hamburg, Krasnova, Lange), which maps any field
+    // element r to a point:
+    //
+    //   w = -A / (1 + 2 r^2),  u = w if w^3 + A w^2 + w is a square,
+    //                          u = -w - A otherwise.
+    //
+    // Exactly one of the two candidates is the u of a curve point, so this
+    // always succeeds. r and -r give the same point, so only r
+    // in [0, (p - 1) / 2], below 2^254, is used as the representative,
+    // and the top two bits of the 32 bytes are ignored here. Callers
+    // sending representatives should fill those bits randomly, otherwise
+    // they are always zero and give the game away.
+    //
+    // Runs in constant time, so it can also map secret values to points
+    // (e.g. as the map_to_curve step of hash-to-curve).
+    pub fn from_representative(representative: &[u8; 32]) -> MontgomeryPoint {
+        let mut bytes = *representative;
+        bytes[31] &= 0x3f;
+        let r = FieldElement::from_bytes(bytes);
+        let a = FieldElement::from(A);
+
+        let mut denominator = FieldElement::ONE + FieldElement::from(2) * r * r;
+        denominator.inverse();
+        let w = -a * denominator;
+        let g = (w * w + a * w + FieldElement::ONE) * w;
+        let (is_square, _) = FieldElement::sqrt_ratio_i(&g, &FieldElement::ONE);
+
+        let u = FieldElement::conditional_select(&(-w - a), &w, Choice::from(is_square as u8));
+        MontgomeryPoint(u.to_bytes())
+    }
+
+    // The inverse of from_representative. Only about half of all points
+    // have a representative: those with u != -A and -2u(u + A) a square.
+    // Key generation for censorship-resistant protocols retries with a new
+    // secret until this returns Some.
+    //
+    // Each such point has two representatives, sqrt(-u / (2(u + A))) and
+    // sqrt(-(u + A) / (2u)). The full map on (u, v) picks between them by
+    // the sign of v; with only u available the caller picks, and choosing
+    // at random (or by the sign of v if it is known) keeps the output
+    // uniform.
+    pub fn to_representative(&self, v_is_negative: bool) -> Option<[u8; 32]> {
+        let u = FieldElement::from_bytes(self.0);
+        let a = FieldElement::from(A);
+        let two = FieldElement::from(2);
+
+        let (first_ok, first) = FieldElement::sqrt_ratio_i(&-u, &(two * (u + a)));
+        let (second_ok, second) = FieldElement::sqrt_ratio_i(&-(u + a), &(two * u));
+        let choice = Choice::from(v_is_negative as u8);
+        let mut r = FieldElement::conditional_select(&first, &second, choice);
+        // of r and -r, take the one at most (p - 1) / 2, which is the one
+        // for which 2r doesn't wrap around p and so stays even
+        let r_is_large = Choice::from((r + r).is_negative() as u8);
+        r.conditional_assign(&-r, r_is_large);
+        let ok = Choice::conditional_select(
+            &Choice::from(first_ok as u8),
+            &Choice::from(second_ok as u8),
+            choice,
+        );
+
+        // u might not be on the curve at all (every 255-bit string is a
+        // valid MontgomeryPoint), so check that r really maps back to it.
+        let representative = r.to_bytes();
+        let maps_back = Self::from_representative(&representative).ct_eq(self);
+        if bool::from(ok & maps_back) {
+            Some(representative)
+        } else {
+            None
+        }
+    }
+
+    // Multiplies by a clamped scalar, which is exactly X25519.
+    pub fn mul_clamped(&self, scalar: &ClampedScalar) -> MontgomeryPoint {
+        self.ladder(scalar.as_bytes(), None)
+    }
+
+    // mul_clamped, also returning the ladder's state after every step, for
+    // following the algorithm bit by bit. The trace contains everything
+    // derived from the secret scalar, so this is for learning and
+    // debugging only.
+    pub fn mul_clamped_traced(&self, scalar: &ClampedScalar) -> (MontgomeryPoint, Vec<LadderStep>) {
+        let mut trace = Vec::with_capacity(255);
+        let result = self.ladder(scalar.as_bytes(), Some(&mut trace));
+        (result, trace)
+    }
+
+    // The Montgomery ladder from RFC 7748, section 5.
+    //
+    // We keep two points, (x2 : z2) = m * P and (x3 : z3) = (m + 1) * P,
+    // for m the bits of k seen so far, starting from m = 0. For each next
+    // bit, m becomes 2m or 2m + 1: if the bit is 0 we double the first
+    // point and add the two to get the second; if it is 1 we do the same
+    // with the roles swapped. Since the two points always differ by P,
+    // their sum can be computed from u(P) alone. Swapping before and after
+    // (with masks, see FieldElement::swap) lets both cases run the same
+    // code, and the swaps are merged so that we only swap when the bit
+    // differs from the previous one.
+    //
+    // Bit 255 is ignored, like in RFC 7748's decodeScalar25519. If trace is
+    // given, the state after each step is pushed onto it.
+    fn ladder(&self, k: &[u8; 32], mut trace: Option<&mut Vec<LadderStep>>) -> MontgomeryPoint {
+        let x1 = FieldElement::from_bytes(self.0);
+        let mut x2 = FieldElement::ONE;
+        let mut z2 = FieldElement::ZERO;
+        let mut x3 = x1;
+        let mut z3 = FieldElement::ONE;
+        let a24 = FieldElement::from(A24);
+
+        let mut swap = 0;
+        for t in (0..255).rev() {
+            let bit = ((k[t / 8] >> (t % 8)) & 1) as i64;
+            swap ^= bit;
+            x2.swap(&mut x3, swap);
+            z2.swap(&mut z3, swap);
+            swap = bit;
+
+            let a = x2 + z2;
+            let aa = a * a;
+            let b = x2 - z2;
+            let bb = b * b;
+            let e = aa - bb;
+            let c = x3 + z3;
+            let d = x3 - z3;
+            let da = d * a;
+            let cb = c * b;
+            x3 = (da + cb) * (da + cb);
+            z3 = x1 * (da - cb) * (da - cb);
+            x2 = aa * bb;
+            z2 = e * (aa + a24 * e);
+
+            if let Some(trace) = trace.as_mut() {
+                trace.push(LadderStep {
+                    bit_index: t,
+                    bit: bit as u8,
+                    swap: swap as u8,
+                    x2,
+                    z2,
+                    x3,
+                    z3,
+                });
+            }
+        }
+        x2.swap(&mut x3, swap);
+        z2.swap(&mut z3, swap);
+
+        // u = x2 / z2; z2 = 0 (the point at infinity) gives u = 0
+        z2.inverse();
+        MontgomeryPoint((x2 * z2).to_bytes())
+    }
+}
+
+// The state of the ladder after processing bit bit_index of the scalar.
+//
+// With m the bits processed so far, the ladder holds m * P and
+// (m + 1) * P as (x2 : z2) and (x3 : z3), except that the swap back is
+// deferred to the next step: when swap is 1 the two are held the other way
+// round. swap is always equal to bit, since the swap before each step is by
+// the XOR of consecutive bits.
+#[derive(Debug, Clone, Copy)]
+pub struct LadderStep {
+    pub bit_index: usize,
+    pub bit: u8,
+    pub swap: u8,
+    pub x2: FieldElement,
+    pub z2: FieldElement,
+    pub x3: FieldElement,
+    pub z3: FieldElement,
+}
+
+impl LadderStep {
+    // The u-coordinate of m * P, undoing the pending swap; u = 0 while m
+    // is still 0 (the point at infinity).
+    pub fn u(&self) -> FieldElement {
+        let (x, mut z) = if self.swap == 1 {
+            (self.x3, self.z3)
+        } else {
+            (self.x2, self.z2)
+        };
+        z.inverse();
+        x * z
+    }
+}
+
+// Compares the u-coordinates as field elements, so the two encodings of a
+// u below 19 (u and u + p) are equal.
+impl ConstantTimeEq for MontgomeryPoint {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        let u = FieldElement::from_bytes(self.0).to_bytes();
+        let v = FieldElement::from_bytes(other.0).to_bytes();
+        u.ct_eq(&v)
+    }
+}
+
+impl PartialEq for MontgomeryPoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for MontgomeryPoint {}
+
+// Multiplication by an arbitrary (unclamped) scalar, using the same ladder.
+impl std::ops::Mul<&Scalar> for &MontgomeryPoint {
+    type Output = MontgomeryPoint;
+
+    fn mul(self, scalar: &Scalar) -> MontgomeryPoint {
+        self.ladder(scalar.as_bytes(), None)
+    }
+}
+
+impl std::ops::Mul<&MontgomeryPoint> for &Scalar {
+    type Output = MontgomeryPoint;
+
+    fn mul(self, point: &MontgomeryPoint) -> MontgomeryPoint {
+        point * self
+    }
+}
+
+impl std::ops::Mul<Scalar> for MontgomeryPoint {
+    type Output = MontgomeryPoint;
+
+    fn mul(self, scalar: Scalar) -> MontgomeryPoint {
+        &self * &scalar
+    }
+}
+
+impl std::ops::Mul<MontgomeryPoint> for Scalar {
+    type Output = MontgomeryPoint;
+
+    fn mul(self, point: MontgomeryPoint) -> MontgomeryPoint {
+        &point * &self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn bytes(h: &str) -> [u8; 32] {
+        hex::decode(h).unwrap().try_into().unwrap()
+    }
+
+    // RFC 7748, section 5.2
+    #[test]
+    fn rfc7748_vector() {
+        let k = Scalar::clamp(bytes(
+            "a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4",
+        ));
+        let u = MontgomeryPoint(bytes(
+            "e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c",
+        ));
+        assert_eq!(
+            u.mul_clamped(&k).to_bytes(),
+            bytes("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552")
+        );
+    }
+
+    #[test]
+    fn small_multiples() {
+        let zero = X25519_BASEPOINT * Scalar::ZERO;
+        assert_eq!(zero.to_bytes(), [0; 32]);
+        assert_eq!(X25519_BASEPOINT * Scalar::ONE, X25519_BASEPOINT);
+        // the top bit of u is ignored
+        let mut high = X25519_BASEPOINT;
+        high.0[31] |= 0x80;
+        assert_eq!(high * Scalar::ONE, X25519_BASEPOINT);
+        assert_eq!(high, X25519_BASEPOINT);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn ladder_prop(a in any::<[u8; 32]>(), b in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let b = Scalar::from_bytes_mod_order(b);
+
+            prop_assert_eq!((X25519_BASEPOINT * a) * b, (X25519_BASEPOINT * b) * a);
+            prop_assert_eq!((X25519_BASEPOINT * a) * b, X25519_BASEPOINT * (a * b));
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+        #[test]
+        fn elligator_prop(r in any::<[u8; 32]>(), v_is_negative in any::<bool>()) {
+            let point = MontgomeryPoint::from_representative(&r);
+            // the image is on the curve, not the twist
+            prop_assert!(point.to_edwards(0).is_some());
+
+            if let Some(representative) = point.to_representative(v_is_negative) {
+                prop_assert_eq!(representative[31] & 0xc0, 0);
+                prop_assert_eq!(MontgomeryPoint::from_representative(&representative), point);
+            }
+        }
+    }
+
+    #[test]
+    fn elligator_inverse() {
+        // roughly half of the points have representatives
+        let mut found = 0;
+        for i in 1..=64u64 {
+            let point = X25519_BASEPOINT * Scalar::from(i);
+            for v_is_negative in [false, true] {
+                if let Some(r) = point.to_representative(v_is_negative) {
+                    assert_eq!(MontgomeryPoint::from_representative(&r), point);
+                    found += 1;
+                }
+            }
+        }
+        assert!((32..=96).contains(&found), "{found} of 128");
+
+        // u = -A has none, nor do points on the twist (u = 2 is one)
+        let minus_a = MontgomeryPoint((-FieldElement::from(A)).to_bytes());
+        assert!(minus_a.to_representative(false).is_none());
+        assert!(minus_a.to_representative(true).is_none());
+        let mut two = [0; 32];
+        two[0] = 2;
+        assert!(MontgomeryPoint(two).to_edwards(0).is_none());
+        assert!(MontgomeryPoint(two).to_representative(false).is_none());
+    }
+
+    #[test]
+    fn ladder_trace() {
+        let k = Scalar::clamp([0x5a; 32]);
+        let (result, trace) = X25519_BASEPOINT.mul_clamped_traced(&k);
+        assert_eq!(result, X25519_BASEPOINT.mul_clamped(&k));
+        assert_eq!(trace.len(), 255);
+        assert_eq!(trace[0].bit_index, 254);
+        assert_eq!(trace[254].bit_index, 0);
+
+        // after each step the ladder holds m * P for m the top bits of k
+        let bytes = k.to_bytes();
+        let mut m = Scalar::ZERO;
+        let checked = trace
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % 50 == 0 || *i == 254);
+        for (i, step) in checked {
+            let t = step.bit_index;
+            assert_eq!(step.bit, (bytes[t / 8] >> (t % 8)) & 1);
+            assert_eq!(step.swap, step.bit);
+            let mut top = [0; 32];
+            for j in t..255 {
+                top[(j - t) / 8] |= ((bytes[j / 8] >> (j % 8)) & 1) << ((j - t) % 8);
+            }
+            m = Scalar::from_bytes_mod_order(top);
+            assert_eq!(
+                step.u().to_bytes(),
+                (X25519_BASEPOINT * m).to_bytes(),
+                "step {i}"
+            );
+        }
+        assert_eq!(m, Scalar::from_bytes_mod_order(bytes));
+    }
+}
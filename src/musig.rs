@@ -0,0 +1,378 @@
+use std::fmt;
+
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::ristretto::{CompressedRistretto, RistrettoPoint, RISTRETTO_BASEPOINT_TABLE};
+use crate::scalar::Scalar;
+use crate::signing::SignatureError;
+use crate::sr25519::{self, Sr25519Keypair, Sr25519PublicKey, Sr25519Signature};
+use crate::transcript::TranscriptProtocol;
+
+// Two-party MuSig (Maxwell, Poelstra, Seurin and Wuille's original,
+// three-round version): two sr25519 keys are combined into one, and the
+// two holders sign together, each with their own key, producing a single
+// ordinary sr25519 signature under the combined key. A verifier sees one
+// key and one signature and can't tell it from a single signer's, which
+// is what a payment channel's 2-of-2 output wants.
+//
+// The combined key weights each key by a hash of both,
+//
+//   X = a_1 * X_1 + a_2 * X_2,   a_i = H(X_1, X_2, X_i)
+//
+// so neither party can pick its key as X_2 = Y - X_1 to own X alone (the
+// rogue-key attack). The keys are sorted before hashing, so both sides
+// get the same X whichever order they list them in.
+//
+// Signing takes three rounds, each message going both ways:
+//
+//   commit     signer i picks a nonce r_i and sends a hash of R_i = r_i * B
+//   reveal     once it has the other's commitment, it sends R_i
+//   sign       with R = R_1 + R_2 and the sr25519 challenge
+//              k = H(context, message, X, R), it sends
+//              s_i = r_i + k * a_i * x_i
+//
+// and (R, s_1 + s_2) is the sr25519 signature. The commitments are what
+// make this safe: without them, whoever reveals second could choose its
+// R after seeing the other's and forge (Wagner's attack on two-round
+// MuSig). Each partial signature is checked when the two are combined,
+// so a bad one is pinned on its sender.
+//
+// Each round consumes the previous round's state, so a nonce can't be
+// used twice: reusing one with a different R from the other side gives
+// two equations in x_i. The nonce is hedged like sign_hedged's, from the
+// transcript, the key's nonce seed and the caller's RNG.
+
+// How the combined key was made: both keys, sorted, and the combination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AggregateKey {
+    keys: [Sr25519PublicKey; 2],
+    combined: Sr25519PublicKey,
+}
+
+impl AggregateKey {
+    pub fn new(first: &Sr25519PublicKey, second: &Sr25519PublicKey) -> Self {
+        let mut keys = [*first, *second];
+        keys.sort_by_key(|k| *k.as_bytes());
+        let points = keys.map(|k| k.point);
+        let coefficients = keys.map(|k| coefficient(&keys, &k));
+        let point = RistrettoPoint::vartime_multiscalar_mul(&coefficients, &points);
+        let combined = Sr25519PublicKey {
+            compressed: point.compress(),
+            point,
+        };
+        Self { keys, combined }
+    }
+
+    // The key the signatures verify under, with Sr25519PublicKey::verify.
+    pub fn public_key(&self) -> &Sr25519PublicKey {
+        &self.combined
+    }
+}
+
+// H(R_i), sent in the first round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceCommitment(pub [u8; 32]);
+
+// R_i, sent in the second round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceReveal(CompressedRistretto);
+
+impl NonceReveal {
+    // Fails with InvalidPoint if the bytes don't decode.
+    pub fn from_bytes(bytes: [u8; 32]) -> Result<Self, SignatureError> {
+        let compressed = CompressedRistretto(bytes);
+        compressed
+            .decompress()
+            .ok_or(SignatureError::InvalidPoint)?;
+        Ok(Self(compressed))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+}
+
+// s_i, sent in the third round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialSignature(Scalar);
+
+impl PartialSignature {
+    // Fails with NonCanonicalScalar if s_i isn't reduced.
+    pub fn from_bytes(bytes: [u8; 32]) -> Result<Self, SignatureError> {
+        Scalar::from_canonical_bytes(bytes)
+            .map(Self)
+            .ok_or(SignatureError::NonCanonicalScalar)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+}
+
+// What both rounds after the first need: the message's transcript, the
+// combined key, our coefficient and key, and the nonce.
+struct Session {
+    transcript: Transcript,
+    key: AggregateKey,
+    coefficient: Scalar,
+    secret: Scalar,
+    public: Sr25519PublicKey,
+    other: Sr25519PublicKey,
+    nonce: Scalar,
+    big_r: CompressedRistretto,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+        self.nonce.zeroize();
+    }
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("key", &self.key)
+            .field("public", &self.public)
+            .field("nonce", &"<redacted>")
+            .finish()
+    }
+}
+
+// A signer after the first round, waiting for the other's commitment.
+#[derive(Debug)]
+pub struct MusigSession(Session);
+
+impl MusigSession {
+    // Starts signing message under context with keypair, together with
+    // the holder of other.
+    pub fn new<R: RngCore + CryptoRng>(
+        keypair: &Sr25519Keypair,
+        other: &Sr25519PublicKey,
+        context: &[u8],
+        message: &[u8],
+        rng: &mut R,
+    ) -> (Self, NonceCommitment) {
+        let public = *keypair.public();
+        let key = AggregateKey::new(&public, other);
+        let transcript = sr25519::transcript(context, message, &key.combined.compressed);
+
+        let mut witness = transcript.clone();
+        witness.append_point(b"musig:other", &other.compressed);
+        let nonce = witness.witness_scalar(b"signing", &keypair.nonce, rng);
+        let big_r = (&nonce * &*RISTRETTO_BASEPOINT_TABLE).compress();
+        let session = Session {
+            transcript,
+            key,
+            coefficient: coefficient(&key.keys, &public),
+            secret: keypair.key,
+            public,
+            other: *other,
+            nonce,
+            big_r,
+        };
+        (Self(session), commit(&big_r))
+    }
+
+    // Takes the other's commitment and reveals our nonce.
+    pub fn reveal(self, theirs: &NonceCommitment) -> (MusigNonces, NonceReveal) {
+        let reveal = NonceReveal(self.0.big_r);
+        (
+            MusigNonces {
+                session: self.0,
+                theirs: *theirs,
+            },
+            reveal,
+        )
+    }
+}
+
+// A signer after the second round, waiting for the other's nonce.
+#[derive(Debug)]
+pub struct MusigNonces {
+    session: Session,
+    theirs: NonceCommitment,
+}
+
+impl MusigNonces {
+    // Takes the other's nonce and makes our partial signature. Fails with
+    // VerificationFailed if the nonce isn't the one they committed to.
+    pub fn sign(
+        self,
+        theirs: &NonceReveal,
+    ) -> Result<(MusigSigned, PartialSignature), SignatureError> {
+        if commit(&theirs.0) != self.theirs {
+            return Err(SignatureError::VerificationFailed);
+        }
+        let mut session = self.session;
+        // both decompress: ours was made here and theirs was checked by
+        // NonceReveal::from_bytes
+        let their_point = theirs.0.decompress().unwrap();
+        let big_r = (session.big_r.decompress().unwrap() + their_point).compress();
+        let k = sr25519::challenge(&mut session.transcript, &big_r);
+        let s = Scalar::muladd(&(k * session.coefficient), &session.secret, &session.nonce);
+        let their_coefficient = coefficient(&session.key.keys, &session.other);
+        let signed = MusigSigned {
+            r: big_r,
+            their_nonce: their_point,
+            their_challenge: k * their_coefficient,
+            other: session.other,
+            s,
+        };
+        Ok((signed, PartialSignature(s)))
+    }
+}
+
+// A signer after the third round, waiting for the other's partial
+// signature.
+#[derive(Debug, Clone)]
+pub struct MusigSigned {
+    r: CompressedRistretto,
+    their_nonce: RistrettoPoint,
+    their_challenge: Scalar,
+    other: Sr25519PublicKey,
+    s: Scalar,
+}
+
+impl MusigSigned {
+    // Combines the partial signatures into the sr25519 signature. Fails
+    // with VerificationFailed if theirs is wrong: s_j * B isn't
+    // R_j + k * a_j * X_j.
+    pub fn aggregate(&self, theirs: &PartialSignature) -> Result<Sr25519Signature, SignatureError> {
+        let expected = RistrettoPoint::vartime_multiscalar_mul(
+            &[Scalar::ONE, self.their_challenge],
+            &[self.their_nonce, self.other.point],
+        );
+        if &theirs.0 * &*RISTRETTO_BASEPOINT_TABLE != expected {
+            return Err(SignatureError::VerificationFailed);
+        }
+        Ok(Sr25519Signature {
+            r: self.r,
+            s: self.s + theirs.0,
+        })
+    }
+}
+
+// a_i for key among keys (sorted).
+fn coefficient(keys: &[Sr25519PublicKey; 2], key: &Sr25519PublicKey) -> Scalar {
+    let mut t = Transcript::new(b"MuSig-aggregate");
+    for k in keys {
+        t.append_point(b"key", &k.compressed);
+    }
+    t.append_point(b"signer", &key.compressed);
+    t.challenge_scalar(b"coefficient")
+}
+
+fn commit(r: &CompressedRistretto) -> NonceCommitment {
+    let mut t = Transcript::new(b"MuSig-commit");
+    t.append_point(b"R", r);
+    let mut commitment = [0; 32];
+    t.challenge_bytes(b"commitment", &mut commitment);
+    NonceCommitment(commitment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sr25519::Sr25519Error;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn keypairs() -> (Sr25519Keypair, Sr25519Keypair) {
+        (
+            Sr25519Keypair::from_mini_secret(&[1; 32]),
+            Sr25519Keypair::from_mini_secret(&[2; 32]),
+        )
+    }
+
+    #[test]
+    fn aggregate_key() {
+        let (alice, bob) = keypairs();
+        let key = AggregateKey::new(alice.public(), bob.public());
+        assert_eq!(key, AggregateKey::new(bob.public(), alice.public()));
+        assert_ne!(key.public_key(), alice.public());
+        assert_ne!(
+            key.public_key().as_bytes(),
+            (alice.public().point + bob.public().point)
+                .compress()
+                .as_bytes()
+        );
+    }
+
+    #[test]
+    fn sign_two_of_two() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let (alice, bob) = keypairs();
+        let message = b"close channel: 3 to alice, 7 to bob";
+
+        let (a, a_commit) = MusigSession::new(&alice, bob.public(), b"test", message, &mut rng);
+        let (b, b_commit) = MusigSession::new(&bob, alice.public(), b"test", message, &mut rng);
+        let (a, a_reveal) = a.reveal(&b_commit);
+        let (b, b_reveal) = b.reveal(&a_commit);
+        // the messages survive being sent
+        let a_reveal = NonceReveal::from_bytes(a_reveal.to_bytes()).unwrap();
+        let (a, a_partial) = a.sign(&b_reveal).unwrap();
+        let (b, b_partial) = b.sign(&a_reveal).unwrap();
+        let b_partial = PartialSignature::from_bytes(b_partial.to_bytes()).unwrap();
+
+        let signature = a.aggregate(&b_partial).unwrap();
+        assert_eq!(b.aggregate(&a_partial), Ok(signature));
+        let key = AggregateKey::new(alice.public(), bob.public());
+        let public = key.public_key();
+        assert_eq!(public.verify(b"test", message, &signature), Ok(()));
+        assert_eq!(
+            Sr25519Signature::from_bytes(&signature.to_bytes()),
+            Ok(signature)
+        );
+        assert_eq!(
+            public.verify(b"test", b"close channel: 10 to bob", &signature),
+            Err(Sr25519Error::Signature(SignatureError::VerificationFailed))
+        );
+        assert_eq!(
+            alice.public().verify(b"test", message, &signature),
+            Err(Sr25519Error::Signature(SignatureError::VerificationFailed))
+        );
+    }
+
+    #[test]
+    fn cheating_is_caught() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let (alice, bob) = keypairs();
+        let start = |rng: &mut StdRng| {
+            let (a, a_commit) = MusigSession::new(&alice, bob.public(), b"", b"m", rng);
+            let (b, b_commit) = MusigSession::new(&bob, alice.public(), b"", b"m", rng);
+            let (a, a_reveal) = a.reveal(&b_commit);
+            let (b, b_reveal) = b.reveal(&a_commit);
+            (a, a_reveal, b, b_reveal)
+        };
+
+        // a nonce other than the committed one
+        let (a, _, _, _) = start(&mut rng);
+        let (_, _, _, other_reveal) = start(&mut rng);
+        assert_eq!(
+            a.sign(&other_reveal).unwrap_err(),
+            SignatureError::VerificationFailed
+        );
+
+        // a partial signature that doesn't check out
+        let (a, a_reveal, b, b_reveal) = start(&mut rng);
+        let (a, _) = a.sign(&b_reveal).unwrap();
+        let (_, b_partial) = b.sign(&a_reveal).unwrap();
+        let bad = PartialSignature(b_partial.0 + Scalar::ONE);
+        assert_eq!(
+            a.aggregate(&bad).unwrap_err(),
+            SignatureError::VerificationFailed
+        );
+        assert!(a.aggregate(&b_partial).is_ok());
+
+        assert_eq!(
+            NonceReveal::from_bytes([0xff; 32]).unwrap_err(),
+            SignatureError::InvalidPoint
+        );
+        assert_eq!(
+            PartialSignature::from_bytes([0xff; 32]).unwrap_err(),
+            SignatureError::NonCanonicalScalar
+        );
+    }
+}
@@ -0,0 +1,778 @@
+use std::fmt;
+
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+use crate::oprf::{OprfClient, OprfError, OprfMode, OprfServer};
+use crate::ristretto::CompressedRistretto;
+use crate::x25519::{PublicKey, StaticSecret};
+
+// OPAQUE (RFC 9807), an asymmetric password-authenticated key exchange:
+// the client logs in with a password that never reaches the server, not
+// even at registration, and what the server stores gives the password
+// away only to a dictionary attack run after stealing it. Both sides come
+// out of a login with a shared session key, and the client also with an
+// export key for encrypting its own data under the password.
+//
+// The OPRF does the password hashing: the client's "password hash" is
+// F(k, password) for a per-user OPRF key k derived from the server's
+// oprf_seed and the user's credential identifier, computed obliviously
+// (see oprf.rs). From it (the randomized password) the client derives
+// the key that seals its envelope and an X25519 key pair, whose public
+// half the server keeps in the registration record:
+//
+//   registration  C -> S  blinded password
+//                 S -> C  evaluated element, server public key
+//                 C -> S  record: client public key, masking key, envelope
+//
+//   login         C -> S  KE1: blinded password, nonce, key share
+//                 S -> C  KE2: evaluated element, the masked server key
+//                              and envelope, nonce, key share, MAC
+//                 C -> S  KE3: MAC
+//
+// In a login the client recovers its private key from the envelope, which
+// only works with the right password, and the two sides run 3DH: the
+// session key is derived from the three X25519 exchanges between
+// ephemeral and static keys, with MACs over the whole transcript in both
+// directions. A wrong password fails at the client (the envelope's tag
+// doesn't match); a client without the password learns nothing it could
+// check guesses against offline, since each guess costs an exchange with
+// the server.
+//
+// The configuration is the RFC's with ristretto255 for the OPRF, X25519
+// for the key exchange (a private key is its 32-byte seed, as for
+// X25519), SHA-512, HKDF-SHA-512 and HMAC-SHA-512, and the identity as the
+// key stretching function. A deployment would stretch with a memory-hard
+// function such as Argon2id, so that a stolen record plus the OPRF key
+// still costs that much per guess.
+//
+// Servers should answer logins for unknown users with a fake record
+// (RegistrationRecord::fake) rather than an error, so that logins don't
+// reveal which users exist.
+
+// The errors of RFC 9807, section 6.4, plus the OPRF's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpaqueError {
+    // EnvelopeRecoveryError: the envelope's tag doesn't match, which is
+    // what a wrong password looks like to the client.
+    EnvelopeRecovery,
+    // ServerAuthenticationError: the server's MAC in KE2 doesn't match.
+    ServerAuthentication,
+    // ClientAuthenticationError: the client's MAC in KE3 doesn't match.
+    ClientAuthentication,
+    // A key share whose exchange gives the all-zero secret.
+    InvalidKeyShare,
+    // An identity or the context longer than 65535 bytes.
+    TooLong,
+    // A blinded or evaluated element that doesn't decode.
+    Oprf(OprfError),
+}
+
+impl fmt::Display for OpaqueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpaqueError::EnvelopeRecovery => f.write_str("envelope recovery failed"),
+            OpaqueError::ServerAuthentication => f.write_str("server authentication failed"),
+            OpaqueError::ClientAuthentication => f.write_str("client authentication failed"),
+            OpaqueError::InvalidKeyShare => f.write_str("invalid key share"),
+            OpaqueError::TooLong => f.write_str("identity or context too long"),
+            OpaqueError::Oprf(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for OpaqueError {}
+
+impl From<OprfError> for OpaqueError {
+    fn from(e: OprfError) -> Self {
+        OpaqueError::Oprf(e)
+    }
+}
+
+const NN: usize = 32;
+const NH: usize = 64;
+const ENVELOPE: usize = NN + NH;
+const MASKED: usize = 32 + ENVELOPE;
+
+// The client's first registration message, the blinded password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistrationRequest(pub [u8; 32]);
+
+// The evaluated element and the server's public key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistrationResponse(pub [u8; 64]);
+
+// What the server stores per user: the client's public key, the masking
+// key and the envelope.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct RegistrationRecord(pub [u8; 32 + NH + ENVELOPE]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ke1(pub [u8; 32 + NN + 32]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ke2(pub [u8; 32 + NN + MASKED + NN + 32 + NH]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ke3(pub [u8; NH]);
+
+// The client and server identities bound into the envelope and the key
+// exchange. If absent, the public keys stand in for them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identities<'a> {
+    pub client: Option<&'a [u8]>,
+    pub server: Option<&'a [u8]>,
+}
+
+// The server's long-term secrets: the seed its per-user OPRF keys are
+// derived from and its X25519 key pair. Zeroized when dropped.
+pub struct ServerSetup {
+    oprf_seed: [u8; NH],
+    private_key: StaticSecret,
+    public_key: PublicKey,
+}
+
+impl ServerSetup {
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut bytes = [0; NH + 32];
+        rng.fill_bytes(&mut bytes);
+        let setup = Self::from_bytes(&bytes);
+        bytes.zeroize();
+        setup
+    }
+
+    // The OPRF seed, then the private key.
+    pub fn from_bytes(bytes: &[u8; NH + 32]) -> Self {
+        let private_key = StaticSecret::from(<[u8; 32]>::try_from(&bytes[NH..]).unwrap());
+        Self {
+            oprf_seed: bytes[..NH].try_into().unwrap(),
+            public_key: PublicKey::from(&private_key),
+            private_key,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; NH + 32] {
+        let mut bytes = [0; NH + 32];
+        bytes[..NH].copy_from_slice(&self.oprf_seed);
+        bytes[NH..].copy_from_slice(self.private_key.as_bytes());
+        bytes
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    // The OPRF key for one user.
+    fn oprf_key(&self, credential_identifier: &[u8]) -> Result<OprfServer, OpaqueError> {
+        let mut seed = expand::<32>(&self.oprf_seed, &[credential_identifier, b"OprfKey"]);
+        let key = OprfServer::derive(OprfMode::Oprf, &seed, b"OPAQUE-DeriveKeyPair");
+        seed.zeroize();
+        Ok(key?)
+    }
+
+    // CreateRegistrationResponse. Fails with Oprf(Deserialize) if the
+    // request isn't a valid blinded element.
+    pub fn registration_response(
+        &self,
+        request: &RegistrationRequest,
+        credential_identifier: &[u8],
+    ) -> Result<RegistrationResponse, OpaqueError> {
+        let evaluated = self
+            .oprf_key(credential_identifier)?
+            .blind_evaluate(&CompressedRistretto(request.0))?;
+        let mut response = [0; 64];
+        response[..32].copy_from_slice(evaluated.as_bytes());
+        response[32..].copy_from_slice(self.public_key.as_bytes());
+        Ok(RegistrationResponse(response))
+    }
+
+    // The server's side of a login: CreateCredentialResponse and the
+    // 3DH response. Fails with Oprf(Deserialize) or InvalidKeyShare for a
+    // malformed KE1, and with TooLong if an identity or the context is too
+    // long.
+    pub fn login_start<R: RngCore + CryptoRng>(
+        &self,
+        record: &RegistrationRecord,
+        credential_identifier: &[u8],
+        ke1: &Ke1,
+        identities: Identities,
+        context: &[u8],
+        rng: &mut R,
+    ) -> Result<(ServerLogin, Ke2), OpaqueError> {
+        let client_public_key = PublicKey::from(<[u8; 32]>::try_from(&record.0[..32]).unwrap());
+        let masking_key = &record.0[32..32 + NH];
+        let envelope = &record.0[32 + NH..];
+
+        let mut ke2 = [0; 32 + NN + MASKED + NN + 32 + NH];
+        let evaluated = self
+            .oprf_key(credential_identifier)?
+            .blind_evaluate(&CompressedRistretto(ke1.0[..32].try_into().unwrap()))?;
+        ke2[..32].copy_from_slice(evaluated.as_bytes());
+
+        let (masking_nonce, rest) = ke2[32..].split_at_mut(NN);
+        rng.fill_bytes(masking_nonce);
+        let pad = expand::<MASKED>(masking_key, &[masking_nonce, b"CredentialResponsePad"]);
+        let credentials = [self.public_key.as_bytes(), envelope].concat();
+        for (i, byte) in rest[..MASKED].iter_mut().enumerate() {
+            *byte = pad[i] ^ credentials[i];
+        }
+
+        let (server_nonce, rest) = rest[MASKED..].split_at_mut(NN);
+        rng.fill_bytes(server_nonce);
+        let keyshare = StaticSecret::random_from_rng(rng);
+        rest[..32].copy_from_slice(PublicKey::from(&keyshare).as_bytes());
+
+        let client_keyshare = PublicKey::from(<[u8; 32]>::try_from(&ke1.0[64..]).unwrap());
+        let ikm = [
+            dh(&keyshare, &client_keyshare)?,
+            dh(&self.private_key, &client_keyshare)?,
+            dh(&keyshare, &client_public_key)?,
+        ]
+        .concat();
+        let preamble = preamble(
+            context,
+            identities.client.unwrap_or(client_public_key.as_bytes()),
+            ke1,
+            identities.server.unwrap_or(self.public_key.as_bytes()),
+            &ke2[..ke2.len() - NH],
+        )?;
+        let keys = derive_keys(&ikm, &preamble);
+        let server_mac = mac(&keys.km2, &[&Sha512::digest(&preamble)]);
+        ke2[32 + NN + MASKED + NN + 32..].copy_from_slice(&server_mac);
+
+        let transcript = Sha512::new()
+            .chain_update(&preamble)
+            .chain_update(server_mac)
+            .finalize();
+        let login = ServerLogin {
+            expected_client_mac: mac(&keys.km3, &[&transcript]),
+            session_key: keys.session_key,
+        };
+        Ok((login, Ke2(ke2)))
+    }
+}
+
+impl Zeroize for ServerSetup {
+    fn zeroize(&mut self) {
+        self.oprf_seed.zeroize();
+        self.private_key.zeroize();
+    }
+}
+
+impl Drop for ServerSetup {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl RegistrationRecord {
+    // A record for a user who doesn't exist: a random client key and
+    // masking key and an all-zero envelope, so the server's KE2 looks like
+    // any other, and the login fails at the client.
+    pub fn fake<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut record = [0; 32 + NH + ENVELOPE];
+        record[..32]
+            .copy_from_slice(PublicKey::from(&StaticSecret::random_from_rng(rng)).as_bytes());
+        rng.fill_bytes(&mut record[32..32 + NH]);
+        RegistrationRecord(record)
+    }
+}
+
+// Debug shows only the public key; the masking key is secret.
+impl std::fmt::Debug for RegistrationRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistrationRecord")
+            .field("client_public_key", &&self.0[..32])
+            .finish_non_exhaustive()
+    }
+}
+
+// The client between sending its registration request and receiving the
+// response.
+pub struct ClientRegistration {
+    oprf: OprfClient,
+}
+
+impl ClientRegistration {
+    // Fails with Oprf(InvalidInput) if the password is longer than 65535
+    // bytes.
+    pub fn start<R: RngCore + CryptoRng>(
+        password: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, RegistrationRequest), OpaqueError> {
+        let oprf = OprfClient::blind(OprfMode::Oprf, password, rng)?;
+        let request = RegistrationRequest(oprf.blinded_element().to_bytes());
+        Ok((Self { oprf }, request))
+    }
+
+    // FinalizeRegistrationRequest: the record to send to the server, and
+    // the export key.
+    pub fn finish<R: RngCore + CryptoRng>(
+        self,
+        response: &RegistrationResponse,
+        identities: Identities,
+        rng: &mut R,
+    ) -> Result<(RegistrationRecord, [u8; 64]), OpaqueError> {
+        let mut randomized_password = randomized_password(&self.oprf, &response.0[..32])?;
+        let server_public_key = PublicKey::from(<[u8; 32]>::try_from(&response.0[32..]).unwrap());
+
+        let mut nonce = [0; NN];
+        rng.fill_bytes(&mut nonce);
+        let keys = EnvelopeKeys::new(&randomized_password, &nonce);
+        let client_public_key = PublicKey::from(&keys.private_key);
+        let auth_tag = keys.auth_tag(&server_public_key, &client_public_key, identities)?;
+
+        let mut record = [0; 32 + NH + ENVELOPE];
+        record[..32].copy_from_slice(client_public_key.as_bytes());
+        record[32..32 + NH].copy_from_slice(&expand::<NH>(&randomized_password, &[b"MaskingKey"]));
+        record[32 + NH..32 + NH + NN].copy_from_slice(&nonce);
+        record[32 + NH + NN..].copy_from_slice(&auth_tag);
+        randomized_password.zeroize();
+        Ok((RegistrationRecord(record), keys.export_key))
+    }
+}
+
+// The client between sending KE1 and receiving KE2. Zeroized when
+// dropped.
+pub struct ClientLogin {
+    oprf: OprfClient,
+    keyshare: StaticSecret,
+    ke1: Ke1,
+}
+
+impl ClientLogin {
+    // Fails with Oprf(InvalidInput) if the password is longer than 65535
+    // bytes.
+    pub fn start<R: RngCore + CryptoRng>(
+        password: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, Ke1), OpaqueError> {
+        let oprf = OprfClient::blind(OprfMode::Oprf, password, rng)?;
+        let keyshare = StaticSecret::random_from_rng(rng);
+        let mut ke1 = [0; 32 + NN + 32];
+        ke1[..32].copy_from_slice(oprf.blinded_element().as_bytes());
+        rng.fill_bytes(&mut ke1[32..32 + NN]);
+        ke1[32 + NN..].copy_from_slice(PublicKey::from(&keyshare).as_bytes());
+        let ke1 = Ke1(ke1);
+        Ok((
+            Self {
+                oprf,
+                keyshare,
+                ke1,
+            },
+            ke1,
+        ))
+    }
+
+    // Recovers the credentials from KE2 and finishes the key exchange:
+    // KE3 for the server, the session key and the export key. Fails with
+    // EnvelopeRecovery for a wrong password, ServerAuthentication for a KE2
+    // whose MAC doesn't check out, and Oprf(Deserialize) or InvalidKeyShare
+    // for one that is malformed.
+    pub fn finish(
+        self,
+        ke2: &Ke2,
+        identities: Identities,
+        context: &[u8],
+    ) -> Result<(Ke3, [u8; 64], [u8; 64]), OpaqueError> {
+        let ke2 = &ke2.0;
+        let mut randomized_password = randomized_password(&self.oprf, &ke2[..32])?;
+        let masking_key = expand::<NH>(&randomized_password, &[b"MaskingKey"]);
+        let masking_nonce = &ke2[32..32 + NN];
+        let pad = expand::<MASKED>(&masking_key, &[masking_nonce, b"CredentialResponsePad"]);
+        let mut credentials = [0; MASKED];
+        for (i, byte) in credentials.iter_mut().enumerate() {
+            *byte = pad[i] ^ ke2[32 + NN + i];
+        }
+        let server_public_key = PublicKey::from(<[u8; 32]>::try_from(&credentials[..32]).unwrap());
+
+        // Recover: the envelope's tag only matches with the right password
+        let keys = EnvelopeKeys::new(&randomized_password, &credentials[32..32 + NN]);
+        randomized_password.zeroize();
+        let client_public_key = PublicKey::from(&keys.private_key);
+        let auth_tag = keys.auth_tag(&server_public_key, &client_public_key, identities)?;
+        if !bool::from(auth_tag.ct_eq(&credentials[32 + NN..])) {
+            return Err(OpaqueError::EnvelopeRecovery);
+        }
+
+        let rest = &ke2[32 + NN + MASKED..];
+        let server_keyshare = PublicKey::from(<[u8; 32]>::try_from(&rest[NN..NN + 32]).unwrap());
+        let server_mac = &rest[NN + 32..];
+        let ikm = [
+            dh(&self.keyshare, &server_keyshare)?,
+            dh(&self.keyshare, &server_public_key)?,
+            dh(&keys.private_key, &server_keyshare)?,
+        ]
+        .concat();
+        let preamble = preamble(
+            context,
+            identities.client.unwrap_or(client_public_key.as_bytes()),
+            &self.ke1,
+            identities.server.unwrap_or(server_public_key.as_bytes()),
+            &ke2[..ke2.len() - NH],
+        )?;
+        let session = derive_keys(&ikm, &preamble);
+        let expected = mac(&session.km2, &[&Sha512::digest(&preamble)]);
+        if !bool::from(expected.ct_eq(server_mac)) {
+            return Err(OpaqueError::ServerAuthentication);
+        }
+
+        let transcript = Sha512::new()
+            .chain_update(&preamble)
+            .chain_update(server_mac)
+            .finalize();
+        let client_mac = mac(&session.km3, &[&transcript]);
+        Ok((Ke3(client_mac), session.session_key, keys.export_key))
+    }
+}
+
+impl Zeroize for ClientLogin {
+    fn zeroize(&mut self) {
+        self.oprf.zeroize();
+        self.keyshare.zeroize();
+    }
+}
+
+impl Drop for ClientLogin {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+// The server between sending KE2 and receiving KE3. Zeroized when
+// dropped.
+pub struct ServerLogin {
+    expected_client_mac: [u8; NH],
+    session_key: [u8; 64],
+}
+
+impl ServerLogin {
+    // The session key, if the client's MAC checks out. Fails with
+    // ClientAuthentication otherwise, which is what a wrong password looks
+    // like from the server's side.
+    pub fn finish(self, ke3: &Ke3) -> Result<[u8; 64], OpaqueError> {
+        if bool::from(self.expected_client_mac.ct_eq(&ke3.0)) {
+            Ok(self.session_key)
+        } else {
+            Err(OpaqueError::ClientAuthentication)
+        }
+    }
+}
+
+impl Zeroize for ServerLogin {
+    fn zeroize(&mut self) {
+        self.expected_client_mac.zeroize();
+        self.session_key.zeroize();
+    }
+}
+
+impl Drop for ServerLogin {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+// The keys an envelope nonce derives from the randomized password.
+struct EnvelopeKeys {
+    auth_key: [u8; NH],
+    export_key: [u8; NH],
+    private_key: StaticSecret,
+    nonce: [u8; NN],
+}
+
+impl EnvelopeKeys {
+    fn new(randomized_password: &[u8], nonce: &[u8]) -> Self {
+        Self {
+            auth_key: expand(randomized_password, &[nonce, b"AuthKey"]),
+            export_key: expand(randomized_password, &[nonce, b"ExportKey"]),
+            private_key: StaticSecret::from(expand::<32>(
+                randomized_password,
+                &[nonce, b"PrivateKey"],
+            )),
+            nonce: nonce.try_into().unwrap(),
+        }
+    }
+
+    // The MAC over the nonce and the cleartext credentials: the server's
+    // public key and both identities.
+    fn auth_tag(
+        &self,
+        server_public_key: &PublicKey,
+        client_public_key: &PublicKey,
+        identities: Identities,
+    ) -> Result<[u8; NH], OpaqueError> {
+        let server = identities.server.unwrap_or(server_public_key.as_bytes());
+        let client = identities.client.unwrap_or(client_public_key.as_bytes());
+        Ok(mac(
+            &self.auth_key,
+            &[
+                &self.nonce,
+                server_public_key.as_bytes(),
+                &length(server)?,
+                server,
+                &length(client)?,
+                client,
+            ],
+        ))
+    }
+}
+
+impl Drop for EnvelopeKeys {
+    fn drop(&mut self) {
+        self.auth_key.zeroize();
+        self.private_key.zeroize();
+    }
+}
+
+// Extract("", oprf_output || Stretch(oprf_output)), with the identity
+// for Stretch.
+fn randomized_password(oprf: &OprfClient, evaluated: &[u8]) -> Result<[u8; 64], OpaqueError> {
+    let mut output = oprf.finalize(&CompressedRistretto(evaluated.try_into().unwrap()))?;
+    let (prk, _) = Hkdf::<Sha512>::extract(None, &[output, output].concat());
+    output.zeroize();
+    Ok(prk.into())
+}
+
+// The 3DH preamble: the context, both identities and everything sent
+// before the server's MAC.
+fn preamble(
+    context: &[u8],
+    client_identity: &[u8],
+    ke1: &Ke1,
+    server_identity: &[u8],
+    ke2: &[u8],
+) -> Result<Vec<u8>, OpaqueError> {
+    let mut preamble = b"OPAQUEv1-".to_vec();
+    for data in [context, client_identity] {
+        preamble.extend_from_slice(&length(data)?);
+        preamble.extend_from_slice(data);
+    }
+    preamble.extend_from_slice(&ke1.0);
+    preamble.extend_from_slice(&length(server_identity)?);
+    preamble.extend_from_slice(server_identity);
+    preamble.extend_from_slice(ke2);
+    Ok(preamble)
+}
+
+struct SessionKeys {
+    km2: [u8; NH],
+    km3: [u8; NH],
+    session_key: [u8; 64],
+}
+
+// DeriveKeys: the server and client MAC keys and the session key, from
+// the three shared secrets and the preamble.
+fn derive_keys(ikm: &[u8], preamble: &[u8]) -> SessionKeys {
+    let (prk, _) = Hkdf::<Sha512>::extract(None, ikm);
+    let hash = Sha512::digest(preamble);
+    let handshake_secret = expand_label(&prk, b"HandshakeSecret", &hash);
+    SessionKeys {
+        km2: expand_label(&handshake_secret, b"ServerMAC", b""),
+        km3: expand_label(&handshake_secret, b"ClientMAC", b""),
+        session_key: expand_label(&prk, b"SessionKey", &hash),
+    }
+}
+
+// Expand-Label, TLS 1.3's HKDF-Expand-Label with "OPAQUE-" for "tls13 ",
+// for 64-byte outputs.
+fn expand_label(secret: &[u8], label: &[u8], context: &[u8]) -> [u8; 64] {
+    expand(
+        secret,
+        &[
+            &64u16.to_be_bytes(),
+            &[(b"OPAQUE-".len() + label.len()) as u8],
+            b"OPAQUE-",
+            label,
+            &[context.len() as u8],
+            context,
+        ],
+    )
+}
+
+fn expand<const N: usize>(prk: &[u8], info: &[&[u8]]) -> [u8; N] {
+    let mut okm = [0; N];
+    Hkdf::<Sha512>::from_prk(prk)
+        .expect("keys are at least 64 bytes")
+        .expand_multi_info(info, &mut okm)
+        .expect("outputs are short");
+    okm
+}
+
+fn mac(key: &[u8], data: &[&[u8]]) -> [u8; NH] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes any key");
+    for piece in data {
+        mac.update(piece);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+// An X25519 exchange, refusing keys that give the all-zero secret.
+fn dh(secret: &StaticSecret, public: &PublicKey) -> Result<[u8; 32], OpaqueError> {
+    secret
+        .diffie_hellman_contributory(public)
+        .map(|shared| shared.to_bytes())
+        .ok_or(OpaqueError::InvalidKeyShare)
+}
+
+// A length as two big-endian bytes.
+fn length(data: &[u8]) -> Result<[u8; 2], OpaqueError> {
+    u16::try_from(data.len())
+        .map(u16::to_be_bytes)
+        .map_err(|_| OpaqueError::TooLong)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const CONTEXT: &[u8] = b"test";
+
+    fn register(
+        setup: &ServerSetup,
+        password: &[u8],
+        identities: Identities,
+        rng: &mut StdRng,
+    ) -> (RegistrationRecord, [u8; 64]) {
+        let (client, request) = ClientRegistration::start(password, rng).unwrap();
+        let response = setup.registration_response(&request, b"alice").unwrap();
+        client.finish(&response, identities, rng).unwrap()
+    }
+
+    // The client's and the server's session keys and the client's export
+    // key.
+    type Keys = ([u8; 64], [u8; 64], [u8; 64]);
+
+    // Runs a login as far as it gets.
+    fn login(
+        setup: &ServerSetup,
+        record: &RegistrationRecord,
+        password: &[u8],
+        identities: Identities,
+        rng: &mut StdRng,
+    ) -> Result<Keys, OpaqueError> {
+        let (client, ke1) = ClientLogin::start(password, rng)?;
+        let (server, ke2) = setup.login_start(record, b"alice", &ke1, identities, CONTEXT, rng)?;
+        let (ke3, client_key, export_key) = client.finish(&ke2, identities, CONTEXT)?;
+        let server_key = server.finish(&ke3)?;
+        Ok((client_key, server_key, export_key))
+    }
+
+    #[test]
+    fn register_and_login() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let setup = ServerSetup::new(&mut rng);
+        let (record, export_key) = register(&setup, b"hunter2", Identities::default(), &mut rng);
+
+        let (client_key, server_key, login_export_key) =
+            login(&setup, &record, b"hunter2", Identities::default(), &mut rng).unwrap();
+        assert_eq!(client_key, server_key);
+        assert_eq!(login_export_key, export_key);
+
+        // a fresh session key each time, the same export key
+        let (again, _, login_export_key) =
+            login(&setup, &record, b"hunter2", Identities::default(), &mut rng).unwrap();
+        assert_ne!(again, client_key);
+        assert_eq!(login_export_key, export_key);
+
+        // the setup survives a round trip through bytes
+        let restored = ServerSetup::from_bytes(&setup.to_bytes());
+        assert_eq!(restored.public_key(), setup.public_key());
+        assert!(login(
+            &restored,
+            &record,
+            b"hunter2",
+            Identities::default(),
+            &mut rng
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn identities() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let setup = ServerSetup::new(&mut rng);
+        let ids = Identities {
+            client: Some(b"alice@example.com"),
+            server: Some(b"example.com"),
+        };
+        let (record, _) = register(&setup, b"hunter2", ids, &mut rng);
+        assert!(login(&setup, &record, b"hunter2", ids, &mut rng).is_ok());
+
+        // the identities are bound into the envelope
+        let other = Identities {
+            server: Some(b"example.org"),
+            ..ids
+        };
+        assert_eq!(
+            login(&setup, &record, b"hunter2", other, &mut rng),
+            Err(OpaqueError::EnvelopeRecovery)
+        );
+        let long = vec![0; 1 << 16];
+        let long = Identities {
+            client: Some(&long),
+            ..ids
+        };
+        assert_eq!(
+            login(&setup, &record, b"hunter2", long, &mut rng),
+            Err(OpaqueError::TooLong)
+        );
+    }
+
+    #[test]
+    fn failed_logins() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let setup = ServerSetup::new(&mut rng);
+        let ids = Identities::default();
+        let (record, _) = register(&setup, b"hunter2", ids, &mut rng);
+
+        // a wrong password, and another user's OPRF key
+        assert_eq!(
+            login(&setup, &record, b"hunter3", ids, &mut rng),
+            Err(OpaqueError::EnvelopeRecovery)
+        );
+        let (client, ke1) = ClientLogin::start(b"hunter2", &mut rng).unwrap();
+        let (_, ke2) = setup
+            .login_start(&record, b"bob", &ke1, ids, CONTEXT, &mut rng)
+            .unwrap();
+        assert_eq!(
+            client.finish(&ke2, ids, CONTEXT).unwrap_err(),
+            OpaqueError::EnvelopeRecovery
+        );
+
+        // a server without the record's server key, or in another context
+        let other = ServerSetup::new(&mut rng);
+        assert_eq!(
+            login(&other, &record, b"hunter2", ids, &mut rng),
+            Err(OpaqueError::EnvelopeRecovery)
+        );
+        let (client, ke1) = ClientLogin::start(b"hunter2", &mut rng).unwrap();
+        let (_, ke2) = setup
+            .login_start(&record, b"alice", &ke1, ids, b"other", &mut rng)
+            .unwrap();
+        assert_eq!(
+            client.finish(&ke2, ids, CONTEXT).unwrap_err(),
+            OpaqueError::ServerAuthentication
+        );
+
+        // a tampered KE3
+        let (client, ke1) = ClientLogin::start(b"hunter2", &mut rng).unwrap();
+        let (server, ke2) = setup
+            .login_start(&record, b"alice", &ke1, ids, CONTEXT, &mut rng)
+            .unwrap();
+        let (mut ke3, _, _) = client.finish(&ke2, ids, CONTEXT).unwrap();
+        ke3.0[0] ^= 1;
+        assert_eq!(server.finish(&ke3), Err(OpaqueError::ClientAuthentication));
+
+        // a fake record gets a normal-looking KE2 that fails at the client
+        let fake = RegistrationRecord::fake(&mut rng);
+        assert_eq!(
+            login(&setup, &fake, b"hunter2", ids, &mut rng),
+            Err(OpaqueError::EnvelopeRecovery)
+        );
+    }
+}
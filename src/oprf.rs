@@ -0,0 +1,664 @@
+use std::fmt;
+
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+#[cfg(feature = "signing")]
+use crate::ristretto::RISTRETTO_BASEPOINT_TABLE;
+use crate::ristretto::{CompressedRistretto, RistrettoPoint};
+use crate::scalar::Scalar;
+
+// Oblivious pseudorandom functions, RFC 9497's OPRF(ristretto255, SHA-512)
+// in the base and verifiable modes. The server holds a key k; the client
+// has an input and learns
+//
+//   F(k, input) = SHA-512(len || input || len || k * HashToGroup(input)
+//                         || "Finalize")
+//
+// without the server learning the input or the client learning k. The
+// client blinds its input with a random scalar r, the server multiplies
+// what it gets by k, and the client removes r:
+//
+//   client: P = HashToGroup(input), sends r * P
+//   server: sends k * (r * P)
+//   client: k * P = r^-1 * (k * r * P), hashed with the input
+//
+// r * P is a uniformly random point whatever the input, so the server
+// sees nothing; k * P is all the client gets, and can't be turned into k.
+// That is the building block for password hardening (the server adds a
+// secret to every password hash without seeing a password, as in OPAQUE)
+// and private set membership (the client learns F of its items and
+// compares with F of the server's set, published by the server).
+//
+// In the verifiable mode (VOPRF) the server also publishes K = k * B and
+// proves, with each response, that it used the k behind K: a DLEQ proof
+// that log_B(K) = log_{r*P}(k*r*P), batched over any number of elements.
+// That stops a malicious server from using a different key per client to
+// tag them. The mode is part of every hash (the context string), so an
+// output in one mode is unrelated to the output in the other, even under
+// the same key. The partially-oblivious mode (POPRF) isn't implemented.
+//
+// Blinds and proof nonces must be random; the _with variants take them
+// from the caller, for tests and for callers with their own RNG plumbing.
+
+// The errors of RFC 9497, section 5.5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OprfError {
+    // DeserializeError: an element that doesn't decode or is the identity,
+    // or a scalar that isn't reduced or a key that is zero.
+    Deserialize,
+    // InvalidInputError: an input or info string longer than 65535 bytes,
+    // or an input that hashes to the identity.
+    InvalidInput,
+    // VerifyError: a DLEQ proof that doesn't verify.
+    Verify,
+    // As many blinded as evaluated elements are needed for a batch proof.
+    LengthMismatch,
+}
+
+impl fmt::Display for OprfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OprfError::Deserialize => f.write_str("invalid OPRF element or scalar"),
+            OprfError::InvalidInput => f.write_str("invalid OPRF input"),
+            OprfError::Verify => f.write_str("OPRF proof verification failed"),
+            OprfError::LengthMismatch => {
+                f.write_str("blinded and evaluated elements differ in number")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OprfError {}
+
+const IDENTIFIER: &[u8] = b"ristretto255-SHA512";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OprfMode {
+    // modeOPRF, 0x00.
+    Oprf,
+    // modeVOPRF, 0x01.
+    Voprf,
+}
+
+impl OprfMode {
+    // "OPRFV1-" || I2OSP(mode, 1) || "-" || identifier.
+    fn context_string(self) -> Vec<u8> {
+        let mode = match self {
+            OprfMode::Oprf => 0x00,
+            OprfMode::Voprf => 0x01,
+        };
+        [b"OPRFV1-", &[mode][..], b"-", IDENTIFIER].concat()
+    }
+
+    fn hash_to_group(self, input: &[u8]) -> RistrettoPoint {
+        let dst = [b"HashToGroup-", &self.context_string()[..]].concat();
+        RistrettoPoint::from_uniform_bytes(&expand_message_xmd(&[input], &dst))
+    }
+
+    fn hash_to_scalar(self, input: &[&[u8]], dst_prefix: &[u8]) -> Scalar {
+        let dst = [dst_prefix, &self.context_string()].concat();
+        Scalar::from_bytes_mod_order_wide(expand_message_xmd(input, &dst))
+    }
+}
+
+// The client's half of one evaluation: the input and the blind, kept
+// until the server's response arrives. Zeroized when dropped.
+pub struct OprfClient {
+    mode: OprfMode,
+    input: Vec<u8>,
+    blind: Scalar,
+    blinded: CompressedRistretto,
+}
+
+impl OprfClient {
+    #[cfg(feature = "rand")]
+    pub fn blind<R: rand_core::RngCore + rand_core::CryptoRng>(
+        mode: OprfMode,
+        input: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, OprfError> {
+        let mut blind = Scalar::random(rng);
+        let client = Self::blind_with(mode, input, &blind);
+        blind.zeroize();
+        client
+    }
+
+    // Fails with InvalidInput if input is longer than 65535 bytes or
+    // hashes to the identity.
+    pub fn blind_with(mode: OprfMode, input: &[u8], blind: &Scalar) -> Result<Self, OprfError> {
+        if input.len() > u16::MAX as usize {
+            return Err(OprfError::InvalidInput);
+        }
+        let point = mode.hash_to_group(input);
+        if point == RistrettoPoint::IDENTITY {
+            return Err(OprfError::InvalidInput);
+        }
+        Ok(Self {
+            mode,
+            input: input.to_vec(),
+            blind: *blind,
+            blinded: (point * blind).compress(),
+        })
+    }
+
+    // What to send to the server.
+    pub fn blinded_element(&self) -> &CompressedRistretto {
+        &self.blinded
+    }
+
+    // The OPRF output, from the server's evaluated element. Fails with
+    // Deserialize if it isn't a valid element.
+    pub fn finalize(&self, evaluated: &CompressedRistretto) -> Result<[u8; 64], OprfError> {
+        let evaluated = deserialize_element(evaluated)?;
+        let unblinded = (evaluated * self.blind.invert()).compress();
+        Ok(Sha512::new()
+            .chain_update((self.input.len() as u16).to_be_bytes())
+            .chain_update(&self.input)
+            .chain_update(32u16.to_be_bytes())
+            .chain_update(unblinded.as_bytes())
+            .chain_update(b"Finalize")
+            .finalize()
+            .into())
+    }
+
+    // finalize in the verifiable mode, after checking the server's proof
+    // for this one element against its public key.
+    pub fn finalize_verifiable(
+        &self,
+        public: &CompressedRistretto,
+        evaluated: &CompressedRistretto,
+        proof: &DleqProof,
+    ) -> Result<[u8; 64], OprfError> {
+        proof.verify(
+            self.mode,
+            public,
+            std::slice::from_ref(&self.blinded),
+            std::slice::from_ref(evaluated),
+        )?;
+        self.finalize(evaluated)
+    }
+}
+
+impl Zeroize for OprfClient {
+    fn zeroize(&mut self) {
+        self.input.zeroize();
+        self.blind.zeroize();
+    }
+}
+
+impl Drop for OprfClient {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+// The server's key k and its public key k * B. Zeroized when dropped.
+#[cfg(feature = "signing")]
+pub struct OprfServer {
+    mode: OprfMode,
+    key: Scalar,
+    public: CompressedRistretto,
+}
+
+#[cfg(feature = "signing")]
+impl OprfServer {
+    // Fails with Deserialize if the key isn't reduced or is zero.
+    pub fn from_bytes(mode: OprfMode, bytes: &[u8; 32]) -> Result<Self, OprfError> {
+        let key = Scalar::from_canonical_bytes(*bytes).ok_or(OprfError::Deserialize)?;
+        if key == Scalar::ZERO {
+            return Err(OprfError::Deserialize);
+        }
+        Ok(Self::from_scalar(mode, key))
+    }
+
+    // DeriveKeyPair: a key from a 32-byte seed and a public info string
+    // naming its use. Fails with InvalidInput if info is longer than
+    // 65535 bytes.
+    pub fn derive(mode: OprfMode, seed: &[u8; 32], info: &[u8]) -> Result<Self, OprfError> {
+        if info.len() > u16::MAX as usize {
+            return Err(OprfError::InvalidInput);
+        }
+        let length = (info.len() as u16).to_be_bytes();
+        for counter in 0..=u8::MAX {
+            let key = mode.hash_to_scalar(&[seed, &length, info, &[counter]], b"DeriveKeyPair");
+            if key != Scalar::ZERO {
+                return Ok(Self::from_scalar(mode, key));
+            }
+        }
+        // each try gives zero with probability 1/L
+        unreachable!("no nonzero key in 256 tries")
+    }
+
+    fn from_scalar(mode: OprfMode, key: Scalar) -> Self {
+        Self {
+            mode,
+            public: (&key * &*RISTRETTO_BASEPOINT_TABLE).compress(),
+            key,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.key.to_bytes()
+    }
+
+    pub fn public(&self) -> &CompressedRistretto {
+        &self.public
+    }
+
+    // BlindEvaluate: k times the client's blinded element. Fails with
+    // Deserialize if it isn't a valid element.
+    pub fn blind_evaluate(
+        &self,
+        blinded: &CompressedRistretto,
+    ) -> Result<CompressedRistretto, OprfError> {
+        Ok((deserialize_element(blinded)? * self.key).compress())
+    }
+
+    // BlindEvaluate for the verifiable mode: the evaluated elements and
+    // one proof covering all of them.
+    #[cfg(feature = "rand")]
+    pub fn blind_evaluate_batch<R: rand_core::RngCore + rand_core::CryptoRng>(
+        &self,
+        blinded: &[CompressedRistretto],
+        rng: &mut R,
+    ) -> Result<(Vec<CompressedRistretto>, DleqProof), OprfError> {
+        let mut r = Scalar::random(rng);
+        let result = self.blind_evaluate_batch_with(blinded, &r);
+        r.zeroize();
+        result
+    }
+
+    // blind_evaluate_batch with the proof's nonce r given.
+    pub fn blind_evaluate_batch_with(
+        &self,
+        blinded: &[CompressedRistretto],
+        r: &Scalar,
+    ) -> Result<(Vec<CompressedRistretto>, DleqProof), OprfError> {
+        let evaluated = blinded
+            .iter()
+            .map(|b| self.blind_evaluate(b))
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = self.prove(blinded, &evaluated, r)?;
+        Ok((evaluated, proof))
+    }
+
+    // GenerateProof, with the composites computed the fast way: Z = k * M.
+    fn prove(
+        &self,
+        blinded: &[CompressedRistretto],
+        evaluated: &[CompressedRistretto],
+        r: &Scalar,
+    ) -> Result<DleqProof, OprfError> {
+        let (m, _) = composites(self.mode, &self.public, blinded, evaluated, false)?;
+        let z = m * self.key;
+        let t2 = r * &*RISTRETTO_BASEPOINT_TABLE;
+        let t3 = m * r;
+        let c = challenge(self.mode, &self.public, &m, &z, &t2, &t3);
+        let s = Scalar::muladd(&-c, &self.key, r);
+        Ok(DleqProof { c, s })
+    }
+}
+
+#[cfg(feature = "signing")]
+impl Zeroize for OprfServer {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+#[cfg(feature = "signing")]
+impl Drop for OprfServer {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+// A proof that the evaluated elements are the blinded ones times the k
+// behind the server's public key: c and s, 64 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DleqProof {
+    c: Scalar,
+    s: Scalar,
+}
+
+impl DleqProof {
+    // VerifyProof for a batch. Fails with Deserialize if an element
+    // doesn't decode and with Verify if the proof is wrong.
+    pub fn verify(
+        &self,
+        mode: OprfMode,
+        public: &CompressedRistretto,
+        blinded: &[CompressedRistretto],
+        evaluated: &[CompressedRistretto],
+    ) -> Result<(), OprfError> {
+        if blinded.len() != evaluated.len() {
+            return Err(OprfError::LengthMismatch);
+        }
+        let k = deserialize_element(public)?;
+        let (m, z) = composites(mode, public, blinded, evaluated, true)?;
+        let t2 = RistrettoPoint::vartime_double_scalar_mul_basepoint(&self.c, &k, &self.s);
+        let t3 = RistrettoPoint::vartime_multiscalar_mul(&[self.s, self.c], &[m, z]);
+        if challenge(mode, public, &m, &z, &t2, &t3) == self.c {
+            Ok(())
+        } else {
+            Err(OprfError::Verify)
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(self.c.as_bytes());
+        bytes[32..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    // Fails with Deserialize if c or s isn't reduced.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, OprfError> {
+        let scalar = |b: &[u8]| {
+            Scalar::from_canonical_bytes(b.try_into().unwrap()).ok_or(OprfError::Deserialize)
+        };
+        Ok(Self {
+            c: scalar(&bytes[..32])?,
+            s: scalar(&bytes[32..])?,
+        })
+    }
+}
+
+// DeserializeElement: a canonical encoding of an element other than the
+// identity.
+fn deserialize_element(bytes: &CompressedRistretto) -> Result<RistrettoPoint, OprfError> {
+    match bytes.decompress() {
+        Some(point) if point != RistrettoPoint::IDENTITY => Ok(point),
+        _ => Err(OprfError::Deserialize),
+    }
+}
+
+// ComputeComposites: random linear combinations M of the blinded and Z of
+// the evaluated elements, with weights hashed from everything, so that one
+// DLEQ proof for (M, Z) covers every pair. The prover computes Z as k * M
+// instead, so Z is only summed when with_z is set.
+fn composites(
+    mode: OprfMode,
+    public: &CompressedRistretto,
+    blinded: &[CompressedRistretto],
+    evaluated: &[CompressedRistretto],
+    with_z: bool,
+) -> Result<(RistrettoPoint, RistrettoPoint), OprfError> {
+    if blinded.len() > u16::MAX as usize {
+        return Err(OprfError::InvalidInput);
+    }
+    let seed_dst = [b"Seed-", &mode.context_string()[..]].concat();
+    let seed: [u8; 64] = Sha512::new()
+        .chain_update(32u16.to_be_bytes())
+        .chain_update(public.as_bytes())
+        .chain_update((seed_dst.len() as u16).to_be_bytes())
+        .chain_update(&seed_dst)
+        .finalize()
+        .into();
+
+    let mut weights = Vec::with_capacity(blinded.len());
+    for (i, (c, d)) in blinded.iter().zip(evaluated).enumerate() {
+        let input: [&[u8]; 8] = [
+            &64u16.to_be_bytes(),
+            &seed,
+            &(i as u16).to_be_bytes(),
+            &32u16.to_be_bytes(),
+            c.as_bytes(),
+            &32u16.to_be_bytes(),
+            d.as_bytes(),
+            b"Composite",
+        ];
+        weights.push(mode.hash_to_scalar(&input, b"HashToScalar-"));
+    }
+    let decode = |points: &[CompressedRistretto]| {
+        points
+            .iter()
+            .map(deserialize_element)
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let m = RistrettoPoint::vartime_multiscalar_mul(&weights, &decode(blinded)?);
+    let z = if with_z {
+        RistrettoPoint::vartime_multiscalar_mul(&weights, &decode(evaluated)?)
+    } else {
+        RistrettoPoint::IDENTITY
+    };
+    Ok((m, z))
+}
+
+// The proof's challenge c, a hash of the public key, the composites and
+// the commitments t2 = r * B and t3 = r * M.
+fn challenge(
+    mode: OprfMode,
+    public: &CompressedRistretto,
+    m: &RistrettoPoint,
+    z: &RistrettoPoint,
+    t2: &RistrettoPoint,
+    t3: &RistrettoPoint,
+) -> Scalar {
+    let elements = [
+        *public,
+        m.compress(),
+        z.compress(),
+        t2.compress(),
+        t3.compress(),
+    ];
+    let mut input: Vec<&[u8]> = Vec::new();
+    for element in &elements {
+        input.push(&[0, 32]);
+        input.push(element.as_bytes());
+    }
+    input.push(b"Challenge");
+    mode.hash_to_scalar(&input, b"HashToScalar-")
+}
+
+// RFC 9380's expand_message_xmd with SHA-512, for the one output length
+// used here, 64 bytes: a single block, b_1. msg is given in pieces, which
+// are hashed as if concatenated.
+fn expand_message_xmd(msg: &[&[u8]], dst: &[u8]) -> [u8; 64] {
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+    let mut h = Sha512::new().chain_update([0; 128]);
+    for piece in msg {
+        h.update(piece);
+    }
+    let b0 = h
+        .chain_update(64u16.to_be_bytes())
+        .chain_update([0])
+        .chain_update(&dst_prime)
+        .finalize();
+    Sha512::new()
+        .chain_update(b0)
+        .chain_update([1])
+        .chain_update(&dst_prime)
+        .finalize()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 9497 appendix A.1: the seed and info both modes derive their
+    // keys from, and the blind and proof nonce all vectors use.
+    #[cfg(feature = "signing")]
+    const SEED: [u8; 32] = [0xa3; 32];
+    #[cfg(feature = "signing")]
+    const INFO: &[u8] = b"test key";
+    const BLIND: &str = "64d37aed22a27f5191de1c1d69fadb899d8862b58eb4220029e036ec4c1f6706";
+    #[cfg(feature = "signing")]
+    const PROOF_NONCE: &str = "222a5e897cf59db8145db8d16e597e8facb80ae7d4e26d9881aa6f61d645fc0e";
+
+    // Input, blinded element, evaluated element and output, for the
+    // OPRF mode (A.1.1)...
+    #[cfg(feature = "signing")]
+    const OPRF: [(&str, &str, &str, &str); 2] = [
+        (
+            "00",
+            "609a0ae68c15a3cf6903766461307e5c8bb2f95e7e6550e1ffa2dc99e412803c",
+            "7ec6578ae5120958eb2db1745758ff379e77cb64fe77b0b2d8cc917ea0869c7e",
+            "527759c3d9366f277d8c6020418d96bb393ba2afb20ff90df23fb7708264e2f3\
+             ab9135e3bd69955851de4b1f9fe8a0973396719b7912ba9ee8aa7d0b5e24bcf6",
+        ),
+        (
+            "5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a",
+            "da27ef466870f5f15296299850aa088629945a17d1f5b7f5ff043f76b3c06418",
+            "b4cbf5a4f1eeda5a63ce7b77c7d23f461db3fcab0dd28e4e17cecb5c90d02c25",
+            "f4a74c9c592497375e796aa837e907b1a045d34306a749db9f34221f7e750cb4\
+             f2a6413a6bf6fa5e19ba6348eb673934a722a7ede2e7621306d18951e7cf2c73",
+        ),
+    ];
+
+    // ... and for the VOPRF mode (A.1.2), with the proof.
+    const VOPRF: [(&str, &str, &str, &str, &str); 2] = [
+        (
+            "00",
+            "863f330cc1a1259ed5a5998a23acfd37fb4351a793a5b3c090b642ddc439b945",
+            "aa8fa048764d5623868679402ff6108d2521884fa138cd7f9c7669a9a014267e",
+            "ddef93772692e535d1a53903db24367355cc2cc78de93b3be5a8ffcc6985dd06\
+             6d4346421d17bf5117a2a1ff0fcb2a759f58a539dfbe857a40bce4cf49ec600d",
+            "b58cfbe118e0cb94d79b5fd6a6dafb98764dff49c14e1770b566e42402da1a7d\
+             a4d8527693914139caee5bd03903af43a491351d23b430948dd50cde10d32b3c",
+        ),
+        (
+            "5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a",
+            "cc0b2a350101881d8a4cba4c80241d74fb7dcbfde4a61fde2f91443c2bf9ef0c",
+            "60a59a57208d48aca71e9e850d22674b611f752bed48b36f7a91b372bd7ad468",
+            "401a0da6264f8cf45bb2f5264bc31e109155600babb3cd4e5af7d181a2c9dc0a\
+             67154fabf031fd936051dec80b0b6ae29c9503493dde7393b722eafdf5a50b02",
+            "8a9a2f3c7f085b65933594309041fc1898d42d0858e59f90814ae90571a6df60\
+             356f4610bf816f27afdd84f47719e480906d27ecd994985890e5f539e7ea74b6",
+        ),
+    ];
+    const VOPRF_PUBLIC: &str = "c803e2cc6b05fc15064549b5920659ca4a77b2cca6f04f6b357009335476ad4e";
+
+    fn scalar(hex: &str) -> Scalar {
+        Scalar::from_canonical_bytes(hex::decode(hex).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    fn element(hex: &str) -> CompressedRistretto {
+        CompressedRistretto(hex::decode(hex).unwrap().try_into().unwrap())
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn rfc9497_oprf() {
+        let server = OprfServer::derive(OprfMode::Oprf, &SEED, INFO).unwrap();
+        assert_eq!(
+            hex::encode(server.to_bytes()),
+            "5ebcea5ee37023ccb9fc2d2019f9d7737be85591ae8652ffa9ef0f4d37063b0e"
+        );
+        for (input, blinded, evaluated, output) in OPRF {
+            let client = OprfClient::blind_with(
+                OprfMode::Oprf,
+                &hex::decode(input).unwrap(),
+                &scalar(BLIND),
+            )
+            .unwrap();
+            assert_eq!(client.blinded_element(), &element(blinded));
+            let evaluation = server.blind_evaluate(client.blinded_element()).unwrap();
+            assert_eq!(evaluation, element(evaluated));
+            assert_eq!(hex::encode(client.finalize(&evaluation).unwrap()), output);
+        }
+    }
+
+    fn proof(hex: &str) -> DleqProof {
+        DleqProof::from_bytes(&hex::decode(hex).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    // The client needs no secret key, so it is checked on its own.
+    #[test]
+    fn rfc9497_voprf_client() {
+        for (input, blinded, evaluated, proof_hex, output) in VOPRF {
+            let input = hex::decode(input).unwrap();
+            let client = OprfClient::blind_with(OprfMode::Voprf, &input, &scalar(BLIND)).unwrap();
+            assert_eq!(client.blinded_element(), &element(blinded));
+            let result = client.finalize_verifiable(
+                &element(VOPRF_PUBLIC),
+                &element(evaluated),
+                &proof(proof_hex),
+            );
+            assert_eq!(hex::encode(result.unwrap()), output);
+
+            // the same input and blind in the other mode hash differently
+            let oprf = OprfClient::blind_with(OprfMode::Oprf, &input, &scalar(BLIND)).unwrap();
+            assert_ne!(oprf.blinded_element(), client.blinded_element());
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn rfc9497_voprf_server() {
+        let server = OprfServer::derive(OprfMode::Voprf, &SEED, INFO).unwrap();
+        assert_eq!(
+            hex::encode(server.to_bytes()),
+            "e6f73f344b79b379f1a0dd37e07ff62e38d9f71345ce62ae3a9bc60b04ccd909"
+        );
+        assert_eq!(server.public(), &element(VOPRF_PUBLIC));
+        for (_, blinded, evaluated, proof_hex, _) in VOPRF {
+            let (evaluation, proof) = server
+                .blind_evaluate_batch_with(&[element(blinded)], &scalar(PROOF_NONCE))
+                .unwrap();
+            assert_eq!(evaluation, [element(evaluated)]);
+            assert_eq!(hex::encode(proof.to_bytes()), proof_hex);
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn batches_and_bad_proofs() {
+        let server = OprfServer::derive(OprfMode::Voprf, &SEED, INFO).unwrap();
+        let client = |input: &[u8], blind: u64| {
+            OprfClient::blind_with(OprfMode::Voprf, input, &Scalar::from(blind)).unwrap()
+        };
+        let clients = [client(b"a", 1), client(b"b", 2), client(b"c", 3)];
+        let blinded: Vec<_> = clients.iter().map(|c| *c.blinded_element()).collect();
+        let (evaluated, proof) = server
+            .blind_evaluate_batch_with(&blinded, &Scalar::from(99u64))
+            .unwrap();
+        let verify = |public, blinded: &[_], evaluated: &[_]| {
+            proof.verify(OprfMode::Voprf, public, blinded, evaluated)
+        };
+        assert_eq!(verify(server.public(), &blinded, &evaluated), Ok(()));
+
+        // the output doesn't depend on the blind
+        let again = client(b"a", 7);
+        let evaluation = server.blind_evaluate(again.blinded_element()).unwrap();
+        assert_eq!(
+            again.finalize(&evaluation),
+            clients[0].finalize(&evaluated[0])
+        );
+
+        // another key, swapped or missing elements, another mode
+        let other = OprfServer::derive(OprfMode::Voprf, &SEED, b"other key").unwrap();
+        let swapped = [evaluated[1], evaluated[0], evaluated[2]];
+        for result in [
+            verify(other.public(), &blinded, &evaluated),
+            verify(server.public(), &blinded, &swapped),
+            verify(server.public(), &blinded[..2], &evaluated[..2]),
+            proof.verify(OprfMode::Oprf, server.public(), &blinded, &evaluated),
+        ] {
+            assert_eq!(result, Err(OprfError::Verify));
+        }
+        assert_eq!(
+            verify(server.public(), &blinded, &evaluated[..2]),
+            Err(OprfError::LengthMismatch)
+        );
+
+        // a server using another key for one client is caught
+        let (evaluation, proof) = other
+            .blind_evaluate_batch_with(&blinded[..1], &Scalar::from(99u64))
+            .unwrap();
+        assert_eq!(
+            clients[0].finalize_verifiable(server.public(), &evaluation[0], &proof),
+            Err(OprfError::Verify)
+        );
+
+        // the identity is refused on both sides
+        let identity = RistrettoPoint::IDENTITY.compress();
+        assert_eq!(
+            server.blind_evaluate(&identity),
+            Err(OprfError::Deserialize)
+        );
+        assert_eq!(clients[0].finalize(&identity), Err(OprfError::Deserialize));
+    }
+}
@@ -0,0 +1,182 @@
+use sha2::{Digest, Sha512};
+
+use crate::ristretto::RistrettoPoint;
+use crate::scalar::Scalar;
+
+// Pedersen commitments to vectors of scalars, in ristretto255:
+//
+//   C = v_1 G_1 + ... + v_n G_n + r H
+//
+// C hides v perfectly while r is uniform and secret, and binds the
+// committer to v as long as nobody knows a linear relation between the
+// generators G_i and H. That is what "independent" means here, and why
+// the generators can't be multiples of B chosen by anyone: each is hashed
+// to the group with RistrettoPoint::from_uniform_bytes, so no discrete
+// logs are known, and anyone can rederive them to check.
+//
+// The generator for index i is the hash of a domain, a label and i,
+//
+//   SHA-512(len(domain) || domain || "G" || i)    len and i 64-bit LE
+//   SHA-512(len(domain) || domain || "H")
+//
+// so the set for one domain is the same whatever its length (the first n
+// of a longer set are the n-generator set), and sets for different
+// domains are unrelated.
+//
+// Commitments add: commit(v, r) + commit(w, s) = commit(v + w, r + s),
+// which is what range and inner-product proofs are built from.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorCommitmentKey {
+    g: Vec<RistrettoPoint>,
+    h: RistrettoPoint,
+}
+
+impl VectorCommitmentKey {
+    // The first n generators for domain, and the blinding generator.
+    pub fn new(domain: &[u8], n: usize) -> Self {
+        Self {
+            g: (0..n as u64)
+                .map(|i| generator(domain, b"G", Some(i)))
+                .collect(),
+            h: generator(domain, b"H", None),
+        }
+    }
+
+    pub fn generators(&self) -> &[RistrettoPoint] {
+        &self.g
+    }
+
+    pub fn blinding_generator(&self) -> &RistrettoPoint {
+        &self.h
+    }
+
+    // The number of values a commitment can hold.
+    pub fn len(&self) -> usize {
+        self.g.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.g.is_empty()
+    }
+
+    // Commits to values with blinding factor blinding, in constant time.
+    // Fewer values than generators are padded with zeros.
+    //
+    // Panics if there are more values than generators.
+    pub fn commit(&self, values: &[Scalar], blinding: &Scalar) -> RistrettoPoint {
+        assert!(values.len() <= self.g.len(), "more values than generators");
+        values
+            .iter()
+            .zip(&self.g)
+            .map(|(v, g)| g * v)
+            .sum::<RistrettoPoint>()
+            + self.h * blinding
+    }
+
+    // Whether commitment opens to values and blinding. Everything is
+    // public by the time a commitment is opened, so this runs in variable
+    // time.
+    pub fn verify_opening(
+        &self,
+        commitment: &RistrettoPoint,
+        values: &[Scalar],
+        blinding: &Scalar,
+    ) -> bool {
+        if values.len() > self.g.len() {
+            return false;
+        }
+        let mut scalars = values.to_vec();
+        scalars.push(*blinding);
+        let mut points = self.g[..values.len()].to_vec();
+        points.push(self.h);
+        RistrettoPoint::vartime_multiscalar_mul(&scalars, &points) == *commitment
+    }
+}
+
+fn generator(domain: &[u8], label: &[u8], index: Option<u64>) -> RistrettoPoint {
+    let mut h = Sha512::new();
+    h.update((domain.len() as u64).to_le_bytes());
+    h.update(domain);
+    h.update(label);
+    if let Some(i) = index {
+        h.update(i.to_le_bytes());
+    }
+    RistrettoPoint::from_uniform_bytes(&h.finalize().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ristretto::RISTRETTO_BASEPOINT;
+
+    fn scalars(values: &[u64]) -> Vec<Scalar> {
+        values.iter().map(|&v| Scalar::from(v)).collect()
+    }
+
+    #[test]
+    fn generators() {
+        let key = VectorCommitmentKey::new(b"test", 4);
+        assert_eq!(key.len(), 4);
+        assert_eq!(key, VectorCommitmentKey::new(b"test", 4));
+        // pinned, so the derivation can't change unnoticed
+        assert_eq!(
+            hex::encode(key.generators()[0].compress().0),
+            "f691d9cd9d2b71280d9de7b97abfcce07abd4885f9111181b8970e7fbd8bdb32"
+        );
+        assert_eq!(
+            hex::encode(key.blinding_generator().compress().0),
+            "2e940114e3e97c7215952d61cf6af0bda758df587cc21abf19033887289afd46"
+        );
+
+        // the first n of a longer set, and unrelated to another domain's
+        let longer = VectorCommitmentKey::new(b"test", 8);
+        assert_eq!(&longer.generators()[..4], key.generators());
+        assert_eq!(longer.blinding_generator(), key.blinding_generator());
+        let other = VectorCommitmentKey::new(b"tests", 4);
+        assert_ne!(other.blinding_generator(), key.blinding_generator());
+
+        // all distinct, and none is B or the identity
+        let mut all = longer.generators().to_vec();
+        all.extend(other.generators());
+        all.push(*key.blinding_generator());
+        all.push(RISTRETTO_BASEPOINT);
+        for (i, a) in all.iter().enumerate() {
+            assert_ne!(*a, RistrettoPoint::IDENTITY);
+            for b in &all[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn commitments() {
+        let key = VectorCommitmentKey::new(b"test", 3);
+        let (v, r) = (scalars(&[1, 2, 3]), Scalar::from(99u64));
+        let c = key.commit(&v, &r);
+        assert!(key.verify_opening(&c, &v, &r));
+        assert!(!key.verify_opening(&c, &scalars(&[1, 2, 4]), &r));
+        assert!(!key.verify_opening(&c, &v, &Scalar::ONE));
+        // padding with zeros, and too many values
+        assert_eq!(
+            key.commit(&v[..2], &r),
+            key.commit(&scalars(&[1, 2, 0]), &r)
+        );
+        assert!(key.verify_opening(&key.commit(&v[..2], &r), &v[..2], &r));
+        assert!(!key.verify_opening(&c, &scalars(&[1, 2, 3, 0]), &r));
+
+        // homomorphic
+        let (w, s) = (scalars(&[10, 20, 30]), Scalar::from(5u64));
+        let sum: Vec<_> = v.iter().zip(&w).map(|(a, b)| a + b).collect();
+        assert_eq!(
+            key.commit(&v, &r) + key.commit(&w, &s),
+            key.commit(&sum, &(r + s))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "more values than generators")]
+    fn too_many_values() {
+        VectorCommitmentKey::new(b"test", 1).commit(&scalars(&[1, 2]), &Scalar::ONE);
+    }
+}
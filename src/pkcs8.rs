@@ -0,0 +1,384 @@
+use std::fmt;
+
+#[cfg(feature = "signing")]
+use zeroize::Zeroizing;
+
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, SignatureError};
+
+// The DER encodings of Ed25519 keys from RFC 8410, which is what OpenSSL
+// and most other tooling read and write (usually wrapped in PEM).
+//
+// A public key is a SubjectPublicKeyInfo: the algorithm identifier, which
+// for Ed25519 is the bare OID 1.3.101.112 with no parameters, and a BIT
+// STRING holding the 32-byte encoding of A.
+//
+// A private key is a PKCS#8 PrivateKeyInfo (RFC 5208, version 0 on the
+// wire, called v1 here as in RFC 5958) holding the algorithm identifier
+// and an OCTET STRING that itself contains an OCTET STRING with the 32-byte
+// seed. Version 1 (RFC 5958's OneAsymmetricKey, v2 here) may add the
+// public key after it, as a [1] BIT STRING, so a reader doesn't have to
+// recompute it. Optional attributes ([0]) are skipped when reading.
+//
+// Only the encodings actually used for Ed25519 keys are handled, so the
+// DER reader below is small: definite lengths in minimal form, and the
+// handful of tags these structures contain.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerError {
+    // DER that isn't one of the RFC 8410 key encodings (or, for x509, a
+    // certificate of the shape it writes).
+    Malformed,
+    // A well-formed key for some algorithm other than Ed25519.
+    UnsupportedAlgorithm,
+    // A key that doesn't decode or doesn't belong to its seed, or a
+    // certificate signature that doesn't verify.
+    Signature(SignatureError),
+}
+
+impl fmt::Display for DerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerError::Malformed => f.write_str("malformed DER key encoding"),
+            DerError::UnsupportedAlgorithm => f.write_str("key is not an Ed25519 key"),
+            DerError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DerError {}
+
+impl From<SignatureError> for DerError {
+    fn from(e: SignatureError) -> Self {
+        DerError::Signature(e)
+    }
+}
+
+// 1.3.101.112 (id-Ed25519), encoded.
+const ED25519_OID: [u8; 3] = [0x2b, 0x65, 0x70];
+
+pub(crate) const SEQUENCE: u8 = 0x30;
+pub(crate) const BIT_STRING: u8 = 0x03;
+pub(crate) const OID: u8 = 0x06;
+// Only in private keys (and certificates).
+#[cfg(any(feature = "signing", feature = "x509"))]
+pub(crate) const INTEGER: u8 = 0x02;
+#[cfg(feature = "signing")]
+pub(crate) const OCTET_STRING: u8 = 0x04;
+#[cfg(feature = "signing")]
+const ATTRIBUTES: u8 = 0xa0;
+#[cfg(feature = "signing")]
+const PUBLIC_KEY: u8 = 0x81;
+
+// Which PrivateKeyInfo to write. V1 is what OpenSSL writes; V2 includes
+// the public key.
+#[cfg(feature = "signing")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    V1,
+    V2,
+}
+
+// The PKCS#8 encoding of a key pair. It contains the seed, so it is wiped
+// when dropped.
+#[cfg(feature = "signing")]
+pub fn encode_keypair(keypair: &Keypair, version: Version) -> Zeroizing<Vec<u8>> {
+    let seed = Zeroizing::new(keypair.secret().to_bytes());
+    let mut private_key = Zeroizing::new(Vec::with_capacity(34));
+    put(&mut private_key, OCTET_STRING, &seed[..]);
+
+    let mut body = Zeroizing::new(Vec::with_capacity(81));
+    let version_byte = match version {
+        Version::V1 => 0,
+        Version::V2 => 1,
+    };
+    put(&mut body, INTEGER, &[version_byte]);
+    put_algorithm(&mut body);
+    put(&mut body, OCTET_STRING, &private_key);
+    if version == Version::V2 {
+        put_bit_string(&mut body, PUBLIC_KEY, keypair.public().as_bytes());
+    }
+
+    let mut der = Zeroizing::new(Vec::with_capacity(83));
+    put(&mut der, SEQUENCE, &body);
+    der
+}
+
+// Reads a v1 or v2 PrivateKeyInfo. If a v2 key includes its public key,
+// it has to be the one the seed produces.
+#[cfg(feature = "signing")]
+pub fn decode_keypair(der: &[u8]) -> Result<Keypair, DerError> {
+    let mut outer = Reader(der);
+    let mut body = Reader(outer.read(SEQUENCE)?);
+    outer.finish()?;
+
+    let version = match body.read(INTEGER)? {
+        [0] => Version::V1,
+        [1] => Version::V2,
+        _ => return Err(DerError::Malformed),
+    };
+    read_algorithm(&mut body)?;
+    let mut private_key = Reader(body.read(OCTET_STRING)?);
+    let seed: [u8; 32] = private_key
+        .read(OCTET_STRING)?
+        .try_into()
+        .map_err(|_| DerError::Malformed)?;
+    private_key.finish()?;
+    let keypair = Keypair::from_seed(seed);
+
+    if body.peek() == Some(ATTRIBUTES) {
+        body.read(ATTRIBUTES)?;
+    }
+    if body.peek() == Some(PUBLIC_KEY) {
+        if version == Version::V1 {
+            return Err(DerError::Malformed);
+        }
+        if read_bit_string(&mut body, PUBLIC_KEY)? != keypair.public().as_bytes() {
+            return Err(SignatureError::KeyMismatch.into());
+        }
+    }
+    body.finish()?;
+    Ok(keypair)
+}
+
+// The SubjectPublicKeyInfo encoding of a public key.
+pub fn encode_public_key(key: &PublicKey) -> Vec<u8> {
+    let mut body = Vec::with_capacity(42);
+    put_algorithm(&mut body);
+    put_bit_string(&mut body, BIT_STRING, key.as_bytes());
+    let mut der = Vec::with_capacity(44);
+    put(&mut der, SEQUENCE, &body);
+    der
+}
+
+pub fn decode_public_key(der: &[u8]) -> Result<PublicKey, DerError> {
+    let mut outer = Reader(der);
+    let mut body = Reader(outer.read(SEQUENCE)?);
+    outer.finish()?;
+    read_algorithm(&mut body)?;
+    let key = read_bit_string(&mut body, BIT_STRING)?;
+    body.finish()?;
+    Ok(PublicKey::from_bytes(
+        key.try_into().map_err(|_| DerError::Malformed)?,
+    )?)
+}
+
+// Appends a tag, length and contents. Keys never need more than the short
+// form; certificates (see x509) do, and two length bytes are as many as
+// Reader takes.
+pub(crate) fn put(out: &mut Vec<u8>, tag: u8, contents: &[u8]) {
+    out.push(tag);
+    match contents.len() {
+        len @ 0..=0x7f => out.push(len as u8),
+        len @ 0x80..=0xff => out.extend_from_slice(&[0x81, len as u8]),
+        len => {
+            let len = u16::try_from(len).expect("DER value too long");
+            out.push(0x82);
+            out.extend_from_slice(&len.to_be_bytes());
+        }
+    }
+    out.extend_from_slice(contents);
+}
+
+pub(crate) fn put_algorithm(out: &mut Vec<u8>) {
+    let mut algorithm = Vec::with_capacity(5);
+    put(&mut algorithm, OID, &ED25519_OID);
+    put(out, SEQUENCE, &algorithm);
+}
+
+// A BIT STRING made of whole bytes: a leading 0 (no unused bits) and the
+// bytes.
+pub(crate) fn put_bit_string(out: &mut Vec<u8>, tag: u8, bytes: &[u8]) {
+    let mut contents = Vec::with_capacity(bytes.len() + 1);
+    contents.push(0);
+    contents.extend_from_slice(bytes);
+    put(out, tag, &contents);
+}
+
+// The algorithm has to be id-Ed25519, with parameters absent as RFC 8410
+// requires. Ed448 and X25519 keys have their own OIDs and are rejected
+// here, rather than failing later in some less obvious way.
+pub(crate) fn read_algorithm(reader: &mut Reader<'_>) -> Result<(), DerError> {
+    let mut algorithm = Reader(reader.read(SEQUENCE)?);
+    if algorithm.read(OID)? != ED25519_OID {
+        return Err(DerError::UnsupportedAlgorithm);
+    }
+    algorithm.finish()
+}
+
+pub(crate) fn read_bit_string<'a>(reader: &mut Reader<'a>, tag: u8) -> Result<&'a [u8], DerError> {
+    match reader.read(tag)? {
+        [0, bytes @ ..] => Ok(bytes),
+        _ => Err(DerError::Malformed),
+    }
+}
+
+// The unread rest of a DER value.
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    #[cfg(any(feature = "signing", feature = "x509"))]
+    pub(crate) fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    // The contents of the next value, which must have the given tag.
+    // Lengths must be in their shortest form, as DER requires; keys and
+    // the certificates in x509 are small, so two length bytes are plenty.
+    pub(crate) fn read(&mut self, tag: u8) -> Result<&'a [u8], DerError> {
+        let (len, rest) = match self.0 {
+            [t, len, rest @ ..] if *t == tag && *len < 0x80 => (*len as usize, rest),
+            [t, 0x81, len, rest @ ..] if *t == tag && *len >= 0x80 => (*len as usize, rest),
+            [t, 0x82, hi, lo, rest @ ..] if *t == tag && *hi != 0 => {
+                (u16::from_be_bytes([*hi, *lo]) as usize, rest)
+            }
+            _ => return Err(DerError::Malformed),
+        };
+        if rest.len() < len {
+            return Err(DerError::Malformed);
+        }
+        let (contents, rest) = rest.split_at(len);
+        self.0 = rest;
+        Ok(contents)
+    }
+
+    // Checks that nothing is left over.
+    pub(crate) fn finish(&self) -> Result<(), DerError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(DerError::Malformed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 8410 section 10.1.
+    const SPKI: &str =
+        "302a300506032b657003210019bf44096984cdfe8541bac167dc3b96c85086aa30b6b6cb0c5c38ad703166e1";
+
+    // RFC 8410 section 10.3: the key pair of SPKI, first as v1, then as v2
+    // with an attribute (a friendly name) and the public key.
+    #[cfg(feature = "signing")]
+    const PRIVATE_V1: &str = "302e020100300506032b657004220420\
+        d4ee72dbf913584ad5b6d8f1f769f8ad3afe7c28cbf1d4fbe097a88f44755842";
+    #[cfg(feature = "signing")]
+    const PRIVATE_V2_WITH_ATTRIBUTES: &str = "3072020101300506032b657004220420\
+        d4ee72dbf913584ad5b6d8f1f769f8ad3afe7c28cbf1d4fbe097a88f44755842\
+        a01f301d060a2a864886f70d01090914310f0c0d437572646c6520436861697273\
+        81210019bf44096984cdfe8541bac167dc3b96c85086aa30b6b6cb0c5c38ad703166e1";
+
+    #[test]
+    fn public_key() {
+        let der = hex::decode(SPKI).unwrap();
+        let key = decode_public_key(&der).unwrap();
+        assert_eq!(
+            hex::encode(key.as_bytes()),
+            "19bf44096984cdfe8541bac167dc3b96c85086aa30b6b6cb0c5c38ad703166e1"
+        );
+        assert_eq!(encode_public_key(&key), der);
+
+        // trailing data, inside and after the SEQUENCE
+        let mut trailing = der.clone();
+        trailing.push(0);
+        assert_eq!(decode_public_key(&trailing), Err(DerError::Malformed));
+        let mut inner = der.clone();
+        inner[1] += 1;
+        inner.push(0);
+        assert_eq!(decode_public_key(&inner), Err(DerError::Malformed));
+        // truncated
+        assert_eq!(
+            decode_public_key(&der[..der.len() - 1]),
+            Err(DerError::Malformed)
+        );
+        // a non-minimal length
+        let mut long_form = vec![0x30, 0x81, 0x2a];
+        long_form.extend_from_slice(&der[2..]);
+        assert_eq!(decode_public_key(&long_form), Err(DerError::Malformed));
+        // unused bits in the BIT STRING
+        let mut unused_bits = der.clone();
+        unused_bits[11] = 1;
+        assert_eq!(decode_public_key(&unused_bits), Err(DerError::Malformed));
+        // X25519 (1.3.101.110)
+        let mut x25519 = der.clone();
+        x25519[8] = 0x6e;
+        assert_eq!(
+            decode_public_key(&x25519),
+            Err(DerError::UnsupportedAlgorithm)
+        );
+        // a key that isn't a point (y = 2)
+        let mut not_a_point = der;
+        not_a_point[12..].copy_from_slice(&[0; 32]);
+        not_a_point[12] = 2;
+        assert_eq!(
+            decode_public_key(&not_a_point),
+            Err(DerError::Signature(SignatureError::InvalidPoint))
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn private_key() {
+        let v1 = hex::decode(PRIVATE_V1).unwrap();
+        let keypair = decode_keypair(&v1).unwrap();
+        assert_eq!(
+            encode_public_key(keypair.public()),
+            hex::decode(SPKI).unwrap()
+        );
+        assert_eq!(*encode_keypair(&keypair, Version::V1), v1);
+
+        let v2 = encode_keypair(&keypair, Version::V2);
+        assert_eq!(
+            hex::encode(&*v2),
+            "3051020101300506032b657004220420\
+             d4ee72dbf913584ad5b6d8f1f769f8ad3afe7c28cbf1d4fbe097a88f44755842\
+             81210019bf44096984cdfe8541bac167dc3b96c85086aa30b6b6cb0c5c38ad703166e1"
+        );
+        assert_eq!(decode_keypair(&v2).unwrap().to_bytes(), keypair.to_bytes());
+
+        let with_attributes = hex::decode(PRIVATE_V2_WITH_ATTRIBUTES).unwrap();
+        assert_eq!(
+            decode_keypair(&with_attributes).unwrap().to_bytes(),
+            keypair.to_bytes()
+        );
+
+        // a v2 public key that doesn't belong to the seed
+        let mut mismatched = v2.to_vec();
+        let last = mismatched.len() - 1;
+        mismatched[last] ^= 1;
+        assert!(matches!(
+            decode_keypair(&mismatched),
+            Err(DerError::Signature(SignatureError::KeyMismatch))
+        ));
+        // a public key in a v1 structure
+        let mut v1_with_public = v2.to_vec();
+        v1_with_public[4] = 0;
+        assert!(matches!(
+            decode_keypair(&v1_with_public),
+            Err(DerError::Malformed)
+        ));
+        // a version this doesn't know
+        let mut v3 = v1.clone();
+        v3[4] = 2;
+        assert!(matches!(decode_keypair(&v3), Err(DerError::Malformed)));
+        // a 31-byte seed
+        let mut short_seed = v1;
+        short_seed.pop();
+        short_seed[1] -= 1;
+        short_seed[14] -= 1;
+        short_seed[15] -= 1;
+        assert!(matches!(
+            decode_keypair(&short_seed),
+            Err(DerError::Malformed)
+        ));
+        // a public key where a private key was expected
+        assert!(matches!(
+            decode_keypair(&hex::decode(SPKI).unwrap()),
+            Err(DerError::Malformed)
+        ));
+    }
+}
@@ -0,0 +1,523 @@
+use std::fmt;
+
+use sha2::{Digest, Sha512};
+#[cfg(feature = "signing")]
+use zeroize::Zeroize;
+
+#[cfg(feature = "signing")]
+use crate::ristretto::RISTRETTO_BASEPOINT_TABLE;
+use crate::ristretto::{CompressedRistretto, RistrettoPoint};
+use crate::scalar::Scalar;
+use crate::signing::SignatureError;
+
+// Spontaneous anonymous group (SAG) signatures, the ring signatures of
+// Abe, Ohkubo and Suzuki as CryptoNote uses them, over ristretto255. The
+// signer picks any set of public keys P_0 .. P_{n-1}, the ring, that
+// includes its own P_j = x * B, and signs so that a verifier learns the
+// message was signed by one of them but not which. "Spontaneous": the
+// other members don't take part, or even know.
+//
+// The signature is a closed chain of Schnorr challenges, one per member:
+//
+//   c_{i+1} = H(ring, message, s_i * B + c_i * P_i)    (indices mod n)
+//
+// Anyone can start at c_0 and walk around the ring with the responses s_i.
+// Without any secret key the chain can only be built forwards from some
+// starting point, and then it doesn't close; the signer closes it at its
+// own position: it starts with c_{j+1} = H(ring, message, a * B) for a
+// nonce a, picks the s_i of the others at random, walks round to c_j and
+// sets s_j = a - c_j * x, so that s_j * B + c_j * P_j = a * B. Every s_i
+// looks uniformly random, so nothing marks position j.
+//
+// H is SHA-512, reduced mod L, of
+//
+//   "SAG" || n || P_0 || ... || P_{n-1} || len(message) || message || L_i
+//
+// with n and the length 64-bit little-endian. The ring is hashed in the
+// order given, so a signature only verifies with its ring in that order.
+// As with Ed25519, the nonce and the other s_i are derived from the secret
+// key, the ring and the message, so signing is deterministic.
+//
+// A signature is c_0 || s_0 || ... || s_{n-1}, 32 * (n + 1) bytes.
+//
+// LinkableRingSignature is the linkable variant (LSAG, Liu, Wei and Wong's
+// as Monero uses it): the signer also publishes its key image
+//
+//   I = x * Hp(P_j)
+//
+// with Hp a hash to the group, and proves, in the same chain, that I has
+// the same discrete log base Hp(P_j) as P_j has base B, by adding
+// s_i * Hp(P_i) + c_i * I to each challenge:
+//
+//   c_{i+1} = H(ring, I, message, s_i * B + c_i * P_i, s_i * Hp(P_i) + c_i * I)
+//
+// I depends only on the key, not on the ring or the message, so two
+// signatures by the same key have the same image, whichever rings they
+// were made in: a verifier keeping the images it has seen spots a key
+// used twice (a double spend) without learning which key it was. Since
+// ristretto255 has prime order there is no torsion to add to I to make
+// it look fresh, the bug Monero had to patch for Ed25519. The hash and
+// the signature encoding are as above with "LSAG" for "SAG", I after the
+// ring, and I in front of the signature: 32 * (n + 2) bytes.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RingError {
+    // Signing with a secret key whose public key isn't in the ring (which
+    // includes an empty ring).
+    SignerNotInRing,
+    // Verifying against a ring of another size than the signature's.
+    RingSizeMismatch,
+    // A signature that doesn't decode, or a chain that doesn't close
+    // (VerificationFailed).
+    Signature(SignatureError),
+}
+
+impl fmt::Display for RingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RingError::SignerNotInRing => f.write_str("signer's key is not in the ring"),
+            RingError::RingSizeMismatch => f.write_str("ring size doesn't match the signature"),
+            RingError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RingError {}
+
+impl From<SignatureError> for RingError {
+    fn from(e: SignatureError) -> Self {
+        RingError::Signature(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RingSignature {
+    challenge: Scalar,
+    responses: Vec<Scalar>,
+}
+
+impl RingSignature {
+    // Signs message as one of ring, with the secret key of one of its
+    // members. Fails with SignerNotInRing if x * B isn't in ring.
+    #[cfg(feature = "signing")]
+    pub fn sign(
+        ring: &[RistrettoPoint],
+        secret: &Scalar,
+        message: &[u8],
+    ) -> Result<Self, RingError> {
+        let public = secret * &*RISTRETTO_BASEPOINT_TABLE;
+        let j = ring
+            .iter()
+            .position(|p| *p == public)
+            .ok_or(RingError::SignerNotInRing)?;
+        let n = ring.len();
+        let prefix = prefix(b"SAG", ring, None, message);
+
+        let mut nonces = Nonces::new(&prefix, secret);
+        let mut a = nonces.next();
+        let mut responses = vec![Scalar::ZERO; n];
+        let mut challenges = vec![Scalar::ZERO; n];
+        challenges[(j + 1) % n] = challenge(&prefix, &[&a * &*RISTRETTO_BASEPOINT_TABLE]);
+        for i in (j + 1..j + n).map(|i| i % n) {
+            responses[i] = nonces.next();
+            let l = &responses[i] * &*RISTRETTO_BASEPOINT_TABLE + ring[i] * challenges[i];
+            challenges[(i + 1) % n] = challenge(&prefix, &[l]);
+        }
+        responses[j] = a - challenges[j] * secret;
+        a.zeroize();
+        Ok(Self {
+            challenge: challenges[0],
+            responses,
+        })
+    }
+
+    // Fails with RingSizeMismatch if the ring isn't the size the signature
+    // was made for, and with VerificationFailed if the chain doesn't close.
+    pub fn verify(&self, ring: &[RistrettoPoint], message: &[u8]) -> Result<(), RingError> {
+        if ring.len() != self.responses.len() {
+            return Err(RingError::RingSizeMismatch);
+        }
+        let prefix = prefix(b"SAG", ring, None, message);
+        let mut c = self.challenge;
+        for (p, s) in ring.iter().zip(&self.responses) {
+            let l = RistrettoPoint::vartime_double_scalar_mul_basepoint(&c, p, s);
+            c = challenge(&prefix, &[l]);
+        }
+        if c == self.challenge {
+            Ok(())
+        } else {
+            Err(SignatureError::VerificationFailed.into())
+        }
+    }
+
+    // The number of members in the ring the signature was made for.
+    pub fn ring_size(&self) -> usize {
+        self.responses.len()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 * (self.responses.len() + 1));
+        bytes.extend_from_slice(self.challenge.as_bytes());
+        for s in &self.responses {
+            bytes.extend_from_slice(s.as_bytes());
+        }
+        bytes
+    }
+
+    // Fails with InvalidLength unless there are a challenge and at least
+    // one response, and with NonCanonicalScalar if one isn't reduced.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RingError> {
+        let (challenge, responses) = read_scalars(bytes)?;
+        Ok(Self {
+            challenge,
+            responses,
+        })
+    }
+}
+
+// A linkable ring signature: a ring signature together with the signer's
+// key image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkableRingSignature {
+    key_image: RistrettoPoint,
+    challenge: Scalar,
+    responses: Vec<Scalar>,
+}
+
+impl LinkableRingSignature {
+    // As RingSignature::sign.
+    #[cfg(feature = "signing")]
+    pub fn sign(
+        ring: &[RistrettoPoint],
+        secret: &Scalar,
+        message: &[u8],
+    ) -> Result<Self, RingError> {
+        let public = secret * &*RISTRETTO_BASEPOINT_TABLE;
+        let j = ring
+            .iter()
+            .position(|p| *p == public)
+            .ok_or(RingError::SignerNotInRing)?;
+        let n = ring.len();
+        let key_image = key_image(secret);
+        let prefix = prefix(b"LSAG", ring, Some(&key_image), message);
+
+        let mut nonces = Nonces::new(&prefix, secret);
+        let mut a = nonces.next();
+        let mut responses = vec![Scalar::ZERO; n];
+        let mut challenges = vec![Scalar::ZERO; n];
+        challenges[(j + 1) % n] = challenge(
+            &prefix,
+            &[&a * &*RISTRETTO_BASEPOINT_TABLE, hash_to_point(&public) * a],
+        );
+        for i in (j + 1..j + n).map(|i| i % n) {
+            responses[i] = nonces.next();
+            let (s, c) = (responses[i], challenges[i]);
+            let l = &s * &*RISTRETTO_BASEPOINT_TABLE + ring[i] * c;
+            let r = hash_to_point(&ring[i]) * s + key_image * c;
+            challenges[(i + 1) % n] = challenge(&prefix, &[l, r]);
+        }
+        responses[j] = a - challenges[j] * secret;
+        a.zeroize();
+        Ok(Self {
+            key_image,
+            challenge: challenges[0],
+            responses,
+        })
+    }
+
+    // As RingSignature::verify.
+    pub fn verify(&self, ring: &[RistrettoPoint], message: &[u8]) -> Result<(), RingError> {
+        if ring.len() != self.responses.len() {
+            return Err(RingError::RingSizeMismatch);
+        }
+        let prefix = prefix(b"LSAG", ring, Some(&self.key_image), message);
+        let mut c = self.challenge;
+        for (p, s) in ring.iter().zip(&self.responses) {
+            let l = RistrettoPoint::vartime_double_scalar_mul_basepoint(&c, p, s);
+            let r = RistrettoPoint::vartime_multiscalar_mul(
+                &[*s, c],
+                &[hash_to_point(p), self.key_image],
+            );
+            c = challenge(&prefix, &[l, r]);
+        }
+        if c == self.challenge {
+            Ok(())
+        } else {
+            Err(SignatureError::VerificationFailed.into())
+        }
+    }
+
+    pub fn key_image(&self) -> &RistrettoPoint {
+        &self.key_image
+    }
+
+    // Whether the two signatures were made with the same key. Only
+    // meaningful for signatures that verify.
+    pub fn is_linked(&self, other: &LinkableRingSignature) -> bool {
+        self.key_image == other.key_image
+    }
+
+    pub fn ring_size(&self) -> usize {
+        self.responses.len()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 * (self.responses.len() + 2));
+        bytes.extend_from_slice(self.key_image.compress().as_bytes());
+        bytes.extend_from_slice(self.challenge.as_bytes());
+        for s in &self.responses {
+            bytes.extend_from_slice(s.as_bytes());
+        }
+        bytes
+    }
+
+    // As RingSignature::from_bytes, and fails with InvalidPoint if the
+    // key image doesn't decode.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RingError> {
+        if bytes.len() < 32 {
+            return Err(SignatureError::InvalidLength.into());
+        }
+        let key_image = CompressedRistretto(bytes[..32].try_into().unwrap())
+            .decompress()
+            .ok_or(SignatureError::InvalidPoint)?;
+        let (challenge, responses) = read_scalars(&bytes[32..])?;
+        Ok(Self {
+            key_image,
+            challenge,
+            responses,
+        })
+    }
+}
+
+// The key image x * Hp(x * B) of a secret key, to look a key up among the
+// images of signatures already seen.
+#[cfg(feature = "signing")]
+pub fn key_image(secret: &Scalar) -> RistrettoPoint {
+    hash_to_point(&(secret * &*RISTRETTO_BASEPOINT_TABLE)) * secret
+}
+
+// Hp(P), a point whose discrete log nobody knows.
+fn hash_to_point(p: &RistrettoPoint) -> RistrettoPoint {
+    let mut input = b"LSAG-Hp".to_vec();
+    input.extend_from_slice(p.compress().as_bytes());
+    RistrettoPoint::hash_from_bytes::<Sha512>(&input)
+}
+
+// The challenge c_0 and at least one response.
+fn read_scalars(bytes: &[u8]) -> Result<(Scalar, Vec<Scalar>), SignatureError> {
+    if bytes.len() < 64 || !bytes.len().is_multiple_of(32) {
+        return Err(SignatureError::InvalidLength);
+    }
+    let mut scalars = bytes.chunks(32).map(|s| {
+        Scalar::from_canonical_bytes(s.try_into().unwrap())
+            .ok_or(SignatureError::NonCanonicalScalar)
+    });
+    Ok((scalars.next().unwrap()?, scalars.collect::<Result<_, _>>()?))
+}
+
+// The hash state after everything but L_i (and R_i).
+fn prefix(
+    domain: &[u8],
+    ring: &[RistrettoPoint],
+    key_image: Option<&RistrettoPoint>,
+    message: &[u8],
+) -> Sha512 {
+    let mut h = Sha512::new();
+    h.update(domain);
+    h.update((ring.len() as u64).to_le_bytes());
+    for p in ring {
+        h.update(p.compress().as_bytes());
+    }
+    if let Some(key_image) = key_image {
+        h.update(key_image.compress().as_bytes());
+    }
+    h.update((message.len() as u64).to_le_bytes());
+    h.update(message);
+    h
+}
+
+fn challenge(prefix: &Sha512, points: &[RistrettoPoint]) -> Scalar {
+    let mut h = prefix.clone();
+    for p in points {
+        h.update(p.compress().as_bytes());
+    }
+    Scalar::from_bytes_mod_order_wide(h.finalize().into())
+}
+
+// The signer's nonce and the other members' responses, in that order:
+// SHA-512(x || SHA-512(prefix) || i) for i = 0, 1, ...
+#[cfg(feature = "signing")]
+struct Nonces {
+    seed: Sha512,
+    counter: u64,
+}
+
+#[cfg(feature = "signing")]
+impl Nonces {
+    fn new(prefix: &Sha512, secret: &Scalar) -> Self {
+        let mut seed = Sha512::new();
+        seed.update(secret.as_bytes());
+        seed.update(prefix.clone().finalize());
+        Self { seed, counter: 0 }
+    }
+
+    fn next(&mut self) -> Scalar {
+        let mut h = self.seed.clone();
+        h.update(self.counter.to_le_bytes());
+        self.counter += 1;
+        let mut bytes: [u8; 64] = h.finalize().into();
+        let scalar = Scalar::from_bytes_mod_order_wide(bytes);
+        bytes.zeroize();
+        scalar
+    }
+}
+
+#[cfg(all(test, feature = "signing"))]
+mod tests {
+    use super::*;
+
+    fn keys(n: u64) -> (Vec<Scalar>, Vec<RistrettoPoint>) {
+        let secrets: Vec<_> = (1..=n).map(|i| Scalar::from(i * 1000 + 7)).collect();
+        let ring = secrets
+            .iter()
+            .map(|x| x * &*RISTRETTO_BASEPOINT_TABLE)
+            .collect();
+        (secrets, ring)
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let (secrets, ring) = keys(5);
+        for x in &secrets {
+            let signature = RingSignature::sign(&ring, x, b"message").unwrap();
+            assert_eq!(signature.verify(&ring, b"message"), Ok(()));
+            assert_eq!(signature.ring_size(), 5);
+            assert_eq!(
+                signature.verify(&ring, b"other message"),
+                Err(RingError::Signature(SignatureError::VerificationFailed))
+            );
+            // deterministic
+            assert_eq!(RingSignature::sign(&ring, x, b"message"), Ok(signature));
+        }
+
+        // a ring of one is a Schnorr signature
+        let signature = RingSignature::sign(&ring[2..3], &secrets[2], b"alone").unwrap();
+        assert_eq!(signature.verify(&ring[2..3], b"alone"), Ok(()));
+    }
+
+    #[test]
+    fn wrong_rings() {
+        let (secrets, ring) = keys(4);
+        let signature = RingSignature::sign(&ring, &secrets[1], b"m").unwrap();
+
+        let mut reordered = ring.clone();
+        reordered.swap(0, 3);
+        assert_eq!(
+            signature.verify(&reordered, b"m"),
+            Err(RingError::Signature(SignatureError::VerificationFailed))
+        );
+        let mut replaced = ring.clone();
+        replaced[1] = ring[0] + ring[2];
+        assert_eq!(
+            signature.verify(&replaced, b"m"),
+            Err(RingError::Signature(SignatureError::VerificationFailed))
+        );
+        assert_eq!(
+            signature.verify(&ring[..3], b"m"),
+            Err(RingError::RingSizeMismatch)
+        );
+        assert_eq!(
+            RingSignature::sign(&ring[2..], &secrets[1], b"m"),
+            Err(RingError::SignerNotInRing)
+        );
+        assert_eq!(
+            RingSignature::sign(&[], &secrets[1], b"m"),
+            Err(RingError::SignerNotInRing)
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let (secrets, ring) = keys(3);
+        let signature = RingSignature::sign(&ring, &secrets[0], b"m").unwrap();
+        let bytes = signature.to_bytes();
+        assert_eq!(bytes.len(), 4 * 32);
+        assert_eq!(RingSignature::from_bytes(&bytes), Ok(signature));
+        assert_eq!(
+            RingSignature::from_bytes(&bytes[..32]),
+            Err(RingError::Signature(SignatureError::InvalidLength))
+        );
+        assert_eq!(
+            RingSignature::from_bytes(&bytes[..95]),
+            Err(RingError::Signature(SignatureError::InvalidLength))
+        );
+        let mut bad = bytes.clone();
+        bad[63] = 0xff;
+        assert_eq!(
+            RingSignature::from_bytes(&bad),
+            Err(RingError::Signature(SignatureError::NonCanonicalScalar))
+        );
+
+        // a tweaked response no longer closes the ring
+        let mut tweaked = bytes;
+        tweaked[32] ^= 1;
+        let tweaked = RingSignature::from_bytes(&tweaked).unwrap();
+        assert_eq!(
+            tweaked.verify(&ring, b"m"),
+            Err(RingError::Signature(SignatureError::VerificationFailed))
+        );
+    }
+
+    #[test]
+    fn linkable() {
+        let (secrets, ring) = keys(4);
+        let signature = LinkableRingSignature::sign(&ring, &secrets[2], b"spend 1").unwrap();
+        assert_eq!(signature.verify(&ring, b"spend 1"), Ok(()));
+        assert_eq!(signature.key_image(), &key_image(&secrets[2]));
+        assert_eq!(
+            signature.verify(&ring, b"spend 2"),
+            Err(RingError::Signature(SignatureError::VerificationFailed))
+        );
+
+        // the same key in another ring, on another message, is linked
+        let (others, mut other_ring) = keys(6);
+        other_ring[5] = ring[2];
+        let again = LinkableRingSignature::sign(&other_ring, &secrets[2], b"spend 2").unwrap();
+        assert_eq!(again.verify(&other_ring, b"spend 2"), Ok(()));
+        assert!(signature.is_linked(&again));
+        let different = LinkableRingSignature::sign(&other_ring, &others[1], b"spend 2").unwrap();
+        assert!(!signature.is_linked(&different));
+
+        // a key image that isn't the signer's doesn't verify
+        let mut forged = signature.clone();
+        forged.key_image = key_image(&secrets[1]);
+        assert_eq!(
+            forged.verify(&ring, b"spend 1"),
+            Err(RingError::Signature(SignatureError::VerificationFailed))
+        );
+        assert_eq!(
+            signature.verify(&ring[1..], b"spend 1"),
+            Err(RingError::RingSizeMismatch)
+        );
+    }
+
+    #[test]
+    fn linkable_serialization() {
+        let (secrets, ring) = keys(3);
+        let signature = LinkableRingSignature::sign(&ring, &secrets[0], b"m").unwrap();
+        let bytes = signature.to_bytes();
+        assert_eq!(bytes.len(), 5 * 32);
+        assert_eq!(signature.ring_size(), 3);
+        assert_eq!(LinkableRingSignature::from_bytes(&bytes), Ok(signature));
+        assert_eq!(
+            LinkableRingSignature::from_bytes(&bytes[..64]),
+            Err(RingError::Signature(SignatureError::InvalidLength))
+        );
+        let mut bad = bytes;
+        bad[..32].copy_from_slice(&[0xff; 32]);
+        assert_eq!(
+            LinkableRingSignature::from_bytes(&bad),
+            Err(RingError::Signature(SignatureError::InvalidPoint))
+        );
+    }
+}
@@ -0,0 +1,754 @@
+use std::sync::LazyLock;
+
+use subtle::{Choice, ConditionallyNegatable, ConditionallySelectable, ConstantTimeEq};
+
+use crate::edwards::{EdwardsBasepointTable, EdwardsPoint, ED25519_BASEPOINT, EDWARDS_D};
+use crate::field::FieldElement;
+use crate::scalar::Scalar;
+use crate::signing::Hash512;
+
+// ristretto255 (RFC 9496), a group of prime order L built on top of the
+// Edwards curve. The curve has 8 * L points, and protocols that want a
+// prime-order group have to deal with the cofactor somehow: clear it,
+// check for torsion, or live with a point having several valid encodings.
+// Ristretto does it once, in the encoding. The points of the curve are
+// grouped into classes of four, P + {0, T2, T4, T6} where T4 has order 2
+// and T2, T6 are the two points of order 4, and each class is one element
+// of the group. Encoding picks the same representative for every point in
+// a class, and decoding accepts exactly the encodings that come out of it.
+//
+// A RistrettoPoint wraps an EdwardsPoint, any member of its class. Adding
+// and negating are the Edwards operations, since the classes are cosets
+// of the 4-torsion subgroup. Equality is up to that subgroup, so it is not
+// the Edwards equality; the underlying point is kept private for that
+// reason.
+//
+// Strictly, the classes are those of the points that are doubles, a
+// subgroup of order 4L that decoding always lands in and that sums and
+// negations stay in. A point outside it (P plus an odd multiple of the
+// order-8 point T) isn't a ristretto255 element at all, and compressing
+// one gives a meaningless result; none can be reached through this API.
+
+// 1 / sqrt(a - d), with a = -1.
+const INVSQRT_A_MINUS_D: FieldElement = FieldElement::from_limbs_unchecked([
+    0x40ea, 0x805d, 0xfdaa, 0x99c8, 0x72be, 0x5a41, 0x1617, 0x9d2f, 0xd840, 0xfe01, 0x7b91, 0x16c2,
+    0xfca2, 0xcfaf, 0x8905, 0x786c,
+]);
+
+// sqrt(a * d - 1), the negative (odd) root, as RFC 9496 specifies.
+const SQRT_AD_MINUS_ONE: FieldElement = FieldElement::from_limbs_unchecked([
+    0x2e1b, 0x497b, 0xf6a0, 0x7e97, 0x54bd, 0x1b78, 0x8e0c, 0xaf9d, 0xd1fd, 0x31f5, 0xfcc9, 0x0f3c,
+    0x48ac, 0x2b83, 0x31bf, 0x3769,
+]);
+
+// 1 - d^2
+const ONE_MINUS_D_SQ: FieldElement = FieldElement::from_limbs_unchecked([
+    0xc176, 0x945f, 0x09c1, 0xe27c, 0x350f, 0xcd5e, 0xa138, 0x2c81, 0xdfe4, 0xbe70, 0xabdd, 0x9994,
+    0xe0d7, 0xb2b3, 0x72a8, 0x0290,
+]);
+
+// (d - 1)^2
+const D_MINUS_ONE_SQ: FieldElement = FieldElement::from_limbs_unchecked([
+    0x4d20, 0x44ed, 0x5aaa, 0x31ad, 0x1999, 0xb01e, 0x4a2c, 0xd29e, 0x4eeb, 0x529b, 0xd32f, 0x4cdc,
+    0x2241, 0xf66c, 0xb37a, 0x5968,
+]);
+
+// The 32-byte encoding of a ristretto255 element: a field element s,
+// little-endian, canonical (s < p) and non-negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressedRistretto(pub [u8; 32]);
+
+impl CompressedRistretto {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    // RFC 9496 section 4.3.1. Unlike CompressedEdwardsY::decompress this
+    // takes no shortcuts: s must be canonical and non-negative, the square
+    // root must exist, and the point must come out with t = xy
+    // non-negative and y != 0. Those checks are what make the encoding
+    // unique, so every byte string is accepted as at most one element and
+    // an element has exactly one encoding.
+    pub fn decompress(&self) -> Option<RistrettoPoint> {
+        let s = FieldElement::from_bytes(self.0);
+        let s_is_canonical = s.to_bytes().ct_eq(&self.0);
+        let s_is_negative = Choice::from(s.is_negative() as u8);
+
+        let ss = s * s;
+        let u1 = FieldElement::ONE - ss;
+        let u2 = FieldElement::ONE + ss;
+        let u2_sqr = u2 * u2;
+
+        // v = -(d * u1^2) - u2^2
+        let v = -(EDWARDS_D * u1 * u1) - u2_sqr;
+        let (was_square, invsqrt) = FieldElement::sqrt_ratio_i(&FieldElement::ONE, &(v * u2_sqr));
+
+        let den_x = invsqrt * u2;
+        let den_y = invsqrt * den_x * v;
+
+        // x = |2 s den_x|
+        let mut x = (s + s) * den_x;
+        let x_is_negative = Choice::from(x.is_negative() as u8);
+        x.conditional_negate(x_is_negative);
+        let y = u1 * den_y;
+        let t = x * y;
+
+        let valid = s_is_canonical
+            & !s_is_negative
+            & Choice::from(was_square as u8)
+            & !Choice::from(t.is_negative() as u8)
+            & !Choice::from(y.is_zero() as u8);
+        if !bool::from(valid) {
+            return None;
+        }
+        Some(RistrettoPoint(EdwardsPoint {
+            x,
+            y,
+            z: FieldElement::ONE,
+            t,
+        }))
+    }
+}
+
+// An element of ristretto255, represented by one of the four Edwards
+// points of its class.
+#[derive(Debug, Clone, Copy)]
+pub struct RistrettoPoint(EdwardsPoint);
+
+// The generator, the class of the ed25519 basepoint.
+pub const RISTRETTO_BASEPOINT: RistrettoPoint = RistrettoPoint(ED25519_BASEPOINT);
+
+impl RistrettoPoint {
+    pub const IDENTITY: RistrettoPoint = RistrettoPoint(EdwardsPoint::IDENTITY);
+
+    // RFC 9496 section 4.3.2. The point is first rotated, by adding a
+    // point of order 4 (which multiplies x and y by sqrt(-1) and swaps
+    // them), if xy is negative, and then y is negated if x is; that picks the
+    // same member of the class whichever one we started from. s is read
+    // off that member. A single inverse square root gives both the
+    // denominators and 1/z.
+    pub fn compress(&self) -> CompressedRistretto {
+        let EdwardsPoint { x, y, z, t } = self.0;
+
+        let u1 = (z + y) * (z - y);
+        let u2 = x * y;
+        let (_, invsqrt) = FieldElement::sqrt_ratio_i(&FieldElement::ONE, &(u1 * u2 * u2));
+        let den1 = invsqrt * u1;
+        let den2 = invsqrt * u2;
+        let z_inv = den1 * den2 * t;
+
+        let ix = x * FieldElement::SQRT_M1;
+        let iy = y * FieldElement::SQRT_M1;
+        let enchanted_denominator = den1 * INVSQRT_A_MINUS_D;
+
+        let rotate = Choice::from((t * z_inv).is_negative() as u8);
+        let x_rot = FieldElement::conditional_select(&x, &iy, rotate);
+        let mut y_rot = FieldElement::conditional_select(&y, &ix, rotate);
+        let den_inv = FieldElement::conditional_select(&den2, &enchanted_denominator, rotate);
+
+        y_rot.conditional_negate(Choice::from((x_rot * z_inv).is_negative() as u8));
+
+        let mut s = den_inv * (z - y_rot);
+        let s_is_negative = Choice::from(s.is_negative() as u8);
+        s.conditional_negate(s_is_negative);
+        CompressedRistretto(s.to_bytes())
+    }
+
+    // The one-way map of RFC 9496 section 4.3.4, for hashing to the group.
+    // Each half of the 64 bytes is read as a field element (top bit
+    // ignored, not necessarily reduced), mapped to a point with
+    // Elligator, and the two points are added. One application of the map
+    // only reaches about half of the group, and not uniformly; the sum of
+    // two is indistinguishable from uniform when the input is, which is
+    // what protocols hashing transcripts to generators need: nobody learns
+    // a discrete log of the result.
+    //
+    // Constant time, so the input may be secret.
+    pub fn from_uniform_bytes(bytes: &[u8; 64]) -> RistrettoPoint {
+        let mut halves = [[0u8; 32]; 2];
+        halves[0].copy_from_slice(&bytes[..32]);
+        halves[1].copy_from_slice(&bytes[32..]);
+        let [p1, p2] = halves.map(|mut half| {
+            half[31] &= 0x7f;
+            elligator(&FieldElement::from_bytes(half))
+        });
+        p1 + p2
+    }
+
+    // from_uniform_bytes of the 64-byte hash of input with D, usually
+    // Sha512.
+    pub fn hash_from_bytes<D: Hash512>(input: &[u8]) -> RistrettoPoint {
+        Self::from_uniform_bytes(&D::digest(input).into())
+    }
+
+    // Constant-time scalar multiplication. Multiplying any member of the
+    // class gives a member of the class of the product, so this is just
+    // EdwardsPoint::mul on the representative.
+    pub fn mul(&mut self, scalar: &Scalar) -> &mut Self {
+        self.0.mul(scalar);
+        self
+    }
+
+    // Variable-time multiplication, for public scalars only.
+    pub fn mul_vartime(&mut self, scalar: &Scalar) -> &mut Self {
+        self.0.mul_vartime(scalar);
+        self
+    }
+
+    // a * A + b * B for the generator B, in variable time; the form
+    // Schnorr verification takes.
+    pub fn vartime_double_scalar_mul_basepoint(
+        a: &Scalar,
+        point: &RistrettoPoint,
+        b: &Scalar,
+    ) -> RistrettoPoint {
+        RistrettoPoint(EdwardsPoint::vartime_double_scalar_mul_basepoint(
+            a, &point.0, b,
+        ))
+    }
+
+    // sum a_i * P_i in variable time, with public scalars.
+    //
+    // Panics if the slices have different lengths.
+    pub fn vartime_multiscalar_mul(
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+    ) -> RistrettoPoint {
+        let points: Vec<EdwardsPoint> = points.iter().map(|p| p.0).collect();
+        RistrettoPoint(EdwardsPoint::vartime_multiscalar_mul(scalars, &points))
+    }
+
+    // The Edwards point this element is represented by. It is some member
+    // of the class, not a particular one, so only use it for things that
+    // don't depend on the choice (e.g. after multiplying by 4).
+    pub fn to_edwards(&self) -> EdwardsPoint {
+        self.0
+    }
+}
+
+// Precomputed multiples of a fixed element, for fast constant-time
+// multiplication by it; see EdwardsBasepointTable, which this wraps.
+#[derive(Clone)]
+pub struct RistrettoBasepointTable(EdwardsBasepointTable);
+
+impl RistrettoBasepointTable {
+    pub fn create(basepoint: &RistrettoPoint) -> Self {
+        Self(EdwardsBasepointTable::create(&basepoint.0))
+    }
+
+    pub fn basepoint(&self) -> RistrettoPoint {
+        RistrettoPoint(self.0.basepoint())
+    }
+
+    pub fn mul_base(&self, scalar: &Scalar) -> RistrettoPoint {
+        RistrettoPoint(self.0.mul_base(scalar))
+    }
+}
+
+// The table for RISTRETTO_BASEPOINT, built the first time it is used. It
+// holds the same points as ED25519_BASEPOINT_TABLE but is built
+// separately, so using one doesn't pay for the other.
+pub static RISTRETTO_BASEPOINT_TABLE: LazyLock<RistrettoBasepointTable> =
+    LazyLock::new(|| RistrettoBasepointTable::create(&RISTRETTO_BASEPOINT));
+
+impl std::ops::Mul<&Scalar> for &RistrettoBasepointTable {
+    type Output = RistrettoPoint;
+
+    fn mul(self, scalar: &Scalar) -> RistrettoPoint {
+        self.mul_base(scalar)
+    }
+}
+
+impl std::ops::Mul<&RistrettoBasepointTable> for &Scalar {
+    type Output = RistrettoPoint;
+
+    fn mul(self, table: &RistrettoBasepointTable) -> RistrettoPoint {
+        table.mul_base(self)
+    }
+}
+
+// The ristretto flavour of Elligator 2, MAP in RFC 9496 section 4.3.4. It
+// maps t to a point of the Jacobi quartic and on to the Edwards curve
+// through the isogeny, giving the result in extended coordinates
+// directly, so there is no inversion.
+fn elligator(t: &FieldElement) -> RistrettoPoint {
+    let one = FieldElement::ONE;
+    let r = FieldElement::SQRT_M1 * t * t;
+    let u = (r + one) * ONE_MINUS_D_SQ;
+    let v = (-one - r * EDWARDS_D) * (r + EDWARDS_D);
+
+    let (was_square, mut s) = FieldElement::sqrt_ratio_i(&u, &v);
+    let was_square = Choice::from(was_square as u8);
+    // s' = -|s t|
+    let mut s_prime = s * t;
+    let s_prime_is_negative = Choice::from(s_prime.is_negative() as u8);
+    s_prime.conditional_negate(!s_prime_is_negative);
+    s.conditional_assign(&s_prime, !was_square);
+    let c = FieldElement::conditional_select(&r, &-one, was_square);
+
+    let n = c * (r - one) * D_MINUS_ONE_SQ - v;
+    let w0 = (s + s) * v;
+    let w1 = n * SQRT_AD_MINUS_ONE;
+    let ss = s * s;
+    let w2 = one - ss;
+    let w3 = one + ss;
+    RistrettoPoint(EdwardsPoint {
+        x: w0 * w3,
+        y: w2 * w1,
+        z: w1 * w3,
+        t: w0 * w2,
+    })
+}
+
+// Two Edwards points are in the same class when x1 y2 = y1 x2 (they
+// differ by the identity or T4) or y1 y2 = x1 x2 (they differ by T2 or
+// T6). Both sides are compared in projective coordinates, where the Z's
+// cancel.
+impl ConstantTimeEq for RistrettoPoint {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        let (a, b) = (&self.0, &other.0);
+        (a.x * b.y).ct_eq(&(a.y * b.x)) | (a.y * b.y).ct_eq(&(a.x * b.x))
+    }
+}
+
+impl PartialEq for RistrettoPoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for RistrettoPoint {}
+
+impl ConditionallySelectable for RistrettoPoint {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        RistrettoPoint(EdwardsPoint::conditional_select(&a.0, &b.0, choice))
+    }
+}
+
+impl std::ops::AddAssign<&RistrettoPoint> for RistrettoPoint {
+    fn add_assign(&mut self, rhs: &RistrettoPoint) {
+        self.0 += &rhs.0;
+    }
+}
+
+impl std::ops::AddAssign<RistrettoPoint> for RistrettoPoint {
+    fn add_assign(&mut self, rhs: RistrettoPoint) {
+        *self += &rhs;
+    }
+}
+
+impl std::ops::Add<&RistrettoPoint> for &RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn add(self, rhs: &RistrettoPoint) -> RistrettoPoint {
+        let mut result = *self;
+        result += rhs;
+        result
+    }
+}
+
+impl std::ops::Add<RistrettoPoint> for RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn add(self, rhs: RistrettoPoint) -> RistrettoPoint {
+        &self + &rhs
+    }
+}
+
+impl std::ops::Add<&RistrettoPoint> for RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn add(mut self, rhs: &RistrettoPoint) -> RistrettoPoint {
+        self += rhs;
+        self
+    }
+}
+
+impl std::ops::Neg for &RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn neg(self) -> RistrettoPoint {
+        RistrettoPoint(-&self.0)
+    }
+}
+
+impl std::ops::Neg for RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn neg(self) -> RistrettoPoint {
+        -&self
+    }
+}
+
+impl std::ops::SubAssign<&RistrettoPoint> for RistrettoPoint {
+    fn sub_assign(&mut self, rhs: &RistrettoPoint) {
+        self.0 -= &rhs.0;
+    }
+}
+
+impl std::ops::SubAssign<RistrettoPoint> for RistrettoPoint {
+    fn sub_assign(&mut self, rhs: RistrettoPoint) {
+        *self -= &rhs;
+    }
+}
+
+impl std::ops::Sub<&RistrettoPoint> for &RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn sub(self, rhs: &RistrettoPoint) -> RistrettoPoint {
+        let mut result = *self;
+        result -= rhs;
+        result
+    }
+}
+
+impl std::ops::Sub<RistrettoPoint> for RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn sub(self, rhs: RistrettoPoint) -> RistrettoPoint {
+        &self - &rhs
+    }
+}
+
+impl std::ops::Sub<&RistrettoPoint> for RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn sub(mut self, rhs: &RistrettoPoint) -> RistrettoPoint {
+        self -= rhs;
+        self
+    }
+}
+
+impl std::ops::Mul<&Scalar> for &RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn mul(self, scalar: &Scalar) -> RistrettoPoint {
+        let mut result = *self;
+        RistrettoPoint::mul(&mut result, scalar);
+        result
+    }
+}
+
+impl std::ops::Mul<&RistrettoPoint> for &Scalar {
+    type Output = RistrettoPoint;
+
+    fn mul(self, point: &RistrettoPoint) -> RistrettoPoint {
+        point * self
+    }
+}
+
+impl std::ops::Mul<Scalar> for RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn mul(self, scalar: Scalar) -> RistrettoPoint {
+        &self * &scalar
+    }
+}
+
+impl std::ops::Mul<RistrettoPoint> for Scalar {
+    type Output = RistrettoPoint;
+
+    fn mul(self, point: RistrettoPoint) -> RistrettoPoint {
+        &point * &self
+    }
+}
+
+impl std::ops::Mul<&Scalar> for RistrettoPoint {
+    type Output = RistrettoPoint;
+
+    fn mul(mut self, scalar: &Scalar) -> RistrettoPoint {
+        self *= scalar;
+        self
+    }
+}
+
+impl std::ops::MulAssign<&Scalar> for RistrettoPoint {
+    fn mul_assign(&mut self, scalar: &Scalar) {
+        RistrettoPoint::mul(self, scalar);
+    }
+}
+
+impl std::ops::MulAssign<Scalar> for RistrettoPoint {
+    fn mul_assign(&mut self, scalar: Scalar) {
+        RistrettoPoint::mul(self, &scalar);
+    }
+}
+
+impl std::iter::Sum for RistrettoPoint {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(RistrettoPoint::IDENTITY, |acc, p| acc + p)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a RistrettoPoint> for RistrettoPoint {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(RistrettoPoint::IDENTITY, |acc, p| acc + p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edwards::EIGHT_TORSION;
+
+    // RFC 9496 appendix A.1: the encodings of 0, B, 2B, ..., 15B.
+    const MULTIPLES: [&str; 16] = [
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76",
+        "6a493210f7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b919",
+        "94741f5d5d52755ece4f23f044ee27d5d1ea1e2bd196b462166b16152a9d0259",
+        "da80862773358b466ffadfe0b3293ab3d9fd53c5ea6c955358f568322daf6a57",
+        "e882b131016b52c1d3337080187cf768423efccbb517bb495ab812c4160ff44e",
+        "f64746d3c92b13050ed8d80236a7f0007c3b3f962f5ba793d19a601ebb1df403",
+        "44f53520926ec81fbd5a387845beb7df85a96a24ece18738bdcfa6a7822a176d",
+        "903293d8f2287ebe10e2374dc1a53e0bc887e592699f02d077d5263cdd55601c",
+        "02622ace8f7303a31cafc63f8fc48fdc16e1c8c8d234b2f0d6685282a9076031",
+        "20706fd788b2720a1ed2a5dad4952b01f413bcf0e7564de8cdc816689e2db95f",
+        "bce83f8ba5dd2fa572864c24ba1810f9522bc6004afe95877ac73241cafdab42",
+        "e4549ee16b9aa03099ca208c67adafcafa4c3f3e4e5303de6026e3ca8ff84460",
+        "aa52e000df2e16f55fb1032fc33bc42742dad6bd5a8fc0be0167436c5948501f",
+        "46376b80f409b29dc2b5f6f0c52591990896e5716f41477cd30085ab7f10301e",
+        "e0c418f7c8d9c4cdd7395b93ea124f3ad99021bb681dfc3302a9d99a2e53e64e",
+    ];
+
+    // RFC 9496 appendix A.2, grouped by the check that rejects them.
+    const BAD_ENCODINGS: [&str; 32] = [
+        // non-canonical field encodings
+        "00ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "f3ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        // negative field elements
+        "0100000000000000000000000000000000000000000000000000000000000000",
+        "01ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "ed57ffd8c914fb201471d1c3d245ce3c746fcbe63a3679d51b6a516ebebe0e20",
+        "c34c4e1826e5d403b78e246e88aa051c36ccf0aafebffe137d148a2bf9104562",
+        "c940e5a4404157cfb1628b108db051a8d439e1a421394ec4ebccb9ec92a8ac78",
+        "47cfc5497c53dc8e61c91d17fd626ffb1c49e2bca94eed052281b510b1117a24",
+        "f1c6165d33367351b0da8f6e4511010c68174a03b6581212c71c0e1d026c3c72",
+        "87260f7a2f12495118360f02c26a470f450dadf34a413d21042b43b9d93e1309",
+        // non-square x^2
+        "26948d35ca62e643e26a83177332e6b6afeb9d08e4268b650f1f5bbd8d81d371",
+        "4eac077a713c57b4f4397629a4145982c661f48044dd3f96427d40b147d9742f",
+        "de6a7b00deadc788eb6b6c8d20c0ae96c2f2019078fa604fee5b87d6e989ad7b",
+        "bcab477be20861e01e4a0e295284146a510150d9817763caf1a6f4b422d67042",
+        "2a292df7e32cababbd9de088d1d1abec9fc0440f637ed2fba145094dc14bea08",
+        "f4a9e534fc0d216c44b218fa0c42d99635a0127ee2e53c712f70609649fdff22",
+        "8268436f8c4126196cf64b3c7ddbda90746a378625f9813dd9b8457077256731",
+        "2810e5cbc2cc4d4eece54f61c6f69758e289aa7ab440b3cbeaa21995c2f4232b",
+        // negative xy value
+        "3eb858e78f5a7254d8c9731174a94f76755fd3941c0ac93735c07ba14579630e",
+        "a45fdc55c76448c049a1ab33f17023edfb2be3581e9c7aade8a6125215e04220",
+        "d483fe813c6ba647ebbfd3ec41adca1c6130c2beeee9d9bf065c8d151c5f396e",
+        "8a2e1d30050198c65a54483123960ccc38aef6848e1ec8f5f780e8523769ba32",
+        "32888462f8b486c68ad7dd9610be5192bbeaf3b443951ac1a8118419d9fa097b",
+        "227142501b9d4355ccba290404bde41575b037693cef1f438c47f8fbf35d1165",
+        "5c37cc491da847cfeb9281d407efc41e15144c876e0170b499a96a22ed31e01e",
+        "445425117cb8c90edcbc7c1cc0e74f747f2c1efa5630a967c64f287792a48a4b",
+        // s = -1, which gives y = 0
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        // more non-canonical encodings: p + 1, and s with the top bit set
+        "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "0000000000000000000000000000000000000000000000000000000000000080",
+        "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2df6",
+    ];
+
+    fn compressed(hex: &str) -> CompressedRistretto {
+        CompressedRistretto(hex::decode(hex).unwrap().try_into().unwrap())
+    }
+
+    #[test]
+    fn multiples_of_the_basepoint() {
+        let mut point = RistrettoPoint::IDENTITY;
+        for (i, expected) in MULTIPLES.iter().enumerate() {
+            assert_eq!(hex::encode(point.compress().0), *expected, "{i}B");
+            let decoded = compressed(expected).decompress().unwrap();
+            assert_eq!(decoded, point, "{i}B");
+            assert_eq!(decoded.compress(), point.compress());
+            point += RISTRETTO_BASEPOINT;
+        }
+    }
+
+    #[test]
+    fn bad_encodings() {
+        for bad in BAD_ENCODINGS {
+            assert!(compressed(bad).decompress().is_none(), "{bad}");
+        }
+    }
+
+    // The four points of a class encode the same and compare equal, and
+    // the other half of the torsion (odd multiples of T) gives different
+    // elements.
+    #[test]
+    fn torsion_is_quotiented_out() {
+        let p = RistrettoPoint(ED25519_BASEPOINT + ED25519_BASEPOINT);
+        for k in [0, 2, 4, 6] {
+            let q = RistrettoPoint(p.0.with_torsion(k));
+            assert_eq!(q, p, "T{k}");
+            assert_eq!(q.compress(), p.compress(), "T{k}");
+        }
+        for k in [1, 3, 5, 7] {
+            let q = RistrettoPoint(p.0 + EIGHT_TORSION[k]);
+            assert_ne!(q, p, "T{k}");
+        }
+        assert_eq!(
+            RistrettoPoint(EIGHT_TORSION[4]).compress(),
+            RistrettoPoint::IDENTITY.compress()
+        );
+    }
+
+    // RFC 9496 appendix A.3: 64-byte inputs (SHA-512 hashes of strings)
+    // and the encodings of the elements they map to.
+    const UNIFORM: [(&str, &str); 7] = [
+        (
+            "5d1be09e3d0c82fc538112490e35701979d99e06ca3e2b5b54bffe8b4dc772c1\
+             4d98b696a1bbfb5ca32c436cc61c16563790306c79eaca7705668b47dffe5bb6",
+            "3066f82a1a747d45120d1740f14358531a8f04bbffe6a819f86dfe50f44a0a46",
+        ),
+        (
+            "f116b34b8f17ceb56e8732a60d913dd10cce47a6d53bee9204be8b44f6678b27\
+             0102a56902e2488c46120e9276cfe54638286b9e4b3cdb470b542d46c2068d38",
+            "f26e5b6f7d362d2d2a94c5d0e7602cb4773c95a2e5c31a64f133189fa76ed61b",
+        ),
+        (
+            "8422e1bbdaab52938b81fd602effb6f89110e1e57208ad12d9ad767e2e25510c\
+             27140775f9337088b982d83d7fcf0b2fa1edffe51952cbe7365e95c86eaf325c",
+            "006ccd2a9e6867e6a2c5cea83d3302cc9de128dd2a9a57dd8ee7b9d7ffe02826",
+        ),
+        (
+            "ac22415129b61427bf464e17baee8db65940c233b98afce8d17c57beeb7876c2\
+             150d15af1cb1fb824bbd14955f2b57d08d388aab431a391cfc33d5bafb5dbbaf",
+            "f8f0c87cf237953c5890aec3998169005dae3eca1fbb04548c635953c817f92a",
+        ),
+        (
+            "165d697a1ef3d5cf3c38565beefcf88c0f282b8e7dbd28544c483432f1cec767\
+             5debea8ebb4e5fe7d6f6e5db15f15587ac4d4d4a1de7191e0c1ca6664abcc413",
+            "ae81e7dedf20a497e10c304a765c1767a42d6e06029758d2d7e8ef7cc4c41179",
+        ),
+        (
+            "a836e6c9a9ca9f1e8d486273ad56a78c70cf18f0ce10abb1c7172ddd605d7fd2\
+             979854f47ae1ccf204a33102095b4200e5befc0465accc263175485f0e17ea5c",
+            "e2705652ff9f5e44d3e841bf1c251cf7dddb77d140870d1ab2ed64f1a9ce8628",
+        ),
+        (
+            "2cdc11eaeb95daf01189417cdddbf95952993aa9cb9c640eb5058d09702c7462\
+             2c9965a697a3b345ec24ee56335b556e677b30e6f90ac77d781064f866a3c982",
+            "80bd07262511cdde4863f8a7434cef696750681cb9510eea557088f76d9e5065",
+        ),
+    ];
+
+    #[test]
+    fn uniform_bytes() {
+        for (input, expected) in UNIFORM {
+            let input: [u8; 64] = hex::decode(input).unwrap().try_into().unwrap();
+            let point = RistrettoPoint::from_uniform_bytes(&input);
+            assert_eq!(hex::encode(point.compress().0), expected);
+            // the result is a proper element: it decodes back to itself
+            assert_eq!(compressed(expected).decompress(), Some(point));
+        }
+    }
+
+    // The map itself sends 0 to the identity, and the top bit of each half
+    // is ignored.
+    #[test]
+    fn uniform_bytes_edge_cases() {
+        assert_eq!(
+            RistrettoPoint::from_uniform_bytes(&[0; 64]),
+            RistrettoPoint::IDENTITY
+        );
+        let (input, _) = UNIFORM[0];
+        let mut input: [u8; 64] = hex::decode(input).unwrap().try_into().unwrap();
+        let point = RistrettoPoint::from_uniform_bytes(&input);
+        input[31] ^= 0x80;
+        input[63] ^= 0x80;
+        assert_eq!(RistrettoPoint::from_uniform_bytes(&input), point);
+        input[0] ^= 1;
+        assert_ne!(RistrettoPoint::from_uniform_bytes(&input), point);
+    }
+
+    #[test]
+    fn hash_from_bytes() {
+        let point = RistrettoPoint::hash_from_bytes::<sha2::Sha512>(
+            b"Ristretto is traditionally a short shot of espresso coffee",
+        );
+        let (_, expected) = UNIFORM[0];
+        assert_eq!(hex::encode(point.compress().0), expected);
+    }
+
+    // k * B for k up to 15 three ways, against the RFC encodings.
+    #[test]
+    fn scalar_multiplication() {
+        for (k, expected) in MULTIPLES.iter().enumerate() {
+            let k = Scalar::from(k as u64);
+            let by_table = &k * &*RISTRETTO_BASEPOINT_TABLE;
+            let by_window = RISTRETTO_BASEPOINT * k;
+            let mut by_naf = RISTRETTO_BASEPOINT;
+            by_naf.mul_vartime(&k);
+            for point in [by_table, by_window, by_naf] {
+                assert_eq!(hex::encode(point.compress().0), *expected);
+            }
+        }
+
+        // -1 (that is, L - 1), and a table for another element
+        let three = compressed(MULTIPLES[3]).decompress().unwrap();
+        assert_eq!(three * -Scalar::ONE, -three);
+        let table = RistrettoBasepointTable::create(&three);
+        assert_eq!(table.basepoint(), three);
+        assert_eq!(
+            hex::encode(table.mul_base(&Scalar::from(5)).compress().0),
+            MULTIPLES[15]
+        );
+
+        // a representative with 4-torsion added gives the same result
+        let rotated = RistrettoPoint(three.0.with_torsion(2));
+        assert_eq!(
+            (rotated * Scalar::from(5)).compress(),
+            (three * Scalar::from(5)).compress()
+        );
+    }
+
+    #[test]
+    fn multiscalar_multiplication() {
+        let points: Vec<_> = MULTIPLES[1..5]
+            .iter()
+            .map(|e| compressed(e).decompress().unwrap())
+            .collect();
+        let scalars: Vec<_> = (0..4u64)
+            .map(|i| Scalar::from_bytes_mod_order([i as u8 * 37 + 1; 32]))
+            .collect();
+        let expected: RistrettoPoint = scalars.iter().zip(&points).map(|(s, p)| p * s).sum();
+        assert_eq!(
+            RistrettoPoint::vartime_multiscalar_mul(&scalars, &points),
+            expected
+        );
+        assert_eq!(
+            RistrettoPoint::vartime_multiscalar_mul(&[], &[]),
+            RistrettoPoint::IDENTITY
+        );
+
+        let (a, b) = (scalars[1], scalars[2]);
+        assert_eq!(
+            RistrettoPoint::vartime_double_scalar_mul_basepoint(&a, &points[3], &b),
+            points[3] * a + &b * &*RISTRETTO_BASEPOINT_TABLE
+        );
+    }
+
+    #[test]
+    fn group_operations() {
+        let b = RISTRETTO_BASEPOINT;
+        let two = compressed(MULTIPLES[2]).decompress().unwrap();
+        let three = compressed(MULTIPLES[3]).decompress().unwrap();
+        assert_eq!(two + b, three);
+        assert_eq!(three - two, b);
+        assert_eq!(-b + b, RistrettoPoint::IDENTITY);
+        assert_eq!([b, b, b].iter().sum::<RistrettoPoint>(), three);
+        assert_eq!((-b).compress(), (two - three).compress());
+
+        let selected = RistrettoPoint::conditional_select(&b, &two, Choice::from(1));
+        assert_eq!(selected, two);
+    }
+}
@@ -0,0 +1,1104 @@
+use std::fmt;
+use std::str::FromStr;
+
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::limbs::{self, Modulus};
+
+// The order of the prime-order subgroup generated by the ed25519 basepoint,
+// L = 2^252 + 27742317777372353535851937790883648493, as little-endian bytes.
+// Scalars multiplying points only matter mod L, so this is the modulus for
+// all the arithmetic in this module.
+const L: [i64; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10,
+];
+
+// L in 8-bit limbs. Reduction is TweetNaCl's modL (see mod_l below), which
+// always produces the canonical representative.
+pub(crate) struct GroupOrder;
+
+impl Modulus<32> for GroupOrder {
+    const LIMB_BITS: u32 = 8;
+    const MODULUS: [i64; 32] = L;
+
+    fn fold_carry(limbs: &mut [i64; 32], carry: i64) {
+        let mut hi = [0; 32];
+        hi[0] = carry;
+        *limbs = Self::reduce_wide(*limbs, hi);
+    }
+
+    fn reduce_wide(lo: [i64; 32], hi: [i64; 32]) -> [i64; 32] {
+        let mut x = [0; 64];
+        x[..32].copy_from_slice(&lo);
+        x[32..].copy_from_slice(&hi);
+        mod_l(x)
+    }
+}
+
+// Reduces a number given as 64 signed "bytes" (limbs weighted by 2^(8 * i),
+// that may be negative or larger than 8 bits) mod L. This is modL from
+// TweetNaCl.
+//
+// L is 2^252 + a 125-bit number, so 2^252 = -(L - 2^252) mod L. Going
+// from the top limb down to limb 32, each limb x[i] is worth
+// x[i] * 2^(8 * i) = 16 * x[i] * 2^(8 * (i - 32)) * 2^252, so it can be
+// removed by subtracting 16 * x[i] * L shifted down by (i - 32) limbs.
+// L only has 16 non-zero low bytes (+ 0x10 at the top that we just
+// cancelled), so only 20 limbs need touching. Carries are rounded
+// ((x + 128) >> 8) to keep limbs within [-128, 128).
+//
+// What is left is below 2^256ish. The top limb x[31] tells us roughly
+// how many multiples of L (= x[31] / 16) remain, so we subtract those
+// and carry normally. That can undershoot by one L, which shows up as
+// a final carry of -1, and we add L back in that case.
+fn mod_l(mut x: [i64; 64]) -> [i64; 32] {
+    for i in (32..64).rev() {
+        let mut carry = 0;
+        for j in (i - 32)..(i - 12) {
+            x[j] += carry - 16 * x[i] * L[j - (i - 32)];
+            carry = (x[j] + 128) >> 8;
+            x[j] -= carry << 8;
+        }
+        x[i - 12] += carry;
+        x[i] = 0;
+    }
+
+    let mut carry = 0;
+    for j in 0..32 {
+        x[j] += carry - (x[31] >> 4) * L[j];
+        carry = x[j] >> 8;
+        x[j] &= 0xff;
+    }
+    for j in 0..32 {
+        x[j] -= carry * L[j];
+    }
+
+    let mut reduced = [0; 32];
+    for i in 0..32 {
+        x[i + 1] += x[i] >> 8;
+        reduced[i] = x[i] & 0xff;
+    }
+    reduced
+}
+
+// An integer mod L, always kept fully reduced as 32 little-endian bytes.
+// Scalars are often secret (private keys, nonces), so equality is
+// constant-time and Debug doesn't print the value.
+#[derive(Clone, Copy, Default)]
+pub struct Scalar {
+    bytes: [u8; 32],
+}
+
+impl Scalar {
+    pub const ZERO: Self = Self { bytes: [0; 32] };
+    pub const ONE: Self = Self {
+        bytes: [
+            1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0,
+        ],
+    };
+
+    // Interprets the bytes as a little-endian 256-bit integer and reduces it mod L.
+    pub fn from_bytes_mod_order(bytes: [u8; 32]) -> Self {
+        let lo = limbs::from_bytes::<GroupOrder, 32>(&bytes);
+        Self::from_reduced(GroupOrder::reduce_wide(lo, [0; 32]))
+    }
+
+    // Accepts only the canonical encoding of a scalar, i.e. bytes that
+    // represent an integer less than L. RFC 8032 requires verifiers to
+    // reject signatures whose S is not reduced, otherwise S + L would be a
+    // second valid signature for the same message.
+    pub fn from_canonical_bytes(bytes: [u8; 32]) -> Option<Self> {
+        let reduced = Self::from_bytes_mod_order(bytes);
+        if reduced.bytes == bytes {
+            Some(reduced)
+        } else {
+            None
+        }
+    }
+
+    // Interprets the bytes as a little-endian 512-bit integer and reduces it
+    // mod L (sc_reduce in the reference implementation). Ed25519 hashes with
+    // SHA-512 and turns the 64-byte digests into scalars this way. Reducing
+    // a number this much larger than L also leaves a negligible bias, which
+    // is why random scalars are sampled from 64 bytes too.
+    pub fn from_bytes_mod_order_wide(bytes: [u8; 64]) -> Self {
+        let lo = limbs::from_bytes::<GroupOrder, 32>(bytes[..32].try_into().unwrap());
+        let hi = limbs::from_bytes::<GroupOrder, 32>(bytes[32..].try_into().unwrap());
+        Self::from_reduced(GroupOrder::reduce_wide(lo, hi))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.bytes
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.bytes
+    }
+
+    // Samples 64 bytes and reduces them mod L. Reducing 512 bits rather
+    // than 256 makes the bias towards small values negligible (about
+    // 2^-259), so the result is as good as uniform.
+    #[cfg(feature = "rand")]
+    pub fn random<R: rand_core::RngCore + rand_core::CryptoRng>(rng: &mut R) -> Self {
+        let mut bytes = [0; 64];
+        rng.fill_bytes(&mut bytes);
+        Self::from_bytes_mod_order_wide(bytes)
+    }
+
+    // Reduces a number given as 64 signed limbs weighted by 2^(8 * i),
+    // which may be negative or larger than 8 bits, mod L.
+    pub fn reduce(x: [i64; 64]) -> Self {
+        let lo = x[..32].try_into().unwrap();
+        let hi = x[32..].try_into().unwrap();
+        Self::from_reduced(GroupOrder::reduce_wide(lo, hi))
+    }
+
+    fn from_reduced(limbs: [i64; 32]) -> Self {
+        Self {
+            bytes: limbs::to_bytes::<GroupOrder, 32>(&limbs),
+        }
+    }
+
+    fn limbs(&self) -> [i64; 32] {
+        limbs::from_bytes::<GroupOrder, 32>(&self.bytes)
+    }
+
+    // Limbs of the sum or difference stay within [-255, 510], so they can
+    // be handed to the reduction without carrying first.
+    pub fn add(&mut self, other: &Self) -> &mut Self {
+        let mut x = self.limbs();
+        limbs::add(&mut x, &other.limbs());
+        *self = Self::from_reduced(GroupOrder::reduce_wide(x, [0; 32]));
+        self
+    }
+
+    pub fn sub(&mut self, other: &Self) -> &mut Self {
+        let mut x = self.limbs();
+        limbs::sub(&mut x, &other.limbs());
+        *self = Self::from_reduced(GroupOrder::reduce_wide(x, [0; 32]));
+        self
+    }
+
+    // Schoolbook multiplication into 64 limbs, each at most
+    // 32 * 255 * 255 < 2^21, which mod_l handles without overflow.
+    pub fn mul(&mut self, other: &Self) -> &mut Self {
+        *self = Self::from_reduced(limbs::mul::<GroupOrder, 32>(&self.limbs(), &other.limbs()));
+        self
+    }
+
+    // The multiplicative inverse, by Fermat's little theorem: a^(L - 2) is
+    // 1/a mod L. The exponent is public, so square-and-multiply over its
+    // bits runs the same steps for every a. Zero has no inverse and maps
+    // to zero.
+    pub fn invert(&self) -> Self {
+        let mut exponent = limbs::to_bytes::<GroupOrder, 32>(&L);
+        exponent[0] -= 2;
+
+        let mut result = Scalar::ONE;
+        for i in (0..256).rev() {
+            result = result * result;
+            if (exponent[i / 8] >> (i % 8)) & 1 == 1 {
+                result *= self;
+            }
+        }
+        result
+    }
+
+    // Turns 32 secret bytes into the scalar used by X25519 and Ed25519:
+    // clears the low 3 bits (a multiple of the cofactor 8, so small-order
+    // components of a point are killed), clears bit 255 and sets bit 254
+    // (so every scalar has the same bit length and the Montgomery ladder
+    // always runs the same number of steps).
+    //
+    // The result is deliberately not reduced mod L: X25519 multiplies by
+    // the clamped integer itself, and k mod L is no longer a multiple of 8.
+    // So it gets its own type rather than being a Scalar.
+    pub fn clamp(mut bytes: [u8; 32]) -> ClampedScalar {
+        bytes[0] &= 248;
+        bytes[31] &= 127;
+        bytes[31] |= 64;
+        ClampedScalar { bytes }
+    }
+
+    // Computes a * b + c mod L with a single reduction, the way S = r + k * s
+    // is computed when signing. The products are accumulated into 64 limbs
+    // as in mul, c is added to the low limbs, and only then is the whole
+    // thing reduced.
+    pub fn muladd(a: &Self, b: &Self, c: &Self) -> Self {
+        let (mut lo, hi) = limbs::mul_wide(&a.limbs(), &b.limbs());
+        limbs::add(&mut lo, &c.limbs());
+        Self::from_reduced(GroupOrder::reduce_wide(lo, hi))
+    }
+
+    // The bits of the scalar, least significant first.
+    pub fn bits(&self) -> [u8; 256] {
+        let mut bits = [0; 256];
+        for i in 0..256 {
+            bits[i] = (self.bytes[i >> 3] >> (i & 7)) & 1;
+        }
+        bits
+    }
+
+    // Width-w non-adjacent form: digits d_i with sum d_i * 2^i equal to
+    // the scalar, where every non-zero digit is odd and |d_i| < 2^(w-1),
+    // and any w consecutive digits contain at most one non-zero digit.
+    // Variable-time scalar multiplication only needs a table of the odd
+    // multiples P, 3P, ..., (2^(w-1) - 1)P and one addition per non-zero
+    // digit, about 256 / (w + 1) of them.
+    //
+    // We scan the bits from the bottom, skipping zeros. At an odd bit we
+    // take the next w bits (plus the carry) as the digit; if it is at
+    // least 2^(w-1) we use digit - 2^w instead and carry 2^w into the
+    // next window. Scalars are below 2^253, so the final carry always
+    // fits in the 256 digits.
+    //
+    // Takes variable time, so it must only be used with public scalars.
+    pub fn non_adjacent_form(&self, w: usize) -> [i8; 256] {
+        assert!((2..=8).contains(&w), "NAF width must be between 2 and 8");
+
+        let bits = self.bits();
+        let width = 1i64 << w;
+        let mut naf = [0; 256];
+        let mut carry = 0;
+        let mut pos = 0;
+        while pos < 256 {
+            if bits[pos] as i64 == carry {
+                // bit + carry is even: zero digit
+                pos += 1;
+                continue;
+            }
+
+            let mut window = carry;
+            for i in 0..w {
+                if pos + i < 256 {
+                    window += (bits[pos + i] as i64) << i;
+                }
+            }
+
+            if window < width / 2 {
+                carry = 0;
+                naf[pos] = window as i8;
+            } else {
+                carry = 1;
+                naf[pos] = (window - width) as i8;
+            }
+            pos += w;
+        }
+
+        naf
+    }
+
+    // Signed radix-2^w digits d_i with sum d_i * 2^(w * i) equal to the
+    // scalar and -2^(w-1) <= d_i < 2^(w-1), for fixed-window scalar
+    // multiplication. Signed digits halve the table size, since -dP is just
+    // the negation of dP. The last digit absorbs the final carry.
+    pub fn to_radix_2w(&self, w: usize) -> Vec<i8> {
+        assert!((2..=8).contains(&w), "radix width must be between 2 and 8");
+
+        let bits = self.bits();
+        let count = 256usize.div_ceil(w);
+        let mut digits = vec![0i64; count];
+        for i in 0..256 {
+            digits[i / w] += (bits[i] as i64) << (i % w);
+        }
+
+        let half = 1i64 << (w - 1);
+        for i in 0..count - 1 {
+            let carry = (digits[i] + half) >> w;
+            digits[i] -= carry << w;
+            digits[i + 1] += carry;
+        }
+
+        digits.into_iter().map(|d| d as i8).collect()
+    }
+}
+
+impl ConstantTimeEq for Scalar {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.bytes.ct_eq(&other.bytes)
+    }
+}
+
+impl PartialEq for Scalar {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for Scalar {}
+
+impl ConditionallySelectable for Scalar {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        let mut bytes = [0; 32];
+        for i in 0..32 {
+            bytes[i] = u8::conditional_select(&a.bytes[i], &b.bytes[i], choice);
+        }
+        Self { bytes }
+    }
+}
+
+impl Zeroize for Scalar {
+    fn zeroize(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+impl fmt::Debug for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Scalar(<redacted>)")
+    }
+}
+
+// Hex encoding of the little-endian bytes, the way scalars appear in test
+// vectors. Unlike Debug this does print the value, so it should only be
+// used deliberately.
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.bytes.iter() {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseScalarError {
+    // The string was not 64 hex digits long.
+    InvalidLength,
+    // The string contained something other than hex digits.
+    InvalidHex,
+    // The bytes encode an integer that is not less than L.
+    NonCanonical,
+}
+
+impl fmt::Display for ParseScalarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseScalarError::InvalidLength => f.write_str("expected 64 hex digits"),
+            ParseScalarError::InvalidHex => f.write_str("invalid hex digit"),
+            ParseScalarError::NonCanonical => f.write_str("scalar is not reduced mod L"),
+        }
+    }
+}
+
+impl std::error::Error for ParseScalarError {}
+
+// Parses the Display format. Like from_canonical_bytes, only reduced
+// scalars are accepted.
+impl FromStr for Scalar {
+    type Err = ParseScalarError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.as_bytes();
+        if s.len() != 64 {
+            return Err(ParseScalarError::InvalidLength);
+        }
+
+        let mut bytes = [0; 32];
+        for i in 0..32 {
+            let hi = (s[2 * i] as char).to_digit(16);
+            let lo = (s[2 * i + 1] as char).to_digit(16);
+            match (hi, lo) {
+                (Some(hi), Some(lo)) => bytes[i] = (hi << 4 | lo) as u8,
+                _ => return Err(ParseScalarError::InvalidHex),
+            }
+        }
+        Scalar::from_canonical_bytes(bytes).ok_or(ParseScalarError::NonCanonical)
+    }
+}
+
+// Serialized as a fixed-size tuple of 32 bytes (no length prefix in binary
+// formats). Deserializing rejects non-canonical encodings.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Scalar {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeTuple;
+
+        let mut tuple = serializer.serialize_tuple(32)?;
+        for byte in self.bytes.iter() {
+            tuple.serialize_element(byte)?;
+        }
+        tuple.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Scalar {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ScalarVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for ScalarVisitor {
+            type Value = Scalar;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("32 bytes encoding a canonical scalar")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<Scalar, A::Error> {
+                let mut bytes = [0; 32];
+                for i in 0..32 {
+                    bytes[i] = seq
+                        .next_element()?
+                        .ok_or_else(|| serde::de::Error::invalid_length(i, &"32 bytes"))?;
+                }
+                Scalar::from_canonical_bytes(bytes)
+                    .ok_or_else(|| serde::de::Error::custom(ParseScalarError::NonCanonical))
+            }
+        }
+
+        deserializer.deserialize_tuple(32, ScalarVisitor)
+    }
+}
+
+// A scalar produced by Scalar::clamp, an integer in [2^254, 2^255) that is
+// a multiple of 8. Only clamp can create one, so an unclamped secret can't
+// be passed where a clamped one is expected.
+#[derive(Clone, Copy)]
+pub struct ClampedScalar {
+    bytes: [u8; 32],
+}
+
+impl ClampedScalar {
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.bytes
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.bytes
+    }
+
+    // The clamped integer reduced mod L. Multiplying a point in the
+    // prime-order subgroup (like the basepoint) by either gives the same
+    // result, which is how Ed25519 uses it.
+    pub fn to_scalar(&self) -> Scalar {
+        Scalar::from_bytes_mod_order(self.bytes)
+    }
+}
+
+impl ConstantTimeEq for ClampedScalar {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.bytes.ct_eq(&other.bytes)
+    }
+}
+
+impl PartialEq for ClampedScalar {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for ClampedScalar {}
+
+impl Zeroize for ClampedScalar {
+    fn zeroize(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+impl fmt::Debug for ClampedScalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ClampedScalar(<redacted>)")
+    }
+}
+
+impl From<u64> for Scalar {
+    fn from(value: u64) -> Self {
+        let mut bytes = [0; 32];
+        bytes[..8].copy_from_slice(&value.to_le_bytes());
+        Self { bytes }
+    }
+}
+
+// Operator versions of add, sub and mul, so formulas over scalars can be
+// written the way they appear on paper.
+macro_rules! impl_scalar_op {
+    ($op:ident, $op_fn:ident, $assign:ident, $assign_fn:ident, $inherent:ident) => {
+        impl<'a> std::ops::$assign<&'a Scalar> for Scalar {
+            fn $assign_fn(&mut self, rhs: &'a Scalar) {
+                Scalar::$inherent(self, rhs);
+            }
+        }
+
+        impl std::ops::$assign<Scalar> for Scalar {
+            fn $assign_fn(&mut self, rhs: Scalar) {
+                Scalar::$inherent(self, &rhs);
+            }
+        }
+
+        impl<'a, 'b> std::ops::$op<&'b Scalar> for &'a Scalar {
+            type Output = Scalar;
+
+            fn $op_fn(self, rhs: &'b Scalar) -> Scalar {
+                let mut result = *self;
+                Scalar::$inherent(&mut result, rhs);
+                result
+            }
+        }
+
+        impl<'b> std::ops::$op<&'b Scalar> for Scalar {
+            type Output = Scalar;
+
+            fn $op_fn(self, rhs: &'b Scalar) -> Scalar {
+                std::ops::$op::$op_fn(&self, rhs)
+            }
+        }
+
+        impl<'a> std::ops::$op<Scalar> for &'a Scalar {
+            type Output = Scalar;
+
+            fn $op_fn(self, rhs: Scalar) -> Scalar {
+                std::ops::$op::$op_fn(self, &rhs)
+            }
+        }
+
+        impl std::ops::$op<Scalar> for Scalar {
+            type Output = Scalar;
+
+            fn $op_fn(self, rhs: Scalar) -> Scalar {
+                std::ops::$op::$op_fn(&self, &rhs)
+            }
+        }
+    };
+}
+
+impl_scalar_op!(Add, add, AddAssign, add_assign, add);
+impl_scalar_op!(Sub, sub, SubAssign, sub_assign, sub);
+impl_scalar_op!(Mul, mul, MulAssign, mul_assign, mul);
+
+impl std::ops::Neg for &Scalar {
+    type Output = Scalar;
+
+    fn neg(self) -> Scalar {
+        Scalar::ZERO - self
+    }
+}
+
+impl std::ops::Neg for Scalar {
+    type Output = Scalar;
+
+    fn neg(self) -> Scalar {
+        -&self
+    }
+}
+
+impl std::iter::Sum for Scalar {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Scalar::ZERO, |acc, x| acc + x)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a Scalar> for Scalar {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Scalar::ZERO, |acc, x| acc + x)
+    }
+}
+
+impl std::iter::Product for Scalar {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Scalar::ONE, |acc, x| acc * x)
+    }
+}
+
+impl<'a> std::iter::Product<&'a Scalar> for Scalar {
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Scalar::ONE, |acc, x| acc * x)
+    }
+}
+
+#[cfg(feature = "ff")]
+mod ff_impl {
+    use super::*;
+    use ff::{Field, PrimeField};
+    use rand_core::RngCore;
+    use subtle::CtOption;
+
+    impl Field for Scalar {
+        const ZERO: Self = Scalar::ZERO;
+        const ONE: Self = Scalar::ONE;
+
+        // Same as Scalar::random: 512 bits reduced mod L.
+        fn random(mut rng: impl RngCore) -> Self {
+            let mut bytes = [0; 64];
+            rng.fill_bytes(&mut bytes);
+            Scalar::from_bytes_mod_order_wide(bytes)
+        }
+
+        fn square(&self) -> Self {
+            self * self
+        }
+
+        fn double(&self) -> Self {
+            self + self
+        }
+
+        fn invert(&self) -> CtOption<Self> {
+            CtOption::new(Scalar::invert(self), !self.is_zero())
+        }
+
+        // L - 1 = 2^2 * t with t odd, so the generic Tonelli-Shanks takes
+        // at most two extra steps; it needs (t - 1) / 2 in 64-bit limbs.
+        fn sqrt(&self) -> CtOption<Self> {
+            ff::helpers::sqrt_tonelli_shanks(
+                self,
+                [
+                    0xcb024c634b9eba7d,
+                    0x029bdf3bd45ef39a,
+                    0x0000000000000000,
+                    0x0200000000000000,
+                ],
+            )
+        }
+
+        fn sqrt_ratio(num: &Self, div: &Self) -> (Choice, Self) {
+            ff::helpers::sqrt_ratio_generic(num, div)
+        }
+    }
+
+    impl PrimeField for Scalar {
+        type Repr = [u8; 32];
+
+        fn from_repr(repr: [u8; 32]) -> CtOption<Self> {
+            let scalar = Scalar::from_bytes_mod_order(repr);
+            let canonical = scalar.bytes.ct_eq(&repr);
+            CtOption::new(scalar, canonical)
+        }
+
+        fn to_repr(&self) -> [u8; 32] {
+            self.bytes
+        }
+
+        fn is_odd(&self) -> Choice {
+            Choice::from(self.bytes[0] & 1)
+        }
+
+        const MODULUS: &'static str =
+            "0x1000000000000000000000000000000014def9dea2f79cd65812631a5cf5d3ed";
+        const NUM_BITS: u32 = 253;
+        const CAPACITY: u32 = 252;
+        // (L + 1) / 2
+        const TWO_INV: Self = Scalar {
+            bytes: [
+                0xf7, 0xe9, 0x7a, 0x2e, 0x8d, 0x31, 0x09, 0x2c, 0x6b, 0xce, 0x7b, 0x51, 0xef, 0x7c,
+                0x6f, 0x0a, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x08,
+            ],
+        };
+        const MULTIPLICATIVE_GENERATOR: Self = Scalar {
+            bytes: [
+                2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0,
+            ],
+        };
+        // L - 1 = 2^2 * t with t odd
+        const S: u32 = 2;
+        // 2^t, a square root of -1 mod L
+        const ROOT_OF_UNITY: Self = Scalar {
+            bytes: [
+                0xd4, 0x07, 0xbe, 0xeb, 0xdf, 0x75, 0x87, 0xbe, 0xfe, 0x83, 0xce, 0x42, 0x53, 0x56,
+                0xf0, 0x0e, 0x7a, 0xc2, 0xc1, 0xab, 0x60, 0x6d, 0x3d, 0x7d, 0xe7, 0x81, 0x79, 0xe0,
+                0x10, 0x73, 0x4a, 0x09,
+            ],
+        };
+        const ROOT_OF_UNITY_INV: Self = Scalar {
+            bytes: [
+                0x19, 0xcc, 0x37, 0x71, 0x3a, 0xed, 0x8a, 0x99, 0xd7, 0x18, 0x29, 0x60, 0x8b, 0xa3,
+                0xee, 0x05, 0x86, 0x3d, 0x3e, 0x54, 0x9f, 0x92, 0xc2, 0x82, 0x18, 0x7e, 0x86, 0x1f,
+                0xef, 0x8c, 0xb5, 0x06,
+            ],
+        };
+        // 2^(2^S)
+        const DELTA: Self = Scalar {
+            bytes: [
+                16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0,
+            ],
+        };
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use proptest::prelude::*;
+
+        #[test]
+        fn constants() {
+            assert_eq!(Scalar::TWO_INV.double(), Scalar::ONE);
+            assert_eq!(
+                Scalar::ROOT_OF_UNITY * Scalar::ROOT_OF_UNITY_INV,
+                Scalar::ONE
+            );
+            assert_eq!(
+                Scalar::ROOT_OF_UNITY.pow_vartime([1 << Scalar::S]),
+                Scalar::ONE
+            );
+            assert_eq!(
+                Scalar::MULTIPLICATIVE_GENERATOR.pow_vartime([1 << Scalar::S]),
+                Scalar::DELTA
+            );
+            assert!(bool::from(
+                Scalar::MULTIPLICATIVE_GENERATOR.sqrt().is_none()
+            ));
+        }
+
+        #[test]
+        fn repr() {
+            let mut l = Scalar::ZERO.to_repr();
+            l.copy_from_slice(&limbs::to_bytes::<GroupOrder, 32>(&L));
+            assert!(bool::from(Scalar::from_repr(l).is_none()));
+            l[0] -= 1;
+            assert_eq!(Scalar::from_repr(l).unwrap(), -Scalar::ONE);
+            assert!(bool::from(Scalar::from(3).is_odd()));
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(16))]
+            #[test]
+            fn field_prop(a in any::<[u8; 32]>()) {
+                let a = Scalar::from_bytes_mod_order(a);
+
+                let square = a.square();
+                let root = square.sqrt().unwrap();
+                prop_assert_eq!(root.square(), square);
+                if a != Scalar::ZERO {
+                    prop_assert_eq!(a * Field::invert(&a).unwrap(), Scalar::ONE);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn scalar(hex: &str) -> Scalar {
+        Scalar::from_bytes_mod_order(hex::decode(hex).unwrap().try_into().unwrap())
+    }
+
+    fn l_minus_one() -> Scalar {
+        scalar("ecd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+    }
+
+    #[test]
+    fn reduction() {
+        let mut l = l_minus_one().to_bytes();
+        l[0] += 1;
+        assert_eq!(Scalar::from_bytes_mod_order(l), Scalar::ZERO);
+        assert_eq!(
+            Scalar::from_bytes_mod_order([0xff; 32]),
+            scalar("1c95988d7431ecd670cf7d73f45befc6feffffffffffffffffffffffffffff0f")
+        );
+        assert_eq!(l_minus_one() + Scalar::ONE, Scalar::ZERO);
+        assert_eq!(-Scalar::ONE, l_minus_one());
+        assert_eq!(l_minus_one() * l_minus_one(), Scalar::ONE);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let bytes: Vec<u8> = (1..=32).collect();
+        let a = Scalar::from_bytes_mod_order(bytes.try_into().unwrap());
+        let bytes: Vec<u8> = (100..132).collect();
+        let b = Scalar::from_bytes_mod_order(bytes.try_into().unwrap());
+
+        assert_eq!(
+            a,
+            scalar("275a174ad03fe2575cd01bc64f1a51e61012131415161718191a1b1c1d1e1f00")
+        );
+        assert_eq!(
+            a + b,
+            scalar("2320cfc96590b9021657cd1dcbbccbb28487898b8d8f91939597999b9d9fa103")
+        );
+        assert_eq!(
+            a - b,
+            scalar("1868552755521d0579e66111b371b52e9d9c9c9c9c9c9c9c9c9c9c9c9c9c9c0c")
+        );
+        assert_eq!(
+            a * b,
+            scalar("45ad8dbf394c8d157cf8a915a8d5cd184b61152036d136cd17ea96a2d1b8fd0a")
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn ring_prop(a in any::<[u8; 32]>(), b in any::<[u8; 32]>(), c in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let b = Scalar::from_bytes_mod_order(b);
+            let c = Scalar::from_bytes_mod_order(c);
+
+            prop_assert_eq!((a - b) + b, a);
+            prop_assert_eq!(a + (-a), Scalar::ZERO);
+            prop_assert_eq!(a * (b + c), a * b + a * c);
+            prop_assert_eq!((a * b) * c, a * (b * c));
+            // reducing an already reduced scalar does nothing
+            prop_assert_eq!(Scalar::from_bytes_mod_order(a.to_bytes()), a);
+            // reduced scalars are below 2^253
+            prop_assert!(a.to_bytes()[31] <= 0x10);
+        }
+    }
+
+    #[test]
+    fn wide_reduction() {
+        // SHA-512("abc")
+        let digest = hex::decode(
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+        )
+        .unwrap();
+        assert_eq!(
+            Scalar::from_bytes_mod_order_wide(digest.try_into().unwrap()),
+            scalar("d15dbef29abf1ff29f9cf91c4b75ee0bb1012cb031d9605d684e841df034de0b")
+        );
+        assert_eq!(
+            Scalar::from_bytes_mod_order_wide([0xff; 64]),
+            scalar("000f9c44e31106a447938568a71b0ed065bef517d273ecce3d9a307c1b419903")
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn wide_reduction_prop(lo in any::<[u8; 32]>(), hi in any::<[u8; 32]>()) {
+            // with the top half zero it agrees with the 256-bit reduction
+            let mut wide = [0; 64];
+            wide[..32].copy_from_slice(&lo);
+            prop_assert_eq!(
+                Scalar::from_bytes_mod_order_wide(wide),
+                Scalar::from_bytes_mod_order(lo)
+            );
+
+            // lo + hi * 2^256
+            wide[32..].copy_from_slice(&hi);
+            let mut two_256 = [0; 64];
+            two_256[32] = 1;
+            let expected = Scalar::from_bytes_mod_order(lo)
+                + Scalar::from_bytes_mod_order(hi) * Scalar::from_bytes_mod_order_wide(two_256);
+            prop_assert_eq!(Scalar::from_bytes_mod_order_wide(wide), expected);
+        }
+    }
+
+    #[test]
+    fn muladd() {
+        let bytes: Vec<u8> = (1..=32).collect();
+        let a = Scalar::from_bytes_mod_order(bytes.try_into().unwrap());
+        let bytes: Vec<u8> = (100..132).collect();
+        let b = Scalar::from_bytes_mod_order(bytes.try_into().unwrap());
+        let bytes: Vec<u8> = (200..232).collect();
+        let c = Scalar::from_bytes_mod_order(bytes.try_into().unwrap());
+
+        assert_eq!(
+            Scalar::muladd(&a, &b, &c),
+            scalar("2a0cf1187b4b48bcbd99fa5c710794b7223bf0fb12af15adf8cb7986b69ee402")
+        );
+        // (L - 1) * (L - 1) + (L - 1) = 1 - 1
+        let m = l_minus_one();
+        assert_eq!(Scalar::muladd(&m, &m, &m), Scalar::ZERO);
+    }
+
+    proptest! {
+        #[test]
+        fn muladd_prop(a in any::<[u8; 32]>(), b in any::<[u8; 32]>(), c in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let b = Scalar::from_bytes_mod_order(b);
+            let c = Scalar::from_bytes_mod_order(c);
+
+            prop_assert_eq!(Scalar::muladd(&a, &b, &c), a * b + c);
+        }
+    }
+
+    #[test]
+    fn canonical_bytes() {
+        let mut bytes = l_minus_one().to_bytes();
+        assert_eq!(Scalar::from_canonical_bytes(bytes), Some(l_minus_one()));
+        assert_eq!(Scalar::from_canonical_bytes([0; 32]), Some(Scalar::ZERO));
+
+        // L, L + 1 and anything with the high bits set are rejected
+        bytes[0] += 1;
+        assert_eq!(Scalar::from_canonical_bytes(bytes), None);
+        bytes[0] += 1;
+        assert_eq!(Scalar::from_canonical_bytes(bytes), None);
+        let mut high = [0; 32];
+        high[31] = 0x80;
+        assert_eq!(Scalar::from_canonical_bytes(high), None);
+        assert_eq!(Scalar::from_canonical_bytes([0xff; 32]), None);
+    }
+
+    proptest! {
+        #[test]
+        fn canonical_bytes_prop(a in any::<[u8; 32]>()) {
+            let reduced = Scalar::from_bytes_mod_order(a);
+            prop_assert_eq!(Scalar::from_canonical_bytes(reduced.to_bytes()), Some(reduced));
+            // S + L encodes the same scalar but must be rejected
+            let bytes = reduced.to_bytes();
+            let mut plus_l = [0; 32];
+            let mut carry = 0;
+            for i in 0..32 {
+                let limb = bytes[i] as i64 + L[i] + carry;
+                plus_l[i] = (limb & 0xff) as u8;
+                carry = limb >> 8;
+            }
+            prop_assert_eq!(Scalar::from_canonical_bytes(plus_l), None);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn clamp_prop(bytes in any::<[u8; 32]>()) {
+            let clamped = Scalar::clamp(bytes);
+            let b = clamped.to_bytes();
+            prop_assert_eq!(b[0] & 7, 0);
+            prop_assert_eq!(b[31] & 0xc0, 0x40);
+            prop_assert_eq!(&b[1..31], &bytes[1..31]);
+            // clamping is idempotent
+            prop_assert_eq!(Scalar::clamp(b), clamped);
+            prop_assert_eq!(clamped.to_scalar(), Scalar::from_bytes_mod_order(b));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn random() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+        assert_ne!(a, b);
+        assert_eq!(Scalar::from_canonical_bytes(a.to_bytes()), Some(a));
+
+        // same seed, same scalars
+        let mut rng = StdRng::seed_from_u64(42);
+        assert_eq!(Scalar::random(&mut rng), a);
+    }
+
+    // Sum of d_i * 2^(step * i) computed with scalar arithmetic.
+    fn from_digits(digits: &[i8], step: usize) -> Scalar {
+        let radix = Scalar::from(1u64 << step);
+        digits.iter().rev().fold(Scalar::ZERO, |acc, &d| {
+            let digit = Scalar::from(d.unsigned_abs() as u64);
+            let digit = if d < 0 { -digit } else { digit };
+            acc * radix + digit
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn naf_prop(a in any::<[u8; 32]>(), w in 2usize..=8) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let naf = a.non_adjacent_form(w);
+
+            prop_assert_eq!(from_digits(&naf, 1), a);
+            for (i, &d) in naf.iter().enumerate() {
+                if d != 0 {
+                    prop_assert_eq!(d & 1, 1);
+                    prop_assert!((d as i64).abs() < 1 << (w - 1));
+                    // the next w - 1 digits are zero
+                    prop_assert!(naf[i + 1..(i + w).min(256)].iter().all(|&d| d == 0));
+                }
+            }
+        }
+
+        #[test]
+        fn radix_2w_prop(a in any::<[u8; 32]>(), w in 2usize..=8) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let digits = a.to_radix_2w(w);
+
+            prop_assert_eq!(digits.len(), 256usize.div_ceil(w));
+            prop_assert_eq!(from_digits(&digits, w), a);
+            let half = 1i64 << (w - 1);
+            for &d in &digits {
+                prop_assert!(-half <= d as i64 && (d as i64) < half);
+            }
+        }
+    }
+
+    #[test]
+    fn naf_of_small_scalars() {
+        // 7 = 8 - 1
+        let naf = Scalar::from(7).non_adjacent_form(2);
+        assert_eq!(&naf[..4], &[-1, 0, 0, 1]);
+        // with a wider window 7 is a single digit
+        let naf = Scalar::from(7).non_adjacent_form(5);
+        assert_eq!(&naf[..4], &[7, 0, 0, 0]);
+        assert_eq!(Scalar::ZERO.non_adjacent_form(5), [0; 256]);
+        assert_eq!(Scalar::from(0xff).to_radix_2w(4)[..3], [-1, 0, 1]);
+    }
+
+    #[test]
+    fn zeroize_and_debug() {
+        let mut a = Scalar::from(0xdead_beef);
+        assert!(bool::from(a.ct_eq(&Scalar::from(0xdead_beef))));
+        assert!(!bool::from(a.ct_eq(&Scalar::ONE)));
+        assert_eq!(format!("{a:?}"), "Scalar(<redacted>)");
+        a.zeroize();
+        assert_eq!(a, Scalar::ZERO);
+
+        let mut clamped = Scalar::clamp([0xef; 32]);
+        assert!(!format!("{clamped:?}").contains("239"));
+        clamped.zeroize();
+        assert_eq!(clamped.to_bytes(), [0; 32]);
+    }
+
+    #[test]
+    fn hex_roundtrip() {
+        let hex = "275a174ad03fe2575cd01bc64f1a51e61012131415161718191a1b1c1d1e1f00";
+        let a: Scalar = hex.parse().unwrap();
+        assert_eq!(a, scalar(hex));
+        assert_eq!(a.to_string(), hex);
+        assert_eq!(
+            "EDD3F55C1A631258D69CF7A2DEF9DE1400000000000000000000000000000010".parse::<Scalar>(),
+            Err(ParseScalarError::NonCanonical)
+        );
+        assert_eq!("00".parse::<Scalar>(), Err(ParseScalarError::InvalidLength));
+        assert_eq!(
+            hex.replace('a', "g").parse::<Scalar>(),
+            Err(ParseScalarError::InvalidHex)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_roundtrip() {
+        let a = l_minus_one();
+        let json = serde_json::to_string(&a).unwrap();
+        assert!(json.starts_with("[236,211,"));
+        assert_eq!(serde_json::from_str::<Scalar>(&json).unwrap(), a);
+
+        // L itself is rejected
+        let json = json.replacen("236", "237", 1);
+        assert!(serde_json::from_str::<Scalar>(&json).is_err());
+        assert!(serde_json::from_str::<Scalar>("[1,2,3]").is_err());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn invert_prop(a in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            if a != Scalar::ZERO {
+                prop_assert_eq!(a * a.invert(), Scalar::ONE);
+            }
+        }
+    }
+
+    #[test]
+    fn invert_edge_cases() {
+        assert_eq!(Scalar::ZERO.invert(), Scalar::ZERO);
+        assert_eq!(Scalar::ONE.invert(), Scalar::ONE);
+        assert_eq!((-Scalar::ONE).invert(), -Scalar::ONE);
+    }
+
+    #[test]
+    fn sum_product() {
+        let a = Scalar::from(6u64);
+        let b = Scalar::from(7u64);
+        assert_eq!([a, b].iter().sum::<Scalar>(), Scalar::from(13u64));
+        assert_eq!([a, b].into_iter().product::<Scalar>(), Scalar::from(42u64));
+        assert_eq!(Scalar::conditional_select(&a, &b, Choice::from(1)), b);
+        assert_eq!(Scalar::conditional_select(&a, &b, Choice::from(0)), a);
+    }
+}
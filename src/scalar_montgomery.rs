@@ -0,0 +1,193 @@
+use crate::scalar::Scalar;
+
+// Scalar arithmetic mod L in Montgomery form, as an alternative to the
+// schoolbook multiplication + modL reduction used by Scalar.
+//
+// A number a is stored as a * R mod L with R = 2^256, in four 64-bit limbs.
+// Multiplying two such numbers and dividing by R gives (a * b) * R mod L,
+// again in Montgomery form, and the division by R can be done with shifts
+// instead of a full reduction (see mont_mul). Converting in and out costs a
+// multiplication each, so this only pays off for long chains of
+// multiplications, e.g. computing many coefficients for batch verification.
+
+// L in 64-bit limbs, least significant first.
+const L: [u64; 4] = [
+    0x5812631a5cf5d3ed,
+    0x14def9dea2f79cd6,
+    0x0000000000000000,
+    0x1000000000000000,
+];
+
+// -L^-1 mod 2^64
+const LFACTOR: u64 = 0xd2b51da312547e1b;
+
+// R^2 mod L, used to convert into Montgomery form.
+const RR: [u64; 4] = [
+    0xa40611e3449c0f01,
+    0xd00e1ba768859347,
+    0xceec73d217f5be65,
+    0x0399411b7c309a3d,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MontgomeryScalar {
+    limbs: [u64; 4],
+}
+
+impl MontgomeryScalar {
+    pub fn from_scalar(scalar: &Scalar) -> Self {
+        let bytes = scalar.to_bytes();
+        let mut limbs = [0; 4];
+        for i in 0..4 {
+            limbs[i] = u64::from_le_bytes(bytes[8 * i..8 * (i + 1)].try_into().unwrap());
+        }
+        Self {
+            limbs: mont_mul(&limbs, &RR),
+        }
+    }
+
+    pub fn to_scalar(&self) -> Scalar {
+        let limbs = mont_mul(&self.limbs, &[1, 0, 0, 0]);
+        let mut bytes = [0; 32];
+        for i in 0..4 {
+            bytes[8 * i..8 * (i + 1)].copy_from_slice(&limbs[i].to_le_bytes());
+        }
+        Scalar::from_bytes_mod_order(bytes)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Self {
+            limbs: mont_mul(&self.limbs, &other.limbs),
+        }
+    }
+
+    pub fn square(&self) -> Self {
+        self.mul(self)
+    }
+
+    // Both operands are below L < 2^253, so the sum fits in 256 bits and
+    // at most one subtraction of L is needed.
+    pub fn add(&self, other: &Self) -> Self {
+        let mut sum = [0; 4];
+        let mut carry = 0u128;
+        for i in 0..4 {
+            let s = self.limbs[i] as u128 + other.limbs[i] as u128 + carry;
+            sum[i] = s as u64;
+            carry = s >> 64;
+        }
+        Self {
+            limbs: sub_l_if_needed(sum),
+        }
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        let (diff, borrow) = sub_limbs(&self.limbs, &other.limbs);
+        Self {
+            limbs: add_l_masked(diff, borrow),
+        }
+    }
+}
+
+// Computes a - b, returning the difference and an all-ones mask if it
+// borrowed (a < b) or zero otherwise.
+fn sub_limbs(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], u64) {
+    let mut diff = [0; 4];
+    let mut borrow = 0u64;
+    for i in 0..4 {
+        let (d, b1) = a[i].overflowing_sub(b[i]);
+        let (d, b2) = d.overflowing_sub(borrow);
+        diff[i] = d;
+        borrow = (b1 | b2) as u64;
+    }
+    (diff, 0u64.wrapping_sub(borrow))
+}
+
+// Adds L to x if mask is all ones, without branching on it.
+fn add_l_masked(x: [u64; 4], mask: u64) -> [u64; 4] {
+    let mut result = [0; 4];
+    let mut carry = 0u128;
+    for i in 0..4 {
+        let s = x[i] as u128 + (L[i] & mask) as u128 + carry;
+        result[i] = s as u64;
+        carry = s >> 64;
+    }
+    result
+}
+
+// Brings x from [0, 2L) into [0, L): subtract L and add it back if that
+// went negative.
+fn sub_l_if_needed(x: [u64; 4]) -> [u64; 4] {
+    let (diff, borrow) = sub_limbs(&x, &L);
+    add_l_masked(diff, borrow)
+}
+
+// Montgomery multiplication (CIOS): returns a * b / R mod L.
+//
+// For each limb b[i] we add a * b[i] to the accumulator t, then pick
+// m = t[0] * -L^-1 mod 2^64 so that t + m * L has a zero lowest limb, and
+// shift that limb out. After four rounds we have divided by 2^256 = R and
+// t < 2L, so one conditional subtraction finishes the reduction.
+fn mont_mul(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+    let mut t = [0u64; 6];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let s = t[j] as u128 + a[j] as u128 * b[i] as u128 + carry;
+            t[j] = s as u64;
+            carry = s >> 64;
+        }
+        let s = t[4] as u128 + carry;
+        t[4] = s as u64;
+        t[5] = (s >> 64) as u64;
+
+        let m = t[0].wrapping_mul(LFACTOR);
+        let s = t[0] as u128 + m as u128 * L[0] as u128;
+        let mut carry = s >> 64;
+        for j in 1..4 {
+            let s = t[j] as u128 + m as u128 * L[j] as u128 + carry;
+            t[j - 1] = s as u64;
+            carry = s >> 64;
+        }
+        let s = t[4] as u128 + carry;
+        t[3] = s as u64;
+        t[4] = t[5] + (s >> 64) as u64;
+    }
+
+    sub_l_if_needed([t[0], t[1], t[2], t[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn matches_scalar_prop(a in any::<[u8; 32]>(), b in any::<[u8; 32]>()) {
+            let a = Scalar::from_bytes_mod_order(a);
+            let b = Scalar::from_bytes_mod_order(b);
+            let ma = MontgomeryScalar::from_scalar(&a);
+            let mb = MontgomeryScalar::from_scalar(&b);
+
+            prop_assert_eq!(ma.to_scalar(), a);
+            prop_assert_eq!(ma.mul(&mb).to_scalar(), a * b);
+            prop_assert_eq!(ma.add(&mb).to_scalar(), a + b);
+            prop_assert_eq!(ma.sub(&mb).to_scalar(), a - b);
+            prop_assert_eq!(ma.square().mul(&ma).to_scalar(), a * a * a);
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        let minus_one = MontgomeryScalar::from_scalar(&-Scalar::ONE);
+        let one = MontgomeryScalar::from_scalar(&Scalar::ONE);
+        assert_eq!(minus_one.square(), one);
+        assert_eq!(minus_one.add(&one).to_scalar(), Scalar::ZERO);
+        assert_eq!(
+            MontgomeryScalar::from_scalar(&Scalar::ZERO)
+                .sub(&one)
+                .to_scalar(),
+            -Scalar::ONE
+        );
+    }
+}
@@ -0,0 +1,383 @@
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::signing::{Keypair, PublicKey, SignatureError};
+
+// Shamir secret sharing of Ed25519 seeds, to back up a signing key in
+// pieces: split a seed into n shares, any t of which give it back, while
+// t - 1 reveal nothing about it.
+//
+// The seed is shared byte by byte over GF(256) (the AES field, modulo
+// x^8 + x^4 + x^3 + x + 1), as ssss and SLIP-39 do, rather than over the
+// scalar field: a seed is 32 arbitrary bytes, not a scalar. For each byte
+// the dealer picks a random polynomial of degree t - 1 whose constant term
+// is that byte, and share x (1 to n) is the 32 polynomials evaluated at
+// x. Recovery interpolates them back at 0.
+//
+// A share is
+//
+//   A (32) || t (1) || x (1) || value (32) || checksum (4)
+//
+// with the checksum the first four bytes of SHA-256 of the rest, so a
+// share mistyped from paper is caught on its own, and the public key A,
+// so shares of different keys aren't mixed and the recovered seed can be
+// checked: a share with a valid checksum but the wrong value (made up, or
+// from another split of the same key) gives a seed that doesn't derive A.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareError {
+    // A share whose checksum doesn't match, or with t or x 0.
+    Malformed,
+    // A threshold of 0 or above the number of shares, or randomness for
+    // another threshold.
+    InvalidThreshold,
+    // Fewer shares than their threshold.
+    TooFewShares,
+    // Shares of different keys or thresholds, or two with the same index.
+    Inconsistent,
+    // The recovered seed doesn't derive the shares' public key.
+    WrongKey,
+    // A public key that doesn't decode.
+    Signature(SignatureError),
+}
+
+impl fmt::Display for ShareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShareError::Malformed => f.write_str("malformed seed share"),
+            ShareError::InvalidThreshold => f.write_str("invalid share threshold"),
+            ShareError::TooFewShares => f.write_str("fewer shares than the threshold"),
+            ShareError::Inconsistent => f.write_str("inconsistent seed shares"),
+            ShareError::WrongKey => f.write_str("recovered seed doesn't match the public key"),
+            ShareError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ShareError {}
+
+impl From<SignatureError> for ShareError {
+    fn from(e: SignatureError) -> Self {
+        ShareError::Signature(e)
+    }
+}
+
+pub const SHARE_LEN: usize = 32 + 1 + 1 + 32 + 4;
+
+// One share of a seed. The value is zeroized when dropped and redacted
+// from Debug; the metadata isn't secret.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SeedShare {
+    public_key: PublicKey,
+    threshold: u8,
+    index: u8,
+    value: [u8; 32],
+}
+
+impl SeedShare {
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    pub fn to_bytes(&self) -> [u8; SHARE_LEN] {
+        let mut bytes = [0; SHARE_LEN];
+        bytes[..32].copy_from_slice(self.public_key.as_bytes());
+        bytes[32] = self.threshold;
+        bytes[33] = self.index;
+        bytes[34..66].copy_from_slice(&self.value);
+        let checksum = checksum(&bytes[..66]);
+        bytes[66..].copy_from_slice(&checksum);
+        bytes
+    }
+
+    // Fails with Malformed if the checksum doesn't match or t or x is 0,
+    // and with Signature(InvalidPoint) if A doesn't decode.
+    pub fn from_bytes(bytes: &[u8; SHARE_LEN]) -> Result<Self, ShareError> {
+        if checksum(&bytes[..66]) != bytes[66..] || bytes[32] == 0 || bytes[33] == 0 {
+            return Err(ShareError::Malformed);
+        }
+        Ok(Self {
+            public_key: PublicKey::from_bytes(bytes[..32].try_into().unwrap())?,
+            threshold: bytes[32],
+            index: bytes[33],
+            value: bytes[34..66].try_into().unwrap(),
+        })
+    }
+}
+
+impl Drop for SeedShare {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl fmt::Debug for SeedShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeedShare")
+            .field("public_key", &self.public_key)
+            .field("threshold", &self.threshold)
+            .field("index", &self.index)
+            .field("value", &"<redacted>")
+            .finish()
+    }
+}
+
+// Splits keypair's seed into count shares, threshold of which recover it.
+// Fails with InvalidThreshold unless 1 <= threshold <= count.
+#[cfg(feature = "rand")]
+pub fn split<R: rand_core::RngCore + rand_core::CryptoRng>(
+    keypair: &Keypair,
+    threshold: u8,
+    count: u8,
+    rng: &mut R,
+) -> Result<Vec<SeedShare>, ShareError> {
+    let mut randomness = Zeroizing::new(vec![[0; 32]; threshold.saturating_sub(1) as usize]);
+    for coefficients in randomness.iter_mut() {
+        rng.fill_bytes(coefficients);
+    }
+    split_with_randomness(keypair, threshold, count, &randomness)
+}
+
+// split with the polynomials' other coefficients given: randomness[k - 1]
+// holds the x^k coefficients of the 32 polynomials, so there must be
+// threshold - 1 of them (InvalidThreshold otherwise). They have to be
+// uniformly random and secret, or the shares leak the seed.
+pub fn split_with_randomness(
+    keypair: &Keypair,
+    threshold: u8,
+    count: u8,
+    randomness: &[[u8; 32]],
+) -> Result<Vec<SeedShare>, ShareError> {
+    if threshold == 0 || threshold > count || randomness.len() != threshold as usize - 1 {
+        return Err(ShareError::InvalidThreshold);
+    }
+    let seed = Zeroizing::new(keypair.secret().to_bytes());
+    Ok((1..=count)
+        .map(|x| {
+            let mut value = [0; 32];
+            for b in 0..32 {
+                // Horner's rule, from the top coefficient down
+                let top = randomness.iter().rev().map(|c| c[b]);
+                value[b] = top.chain([seed[b]]).fold(0, |acc, c| gf_mul(acc, x) ^ c);
+            }
+            SeedShare {
+                public_key: *keypair.public(),
+                threshold,
+                index: x,
+                value,
+            }
+        })
+        .collect())
+}
+
+// Recovers the keypair from the first threshold of shares; any others are
+// ignored. Fails with TooFewShares if there are fewer than that, with
+// Inconsistent if the shares are of different keys or thresholds or two
+// have the same index, and with WrongKey if the seed they give doesn't
+// derive their public key (a share is wrong, or from another split).
+pub fn recover(shares: &[SeedShare]) -> Result<Keypair, ShareError> {
+    let first = shares.first().ok_or(ShareError::TooFewShares)?;
+    let shares = shares
+        .get(..first.threshold as usize)
+        .ok_or(ShareError::TooFewShares)?;
+    for (i, share) in shares.iter().enumerate() {
+        if share.public_key != first.public_key
+            || share.threshold != first.threshold
+            || shares[..i].iter().any(|s| s.index == share.index)
+        {
+            return Err(ShareError::Inconsistent);
+        }
+    }
+
+    // Lagrange interpolation at 0: the seed is the sum of the values, each
+    // weighted by the product of x_j / (x_j - x_i) over the other shares.
+    // Subtraction is XOR in GF(256).
+    let mut seed = Zeroizing::new([0; 32]);
+    for share in shares {
+        let weight = shares
+            .iter()
+            .filter(|s| s.index != share.index)
+            .fold(1, |acc, s| {
+                gf_mul(acc, gf_mul(s.index, gf_inv(s.index ^ share.index)))
+            });
+        for b in 0..32 {
+            seed[b] ^= gf_mul(weight, share.value[b]);
+        }
+    }
+
+    let keypair = Keypair::from_seed(*seed);
+    if *keypair.public() != first.public_key {
+        return Err(ShareError::WrongKey);
+    }
+    Ok(keypair)
+}
+
+fn checksum(bytes: &[u8]) -> [u8; 4] {
+    Sha256::digest(bytes)[..4].try_into().unwrap()
+}
+
+// Multiplication in GF(256): shift and add, reducing by the field
+// polynomial whenever a bit falls off the top. Masks rather than branches,
+// so the time doesn't depend on the (secret) operands.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+// a^-1 = a^254, since the multiplicative group has order 255.
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1;
+    let mut power = a;
+    for bit in 0..8 {
+        if (254 >> bit) & 1 == 1 {
+            result = gf_mul(result, power);
+        }
+        power = gf_mul(power, power);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+    // split, without needing the rand feature.
+    fn split_random(
+        keypair: &Keypair,
+        threshold: u8,
+        count: u8,
+        rng: &mut StdRng,
+    ) -> Result<Vec<SeedShare>, ShareError> {
+        let mut randomness = vec![[0; 32]; threshold.saturating_sub(1) as usize];
+        for coefficients in randomness.iter_mut() {
+            rng.fill_bytes(coefficients);
+        }
+        split_with_randomness(keypair, threshold, count, &randomness)
+    }
+
+    #[test]
+    fn field() {
+        // FIPS 197 section 4.2
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        for a in 1..=255 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn split_and_recover() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let keypair = Keypair::from_seed([7; 32]);
+        let shares = split_random(&keypair, 3, 5, &mut rng).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        // any three, in any order
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4], [3, 4, 2]] {
+            let picked: Vec<_> = subset.iter().map(|&i| shares[i].clone()).collect();
+            let recovered = recover(&picked).unwrap();
+            assert_eq!(recovered.secret().to_bytes(), [7; 32]);
+        }
+        // extra shares past the threshold are ignored
+        assert!(recover(&shares).is_ok());
+        assert_eq!(recover(&shares[..2]).unwrap_err(), ShareError::TooFewShares);
+
+        // with threshold 1 every share is the seed itself
+        let shares = split_random(&keypair, 1, 2, &mut rng).unwrap();
+        assert_eq!(shares[1].value, [7; 32]);
+        assert_eq!(
+            split_random(&keypair, 3, 2, &mut rng).unwrap_err(),
+            ShareError::InvalidThreshold
+        );
+        assert_eq!(
+            split_random(&keypair, 0, 2, &mut rng).unwrap_err(),
+            ShareError::InvalidThreshold
+        );
+    }
+
+    #[test]
+    fn known_shares() {
+        // f(x) = seed + 0x01 x byte-wise, so share x is seed ^ x
+        let keypair = Keypair::from_seed([0x40; 32]);
+        let shares = split_with_randomness(&keypair, 2, 3, &[[1; 32]]).unwrap();
+        assert_eq!(shares[0].value, [0x41; 32]);
+        assert_eq!(shares[2].value, [0x43; 32]);
+        assert_eq!(
+            split_with_randomness(&keypair, 2, 3, &[]).unwrap_err(),
+            ShareError::InvalidThreshold
+        );
+    }
+
+    #[test]
+    fn integrity_checks() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let keypair = Keypair::from_seed([1; 32]);
+        let shares = split_random(&keypair, 2, 3, &mut rng).unwrap();
+
+        let bytes = shares[0].to_bytes();
+        assert_eq!(SeedShare::from_bytes(&bytes), Ok(shares[0].clone()));
+        assert!(!format!("{:?}", shares[0]).contains(&format!("{:?}", shares[0].value)));
+        for i in [0, 32, 33, 40, 69] {
+            let mut corrupted = bytes;
+            corrupted[i] ^= 1;
+            assert_eq!(
+                SeedShare::from_bytes(&corrupted),
+                Err(ShareError::Malformed)
+            );
+        }
+
+        // a share with a good checksum but the wrong value
+        let mut forged = shares[1].clone();
+        forged.value[0] ^= 1;
+        let forged = SeedShare::from_bytes(&forged.to_bytes()).unwrap();
+        assert_eq!(
+            recover(&[shares[0].clone(), forged]).unwrap_err(),
+            ShareError::WrongKey
+        );
+
+        // shares from two splits of the same key don't mix
+        let other = split_random(&keypair, 2, 3, &mut rng).unwrap();
+        assert_eq!(
+            recover(&[shares[0].clone(), other[1].clone()]).unwrap_err(),
+            ShareError::WrongKey
+        );
+        assert!(recover(&[other[2].clone(), other[0].clone()]).is_ok());
+
+        // nor shares of different keys, or the same share twice
+        let another = split_random(&Keypair::from_seed([2; 32]), 2, 3, &mut rng).unwrap();
+        assert_eq!(
+            recover(&[shares[0].clone(), another[1].clone()]).unwrap_err(),
+            ShareError::Inconsistent
+        );
+        assert_eq!(
+            recover(&[shares[0].clone(), shares[0].clone()]).unwrap_err(),
+            ShareError::Inconsistent
+        );
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn split_with_rng() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let keypair = Keypair::from_seed([3; 32]);
+        let shares = split(&keypair, 2, 2, &mut rng).unwrap();
+        assert_eq!(recover(&shares).unwrap().secret().to_bytes(), [3; 32]);
+    }
+}
@@ -0,0 +1,441 @@
+use std::fmt;
+
+#[cfg(feature = "signing")]
+use sha2::{Digest, Sha512};
+#[cfg(feature = "signing")]
+use zeroize::Zeroizing;
+
+use crate::base64;
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, Signature, SignatureError};
+
+// The key and signature files of OpenBSD's signify, which signs the
+// OpenBSD releases and packages. Every file is two lines,
+//
+//   untrusted comment: <text>
+//   <base64 of a small binary structure>
+//
+// and the structures all start with the algorithm, "Ed", and an 8-byte key
+// number that ties signatures to the key that made them:
+//
+//   public key   "Ed" || keynum || A
+//   signature    "Ed" || keynum || R || S
+//   secret key   "Ed" || "BK" || uint32(rounds) || salt(16) || checksum(8)
+//                     || keynum || seed || A
+//
+// The signature is a plain Ed25519 signature of the file. The comment is
+// "untrusted" because nothing signs it. In a secret key the checksum is
+// the first 8 bytes of SHA-512 of seed || A, and with rounds > 0 the key
+// is encrypted under a passphrase with bcrypt_pbkdf, which isn't
+// implemented here: only keys made with signify -n can be read.
+//
+// signify -e makes an embedded signature instead, the signature file with
+// the message appended; OpenBSD's SHA256.sig files are these. See
+// SignifySignature::from_embedded.
+//
+// minisign (see the minisign module) uses the same public key format and,
+// for its legacy signatures, the same signature line.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignifyError {
+    // Text that isn't a signify or minisign key or signature file: no
+    // "untrusted comment: " line, a structure of the wrong size, a secret
+    // key whose checksum doesn't match, or lines missing or left over.
+    Malformed,
+    // A structure for an algorithm other than Ed25519, or a secret key
+    // encrypted with a KDF this crate doesn't implement.
+    UnsupportedAlgorithm,
+    // A line that should be base64 but isn't.
+    InvalidBase64,
+    // A key that isn't a point, a signature that doesn't verify, or one
+    // made by a key with another key number (KeyMismatch).
+    Signature(SignatureError),
+}
+
+impl fmt::Display for SignifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignifyError::Malformed => f.write_str("malformed signify or minisign file"),
+            SignifyError::UnsupportedAlgorithm => f.write_str("not an Ed25519 key or signature"),
+            SignifyError::InvalidBase64 => f.write_str("invalid base64"),
+            SignifyError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SignifyError {}
+
+impl From<SignatureError> for SignifyError {
+    fn from(e: SignatureError) -> Self {
+        SignifyError::Signature(e)
+    }
+}
+
+const ALGORITHM: &[u8; 2] = b"Ed";
+#[cfg(feature = "signing")]
+const KDF_ALGORITHM: &[u8; 2] = b"BK";
+const COMMENT_PREFIX: &str = "untrusted comment: ";
+// signify's limit, which counts the terminating NUL.
+const MAX_COMMENT_LEN: usize = 1023;
+
+const PUBLIC_KEY_LEN: usize = 2 + 8 + 32;
+const SIGNATURE_LEN: usize = 2 + 8 + 64;
+#[cfg(feature = "signing")]
+const SECRET_KEY_LEN: usize = 2 + 2 + 4 + 16 + 8 + 8 + 64;
+
+// A public key with its key number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignifyPublicKey {
+    pub key_number: [u8; 8],
+    pub public: PublicKey,
+}
+
+impl SignifyPublicKey {
+    // The base64 line alone, as minisign -P takes a key on the command
+    // line.
+    pub fn from_base64(line: &str) -> Result<Self, SignifyError> {
+        let bytes = base64::decode(line).ok_or(SignifyError::InvalidBase64)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn to_base64(&self) -> String {
+        let mut bytes = ALGORITHM.to_vec();
+        bytes.extend_from_slice(&self.key_number);
+        bytes.extend_from_slice(self.public.as_bytes());
+        base64::encode(&bytes, true)
+    }
+
+    pub fn from_file(text: &str) -> Result<Self, SignifyError> {
+        let (_, bytes, rest) = read_box(text)?;
+        only_whitespace(rest)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn to_file(&self, comment: &str) -> String {
+        write_box(comment, &self.to_base64())
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SignifyError> {
+        if bytes.len() != PUBLIC_KEY_LEN {
+            return Err(SignifyError::Malformed);
+        }
+        algorithm(bytes)?;
+        Ok(Self {
+            key_number: bytes[2..10].try_into().unwrap(),
+            public: PublicKey::from_bytes(bytes[10..].try_into().unwrap())?,
+        })
+    }
+}
+
+// An unencrypted secret key. The key number is chosen at random when a key
+// is generated; any 8 bytes will do, as long as each key has its own.
+#[cfg(feature = "signing")]
+#[derive(Debug, Clone)]
+pub struct SignifySecretKey {
+    pub key_number: [u8; 8],
+    pub keypair: Keypair,
+}
+
+#[cfg(feature = "signing")]
+impl SignifySecretKey {
+    pub fn public_key(&self) -> SignifyPublicKey {
+        SignifyPublicKey {
+            key_number: self.key_number,
+            public: *self.keypair.public(),
+        }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> SignifySignature {
+        SignifySignature {
+            key_number: self.key_number,
+            signature: self.keypair.sign(message),
+        }
+    }
+
+    // An encrypted key fails with UnsupportedAlgorithm, and one whose
+    // checksum doesn't match with Malformed.
+    pub fn from_file(text: &str) -> Result<Self, SignifyError> {
+        let (_, bytes, rest) = read_box(text)?;
+        let bytes = Zeroizing::new(bytes);
+        only_whitespace(rest)?;
+        if bytes.len() != SECRET_KEY_LEN {
+            return Err(SignifyError::Malformed);
+        }
+        algorithm(&bytes)?;
+        if &bytes[2..4] != KDF_ALGORITHM || bytes[4..8] != [0; 4] {
+            return Err(SignifyError::UnsupportedAlgorithm);
+        }
+        let secret = Zeroizing::new(<[u8; 64]>::try_from(&bytes[40..]).unwrap());
+        if Sha512::digest(&secret[..])[..8] != bytes[24..32] {
+            return Err(SignifyError::Malformed);
+        }
+        Ok(Self {
+            key_number: bytes[32..40].try_into().unwrap(),
+            keypair: Keypair::from_bytes(&secret)?,
+        })
+    }
+
+    // Written as signify -n writes it: no rounds, so no encryption, and
+    // the salt, which would only feed the passphrase hash, left zero.
+    pub fn to_file(&self, comment: &str) -> Zeroizing<String> {
+        let secret = Zeroizing::new(self.keypair.to_bytes());
+        let mut bytes = Zeroizing::new(ALGORITHM.to_vec());
+        bytes.extend_from_slice(KDF_ALGORITHM);
+        bytes.extend_from_slice(&0u32.to_be_bytes());
+        bytes.extend_from_slice(&[0; 16]);
+        bytes.extend_from_slice(&Sha512::digest(&secret[..])[..8]);
+        bytes.extend_from_slice(&self.key_number);
+        bytes.extend_from_slice(&secret[..]);
+        let line = Zeroizing::new(base64::encode(&bytes, true));
+        Zeroizing::new(write_box(comment, &line))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignifySignature {
+    key_number: [u8; 8],
+    signature: Signature,
+}
+
+impl SignifySignature {
+    // The number of the key that made the signature.
+    pub fn key_number(&self) -> &[u8; 8] {
+        &self.key_number
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    // Checks the signature against key, as signify -V does. A signature
+    // from a key with a different number fails with KeyMismatch without
+    // any curve arithmetic.
+    pub fn verify(&self, key: &SignifyPublicKey, message: &[u8]) -> Result<(), SignifyError> {
+        if self.key_number != key.key_number {
+            return Err(SignatureError::KeyMismatch.into());
+        }
+        Ok(key.public.verify(message, &self.signature)?)
+    }
+
+    pub fn from_file(text: &str) -> Result<Self, SignifyError> {
+        let (_, bytes, rest) = read_box(text)?;
+        only_whitespace(rest)?;
+        Self::from_bytes(&bytes)
+    }
+
+    // signify writes "verify with <public key file name>" as the comment.
+    pub fn to_file(&self, comment: &str) -> String {
+        write_box(comment, &base64::encode(&self.to_bytes(), true))
+    }
+
+    // Splits an embedded signature into the signature and the message
+    // after it, which is what has to be verified.
+    pub fn from_embedded(text: &str) -> Result<(Self, &str), SignifyError> {
+        let (_, bytes, message) = read_box(text)?;
+        Ok((Self::from_bytes(&bytes)?, message))
+    }
+
+    pub fn to_embedded(&self, comment: &str, message: &str) -> String {
+        self.to_file(comment) + message
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = ALGORITHM.to_vec();
+        bytes.extend_from_slice(&self.key_number);
+        bytes.extend_from_slice(&self.signature.to_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SignifyError> {
+        if bytes.len() != SIGNATURE_LEN {
+            return Err(SignifyError::Malformed);
+        }
+        algorithm(bytes)?;
+        Ok(Self {
+            key_number: bytes[2..10].try_into().unwrap(),
+            signature: Signature::try_from(&bytes[10..])?,
+        })
+    }
+}
+
+// Anything not starting with "Ed" is for another algorithm; minisign's
+// prehashed signatures start with "ED".
+fn algorithm(bytes: &[u8]) -> Result<(), SignifyError> {
+    if &bytes[..2] != ALGORITHM {
+        return Err(SignifyError::UnsupportedAlgorithm);
+    }
+    Ok(())
+}
+
+// Splits text into the untrusted comment, the decoded base64 line and
+// whatever follows that line. Like signify, this wants a non-empty
+// comment and a newline after the base64.
+pub(crate) fn read_box(text: &str) -> Result<(&str, Vec<u8>, &str), SignifyError> {
+    let malformed = SignifyError::Malformed;
+    let (comment, rest) = text.split_once('\n').ok_or(malformed)?;
+    let comment = comment.strip_prefix(COMMENT_PREFIX).ok_or(malformed)?;
+    if comment.is_empty() || comment.len() > MAX_COMMENT_LEN {
+        return Err(malformed);
+    }
+    let (line, rest) = rest.split_once('\n').ok_or(malformed)?;
+    let bytes = base64::decode(line).ok_or(SignifyError::InvalidBase64)?;
+    Ok((comment, bytes, rest))
+}
+
+// The comment is written as given, so it must not contain a newline.
+pub(crate) fn write_box(comment: &str, line: &str) -> String {
+    format!("{COMMENT_PREFIX}{comment}\n{line}\n")
+}
+
+fn only_whitespace(rest: &str) -> Result<(), SignifyError> {
+    if !rest.trim().is_empty() {
+        return Err(SignifyError::Malformed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A minisign public key, which is also a signify one (from the
+    // minisign-verify crate's tests).
+    const PUBLIC_KEY: &str = "untrusted comment: minisign public key E7620F1842B4E81F\n\
+        RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3\n";
+
+    // A legacy minisign signature of "test" by that key, without its
+    // trusted comment lines: the signature line is signify's.
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key\n\
+        RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=\n";
+
+    #[test]
+    fn public_key() {
+        let key = SignifyPublicKey::from_file(PUBLIC_KEY).unwrap();
+        assert_eq!(hex::encode(key.key_number), "1fe8b442180f62e7");
+        assert_eq!(
+            key.to_file("minisign public key E7620F1842B4E81F"),
+            PUBLIC_KEY
+        );
+        assert_eq!(
+            SignifyPublicKey::from_base64(
+                "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"
+            ),
+            Ok(key)
+        );
+    }
+
+    #[test]
+    fn verify() {
+        let key = SignifyPublicKey::from_file(PUBLIC_KEY).unwrap();
+        let signature = SignifySignature::from_file(SIGNATURE).unwrap();
+        assert_eq!(signature.verify(&key, b"test"), Ok(()));
+        assert_eq!(
+            signature.verify(&key, b"Test"),
+            Err(SignifyError::Signature(SignatureError::VerificationFailed))
+        );
+        assert_eq!(
+            signature.to_file("signature from minisign secret key"),
+            SIGNATURE
+        );
+
+        let other = SignifyPublicKey {
+            key_number: [0; 8],
+            ..key
+        };
+        assert_eq!(
+            signature.verify(&other, b"test"),
+            Err(SignifyError::Signature(SignatureError::KeyMismatch))
+        );
+    }
+
+    #[test]
+    fn embedded() {
+        let text = format!("{SIGNATURE}test");
+        let (signature, message) = SignifySignature::from_embedded(&text).unwrap();
+        assert_eq!(message, "test");
+        let key = SignifyPublicKey::from_file(PUBLIC_KEY).unwrap();
+        assert_eq!(signature.verify(&key, message.as_bytes()), Ok(()));
+        assert_eq!(
+            signature.to_embedded("signature from minisign secret key", "test"),
+            text
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn secret_key() {
+        let secret = SignifySecretKey {
+            key_number: *b"keynum01",
+            keypair: Keypair::from_seed([7; 32]),
+        };
+        let file = secret.to_file("signify secret key");
+        assert!(file.starts_with("untrusted comment: signify secret key\nRWRCSwAAAAA"));
+        let read = SignifySecretKey::from_file(&file).unwrap();
+        assert_eq!(read.key_number, secret.key_number);
+        assert_eq!(read.keypair.to_bytes(), secret.keypair.to_bytes());
+
+        let signature = read.sign(b"release");
+        let signature = SignifySignature::from_file(&signature.to_file("verify with key.pub"));
+        assert_eq!(
+            signature.unwrap().verify(&secret.public_key(), b"release"),
+            Ok(())
+        );
+
+        // one bit of the seed flipped, caught by the checksum; 16 rounds
+        let mut bytes = base64::decode(file.lines().nth(1).unwrap()).unwrap();
+        bytes[40] ^= 1;
+        let corrupt = write_box("c", &base64::encode(&bytes, true));
+        assert!(matches!(
+            SignifySecretKey::from_file(&corrupt),
+            Err(SignifyError::Malformed)
+        ));
+        bytes[40] ^= 1;
+        bytes[7] = 16;
+        let encrypted = write_box("c", &base64::encode(&bytes, true));
+        assert!(matches!(
+            SignifySecretKey::from_file(&encrypted),
+            Err(SignifyError::UnsupportedAlgorithm)
+        ));
+    }
+
+    #[test]
+    fn malformed() {
+        let line = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+        let cases = [
+            (line.to_owned(), SignifyError::Malformed),
+            (
+                format!("untrusted comment: \n{line}\n"),
+                SignifyError::Malformed,
+            ),
+            (
+                format!("trusted comment: x\n{line}\n"),
+                SignifyError::Malformed,
+            ),
+            (
+                format!("untrusted comment: x\n{line}"),
+                SignifyError::Malformed,
+            ),
+            (
+                format!("untrusted comment: x\n{line}\nmore\n"),
+                SignifyError::Malformed,
+            ),
+            (
+                format!("untrusted comment: x\n{}\n", &line[4..]),
+                SignifyError::Malformed,
+            ),
+            (
+                format!("untrusted comment: x\n{line}=\n"),
+                SignifyError::InvalidBase64,
+            ),
+            // "ED", a minisign prehashed signature's algorithm
+            (
+                format!("untrusted comment: x\nRUQ{}\n", &line[3..]),
+                SignifyError::UnsupportedAlgorithm,
+            ),
+        ];
+        for (text, error) in cases {
+            assert_eq!(SignifyPublicKey::from_file(&text), Err(error), "{text}");
+        }
+    }
+}
@@ -0,0 +1,1916 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use sha2::digest::consts::U64;
+use sha2::{Digest, Sha256, Sha512};
+#[cfg(feature = "signing")]
+use zeroize::Zeroize;
+
+#[cfg(feature = "signing")]
+use crate::edwards::ED25519_BASEPOINT_TABLE;
+use crate::edwards::{CompressedEdwardsY, EdwardsPoint, NafLookupTable};
+use crate::scalar::Scalar;
+
+// Ed25519 signatures (RFC 8032) on top of the Edwards curve arithmetic.
+//
+// A secret key is 32 random bytes, the seed. Hashing it with SHA-512 gives
+// 64 bytes: the first half, clamped, is the secret scalar s, and the second
+// half is a "prefix" used to derive nonces. The public key is A = s * B.
+//
+// The key and signing types are generic over the hash (see Hash512), with
+// aliases for the standard SHA-512 instantiation: Keypair is
+// GenericKeypair<Sha512>, and so on.
+//
+// Everything that needs the secret key is behind the default "signing"
+// feature. A verifier that only checks signatures (a bootloader checking
+// firmware, say) can turn it off to build without the key types, signing
+// and nonce code; see examples/verify_only.rs.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    // 32 bytes that are not the encoding of a curve point.
+    InvalidPoint,
+    // A point encoding that decodes, but isn't the one compress produces:
+    // y >= p, or the sign bit set with x = 0. Only rejected by
+    // verify_strict.
+    NonCanonicalPoint,
+    // R or A has small order. Only rejected by verify_strict.
+    SmallOrderPoint,
+    // The S half of a signature is not reduced mod L.
+    NonCanonicalScalar,
+    // A signature that is not 64 bytes long.
+    InvalidLength,
+    // Contexts are at most 255 bytes, since their length is hashed as one
+    // byte.
+    ContextTooLong,
+    // Ed25519ctx needs a non-empty context; see PublicKey::verify_with_context.
+    EmptyContext,
+    // verify_batch was given different numbers of messages, signatures and
+    // keys.
+    BatchLengthMismatch,
+    // A 64-byte (libsodium style) secret key whose second half is not the
+    // public key of its first, or a signature made by a key other than the
+    // expected one.
+    KeyMismatch,
+    // The signature is well-formed but doesn't match the message and key.
+    VerificationFailed,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::InvalidPoint => f.write_str("bytes do not encode a curve point"),
+            SignatureError::NonCanonicalPoint => f.write_str("point encoding is not canonical"),
+            SignatureError::SmallOrderPoint => f.write_str("point has small order"),
+            SignatureError::NonCanonicalScalar => {
+                f.write_str("signature scalar is not reduced mod L")
+            }
+            SignatureError::InvalidLength => f.write_str("signature must be 64 bytes"),
+            SignatureError::ContextTooLong => f.write_str("context is longer than 255 bytes"),
+            SignatureError::EmptyContext => f.write_str("context must not be empty"),
+            SignatureError::BatchLengthMismatch => {
+                f.write_str("batch needs one message and key per signature")
+            }
+            SignatureError::KeyMismatch => f.write_str("public key does not match the secret key"),
+            SignatureError::VerificationFailed => f.write_str("signature verification failed"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+// A signature is the encoding of the point R followed by the scalar S.
+// R is kept as bytes, since verification hashes and compares the encoding
+// and only needs the point for strict checks; S is parsed up front.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    r: CompressedEdwardsY,
+    s: Scalar,
+}
+
+impl Signature {
+    // Rejects S >= L: S and S + L satisfy the same verification equation,
+    // so accepting both would let anyone turn one valid signature into
+    // another (malleability). R is not checked here; verification does.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, SignatureError> {
+        let r = CompressedEdwardsY(bytes[..32].try_into().unwrap());
+        let s = Scalar::from_canonical_bytes(bytes[32..].try_into().unwrap())
+            .ok_or(SignatureError::NonCanonicalScalar)?;
+        Ok(Self { r, s })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(self.r.as_bytes());
+        bytes[32..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    pub fn r(&self) -> &CompressedEdwardsY {
+        &self.r
+    }
+
+    pub fn s(&self) -> &Scalar {
+        &self.s
+    }
+}
+
+impl TryFrom<&[u8]> for Signature {
+    type Error = SignatureError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SignatureError> {
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| SignatureError::InvalidLength)?;
+        Self::from_bytes(bytes)
+    }
+}
+
+// S is public, so unlike Scalar's Debug this prints both halves.
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Signature(R: ")?;
+        for byte in self.r.as_bytes() {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ", S: {})", self.s)
+    }
+}
+
+// A hash with a 64-byte output, used everywhere Ed25519 uses SHA-512: to
+// expand the seed, and to derive the nonce r and the challenge k. Nothing
+// in the construction depends on SHA-512 beyond its output size, so
+// swapping in SHA3-512 or BLAKE2b-512 gives a working (but incompatible)
+// signature scheme, which is useful for studying variants and needed for
+// ecosystems that standardised on another hash. Implemented for every
+// Digest with a 64-byte output.
+pub trait Hash512: Digest<OutputSize = U64> {}
+
+impl<D: Digest<OutputSize = U64>> Hash512 for D {}
+
+// Ed25519-BLAKE2b, the variant used by Nano: the same scheme with
+// BLAKE2b-512 for all three hashes. There is no dom2 prefix in that
+// ecosystem, so only the plain sign and verify are meant to be used.
+#[cfg(all(feature = "blake2b", feature = "signing"))]
+pub type Blake2bKeypair = GenericKeypair<blake2::Blake2b512>;
+#[cfg(feature = "blake2b")]
+pub type Blake2bPublicKey = GenericPublicKey<blake2::Blake2b512>;
+#[cfg(all(feature = "blake2b", feature = "signing"))]
+pub type Blake2bExpandedSecretKey = GenericExpandedSecretKey<blake2::Blake2b512>;
+
+// H of the concatenated parts, reduced mod L.
+fn hash_to_scalar<D: Hash512>(parts: &[&[u8]]) -> Scalar {
+    let mut hasher = D::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    Scalar::from_bytes_mod_order_wide(hasher.finalize().into())
+}
+
+// RFC 8032 section 5.1. Plain Ed25519 hashes nothing in front of its two
+// hashes; the other variants prepend
+//
+//   dom2(F, C) = "SigEd25519 no Ed25519 collisions" || F || len(C) || C
+//
+// where F is 1 for the prehashed variant, so a signature made under one
+// variant or context never verifies under another.
+fn dom2(phflag: u8, context: &[u8]) -> Result<Vec<u8>, SignatureError> {
+    let len = u8::try_from(context.len()).map_err(|_| SignatureError::ContextTooLong)?;
+    let mut dom = b"SigEd25519 no Ed25519 collisions".to_vec();
+    dom.push(phflag);
+    dom.push(len);
+    dom.extend_from_slice(context);
+    Ok(dom)
+}
+
+fn dom2_ctx(context: &[u8]) -> Result<Vec<u8>, SignatureError> {
+    if context.is_empty() {
+        return Err(SignatureError::EmptyContext);
+    }
+    dom2(0, context)
+}
+
+// The 32-byte seed. Like the other secret-bearing types below, it is
+// zeroized when dropped, its Debug output is redacted, and to_bytes is the
+// only way to get the raw secret back out, so it can't leak through a log
+// line or a stray reference by accident.
+#[cfg(feature = "signing")]
+#[derive(Clone)]
+pub struct SecretKey([u8; 32]);
+
+#[cfg(feature = "signing")]
+impl SecretKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    #[cfg(feature = "rand")]
+    pub fn generate<R: rand_core::RngCore + rand_core::CryptoRng>(rng: &mut R) -> Self {
+        let mut bytes = [0; 32];
+        rng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    // RFC 8032 section 5.1.5: the hash of the seed, split into the clamped
+    // secret scalar and the nonce prefix.
+    pub(crate) fn expand<D: Hash512>(&self) -> (Scalar, [u8; 32]) {
+        let mut h: [u8; 64] = D::digest(self.0).into();
+        let scalar = Scalar::clamp(h[..32].try_into().unwrap()).to_scalar();
+        let prefix = h[32..].try_into().unwrap();
+        h.zeroize();
+        (scalar, prefix)
+    }
+}
+
+#[cfg(feature = "signing")]
+impl Zeroize for SecretKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "signing")]
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "signing")]
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(<redacted>)")
+    }
+}
+
+// The secret scalar and nonce prefix from SecretKey::expand, together with
+// the public key. Signing with a Keypair hashes the seed and clamps the
+// result for every message; a signer producing many signatures can expand
+// once and sign with this instead. The public key is kept inside rather
+// than passed to sign, since signing with the wrong A for a given secret
+// gives two signatures with the same r, which reveals s.
+#[cfg(feature = "signing")]
+pub struct GenericExpandedSecretKey<D> {
+    scalar: Scalar,
+    prefix: [u8; 32],
+    public: GenericPublicKey<D>,
+}
+
+#[cfg(feature = "signing")]
+pub type ExpandedSecretKey = GenericExpandedSecretKey<Sha512>;
+
+#[cfg(feature = "signing")]
+impl<D: Hash512> GenericExpandedSecretKey<D> {
+    pub fn public(&self) -> &GenericPublicKey<D> {
+        &self.public
+    }
+
+    // The scalar followed by the prefix, the layout ed25519-dalek uses.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(self.scalar.as_bytes());
+        bytes[32..].copy_from_slice(&self.prefix);
+        bytes
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        sign_expanded(&self.scalar, &self.prefix, &self.public, &[], &[], message)
+    }
+
+    // See Keypair::sign_hedged.
+    #[cfg(feature = "rand")]
+    pub fn sign_hedged<R: rand_core::RngCore + rand_core::CryptoRng>(
+        &self,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Signature {
+        let mut z = [0; 32];
+        rng.fill_bytes(&mut z);
+        let signature = sign_expanded(&self.scalar, &self.prefix, &self.public, &[], &z, message);
+        z.zeroize();
+        signature
+    }
+
+    pub fn sign_with_context(
+        &self,
+        message: &[u8],
+        context: &[u8],
+    ) -> Result<Signature, SignatureError> {
+        let dom = dom2_ctx(context)?;
+        Ok(sign_expanded(
+            &self.scalar,
+            &self.prefix,
+            &self.public,
+            &dom,
+            &[],
+            message,
+        ))
+    }
+
+    pub fn sign_prehashed(
+        &self,
+        digest: &[u8; 64],
+        context: Option<&[u8]>,
+    ) -> Result<Signature, SignatureError> {
+        let dom = dom2(1, context.unwrap_or_default())?;
+        Ok(sign_expanded(
+            &self.scalar,
+            &self.prefix,
+            &self.public,
+            &dom,
+            &[],
+            digest,
+        ))
+    }
+}
+
+// Written out rather than derived, since deriving would require D: Clone
+// (and D: Copy for GenericPublicKey) although no D is stored.
+#[cfg(feature = "signing")]
+impl<D> Clone for GenericExpandedSecretKey<D> {
+    fn clone(&self) -> Self {
+        Self {
+            scalar: self.scalar,
+            prefix: self.prefix,
+            public: self.public,
+        }
+    }
+}
+
+#[cfg(feature = "signing")]
+impl<D: Hash512> From<&SecretKey> for GenericExpandedSecretKey<D> {
+    fn from(secret: &SecretKey) -> Self {
+        let (scalar, prefix) = secret.expand::<D>();
+        let public = GenericPublicKey::from_scalar(&scalar);
+        Self {
+            scalar,
+            prefix,
+            public,
+        }
+    }
+}
+
+#[cfg(feature = "signing")]
+impl<D: Hash512> From<&GenericKeypair<D>> for GenericExpandedSecretKey<D> {
+    fn from(keypair: &GenericKeypair<D>) -> Self {
+        let (scalar, prefix) = keypair.secret.expand::<D>();
+        Self {
+            scalar,
+            prefix,
+            public: keypair.public,
+        }
+    }
+}
+
+#[cfg(feature = "signing")]
+impl<D> Zeroize for GenericExpandedSecretKey<D> {
+    fn zeroize(&mut self) {
+        self.scalar.zeroize();
+        self.prefix.zeroize();
+    }
+}
+
+#[cfg(feature = "signing")]
+impl<D> Drop for GenericExpandedSecretKey<D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "signing")]
+impl<D> fmt::Debug for GenericExpandedSecretKey<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ExpandedSecretKey(<redacted>)")
+    }
+}
+
+// Which form of the verification equation to check. The RFC's is
+// cofactored, 8 * S * B = 8 * R + 8 * k * A, but many implementations
+// (including this crate's verify) check S * B = R + k * A without the 8.
+// The two agree on every honestly made signature and differ only when R or
+// A has a small-order component: multiplying by 8 clears it, so the
+// cofactored check accepts signatures the cofactorless one rejects. Which
+// one is right depends on what the other verifiers in a system do, since
+// everyone has to agree on which signatures are valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerificationPolicy {
+    // S * B - k * A, compressed, equals the bytes of R. What verify does.
+    #[default]
+    Cofactorless,
+    // 8 * (S * B - k * A - R) is the identity. What verify_batch does, and
+    // the only policy a batch verifier can match.
+    Cofactored,
+}
+
+// The point A together with its encoding, which is what gets hashed when
+// signing and verifying, so it is kept rather than recomputed. D is the
+// hash the key is used with; the point itself doesn't depend on it.
+pub struct GenericPublicKey<D> {
+    compressed: CompressedEdwardsY,
+    point: EdwardsPoint,
+    hash: PhantomData<fn() -> D>,
+}
+
+pub type PublicKey = GenericPublicKey<Sha512>;
+
+impl<D: Hash512> GenericPublicKey<D> {
+    // Accepts any encoding that decodes to a point, including non-canonical
+    // ones and small-order points: verify keeps the encoding as given, and
+    // it is verify_strict that rejects such keys. The borsh, bincode and
+    // cbor decoders go through here and accept the same keys.
+    pub fn from_bytes(bytes: [u8; 32]) -> Result<Self, SignatureError> {
+        let compressed = CompressedEdwardsY(bytes);
+        let point = compressed
+            .decompress()
+            .ok_or(SignatureError::InvalidPoint)?;
+        Ok(Self {
+            compressed,
+            point,
+            hash: PhantomData,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.compressed.to_bytes()
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        self.compressed.as_bytes()
+    }
+
+    pub fn as_point(&self) -> &EdwardsPoint {
+        &self.point
+    }
+
+    // RFC 8032 section 5.1.7. The signer claims S = r + k * s, so
+    // multiplying by B, S * B = R + k * A. We check that R decodes (S was
+    // checked to be reduced when the Signature was parsed), recompute
+    // k = H(R || A || M), and compare S * B - k * A with R by their
+    // encodings. This is the cofactorless equation; both scalars are
+    // public, so the double-scalar multiplication can run in variable time.
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        self.verify_with_policy(message, signature, VerificationPolicy::Cofactorless)
+    }
+
+    pub fn verify_with_policy(
+        &self,
+        message: &[u8],
+        signature: &Signature,
+        policy: VerificationPolicy,
+    ) -> Result<(), SignatureError> {
+        self.verify_with_dom(&[], message, signature, policy)
+    }
+
+    // verify, with the extra checks from ed25519-dalek's verify_strict: the
+    // encodings of A and R must be canonical and neither point may have
+    // small order. A small-order key signs nothing; with A = identity,
+    // (R, S) = (identity, 0) verifies for every message. Rejecting these
+    // (and the alternative encodings of a point, which would otherwise
+    // give a second valid signature or key) means a signature can only
+    // verify under the key that made it.
+    pub fn verify_strict(
+        &self,
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        check_strict(&self.compressed, &self.point)?;
+        let big_r = signature.r();
+        let r_point = big_r.decompress().ok_or(SignatureError::InvalidPoint)?;
+        check_strict(big_r, &r_point)?;
+        self.verify(message, signature)
+    }
+
+    // Ed25519ctx (RFC 8032 section 5.1): the signature is bound to a
+    // context string naming the protocol it was made for, so it can't be
+    // replayed in another protocol using the same key. The RFC says the
+    // context should not be empty (that would just be Ed25519 under a
+    // different name), and it is rejected here.
+    pub fn verify_with_context(
+        &self,
+        message: &[u8],
+        context: &[u8],
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        let dom = dom2_ctx(context)?;
+        self.verify_with_dom(&dom, message, signature, VerificationPolicy::Cofactorless)
+    }
+
+    // Ed25519ph (RFC 8032 section 5.1): the message is replaced by its
+    // SHA-512 digest, so a large or streamed message only has to be hashed
+    // once, by the caller. The context is empty if none is given.
+    pub fn verify_prehashed(
+        &self,
+        digest: &[u8; 64],
+        context: Option<&[u8]>,
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        let dom = dom2(1, context.unwrap_or_default())?;
+        self.verify_with_dom(&dom, digest, signature, VerificationPolicy::Cofactorless)
+    }
+
+    // Ed25519ph over data that arrives in pieces; see StreamVerifier.
+    pub fn stream_verifier(
+        &self,
+        signature: &Signature,
+        context: Option<&[u8]>,
+    ) -> Result<StreamVerifier<D>, SignatureError> {
+        Ok(StreamVerifier {
+            public: *self,
+            signature: *signature,
+            dom: dom2(1, context.unwrap_or_default())?,
+            hasher: D::new(),
+        })
+    }
+
+    fn verify_with_dom(
+        &self,
+        dom: &[u8],
+        message: &[u8],
+        signature: &Signature,
+        policy: VerificationPolicy,
+    ) -> Result<(), SignatureError> {
+        let big_r = signature.r();
+        let r_point = big_r.decompress().ok_or(SignatureError::InvalidPoint)?;
+
+        let k = hash_to_scalar::<D>(&[dom, big_r.as_bytes(), self.as_bytes(), message]);
+        let expected =
+            EdwardsPoint::vartime_double_scalar_mul_basepoint(&-k, &self.point, signature.s());
+        let valid = match policy {
+            VerificationPolicy::Cofactorless => expected.compress() == *big_r,
+            VerificationPolicy::Cofactored => (expected - r_point).mul_by_cofactor().is_identity(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(SignatureError::VerificationFailed)
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    fn from_scalar(s: &Scalar) -> Self {
+        let point = s * &*ED25519_BASEPOINT_TABLE;
+        Self {
+            compressed: point.compress(),
+            point,
+            hash: PhantomData,
+        }
+    }
+}
+
+impl PublicKey {
+    // The SHA-256 fingerprint OpenSSH shows for this key, e.g. in
+    // ssh-keygen -l and on first connection to a host. See Fingerprint.
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint(Sha256::digest(self.ssh_blob()).into())
+    }
+
+    // The SSH wire encoding of the key (RFC 8709): the SSH strings
+    // "ssh-ed25519" and A, each a 4-byte big-endian length followed by the
+    // bytes. The ssh module builds its formats on this.
+    pub(crate) fn ssh_blob(&self) -> [u8; 51] {
+        let mut blob = [0; 51];
+        blob[..4].copy_from_slice(&11u32.to_be_bytes());
+        blob[4..15].copy_from_slice(b"ssh-ed25519");
+        blob[15..19].copy_from_slice(&32u32.to_be_bytes());
+        blob[19..].copy_from_slice(self.as_bytes());
+        blob
+    }
+}
+
+impl<D> Clone for GenericPublicKey<D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D> Copy for GenericPublicKey<D> {}
+
+impl<D> fmt::Debug for GenericPublicKey<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PublicKey")
+            .field("compressed", &self.compressed)
+            .field("point", &self.point)
+            .finish()
+    }
+}
+
+fn check_strict(
+    compressed: &CompressedEdwardsY,
+    point: &EdwardsPoint,
+) -> Result<(), SignatureError> {
+    if point.compress() != *compressed {
+        return Err(SignatureError::NonCanonicalPoint);
+    }
+    if point.is_small_order() {
+        return Err(SignatureError::SmallOrderPoint);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "signing")]
+impl<D: Hash512> From<&SecretKey> for GenericPublicKey<D> {
+    fn from(secret: &SecretKey) -> Self {
+        let (mut s, _) = secret.expand::<D>();
+        let public = Self::from_scalar(&s);
+        s.zeroize();
+        public
+    }
+}
+
+impl<D> PartialEq for GenericPublicKey<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.compressed == other.compressed
+    }
+}
+
+impl<D> Eq for GenericPublicKey<D> {}
+
+// A short, stable name for a public key: SHA-256 of the key's SSH wire
+// encoding (see PublicKey::ssh_blob) rather than of the 32 bytes alone, so
+// it is the same hash OpenSSH computes. Display gives OpenSSH's form,
+// "SHA256:" followed by unpadded base64; {:x} gives the digest in hex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SHA256:{}", crate::base64::encode(&self.0, false))
+    }
+}
+
+impl fmt::LowerHex for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+// A verifier for one public key that checks many signatures, e.g. a
+// validator checking messages from a fixed set of peers. It keeps a
+// width-8 table of odd multiples of A (see NafLookupTable), which costs
+// about a fifth of a verification to build and then saves a few additions
+// on every verification, besides the table the plain verify rebuilds each
+// time. Verification is the cofactorless check of PublicKey::verify.
+#[derive(Clone)]
+pub struct GenericPrecomputedVerifier<D> {
+    public: GenericPublicKey<D>,
+    table: NafLookupTable,
+}
+
+pub type PrecomputedVerifier = GenericPrecomputedVerifier<Sha512>;
+
+impl<D: Hash512> GenericPrecomputedVerifier<D> {
+    pub fn new(public: &GenericPublicKey<D>) -> Self {
+        Self {
+            public: *public,
+            table: NafLookupTable::new(&public.point),
+        }
+    }
+
+    pub fn public(&self) -> &GenericPublicKey<D> {
+        &self.public
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        let big_r = signature.r();
+        big_r.decompress().ok_or(SignatureError::InvalidPoint)?;
+
+        let k = hash_to_scalar::<D>(&[big_r.as_bytes(), self.public.as_bytes(), message]);
+        let expected = self
+            .table
+            .vartime_double_scalar_mul_basepoint(&-k, signature.s());
+        if expected.compress() == *big_r {
+            Ok(())
+        } else {
+            Err(SignatureError::VerificationFailed)
+        }
+    }
+}
+
+// The smallest chunk verify_batch hands to one thread: below this the
+// doublings a chunk can't share outweigh what a thread saves.
+#[cfg(feature = "parallel")]
+const BATCH_CHUNK_TERMS: usize = 32;
+
+// Checks many signatures at once. Each one claims
+//
+//   S_i * B = R_i + k_i * A_i,
+//
+// and instead of checking them separately we pick 128-bit weights z_i and
+// check the single combination
+//
+//   8 * ((-sum z_i S_i) * B + sum z_i R_i + sum (z_i k_i) A_i) = identity
+//
+// with one multiscalar multiplication, sharing the doublings across all
+// 2n + 1 terms. If any signature is invalid, the combination only
+// vanishes if the z_i happen to cancel the error, which has probability
+// about 2^-128.
+//
+// The z_i are not drawn from an RNG but hashed from the whole batch (every
+// R, S, A and k, and so every message), the same Fiat-Shamir step that
+// turns the signatures themselves non-interactive. A forger has to fix
+// all the signatures before learning the z_i they are weighted by, and any
+// change to one of them changes every z_i, so errors that cancel under one
+// set of weights (say S_1 + d and S_2 - d, which cancel when z_1 = z_2)
+// don't under the next. This keeps verify_batch available without the
+// rand feature, in the verify-only build, and a batch that fails does so
+// every time. Its security rests on SHA-512 behaving as a random oracle,
+// which Ed25519 already assumes. verify_batch_with_rng is the textbook
+// version, with random z_i: it mixes 32 bytes from the RNG into the
+// transcript, so the weights are unpredictable even to someone who can
+// choose every signature in the batch.
+//
+// The check is cofactored, so it accepts some signatures that verify
+// rejects: those where R or A has a small-order component that the
+// multiplication by 8 clears. Batch verification can't be cofactorless,
+// since the random weights would make the outcome for such signatures
+// depend on the z_i. A failure only says that some signature is bad, not
+// which one; callers that need to know verify them one by one.
+//
+// The transcript for the z_i always uses SHA-512, whatever hash the keys
+// are used with; it is internal to the verifier.
+//
+// With the parallel feature the terms are split into one chunk per rayon
+// thread, each chunk gets its own multiscalar multiplication, and the
+// partial sums are added at the end. Every chunk repeats the ~253
+// doublings, so chunks are kept to at least BATCH_CHUNK_TERMS terms.
+pub fn verify_batch<D: Hash512>(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[GenericPublicKey<D>],
+) -> Result<(), SignatureError> {
+    verify_batch_seeded(messages, signatures, public_keys, &[])
+}
+
+// verify_batch with weights drawn from rng (mixed with the batch hash, so
+// a weak RNG is no worse than none).
+#[cfg(feature = "rand")]
+pub fn verify_batch_with_rng<D: Hash512, R: rand_core::RngCore + rand_core::CryptoRng>(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[GenericPublicKey<D>],
+    rng: &mut R,
+) -> Result<(), SignatureError> {
+    let mut randomness = [0; 32];
+    rng.fill_bytes(&mut randomness);
+    verify_batch_seeded(messages, signatures, public_keys, &randomness)
+}
+
+// The batch check with randomness (empty for verify_batch) hashed into the
+// weights' transcript ahead of the batch.
+fn verify_batch_seeded<D: Hash512>(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[GenericPublicKey<D>],
+    randomness: &[u8],
+) -> Result<(), SignatureError> {
+    if messages.len() != signatures.len() || public_keys.len() != signatures.len() {
+        return Err(SignatureError::BatchLengthMismatch);
+    }
+
+    let mut transcript = Sha512::new();
+    transcript.update(b"ed25519 batch verification");
+    transcript.update(randomness);
+    let mut ks = Vec::with_capacity(signatures.len());
+    let mut big_rs = Vec::with_capacity(signatures.len());
+    for ((message, signature), public) in messages.iter().zip(signatures).zip(public_keys) {
+        let big_r = signature.r();
+        big_rs.push(big_r.decompress().ok_or(SignatureError::InvalidPoint)?);
+        let k = hash_to_scalar::<D>(&[big_r.as_bytes(), public.as_bytes(), message]);
+        transcript.update(signature.to_bytes());
+        transcript.update(public.as_bytes());
+        transcript.update(k.as_bytes());
+        ks.push(k);
+    }
+    let seed = transcript.finalize();
+
+    let mut b_coefficient = Scalar::ZERO;
+    let mut scalars = Vec::with_capacity(2 * signatures.len());
+    let mut points = Vec::with_capacity(2 * signatures.len());
+    for (i, ((signature, public), (k, big_r))) in signatures
+        .iter()
+        .zip(public_keys)
+        .zip(ks.iter().zip(big_rs))
+        .enumerate()
+    {
+        let digest = Sha512::new()
+            .chain_update(seed)
+            .chain_update((i as u64).to_le_bytes())
+            .finalize();
+        let mut z = [0; 32];
+        z[..16].copy_from_slice(&digest[..16]);
+        let z = Scalar::from_bytes_mod_order(z);
+
+        b_coefficient -= z * signature.s();
+        scalars.push(z);
+        points.push(big_r);
+        scalars.push(z * k);
+        points.push(public.point);
+    }
+    scalars.push(b_coefficient);
+    points.push(crate::edwards::ED25519_BASEPOINT);
+
+    #[cfg(feature = "parallel")]
+    let sum: EdwardsPoint = {
+        use rayon::prelude::*;
+        let chunk = scalars
+            .len()
+            .div_ceil(rayon::current_num_threads())
+            .max(BATCH_CHUNK_TERMS);
+        scalars
+            .par_chunks(chunk)
+            .zip(points.par_chunks(chunk))
+            .map(|(scalars, points)| EdwardsPoint::vartime_multiscalar_mul(scalars, points))
+            .sum()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let sum = EdwardsPoint::vartime_multiscalar_mul(&scalars, &points);
+
+    if sum.mul_by_cofactor().is_identity() {
+        Ok(())
+    } else {
+        Err(SignatureError::VerificationFailed)
+    }
+}
+
+#[cfg(feature = "signing")]
+pub struct GenericKeypair<D> {
+    secret: SecretKey,
+    public: GenericPublicKey<D>,
+}
+
+#[cfg(feature = "signing")]
+pub type Keypair = GenericKeypair<Sha512>;
+
+#[cfg(feature = "signing")]
+impl<D: Hash512> GenericKeypair<D> {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self::from(SecretKey::from_bytes(seed))
+    }
+
+    #[cfg(feature = "rand")]
+    pub fn generate<R: rand_core::RngCore + rand_core::CryptoRng>(rng: &mut R) -> Self {
+        Self::from(SecretKey::generate(rng))
+    }
+
+    // The seed followed by the public key, as libsodium stores secret keys.
+    // Fails if the public key isn't the one the seed derives: signing with
+    // a mismatched one would reuse nonces.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, SignatureError> {
+        let keypair = Self::from_seed(bytes[..32].try_into().unwrap());
+        if keypair.public.as_bytes()[..] != bytes[32..] {
+            return Err(SignatureError::KeyMismatch);
+        }
+        Ok(keypair)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(&self.secret.0);
+        bytes[32..].copy_from_slice(self.public.as_bytes());
+        bytes
+    }
+
+    pub fn secret(&self) -> &SecretKey {
+        &self.secret
+    }
+
+    pub fn public(&self) -> &GenericPublicKey<D> {
+        &self.public
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        self.sign_with_dom(&[], &[], message)
+    }
+
+    // Deterministic nonces have a weakness of their own: a fault injected
+    // while computing one of two signatures of the same message (a glitch
+    // that flips a bit of k, say) gives two signatures with the same r,
+    // from which s follows. Hedged signing mixes 32 fresh random bytes Z
+    // into the nonce,
+    //
+    //   r = H(0xFE || 0xFF x 31 || prefix || Z || M),
+    //
+    // so repeated signatures of one message use different nonces. The
+    // leading block keeps hedged nonces apart from sign's: without it, a
+    // hedged signature of M with a predictable Z (a broken RNG returning
+    // zeros, say) would use the same r as sign(Z || M), and the two
+    // signatures together would give away s. With it, the two hashes
+    // could only meet if the secret prefix were that block. If the RNG is
+    // broken the prefix still keeps r secret, so this is never worse than
+    // sign. The result is an ordinary Ed25519 signature; only the signer
+    // can tell how r was chosen, but it is no longer reproducible.
+    #[cfg(feature = "rand")]
+    pub fn sign_hedged<R: rand_core::RngCore + rand_core::CryptoRng>(
+        &self,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Signature {
+        let mut z = [0; 32];
+        rng.fill_bytes(&mut z);
+        let signature = self.sign_with_dom(&[], &z, message);
+        z.zeroize();
+        signature
+    }
+
+    // Ed25519ctx; see PublicKey::verify_with_context.
+    pub fn sign_with_context(
+        &self,
+        message: &[u8],
+        context: &[u8],
+    ) -> Result<Signature, SignatureError> {
+        let dom = dom2_ctx(context)?;
+        Ok(self.sign_with_dom(&dom, &[], message))
+    }
+
+    // Ed25519ph, signing the SHA-512 digest of the message; see
+    // PublicKey::verify_prehashed.
+    pub fn sign_prehashed(
+        &self,
+        digest: &[u8; 64],
+        context: Option<&[u8]>,
+    ) -> Result<Signature, SignatureError> {
+        let dom = dom2(1, context.unwrap_or_default())?;
+        Ok(self.sign_with_dom(&dom, &[], digest))
+    }
+
+    // Ed25519ph over data that arrives in pieces; see StreamSigner.
+    pub fn stream_signer(
+        &self,
+        context: Option<&[u8]>,
+    ) -> Result<StreamSigner<'_, D>, SignatureError> {
+        Ok(StreamSigner {
+            keypair: self,
+            dom: dom2(1, context.unwrap_or_default())?,
+            hasher: D::new(),
+        })
+    }
+
+    fn sign_with_dom(&self, dom: &[u8], hedge: &[u8], message: &[u8]) -> Signature {
+        let (mut s, mut prefix) = self.secret.expand::<D>();
+        let signature = sign_expanded(&s, &prefix, &self.public, dom, hedge, message);
+        s.zeroize();
+        prefix.zeroize();
+        signature
+    }
+}
+
+// Signs a message too large to hold in memory. Plain Ed25519 hashes the
+// message twice, once for r and once for k, with r needed before k can
+// start, so it has to see the whole message twice; Ed25519ph signs the
+// 64-byte digest of the message instead, which can be computed in one pass.
+// Feed the data to update (or write it through io::Write, e.g. with
+// io::copy from a file) and call finalize for the Ed25519ph signature.
+//
+// The digest is taken with the key's hash, which for the standard
+// Keypair is the SHA-512 the RFC specifies.
+#[cfg(feature = "signing")]
+pub struct StreamSigner<'a, D> {
+    keypair: &'a GenericKeypair<D>,
+    dom: Vec<u8>,
+    hasher: D,
+}
+
+#[cfg(feature = "signing")]
+impl<D: Hash512> StreamSigner<'_, D> {
+    pub fn update(&mut self, data: &[u8]) -> &mut Self {
+        self.hasher.update(data);
+        self
+    }
+
+    pub fn finalize(self) -> Signature {
+        let digest = self.hasher.finalize();
+        self.keypair.sign_with_dom(&self.dom, &[], &digest)
+    }
+}
+
+#[cfg(feature = "signing")]
+impl<D: Hash512> std::io::Write for StreamSigner<'_, D> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// The verifying side of StreamSigner: the signature and context are given
+// up front, the message is fed through update or io::Write, and finalize
+// checks the Ed25519ph signature of its digest.
+pub struct StreamVerifier<D> {
+    public: GenericPublicKey<D>,
+    signature: Signature,
+    dom: Vec<u8>,
+    hasher: D,
+}
+
+impl<D: Hash512> StreamVerifier<D> {
+    pub fn update(&mut self, data: &[u8]) -> &mut Self {
+        self.hasher.update(data);
+        self
+    }
+
+    pub fn finalize(self) -> Result<(), SignatureError> {
+        let digest = self.hasher.finalize();
+        self.public.verify_with_dom(
+            &self.dom,
+            &digest,
+            &self.signature,
+            VerificationPolicy::Cofactorless,
+        )
+    }
+}
+
+impl<D: Hash512> std::io::Write for StreamVerifier<D> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// RFC 8032 section 5.1.6. The nonce r is derived from the secret prefix and
+// the message rather than drawn at random, so signing the same message twice
+// gives the same signature and a bad RNG can't leak the key (reusing r for
+// two different messages would reveal s).
+//
+//   r = H(dom || prefix || M),  R = r * B,  k = H(dom || R || A || M),
+//   S = r + k * s mod L
+//
+// with dom empty for plain Ed25519 (see dom2). sign_hedged passes a hedge
+// Z, and then r = H(dom || HEDGE_BLOCK || prefix || Z || M) instead (see
+// Keypair::sign_hedged).
+#[cfg(feature = "signing")]
+const HEDGE_BLOCK: [u8; 32] = {
+    let mut block = [0xff; 32];
+    block[0] = 0xfe;
+    block
+};
+
+#[cfg(feature = "signing")]
+fn sign_expanded<D: Hash512>(
+    s: &Scalar,
+    prefix: &[u8; 32],
+    public: &GenericPublicKey<D>,
+    dom: &[u8],
+    hedge: &[u8],
+    message: &[u8],
+) -> Signature {
+    let block: &[u8] = if hedge.is_empty() { &[] } else { &HEDGE_BLOCK };
+    let mut r = hash_to_scalar::<D>(&[dom, block, prefix, hedge, message]);
+    let big_r = (&r * &*ED25519_BASEPOINT_TABLE).compress();
+    let k = hash_to_scalar::<D>(&[dom, big_r.as_bytes(), public.as_bytes(), message]);
+    let big_s = r + k * s;
+    r.zeroize();
+
+    Signature { r: big_r, s: big_s }
+}
+
+// The secret half zeroizes itself when dropped and redacts its Debug.
+#[cfg(feature = "signing")]
+impl<D> fmt::Debug for GenericKeypair<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keypair")
+            .field("secret", &self.secret)
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
+#[cfg(feature = "signing")]
+impl<D> Zeroize for GenericKeypair<D> {
+    fn zeroize(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+#[cfg(feature = "signing")]
+impl<D> Clone for GenericKeypair<D> {
+    fn clone(&self) -> Self {
+        Self {
+            secret: self.secret.clone(),
+            public: self.public,
+        }
+    }
+}
+
+#[cfg(feature = "signing")]
+impl<D: Hash512> From<SecretKey> for GenericKeypair<D> {
+    fn from(secret: SecretKey) -> Self {
+        let public = GenericPublicKey::from(&secret);
+        Self { secret, public }
+    }
+}
+
+// Signer and Verifier from the RustCrypto signature crate, so code written
+// against those traits can use these keys. Signing can't fail; a failed
+// verification becomes an opaque signature::Error.
+#[cfg(feature = "signature")]
+mod signature_impl {
+    use super::*;
+
+    #[cfg(feature = "signing")]
+    impl<D: Hash512> signature::Signer<Signature> for GenericKeypair<D> {
+        fn try_sign(&self, message: &[u8]) -> Result<Signature, signature::Error> {
+            Ok(self.sign(message))
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    impl<D: Hash512> signature::Signer<Signature> for GenericExpandedSecretKey<D> {
+        fn try_sign(&self, message: &[u8]) -> Result<Signature, signature::Error> {
+            Ok(self.sign(message))
+        }
+    }
+
+    impl<D: Hash512> signature::Verifier<Signature> for GenericPublicKey<D> {
+        fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), signature::Error> {
+            GenericPublicKey::verify(self, message, signature).map_err(|_| signature::Error::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(h: &str) -> [u8; 32] {
+        hex::decode(h).unwrap().try_into().unwrap()
+    }
+
+    // RFC 8032 section 7.1, tests 1 to 3 and SHA(abc): seed, public key,
+    // message and signature.
+    const RFC8032: [(&str, &str, &str, &str); 4] = [
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            "af82",
+            "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+        ),
+        (
+            "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42",
+            "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf",
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            "dc2a4459e7369633a52b1bf277839a00201009a3efbf3ecb69bea2186c26b58909351fc9ac90b3ecfdfbc7c66431e0303dca179c138ac17ad9bef1177331a704",
+        ),
+    ];
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn rfc8032_signatures() {
+        for (seed, _, message, signature) in RFC8032 {
+            let keypair = Keypair::from_seed(bytes(seed));
+            let message = hex::decode(message).unwrap();
+            let signature: [u8; 64] = hex::decode(signature).unwrap().try_into().unwrap();
+            assert_eq!(keypair.sign(&message).to_bytes(), signature);
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn rfc8032_public_keys() {
+        for (seed, public, _, _) in RFC8032 {
+            let keypair = Keypair::from_seed(bytes(seed));
+            assert_eq!(keypair.public().to_bytes(), bytes(public));
+            assert_eq!(
+                PublicKey::from_bytes(bytes(public)).unwrap(),
+                *keypair.public()
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_public_key() {
+        // y = 2 is not the y-coordinate of any point
+        let mut invalid = [0; 32];
+        invalid[0] = 2;
+        assert_eq!(
+            PublicKey::from_bytes(invalid),
+            Err(SignatureError::InvalidPoint)
+        );
+    }
+
+    #[test]
+    fn rfc8032_verify() {
+        for (_, public, message, signature) in RFC8032 {
+            let public = PublicKey::from_bytes(bytes(public)).unwrap();
+            let message = hex::decode(message).unwrap();
+            let signature: [u8; 64] = hex::decode(signature).unwrap().try_into().unwrap();
+            let signature = Signature::from_bytes(&signature).unwrap();
+            assert_eq!(public.verify(&message, &signature), Ok(()));
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn verify_rejects() {
+        let keypair = Keypair::from_seed([1; 32]);
+        let public = keypair.public();
+        let signature = keypair.sign(b"message");
+        assert_eq!(public.verify(b"message", &signature), Ok(()));
+
+        assert_eq!(
+            public.verify(b"massage", &signature),
+            Err(SignatureError::VerificationFailed)
+        );
+        let other = Keypair::from_seed([2; 32]);
+        assert_eq!(
+            other.public().verify(b"message", &signature),
+            Err(SignatureError::VerificationFailed)
+        );
+
+        // S + L is the same scalar but must be rejected
+        let mut bytes = signature.to_bytes();
+        let l = bytes_l();
+        let mut carry = 0u16;
+        for i in 0..32 {
+            let sum = bytes[32 + i] as u16 + l[i] as u16 + carry;
+            bytes[32 + i] = sum as u8;
+            carry = sum >> 8;
+        }
+        assert_eq!(
+            Signature::from_bytes(&bytes),
+            Err(SignatureError::NonCanonicalScalar)
+        );
+
+        // R with y = 2 is not a point
+        let mut bytes = signature.to_bytes();
+        bytes[..32].copy_from_slice(&[0; 32]);
+        bytes[0] = 2;
+        let signature = Signature::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            public.verify(b"message", &signature),
+            Err(SignatureError::InvalidPoint)
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    // L in little-endian bytes
+    fn bytes_l() -> [u8; 32] {
+        bytes("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn signature_bytes() {
+        let signature = Keypair::from_seed([3; 32]).sign(b"");
+        let bytes = signature.to_bytes();
+        assert_eq!(Signature::from_bytes(&bytes), Ok(signature));
+        assert_eq!(signature.r().as_bytes(), &bytes[..32]);
+        assert_eq!(signature.s().as_bytes(), &bytes[32..]);
+
+        assert_eq!(Signature::try_from(&bytes[..]), Ok(signature));
+        assert_eq!(
+            Signature::try_from(&bytes[1..]),
+            Err(SignatureError::InvalidLength)
+        );
+        // S = 2^256 - 1
+        let mut high = bytes;
+        high[32..].copy_from_slice(&[0xff; 32]);
+        assert_eq!(
+            Signature::from_bytes(&high),
+            Err(SignatureError::NonCanonicalScalar)
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn expanded_secret_key() {
+        for (seed, public, message, signature) in RFC8032 {
+            let secret = SecretKey::from_bytes(bytes(seed));
+            let expanded = ExpandedSecretKey::from(&secret);
+            assert_eq!(expanded.public().to_bytes(), bytes(public));
+            let message = hex::decode(message).unwrap();
+            assert_eq!(hex::encode(expanded.sign(&message).to_bytes()), signature);
+
+            let keypair = Keypair::from(secret);
+            let expanded = ExpandedSecretKey::from(&keypair);
+            assert_eq!(expanded.sign(&message), keypair.sign(&message));
+        }
+    }
+
+    // RFC 8032 section 7.3, Ed25519ph of "abc" with an empty context.
+    #[cfg(feature = "signing")]
+    #[test]
+    fn rfc8032_prehashed() {
+        let keypair = Keypair::from_seed(bytes(
+            "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42",
+        ));
+        assert_eq!(
+            hex::encode(keypair.public().as_bytes()),
+            "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf"
+        );
+        let digest: [u8; 64] = Sha512::digest(b"abc").into();
+        let signature = keypair.sign_prehashed(&digest, None).unwrap();
+        assert_eq!(
+            hex::encode(signature.to_bytes()),
+            "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae41\
+             31f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406"
+        );
+        assert_eq!(
+            ExpandedSecretKey::from(&keypair).sign_prehashed(&digest, Some(b"")),
+            Ok(signature)
+        );
+
+        let public = keypair.public();
+        assert_eq!(public.verify_prehashed(&digest, None, &signature), Ok(()));
+        // Neither the digest signed as a plain message nor a different
+        // context verifies.
+        assert_eq!(
+            public.verify(&digest, &signature),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            public.verify_prehashed(&digest, Some(b"ctx"), &signature),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            keypair.sign_prehashed(&digest, Some(&[0; 256])),
+            Err(SignatureError::ContextTooLong)
+        );
+    }
+
+    // RFC 8032 section 7.2, the first Ed25519ctx test and the same message
+    // under a second context.
+    #[cfg(feature = "signing")]
+    #[test]
+    fn rfc8032_context() {
+        let keypair = Keypair::from_seed(bytes(
+            "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+        ));
+        assert_eq!(
+            hex::encode(keypair.public().as_bytes()),
+            "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292"
+        );
+        let message = hex::decode("f726936d19c800494e3fdaff20b276a8").unwrap();
+        let foo = keypair.sign_with_context(&message, b"foo").unwrap();
+        assert_eq!(
+            hex::encode(foo.to_bytes()),
+            "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a\
+             8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d"
+        );
+        let bar = ExpandedSecretKey::from(&keypair)
+            .sign_with_context(&message, b"bar")
+            .unwrap();
+        assert_eq!(
+            hex::encode(bar.to_bytes()),
+            "fc60d5872fc46b3aa69f8b5b4351d5808f92bcc044606db097abab6dbcb1aee3\
+             216c48e8b3b66431b5b186d1d28f8ee15a5ca2df6668346291c2043d4eb3e90d"
+        );
+
+        let public = keypair.public();
+        assert_eq!(public.verify_with_context(&message, b"foo", &foo), Ok(()));
+        assert_eq!(
+            public.verify_with_context(&message, b"bar", &foo),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            public.verify(&message, &foo),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            keypair.sign_with_context(&message, b""),
+            Err(SignatureError::EmptyContext)
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn verify_batch_valid_and_invalid() {
+        let keypairs: Vec<_> = (0..4u8).map(|i| Keypair::from_seed([i; 32])).collect();
+        let messages: Vec<&[u8]> = vec![b"", b"a", b"batch", b"verification"];
+        let mut signatures: Vec<_> = keypairs
+            .iter()
+            .zip(&messages)
+            .map(|(keypair, message)| keypair.sign(message))
+            .collect();
+        let public_keys: Vec<_> = keypairs.iter().map(|k| *k.public()).collect();
+
+        assert_eq!(verify_batch(&messages, &signatures, &public_keys), Ok(()));
+        assert_eq!(verify_batch::<Sha512>(&[], &[], &[]), Ok(()));
+
+        // enough signatures for several chunks with the parallel feature;
+        // one bad signature anywhere fails the batch
+        let keypairs: Vec<_> = (0..40u8).map(|i| Keypair::from_seed([i; 32])).collect();
+        let many: Vec<&[u8]> = vec![b"many"; 40];
+        let mut many_signatures: Vec<_> = keypairs.iter().map(|k| k.sign(b"many")).collect();
+        let many_keys: Vec<_> = keypairs.iter().map(|k| *k.public()).collect();
+        assert_eq!(verify_batch(&many, &many_signatures, &many_keys), Ok(()));
+        #[cfg(feature = "parallel")]
+        {
+            // with four threads the 81 terms are split into three chunks
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(4)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                assert_eq!(verify_batch(&many, &many_signatures, &many_keys), Ok(()));
+            });
+        }
+        many_signatures[37] = keypairs[37].sign(b"other");
+        assert_eq!(
+            verify_batch(&many, &many_signatures, &many_keys),
+            Err(SignatureError::VerificationFailed)
+        );
+
+        // swap two signatures: each is valid, but not for its message
+        signatures.swap(1, 2);
+        assert_eq!(
+            verify_batch(&messages, &signatures, &public_keys),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            verify_batch(&messages[1..], &signatures, &public_keys),
+            Err(SignatureError::BatchLengthMismatch)
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn verify_batch_rejects_cancelling_forgeries() {
+        // shift S by d in one signature and by -d in another: both are
+        // invalid, but the errors cancel in the unweighted sum
+        let keypairs: Vec<_> = (0..2u8).map(|i| Keypair::from_seed([i; 32])).collect();
+        let messages: Vec<&[u8]> = vec![b"first", b"second"];
+        let public_keys: Vec<_> = keypairs.iter().map(|k| *k.public()).collect();
+        let d = Scalar::from_bytes_mod_order([7; 32]);
+        let forged: Vec<_> = keypairs
+            .iter()
+            .zip(&messages)
+            .zip([d, -d])
+            .map(|((keypair, message), shift)| {
+                let signature = keypair.sign(message);
+                Signature {
+                    r: *signature.r(),
+                    s: *signature.s() + shift,
+                }
+            })
+            .collect();
+
+        for ((message, signature), public) in messages.iter().zip(&forged).zip(&public_keys) {
+            assert_eq!(
+                public.verify(message, signature),
+                Err(SignatureError::VerificationFailed)
+            );
+        }
+        let mut sum = EdwardsPoint::IDENTITY;
+        for ((message, signature), public) in messages.iter().zip(&forged).zip(&public_keys) {
+            let k =
+                hash_to_scalar::<Sha512>(&[signature.r().as_bytes(), public.as_bytes(), message]);
+            sum = sum + signature.r().decompress().unwrap() + public.point * k
+                - crate::edwards::ED25519_BASEPOINT * *signature.s();
+        }
+        assert!(sum.is_identity());
+
+        assert_eq!(
+            verify_batch(&messages, &forged, &public_keys),
+            Err(SignatureError::VerificationFailed)
+        );
+    }
+
+    #[cfg(all(feature = "rand", feature = "signing"))]
+    #[test]
+    fn verify_batch_with_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let keypairs: Vec<_> = (0..4u8).map(|i| Keypair::from_seed([i; 32])).collect();
+        let messages: Vec<&[u8]> = vec![b"", b"a", b"batch", b"verification"];
+        let mut signatures: Vec<_> = keypairs
+            .iter()
+            .zip(&messages)
+            .map(|(keypair, message)| keypair.sign(message))
+            .collect();
+        let public_keys: Vec<_> = keypairs.iter().map(|k| *k.public()).collect();
+        assert_eq!(
+            super::verify_batch_with_rng(&messages, &signatures, &public_keys, &mut rng),
+            Ok(())
+        );
+
+        signatures[3] = keypairs[3].sign(b"other");
+        assert_eq!(
+            super::verify_batch_with_rng(&messages, &signatures, &public_keys, &mut rng),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            super::verify_batch_with_rng(&messages[1..], &signatures, &public_keys, &mut rng),
+            Err(SignatureError::BatchLengthMismatch)
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn verify_strict() {
+        for (_, public, message, signature) in RFC8032 {
+            let public = PublicKey::from_bytes(bytes(public)).unwrap();
+            let signature: [u8; 64] = hex::decode(signature).unwrap().try_into().unwrap();
+            let signature = Signature::from_bytes(&signature).unwrap();
+            let message = hex::decode(message).unwrap();
+            assert_eq!(public.verify_strict(&message, &signature), Ok(()));
+            assert_eq!(
+                public.verify_strict(b"other", &signature),
+                Err(SignatureError::VerificationFailed)
+            );
+        }
+
+        // A = R = identity and S = 0 passes the plain check for any message
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        let mut forged = [0; 64];
+        forged[..32].copy_from_slice(&identity);
+        let forged = Signature::from_bytes(&forged).unwrap();
+        let weak = PublicKey::from_bytes(identity).unwrap();
+        assert_eq!(weak.verify(b"anything", &forged), Ok(()));
+        assert_eq!(
+            weak.verify_strict(b"anything", &forged),
+            Err(SignatureError::SmallOrderPoint)
+        );
+
+        // the identity encoded with y = p + 1
+        let mut y_plus_p = [0xff; 32];
+        y_plus_p[0] = 0xee;
+        y_plus_p[31] = 0x7f;
+        let weak = PublicKey::from_bytes(y_plus_p).unwrap();
+        assert_eq!(weak.verify(b"anything", &forged), Ok(()));
+        assert_eq!(
+            weak.verify_strict(b"anything", &forged),
+            Err(SignatureError::NonCanonicalPoint)
+        );
+
+        // R = identity with the sign bit set, under a valid key
+        let public = *Keypair::from_seed([1; 32]).public();
+        let mut negative_zero = [0; 64];
+        negative_zero[..32].copy_from_slice(&identity);
+        negative_zero[31] |= 0x80;
+        let signature = Signature::from_bytes(&negative_zero).unwrap();
+        assert_eq!(
+            public.verify_strict(b"", &signature),
+            Err(SignatureError::NonCanonicalPoint)
+        );
+    }
+
+    // R with a small-order component added: S = r + k * s still satisfies
+    // the equation up to that component, which only the cofactored check
+    // ignores.
+    #[cfg(feature = "signing")]
+    #[test]
+    fn verification_policies_diverge() {
+        let keypair = Keypair::from_seed([5; 32]);
+        let public = keypair.public();
+        let (s, _) = keypair.secret().expand::<Sha512>();
+        let message = b"torsion";
+
+        let r = Scalar::from(1234);
+        let big_r = (&r * &*ED25519_BASEPOINT_TABLE).with_torsion(1).compress();
+        let k = hash_to_scalar::<Sha512>(&[big_r.as_bytes(), public.as_bytes(), message]);
+        let signature = Signature {
+            r: big_r,
+            s: r + k * s,
+        };
+
+        assert_eq!(
+            public.verify_with_policy(message, &signature, VerificationPolicy::Cofactored),
+            Ok(())
+        );
+        assert_eq!(
+            public.verify_with_policy(message, &signature, VerificationPolicy::Cofactorless),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            public.verify(message, &signature),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(verify_batch(&[message], &[signature], &[*public]), Ok(()));
+
+        // an honest signature passes both
+        let signature = keypair.sign(message);
+        for policy in [
+            VerificationPolicy::Cofactorless,
+            VerificationPolicy::Cofactored,
+        ] {
+            assert_eq!(
+                public.verify_with_policy(message, &signature, policy),
+                Ok(())
+            );
+            assert_eq!(
+                public.verify_with_policy(b"other", &signature, policy),
+                Err(SignatureError::VerificationFailed)
+            );
+        }
+    }
+
+    #[cfg(all(feature = "rand", feature = "signing"))]
+    #[test]
+    fn sign_hedged() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let keypair = Keypair::from_seed([9; 32]);
+        let message = b"hedged";
+        let a = keypair.sign_hedged(message, &mut rng);
+        let b = ExpandedSecretKey::from(&keypair).sign_hedged(message, &mut rng);
+        assert_ne!(a.r(), b.r());
+        assert_ne!(a, keypair.sign(message));
+        for signature in [a, b] {
+            assert_eq!(keypair.public().verify(message, &signature), Ok(()));
+            assert_eq!(keypair.public().verify_strict(message, &signature), Ok(()));
+        }
+
+        // same RNG state, same signature
+        let mut rng = StdRng::seed_from_u64(7);
+        assert_eq!(keypair.sign_hedged(message, &mut rng), a);
+    }
+
+    // With an RNG stuck at zero, Z is known; the hedged nonce still must
+    // not be the one sign uses for Z || M.
+    #[cfg(all(feature = "rand", feature = "signing"))]
+    #[test]
+    fn sign_hedged_is_domain_separated() {
+        struct ZeroRng;
+
+        impl rand_core::RngCore for ZeroRng {
+            fn next_u32(&mut self) -> u32 {
+                0
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                0
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                dest.fill(0);
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+                dest.fill(0);
+                Ok(())
+            }
+        }
+
+        impl rand_core::CryptoRng for ZeroRng {}
+
+        let keypair = Keypair::from_seed([9; 32]);
+        let message = b"hedged";
+        let mut z_message = [0; 32].to_vec();
+        z_message.extend_from_slice(message);
+        let deterministic = keypair.sign(&z_message);
+        for hedged in [
+            keypair.sign_hedged(message, &mut ZeroRng),
+            ExpandedSecretKey::from(&keypair).sign_hedged(message, &mut ZeroRng),
+        ] {
+            assert_ne!(hedged.r(), deterministic.r());
+            assert_eq!(keypair.public().verify(message, &hedged), Ok(()));
+        }
+    }
+
+    // The same seed with SHA3-512 in place of SHA-512: a different key and
+    // different signatures, which don't verify under the other hash.
+    #[cfg(feature = "signing")]
+    #[test]
+    fn other_hash() {
+        use sha3::Sha3_512;
+
+        let seed = [11; 32];
+        let keypair = GenericKeypair::<Sha3_512>::from_seed(seed);
+        let standard = Keypair::from_seed(seed);
+        assert_ne!(keypair.public().as_bytes(), standard.public().as_bytes());
+
+        let message = b"variant";
+        let signature = keypair.sign(message);
+        assert_eq!(keypair.public().verify(message, &signature), Ok(()));
+        assert_eq!(keypair.public().verify_strict(message, &signature), Ok(()));
+        assert_eq!(
+            ExpandedSecretKey::from(&standard).sign(message),
+            standard.sign(message)
+        );
+        assert_eq!(
+            GenericExpandedSecretKey::from(&keypair).sign(message),
+            signature
+        );
+        assert_eq!(
+            verify_batch(&[message], &[signature], &[*keypair.public()]),
+            Ok(())
+        );
+
+        // the same key bytes, read as a SHA-512 key
+        let as_standard = PublicKey::from_bytes(keypair.public().to_bytes()).unwrap();
+        assert_eq!(
+            as_standard.verify(message, &signature),
+            Err(SignatureError::VerificationFailed)
+        );
+    }
+
+    // The key pair from Nano's documentation (the public key is what
+    // nano_3i1aq1cchnmbn9x5rsbap8b15akfh7wj7pwskuzi7ahz8oq6cobd99d4r3b7
+    // encodes), with a signature computed by a reference implementation.
+    #[cfg(all(feature = "blake2b", feature = "signing"))]
+    #[test]
+    fn blake2b_variant() {
+        let keypair = Blake2bKeypair::from_seed(bytes(
+            "9f0e444c69f77a49bd0be89db92c38fe713e0963165cca12faf5712d7657120f",
+        ));
+        assert_eq!(
+            hex::encode(keypair.public().as_bytes()),
+            "c008b814a7d269a1fa3c6528b19201a24d797912db9996ff02a1ff356e45552b"
+        );
+        let signature = keypair.sign(b"nano");
+        assert_eq!(
+            hex::encode(signature.to_bytes()),
+            "383dba9ff0af35c50b314576d2a0351c7c418ae8a187e0deb4b7bcca15153f14\
+             39363d5a3e0aecadb3a9d5a709f2ebfef78a0ce4502216297a8b1f87c94b2309"
+        );
+
+        let public = Blake2bPublicKey::from_bytes(keypair.public().to_bytes()).unwrap();
+        assert_eq!(public.verify(b"nano", &signature), Ok(()));
+        assert_eq!(
+            Blake2bExpandedSecretKey::from(keypair.secret()).sign(b"nano"),
+            signature
+        );
+    }
+
+    #[cfg(all(feature = "signature", feature = "signing"))]
+    #[test]
+    fn signature_traits() {
+        use signature::{Signer, Verifier};
+
+        fn sign_with<S: Signer<Signature>>(signer: &S, message: &[u8]) -> Signature {
+            signer.sign(message)
+        }
+
+        let keypair = Keypair::from_seed([13; 32]);
+        let signature = sign_with(&keypair, b"generic");
+        assert_eq!(signature, keypair.sign(b"generic"));
+        assert_eq!(
+            sign_with(&ExpandedSecretKey::from(&keypair), b"generic"),
+            signature
+        );
+
+        let public: &dyn Verifier<Signature> = keypair.public();
+        assert!(public.verify(b"generic", &signature).is_ok());
+        assert!(public.verify(b"other", &signature).is_err());
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn streaming() {
+        let keypair = Keypair::from_seed(bytes(
+            "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42",
+        ));
+        let digest: [u8; 64] = Sha512::digest(b"abc").into();
+        let expected = keypair.sign_prehashed(&digest, None).unwrap();
+
+        let mut signer = keypair.stream_signer(None).unwrap();
+        signer.update(b"a").update(b"").update(b"bc");
+        let signature = signer.finalize();
+        assert_eq!(signature, expected);
+
+        let mut verifier = keypair.public().stream_verifier(&signature, None).unwrap();
+        verifier.update(b"ab").update(b"c");
+        assert_eq!(verifier.finalize(), Ok(()));
+        let mut verifier = keypair.public().stream_verifier(&signature, None).unwrap();
+        verifier.update(b"abd");
+        assert_eq!(verifier.finalize(), Err(SignatureError::VerificationFailed));
+
+        // through io::Write, with a context
+        let data = vec![0x5a; 100_000];
+        let mut signer = keypair.stream_signer(Some(b"file")).unwrap();
+        std::io::copy(&mut &data[..], &mut signer).unwrap();
+        let signature = signer.finalize();
+        let digest: [u8; 64] = Sha512::digest(&data).into();
+        assert_eq!(
+            keypair
+                .public()
+                .verify_prehashed(&digest, Some(b"file"), &signature),
+            Ok(())
+        );
+        let mut verifier = keypair
+            .public()
+            .stream_verifier(&signature, Some(b"file"))
+            .unwrap();
+        std::io::copy(&mut &data[..], &mut verifier).unwrap();
+        assert_eq!(verifier.finalize(), Ok(()));
+
+        assert!(matches!(
+            keypair.stream_signer(Some(&[0; 256])),
+            Err(SignatureError::ContextTooLong)
+        ));
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn secrets_are_redacted_and_zeroized() {
+        let mut keypair = Keypair::from_seed([17; 32]);
+        let debug = format!("{keypair:?}");
+        assert!(debug.starts_with("Keypair { secret: SecretKey(<redacted>), public: PublicKey {"));
+        assert!(!debug.contains("17, 17"));
+        let mut expanded = ExpandedSecretKey::from(&keypair);
+        assert_eq!(format!("{expanded:?}"), "ExpandedSecretKey(<redacted>)");
+
+        let (scalar, prefix) = keypair.secret().expand::<Sha512>();
+        let bytes = expanded.to_bytes();
+        assert_eq!(&bytes[..32], scalar.as_bytes());
+        assert_eq!(bytes[32..], prefix);
+        expanded.zeroize();
+        assert_eq!(expanded.to_bytes(), [0; 64]);
+
+        let bytes = keypair.to_bytes();
+        assert_eq!(bytes[..32], [17; 32]);
+        assert_eq!(&bytes[32..], keypair.public().as_bytes());
+        let restored = Keypair::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.sign(b""), keypair.sign(b""));
+        let mut mismatched = bytes;
+        mismatched[32] ^= 1;
+        assert!(matches!(
+            Keypair::from_bytes(&mismatched),
+            Err(SignatureError::KeyMismatch)
+        ));
+
+        keypair.zeroize();
+        assert_eq!(keypair.secret().to_bytes(), [0; 32]);
+    }
+
+    #[test]
+    fn precomputed_verifier() {
+        for (_, public, message, signature) in RFC8032 {
+            let public = PublicKey::from_bytes(bytes(public)).unwrap();
+            let verifier = PrecomputedVerifier::new(&public);
+            assert_eq!(verifier.public(), &public);
+            let signature: [u8; 64] = hex::decode(signature).unwrap().try_into().unwrap();
+            let signature = Signature::from_bytes(&signature).unwrap();
+            let message = hex::decode(message).unwrap();
+            assert_eq!(verifier.verify(&message, &signature), Ok(()));
+            assert_eq!(
+                verifier.verify(b"other", &signature),
+                Err(SignatureError::VerificationFailed)
+            );
+        }
+
+        // R with y = 2 is not a point
+        let public = PublicKey::from_bytes(bytes(RFC8032[0].1)).unwrap();
+        let mut r_not_a_point = [0; 64];
+        r_not_a_point[0] = 2;
+        let signature = Signature::from_bytes(&r_not_a_point).unwrap();
+        assert_eq!(
+            PrecomputedVerifier::new(&public).verify(b"", &signature),
+            Err(SignatureError::InvalidPoint)
+        );
+    }
+
+    // Fingerprints as printed by ssh-keygen -l for the same keys.
+    #[test]
+    fn fingerprint() {
+        let public = PublicKey::from_bytes(bytes(RFC8032[0].1)).unwrap();
+        let fingerprint = public.fingerprint();
+        assert_eq!(
+            fingerprint.to_string(),
+            "SHA256:bbXpuKG6zhzdmnxq256TlqzFBzRl2f6OOg722cYNbU8"
+        );
+        assert_eq!(
+            format!("{fingerprint:x}"),
+            "6db5e9b8a1bace1cdd9a7c6adb9e9396acc5073465d9fe8e3a0ef6d9c60d6d4f"
+        );
+        assert_eq!(
+            hex::encode(fingerprint.as_bytes()),
+            format!("{fingerprint:x}")
+        );
+
+        let other = PublicKey::from_bytes(bytes(
+            "f56e2389c0fe3cb42640f364c77d5ec443d33c4a51bbd8722243c791d65bc699",
+        ))
+        .unwrap();
+        assert_eq!(
+            other.fingerprint().to_string(),
+            "SHA256:ZE7Uj014hdyFjzgf6HiITHrd0gQvrWbmPAy+y0NUSMI"
+        );
+        assert_ne!(other.fingerprint(), fingerprint);
+    }
+}
@@ -0,0 +1,183 @@
+#[cfg(all(feature = "signing", feature = "rand"))]
+use zeroize::Zeroize;
+
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, Signature, SignatureError};
+
+// libsodium's crypto_sign API (Ed25519) over byte arrays, for exchanging
+// keys and signatures with sodium-based applications.
+//
+// libsodium stores a secret key as 64 bytes, the 32-byte seed followed by
+// the public key, and signs either detached (just the 64-byte signature)
+// or combined (the signature followed by the message, which
+// crypto_sign_open strips again). Its verification is the strict one:
+// non-canonical and small-order keys and R values are rejected, which is
+// what PublicKey::verify_strict does.
+//
+// Unlike libsodium, signing checks that the public half of the secret key
+// belongs to the seed. libsodium hashes the stored public key as given, so
+// a corrupted one produces signatures with a reused nonce, which leak the
+// key.
+
+pub const CRYPTO_SIGN_BYTES: usize = 64;
+pub const CRYPTO_SIGN_SEEDBYTES: usize = 32;
+pub const CRYPTO_SIGN_PUBLICKEYBYTES: usize = 32;
+pub const CRYPTO_SIGN_SECRETKEYBYTES: usize = 64;
+
+// The key pair for a seed, as (public key, secret key).
+#[cfg(feature = "signing")]
+pub fn crypto_sign_seed_keypair(seed: &[u8; 32]) -> ([u8; 32], [u8; 64]) {
+    let keypair = Keypair::from_seed(*seed);
+    (keypair.public().to_bytes(), keypair.to_bytes())
+}
+
+// A key pair from a fresh random seed.
+#[cfg(all(feature = "signing", feature = "rand"))]
+pub fn crypto_sign_keypair<R: rand_core::RngCore + rand_core::CryptoRng>(
+    rng: &mut R,
+) -> ([u8; 32], [u8; 64]) {
+    let mut seed = [0; 32];
+    rng.fill_bytes(&mut seed);
+    let keypair = crypto_sign_seed_keypair(&seed);
+    seed.zeroize();
+    keypair
+}
+
+pub fn crypto_sign_ed25519_sk_to_seed(secret: &[u8; 64]) -> [u8; 32] {
+    secret[..32].try_into().unwrap()
+}
+
+pub fn crypto_sign_ed25519_sk_to_pk(secret: &[u8; 64]) -> [u8; 32] {
+    secret[32..].try_into().unwrap()
+}
+
+#[cfg(feature = "signing")]
+pub fn crypto_sign_detached(message: &[u8], secret: &[u8; 64]) -> Result<[u8; 64], SignatureError> {
+    Ok(Keypair::from_bytes(secret)?.sign(message).to_bytes())
+}
+
+// The signature followed by the message.
+#[cfg(feature = "signing")]
+pub fn crypto_sign(message: &[u8], secret: &[u8; 64]) -> Result<Vec<u8>, SignatureError> {
+    let mut signed = Vec::with_capacity(CRYPTO_SIGN_BYTES + message.len());
+    signed.extend_from_slice(&crypto_sign_detached(message, secret)?);
+    signed.extend_from_slice(message);
+    Ok(signed)
+}
+
+pub fn crypto_sign_verify_detached(
+    signature: &[u8; 64],
+    message: &[u8],
+    public: &[u8; 32],
+) -> Result<(), SignatureError> {
+    let public = PublicKey::from_bytes(*public)?;
+    public.verify_strict(message, &Signature::from_bytes(signature)?)
+}
+
+// Checks a combined signature and returns the message without it.
+pub fn crypto_sign_open(signed: &[u8], public: &[u8; 32]) -> Result<Vec<u8>, SignatureError> {
+    if signed.len() < CRYPTO_SIGN_BYTES {
+        return Err(SignatureError::InvalidLength);
+    }
+    let (signature, message) = signed.split_at(CRYPTO_SIGN_BYTES);
+    crypto_sign_verify_detached(signature.try_into().unwrap(), message, public)?;
+    Ok(message.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes<const N: usize>(h: &str) -> [u8; N] {
+        hex::decode(h).unwrap().try_into().unwrap()
+    }
+
+    // RFC 8032 section 7.1, test 2; libsodium gives the same key and
+    // signature.
+    #[cfg(feature = "signing")]
+    const SEED: &str = "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+    const PUBLIC: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const SIGNATURE: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+                             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+    #[test]
+    fn verify() {
+        let signature: [u8; 64] = bytes(SIGNATURE);
+        let public = bytes(PUBLIC);
+        assert_eq!(
+            crypto_sign_verify_detached(&signature, &[0x72], &public),
+            Ok(())
+        );
+        assert_eq!(
+            crypto_sign_verify_detached(&signature, &[0x73], &public),
+            Err(SignatureError::VerificationFailed)
+        );
+
+        let mut signed = signature.to_vec();
+        signed.push(0x72);
+        assert_eq!(crypto_sign_open(&signed, &public), Ok(vec![0x72]));
+        assert_eq!(
+            crypto_sign_open(&signed[..63], &public),
+            Err(SignatureError::InvalidLength)
+        );
+
+        // the identity as a public key, which libsodium refuses
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        let mut forged = [0; 64];
+        forged[0] = 1;
+        assert_eq!(
+            crypto_sign_verify_detached(&forged, b"anything", &identity),
+            Err(SignatureError::SmallOrderPoint)
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn sign() {
+        let (public, secret) = crypto_sign_seed_keypair(&bytes(SEED));
+        assert_eq!(hex::encode(public), PUBLIC);
+        assert_eq!(crypto_sign_ed25519_sk_to_seed(&secret), bytes(SEED));
+        assert_eq!(crypto_sign_ed25519_sk_to_pk(&secret), public);
+
+        let signature = crypto_sign_detached(&[0x72], &secret).unwrap();
+        assert_eq!(hex::encode(signature), SIGNATURE);
+
+        let signed = crypto_sign(b"combined", &secret).unwrap();
+        assert_eq!(signed.len(), CRYPTO_SIGN_BYTES + 8);
+        assert_eq!(&signed[64..], b"combined");
+        assert_eq!(crypto_sign_open(&signed, &public), Ok(b"combined".to_vec()));
+
+        let mut tampered = signed.clone();
+        tampered[64] ^= 1;
+        assert_eq!(
+            crypto_sign_open(&tampered, &public),
+            Err(SignatureError::VerificationFailed)
+        );
+
+        let mut mismatched = secret;
+        mismatched[63] ^= 1;
+        assert_eq!(
+            crypto_sign_detached(b"", &mismatched),
+            Err(SignatureError::KeyMismatch)
+        );
+    }
+
+    #[cfg(all(feature = "signing", feature = "rand"))]
+    #[test]
+    fn random_keypair() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let (public, secret) = crypto_sign_keypair(&mut StdRng::seed_from_u64(1));
+        assert_eq!(
+            crypto_sign_seed_keypair(&crypto_sign_ed25519_sk_to_seed(&secret)).0,
+            public
+        );
+        let signature = crypto_sign_detached(b"random", &secret).unwrap();
+        assert_eq!(
+            crypto_sign_verify_detached(&signature, b"random", &public),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+
+#[cfg(feature = "signing")]
+use zeroize::Zeroizing;
+
+use crate::base58;
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, SignatureError};
+
+// Solana's key encodings. An address is the 32-byte public key in base58
+// (Bitcoin alphabet, no checksum). A secret key is the 64-byte seed ||
+// public key, which wallets export in base58 and solana-keygen writes to
+// its keypair files as a JSON array of the 64 byte values:
+//
+//   [174,47,154,...,61,72]
+//
+// The public half has to match the seed, as for libsodium keys.
+//
+// Not every address is a key: program-derived addresses are deliberately
+// off the curve, and decode_public_key refuses them.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolanaError {
+    // An address or secret key that can't be read: bad base58, the wrong
+    // length, or a keypair file that isn't a JSON array of 64 bytes.
+    Malformed,
+    // An address off the curve, or a secret key whose public half doesn't
+    // belong to its seed (KeyMismatch).
+    Signature(SignatureError),
+}
+
+impl fmt::Display for SolanaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolanaError::Malformed => f.write_str("malformed Solana key"),
+            SolanaError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SolanaError {}
+
+impl From<SignatureError> for SolanaError {
+    fn from(e: SignatureError) -> Self {
+        SolanaError::Signature(e)
+    }
+}
+
+pub fn encode_public_key(key: &PublicKey) -> String {
+    base58::encode(key.as_bytes())
+}
+
+pub fn decode_public_key(address: &str) -> Result<PublicKey, SolanaError> {
+    let bytes = base58::decode(address).ok_or(SolanaError::Malformed)?;
+    Ok(PublicKey::from_bytes(
+        bytes.try_into().map_err(|_| SolanaError::Malformed)?,
+    )?)
+}
+
+// The base58 secret key, as wallets export it.
+#[cfg(feature = "signing")]
+pub fn encode_keypair(keypair: &Keypair) -> Zeroizing<String> {
+    Zeroizing::new(base58::encode(&Zeroizing::new(keypair.to_bytes())[..]))
+}
+
+#[cfg(feature = "signing")]
+pub fn decode_keypair(text: &str) -> Result<Keypair, SolanaError> {
+    let bytes = Zeroizing::new(base58::decode(text).ok_or(SolanaError::Malformed)?);
+    keypair_from_slice(&bytes)
+}
+
+// The contents of a solana-keygen keypair file, without spaces or a
+// trailing newline, as solana-keygen writes it.
+#[cfg(feature = "signing")]
+pub fn encode_keypair_json(keypair: &Keypair) -> Zeroizing<String> {
+    let bytes = Zeroizing::new(keypair.to_bytes());
+    let mut json = Zeroizing::new(String::with_capacity(4 * 64 + 2));
+    json.push('[');
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push_str(&byte.to_string());
+    }
+    json.push(']');
+    json
+}
+
+// Reads a keypair file. Whitespace is allowed around the numbers and
+// brackets, but nothing else: exactly 64 decimal numbers up to 255.
+#[cfg(feature = "signing")]
+pub fn decode_keypair_json(json: &str) -> Result<Keypair, SolanaError> {
+    let malformed = SolanaError::Malformed;
+    let list = json
+        .trim()
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or(malformed)?;
+    let mut bytes = Zeroizing::new(Vec::with_capacity(64));
+    for number in list.split(',') {
+        let number = number.trim();
+        if number.is_empty() || !number.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(malformed);
+        }
+        bytes.push(number.parse::<u8>().map_err(|_| malformed)?);
+    }
+    keypair_from_slice(&bytes)
+}
+
+#[cfg(feature = "signing")]
+fn keypair_from_slice(bytes: &[u8]) -> Result<Keypair, SolanaError> {
+    let bytes: &[u8; 64] = bytes.try_into().map_err(|_| SolanaError::Malformed)?;
+    Ok(Keypair::from_bytes(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 8032 test 1's public key, and its seed and key as a Solana
+    // secret key; the base58 was checked with an independent
+    // implementation.
+    const PUBLIC: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const ADDRESS: &str = "FVen3X669xLzsi6N2V91DoiyzHzg1uAgqiT8jZ9nS96Z";
+    #[cfg(feature = "signing")]
+    const SEED: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    #[cfg(feature = "signing")]
+    const SECRET: &str =
+        "49W385L4rePHy6PAaQUovbD2aacgN4HsKXSMeUzRg4fmwXszN91JuMFrQRj3vMDpZuRF3ZknQBuRBoWQJEfXstMw";
+
+    fn public() -> PublicKey {
+        PublicKey::from_bytes(hex::decode(PUBLIC).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn addresses() {
+        assert_eq!(encode_public_key(&public()), ADDRESS);
+        assert_eq!(decode_public_key(ADDRESS), Ok(public()));
+
+        // a character outside the alphabet, a byte short
+        let bad = ADDRESS.replacen(&ADDRESS[1..2], "0", 1);
+        let short = base58::encode(&public().as_bytes()[1..]);
+        for bad in [&bad, &short] {
+            assert_eq!(decode_public_key(bad), Err(SolanaError::Malformed));
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn keypairs() {
+        let keypair = Keypair::from_seed(hex::decode(SEED).unwrap().try_into().unwrap());
+        assert_eq!(*encode_keypair(&keypair), SECRET);
+        assert_eq!(
+            decode_keypair(SECRET).unwrap().to_bytes(),
+            keypair.to_bytes()
+        );
+
+        let json = encode_keypair_json(&keypair);
+        assert!(json.starts_with("[157,97,177,"));
+        assert!(json.ends_with(",7,81,26]"));
+        assert_eq!(
+            decode_keypair_json(&json).unwrap().to_bytes(),
+            keypair.to_bytes()
+        );
+        let spaced = format!(" {}\n", json.replace(',', ", "));
+        assert_eq!(
+            decode_keypair_json(&spaced).unwrap().to_bytes(),
+            keypair.to_bytes()
+        );
+
+        // a public half that doesn't match
+        let mut mismatched = keypair.to_bytes();
+        mismatched[63] ^= 1;
+        assert!(matches!(
+            decode_keypair(&base58::encode(&mismatched)),
+            Err(SolanaError::Signature(SignatureError::KeyMismatch))
+        ));
+        // 63 numbers, 65, a number too big, a negative one, not a list
+        let short = json.replacen("[157,", "[", 1);
+        let long = json.replacen("[157,", "[1,157,", 1);
+        let big = json.replacen("[157,", "[256,", 1);
+        let negative = json.replacen("[157,", "[-1,", 1);
+        let object = json.replace('[', "{").replace(']', "}");
+        for bad in [&short, &long, &big, &negative, &object, "[]"] {
+            assert!(
+                matches!(decode_keypair_json(bad), Err(SolanaError::Malformed)),
+                "{bad}"
+            );
+        }
+    }
+}
@@ -0,0 +1,483 @@
+use std::fmt;
+
+use merlin::Transcript;
+#[cfg(feature = "signing")]
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "signing")]
+use sha2::{Digest, Sha512};
+#[cfg(feature = "signing")]
+use zeroize::Zeroize;
+
+#[cfg(feature = "signing")]
+use crate::ristretto::RISTRETTO_BASEPOINT_TABLE;
+use crate::ristretto::{CompressedRistretto, RistrettoPoint};
+use crate::scalar::Scalar;
+use crate::signing::SignatureError;
+use crate::transcript::{DlogProof, TranscriptProtocol};
+
+// sr25519, the Schnorr signatures over ristretto255 that schnorrkel
+// implements and Substrate and Polkadot use for account keys. Keys and
+// signatures made here verify with schnorrkel and the other way around.
+//
+// The group is ristretto255, so unlike Ed25519 there is no cofactor to
+// worry about: a public key is one compressed RistrettoPoint, and there is
+// exactly one way to verify. The hashing is all done by a Merlin
+// transcript (STROBE over Keccak-f[1600]) rather than SHA-512:
+//
+//   t = Transcript("SigningContext")
+//   t.append("", context)             the signing context, "substrate"
+//   t.append("sign-bytes", message)
+//   t.append("proto-name", "Schnorr-sig")
+//   t.append("sign:pk", A)
+//   t.append("sign:R", R)
+//   k = t.challenge("sign:c", 64 bytes) mod L
+//
+// and the signature is R || s with s = k * a + r, like Ed25519's but
+// with the top bit of s set. schnorrkel sets that bit to tell its
+// signatures from Ed25519 ones and refuses signatures without it; so do
+// we, with Sr25519Error::MissingMarker.
+//
+// The nonce r comes from the transcript as well: a TranscriptRng rekeyed
+// with the secret nonce seed and, in schnorrkel, 32 bytes from the system
+// RNG. sign leaves those out, which makes it deterministic like Ed25519;
+// sign_hedged mixes in bytes from the caller's RNG as schnorrkel does.
+//
+// Substrate's 32-byte "mini secret key" is expanded the Ed25519 way
+// (schnorrkel's ExpansionMode::Ed25519): SHA-512 of it gives a clamped
+// scalar and the nonce seed. schnorrkel then divides the scalar by the
+// cofactor, exactly since clamping cleared its low three bits, so that
+// multiplying the key by 8 gives back the Ed25519 scalar.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sr25519Error {
+    // A signature without schnorrkel's marker bit (the top bit of its
+    // last byte), such as an Ed25519 signature.
+    MissingMarker,
+    // A key that isn't a ristretto255 encoding, an unreduced scalar, or a
+    // signature that doesn't verify.
+    Signature(SignatureError),
+}
+
+impl fmt::Display for Sr25519Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sr25519Error::MissingMarker => f.write_str("not a schnorrkel sr25519 signature"),
+            Sr25519Error::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Sr25519Error {}
+
+impl From<SignatureError> for Sr25519Error {
+    fn from(e: SignatureError) -> Self {
+        Sr25519Error::Signature(e)
+    }
+}
+
+// schnorrkel's marker bit, the top bit of the signature's last byte.
+const MARKER: u8 = 0x80;
+
+// The transcript up to the nonce: the message, then the protocol name and
+// the signer's key.
+pub(crate) fn transcript(
+    context: &[u8],
+    message: &[u8],
+    public: &CompressedRistretto,
+) -> Transcript {
+    let mut t = Transcript::new(b"SigningContext");
+    t.append_message(b"", context);
+    t.append_message(b"sign-bytes", message);
+    t.proto_name(b"Schnorr-sig");
+    t.append_point(b"sign:pk", public);
+    t
+}
+
+// Appends R and returns the challenge k.
+pub(crate) fn challenge(t: &mut Transcript, r: &CompressedRistretto) -> Scalar {
+    t.append_point(b"sign:R", r);
+    t.challenge_scalar(b"sign:c")
+}
+
+// The context Substrate signs extrinsics and messages under.
+pub const SUBSTRATE_CONTEXT: &[u8] = b"substrate";
+
+// A public key: a compressed RistrettoPoint that decompresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sr25519PublicKey {
+    pub(crate) compressed: CompressedRistretto,
+    pub(crate) point: RistrettoPoint,
+}
+
+impl Sr25519PublicKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Result<Self, Sr25519Error> {
+        let compressed = CompressedRistretto(bytes);
+        let point = compressed
+            .decompress()
+            .ok_or(SignatureError::InvalidPoint)?;
+        Ok(Self { compressed, point })
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        self.compressed.as_bytes()
+    }
+
+    // Checks R = s * B - k * A. Since R is compared in encoded form and
+    // the group has prime order, there are no small-order or
+    // non-canonical cases left to decide.
+    pub fn verify(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        signature: &Sr25519Signature,
+    ) -> Result<(), Sr25519Error> {
+        let mut t = transcript(context, message, &self.compressed);
+        let k = challenge(&mut t, &signature.r);
+        let r = RistrettoPoint::vartime_double_scalar_mul_basepoint(&k, &-self.point, &signature.s);
+        if r.compress() == signature.r {
+            Ok(())
+        } else {
+            Err(SignatureError::VerificationFailed.into())
+        }
+    }
+
+    // Checks a proof that the key's owner knows its secret scalar (see
+    // DlogProof::prove_possession).
+    pub fn verify_possession(&self, proof: &DlogProof) -> Result<(), Sr25519Error> {
+        Ok(proof.verify_possession(&self.point)?)
+    }
+}
+
+// R and s. R isn't decompressed, since verification only compares
+// encodings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sr25519Signature {
+    pub(crate) r: CompressedRistretto,
+    pub(crate) s: Scalar,
+}
+
+impl Sr25519Signature {
+    // Fails with MissingMarker if the marker bit is clear and with
+    // NonCanonicalScalar if s (with the bit cleared) isn't reduced.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, Sr25519Error> {
+        if bytes[63] & MARKER == 0 {
+            return Err(Sr25519Error::MissingMarker);
+        }
+        let mut s: [u8; 32] = bytes[32..].try_into().unwrap();
+        s[31] &= !MARKER;
+        Ok(Self {
+            r: CompressedRistretto(bytes[..32].try_into().unwrap()),
+            s: Scalar::from_canonical_bytes(s).ok_or(SignatureError::NonCanonicalScalar)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(self.r.as_bytes());
+        bytes[32..].copy_from_slice(self.s.as_bytes());
+        bytes[63] |= MARKER;
+        bytes
+    }
+}
+
+// The expanded secret key, a scalar and a 32-byte nonce seed, with its
+// public key. Zeroized when dropped; Debug redacts the secret half.
+#[cfg(feature = "signing")]
+#[derive(Clone)]
+pub struct Sr25519Keypair {
+    pub(crate) key: Scalar,
+    pub(crate) nonce: [u8; 32],
+    public: Sr25519PublicKey,
+}
+
+#[cfg(feature = "signing")]
+impl Sr25519Keypair {
+    // Expands a Substrate mini secret key (the seed behind a mnemonic or a
+    // dev account), as schnorrkel's ExpansionMode::Ed25519 does.
+    pub fn from_mini_secret(mini_secret: &[u8; 32]) -> Self {
+        let mut h: [u8; 64] = Sha512::digest(mini_secret).into();
+        let mut key: [u8; 32] = h[..32].try_into().unwrap();
+        key[0] &= 248;
+        key[31] &= 63;
+        key[31] |= 64;
+        // divide by the cofactor 8: the low three bits are clear, so this
+        // is a right shift of the 256-bit number
+        let mut carry = 0;
+        for byte in key.iter_mut().rev() {
+            let next = *byte & 7;
+            *byte = (*byte >> 3) | (carry << 5);
+            carry = next;
+        }
+        let keypair = Self::from_parts(
+            Scalar::from_bytes_mod_order(key),
+            h[32..].try_into().unwrap(),
+        );
+        key.zeroize();
+        h.zeroize();
+        keypair
+    }
+
+    // schnorrkel's SecretKey::to_bytes: the scalar, then the nonce seed.
+    // Fails with NonCanonicalScalar if the scalar isn't reduced.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, Sr25519Error> {
+        let key = Scalar::from_canonical_bytes(bytes[..32].try_into().unwrap())
+            .ok_or(SignatureError::NonCanonicalScalar)?;
+        Ok(Self::from_parts(key, bytes[32..].try_into().unwrap()))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(self.key.as_bytes());
+        bytes[32..].copy_from_slice(&self.nonce);
+        bytes
+    }
+
+    fn from_parts(key: Scalar, nonce: [u8; 32]) -> Self {
+        let point = &key * &*RISTRETTO_BASEPOINT_TABLE;
+        let public = Sr25519PublicKey {
+            compressed: point.compress(),
+            point,
+        };
+        Self { key, nonce, public }
+    }
+
+    pub fn public(&self) -> &Sr25519PublicKey {
+        &self.public
+    }
+
+    pub fn sign(&self, context: &[u8], message: &[u8]) -> Sr25519Signature {
+        self.sign_with_rng(context, message, &mut NoRandomness)
+    }
+
+    // Mixes 32 bytes from rng into the nonce, as schnorrkel always does;
+    // see GenericKeypair::sign_hedged for why that helps.
+    #[cfg(feature = "rand")]
+    pub fn sign_hedged<R: RngCore + CryptoRng>(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        rng: &mut R,
+    ) -> Sr25519Signature {
+        self.sign_with_rng(context, message, rng)
+    }
+
+    // A proof of possession of the secret key, deterministic like sign.
+    pub fn prove_possession(&self) -> DlogProof {
+        DlogProof::prove_possession(&self.key, &mut NoRandomness)
+    }
+
+    fn sign_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        rng: &mut R,
+    ) -> Sr25519Signature {
+        let mut t = transcript(context, message, &self.public.compressed);
+
+        let mut r = t.witness_scalar(b"signing", &self.nonce, rng);
+        let big_r = (&r * &*RISTRETTO_BASEPOINT_TABLE).compress();
+        let k = challenge(&mut t, &big_r);
+        let s = Scalar::muladd(&k, &self.key, &r);
+        r.zeroize();
+        Sr25519Signature { r: big_r, s }
+    }
+}
+
+#[cfg(feature = "signing")]
+impl Zeroize for Sr25519Keypair {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+        self.nonce.zeroize();
+    }
+}
+
+#[cfg(feature = "signing")]
+impl Drop for Sr25519Keypair {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "signing")]
+impl fmt::Debug for Sr25519Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sr25519Keypair")
+            .field("secret", &"<redacted>")
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
+// The "RNG" for deterministic signing: finalize wants one, and with no
+// bytes from it the nonce depends only on the transcript and the secret
+// nonce seed.
+#[cfg(feature = "signing")]
+struct NoRandomness;
+
+#[cfg(feature = "signing")]
+impl RngCore for NoRandomness {
+    fn next_u32(&mut self) -> u32 {
+        0
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        0
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.fill(0);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        dest.fill(0);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "signing")]
+impl CryptoRng for NoRandomness {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Substrate's development seed (the mini secret key behind "bottom
+    // drive obey lake ..."), its public key as Substrate prints it, and
+    // the expanded secret and a signature of MESSAGE from schnorrkel 0.11.
+    #[cfg(feature = "signing")]
+    const MINI_SECRET: &str = "fac7959dbfe72f052e5a0c3c8d6530f202b02fd8f9f5ca3580ec8deb7797479e";
+    #[cfg(feature = "signing")]
+    const SECRET: &str = "05d65584630d16cd4af6d0bec10f34bb504a5dcb62dba2122d49f5a663763d0a\
+        fd190cce74df356432b410bd64682309d6dedb27c76845daf388557cbac3ca34";
+    const PUBLIC: &str = "46ebddef8cd9bb167dc30878d7113b7e168e6f0646beffd77d69d39bad76b47a";
+    const MESSAGE: &[u8] = b"hello sr25519";
+    const SIGNATURE: &str = "8639ab3a4f4f1c62a54e4f9a30b1f0cfbd96553080ca44b317d5269748f19636\
+        6a9d6a6088b702f83e426e4052bf74e3c3fc2249780336402e3e5bc444dee185";
+    // Our deterministic signature of MESSAGE, which schnorrkel accepts.
+    #[cfg(feature = "signing")]
+    const DETERMINISTIC: &str = "9675a3e460d9e441bfcde914488ffa076f10604e7f67396f4e77864fe311b37e\
+        2652372d22bdb1badc8b8fd3e73e0c58400c5414d920e40fa9d2f7379bb3468d";
+
+    fn public() -> Sr25519PublicKey {
+        Sr25519PublicKey::from_bytes(hex::decode(PUBLIC).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    fn signature(hex: &str) -> Sr25519Signature {
+        Sr25519Signature::from_bytes(&hex::decode(hex).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn verifies_schnorrkel_signatures() {
+        let signature = signature(SIGNATURE);
+        assert_eq!(hex::encode(signature.to_bytes()), SIGNATURE);
+        assert_eq!(
+            public().verify(SUBSTRATE_CONTEXT, MESSAGE, &signature),
+            Ok(())
+        );
+
+        // another message, another context
+        assert_eq!(
+            public().verify(SUBSTRATE_CONTEXT, b"hello sr25518", &signature),
+            Err(Sr25519Error::Signature(SignatureError::VerificationFailed))
+        );
+        assert_eq!(
+            public().verify(b"polkadot", MESSAGE, &signature),
+            Err(Sr25519Error::Signature(SignatureError::VerificationFailed))
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let mut bytes: [u8; 64] = hex::decode(SIGNATURE).unwrap().try_into().unwrap();
+        bytes[63] &= !MARKER;
+        assert_eq!(
+            Sr25519Signature::from_bytes(&bytes),
+            Err(Sr25519Error::MissingMarker)
+        );
+        // s = L
+        bytes[32..].copy_from_slice(
+            &hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+                .unwrap(),
+        );
+        bytes[63] |= MARKER;
+        assert_eq!(
+            Sr25519Signature::from_bytes(&bytes),
+            Err(Sr25519Error::Signature(SignatureError::NonCanonicalScalar))
+        );
+        // a key whose s is negative, so not a ristretto255 encoding
+        let mut key: [u8; 32] = hex::decode(PUBLIC).unwrap().try_into().unwrap();
+        key[0] ^= 1;
+        assert_eq!(
+            Sr25519PublicKey::from_bytes(key),
+            Err(Sr25519Error::Signature(SignatureError::InvalidPoint))
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn substrate_keys() {
+        let mini_secret = hex::decode(MINI_SECRET).unwrap().try_into().unwrap();
+        let keypair = Sr25519Keypair::from_mini_secret(&mini_secret);
+        assert_eq!(keypair.public(), &public());
+        assert_eq!(hex::encode(keypair.to_bytes()), SECRET);
+        let again = Sr25519Keypair::from_bytes(&keypair.to_bytes()).unwrap();
+        assert_eq!(again.public(), &public());
+        assert!(!format!("{keypair:?}").contains(&SECRET[..8]));
+
+        let mut unreduced = keypair.to_bytes();
+        unreduced[31] = 0xff;
+        assert!(matches!(
+            Sr25519Keypair::from_bytes(&unreduced),
+            Err(Sr25519Error::Signature(SignatureError::NonCanonicalScalar))
+        ));
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn sign() {
+        let mini_secret = hex::decode(MINI_SECRET).unwrap().try_into().unwrap();
+        let keypair = Sr25519Keypair::from_mini_secret(&mini_secret);
+        let signature = keypair.sign(SUBSTRATE_CONTEXT, MESSAGE);
+        assert_eq!(hex::encode(signature.to_bytes()), DETERMINISTIC);
+        assert_eq!(
+            public().verify(SUBSTRATE_CONTEXT, MESSAGE, &signature),
+            Ok(())
+        );
+        assert_ne!(keypair.sign(b"", MESSAGE), signature);
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn proof_of_possession() {
+        let mini_secret = hex::decode(MINI_SECRET).unwrap().try_into().unwrap();
+        let keypair = Sr25519Keypair::from_mini_secret(&mini_secret);
+        let proof = keypair.prove_possession();
+        assert_eq!(proof, keypair.prove_possession());
+        assert_eq!(public().verify_possession(&proof), Ok(()));
+        let other = Sr25519Keypair::from_mini_secret(&[1; 32]);
+        assert_eq!(
+            other.public().verify_possession(&proof),
+            Err(Sr25519Error::Signature(SignatureError::VerificationFailed))
+        );
+    }
+
+    #[cfg(all(feature = "rand", feature = "signing"))]
+    #[test]
+    fn sign_hedged() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mini_secret = hex::decode(MINI_SECRET).unwrap().try_into().unwrap();
+        let keypair = Sr25519Keypair::from_mini_secret(&mini_secret);
+        let a = keypair.sign_hedged(SUBSTRATE_CONTEXT, MESSAGE, &mut rng);
+        let b = keypair.sign_hedged(SUBSTRATE_CONTEXT, MESSAGE, &mut rng);
+        assert_ne!(a, b);
+        assert_ne!(a, signature(DETERMINISTIC));
+        for signature in [a, b] {
+            assert_eq!(
+                public().verify(SUBSTRATE_CONTEXT, MESSAGE, &signature),
+                Ok(())
+            );
+        }
+    }
+}
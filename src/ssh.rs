@@ -0,0 +1,212 @@
+use std::fmt;
+
+use crate::pem::PemError;
+use crate::signing::{PublicKey, Signature, SignatureError};
+
+// OpenSSH's encodings of Ed25519 keys (RFC 8709).
+//
+// SSH frames everything on the wire as "strings": a 4-byte big-endian
+// length followed by that many bytes. A public key is the string
+// "ssh-ed25519" followed by the string holding the 32-byte encoding of A;
+// base64 of that blob is the middle field of an authorized_keys or .pub
+// line, and its SHA-256 is the key's fingerprint.
+//
+// The formats built from these blobs (signatures made with ssh-keygen -Y,
+// and certificates) are in the submodules, as is a minimal ssh-agent.
+
+#[cfg(feature = "ssh-agent")]
+pub mod agent;
+pub mod cert;
+pub mod sshsig;
+
+pub const KEY_TYPE: &str = "ssh-ed25519";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SshError {
+    // Malformed SSH wire data: a truncated string, trailing bytes, or a
+    // field that doesn't hold what the format says it must.
+    Malformed,
+    // A well-formed key, signature or hash for an algorithm other than
+    // Ed25519 (or, for signatures, SHA-256 and SHA-512).
+    UnsupportedAlgorithm,
+    // A certificate line whose key blob isn't base64.
+    InvalidBase64,
+    // An armored signature whose armor is damaged; see pem.
+    Pem(PemError),
+    // An SSH signature for a different namespace than the one checked,
+    // or an empty namespace.
+    WrongNamespace,
+    // A certificate for a user where a host was expected, or the other
+    // way around.
+    WrongCertificateType,
+    // A certificate checked before its valid-after time, or from its
+    // valid-before time on.
+    NotValidNow,
+    // A certificate that doesn't list the principal it was checked for.
+    PrincipalNotAllowed,
+    // A certificate with a critical option this crate doesn't know, which
+    // a verifier must not ignore.
+    UnknownCriticalOption,
+    // A key that isn't a point, a signature that doesn't verify, or one
+    // made by a key other than the expected one (KeyMismatch).
+    Signature(SignatureError),
+}
+
+impl fmt::Display for SshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SshError::Malformed => f.write_str("malformed SSH encoding"),
+            SshError::UnsupportedAlgorithm => f.write_str("not an Ed25519 key or signature"),
+            SshError::InvalidBase64 => f.write_str("invalid base64"),
+            SshError::Pem(e) => e.fmt(f),
+            SshError::WrongNamespace => f.write_str("wrong or empty SSH signature namespace"),
+            SshError::WrongCertificateType => f.write_str("wrong SSH certificate type"),
+            SshError::NotValidNow => f.write_str("SSH certificate is outside its validity period"),
+            SshError::PrincipalNotAllowed => {
+                f.write_str("principal is not listed in the SSH certificate")
+            }
+            SshError::UnknownCriticalOption => {
+                f.write_str("SSH certificate has an unknown critical option")
+            }
+            SshError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SshError {}
+
+impl From<PemError> for SshError {
+    fn from(e: PemError) -> Self {
+        SshError::Pem(e)
+    }
+}
+
+impl From<SignatureError> for SshError {
+    fn from(e: SignatureError) -> Self {
+        SshError::Signature(e)
+    }
+}
+
+// Appends data to out as an SSH string.
+pub(crate) fn put_string(out: &mut Vec<u8>, data: &[u8]) {
+    let len = u32::try_from(data.len()).expect("SSH strings are at most 2^32 - 1 bytes");
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+// The SSH wire encoding of a public key.
+pub fn public_key_blob(key: &PublicKey) -> Vec<u8> {
+    key.ssh_blob().to_vec()
+}
+
+// Reads a public key blob. Keys of other types (ssh-rsa, say) are
+// well-formed but not something this crate can use.
+pub fn parse_public_key_blob(blob: &[u8]) -> Result<PublicKey, SshError> {
+    let mut reader = Reader(blob);
+    let key_type = reader.string().ok_or(SshError::Malformed)?;
+    if key_type != KEY_TYPE.as_bytes() {
+        return Err(SshError::UnsupportedAlgorithm);
+    }
+    let key = reader.string().ok_or(SshError::Malformed)?;
+    reader.finish().ok_or(SshError::Malformed)?;
+    Ok(PublicKey::from_bytes(
+        key.try_into().map_err(|_| SshError::Malformed)?,
+    )?)
+}
+
+// The SSH wire encoding of a signature: the string "ssh-ed25519" followed
+// by the string holding R || S.
+pub(crate) fn signature_blob(signature: &Signature) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(83);
+    put_string(&mut blob, KEY_TYPE.as_bytes());
+    put_string(&mut blob, &signature.to_bytes());
+    blob
+}
+
+pub(crate) fn parse_signature_blob(blob: &[u8]) -> Result<Signature, SshError> {
+    let malformed = SshError::Malformed;
+    let mut reader = Reader(blob);
+    if reader.string().ok_or(malformed)? != KEY_TYPE.as_bytes() {
+        return Err(SshError::UnsupportedAlgorithm);
+    }
+    let bytes: &[u8; 64] = reader
+        .string()
+        .ok_or(malformed)?
+        .try_into()
+        .map_err(|_| malformed)?;
+    reader.finish().ok_or(malformed)?;
+    Ok(Signature::from_bytes(bytes)?)
+}
+
+// The unread rest of an SSH wire encoding. Each read returns None if the
+// data runs out.
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (bytes, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_be_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn string(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()?;
+        self.bytes(len as usize)
+    }
+
+    // Some(()) if nothing is left over.
+    pub(crate) fn finish(&self) -> Option<()> {
+        self.0.is_empty().then_some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The public key from RFC 8032 test 1, as ssh-keygen prints it in a
+    // .pub file.
+    #[test]
+    fn blob_matches_openssh() {
+        let key = PublicKey::from_bytes(
+            hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            crate::base64::encode(&public_key_blob(&key), true),
+            "AAAAC3NzaC1lZDI1NTE5AAAAINdamAGCsQq31Uv+08lkBzoO4XLz2qYjJa8CGmj3B1Ea"
+        );
+        let blob = public_key_blob(&key);
+        assert_eq!(parse_public_key_blob(&blob), Ok(key));
+
+        // trailing data, a short key, and another key type
+        let mut trailing = blob.clone();
+        trailing.push(0);
+        assert_eq!(parse_public_key_blob(&trailing), Err(SshError::Malformed));
+        let mut short = blob[..blob.len() - 1].to_vec();
+        short[18] -= 1;
+        assert_eq!(parse_public_key_blob(&short), Err(SshError::Malformed));
+        assert_eq!(parse_public_key_blob(&blob[..20]), Err(SshError::Malformed));
+        let mut rsa = Vec::new();
+        put_string(&mut rsa, b"ssh-rsa");
+        put_string(&mut rsa, &[1, 0, 1]);
+        assert_eq!(
+            parse_public_key_blob(&rsa),
+            Err(SshError::UnsupportedAlgorithm)
+        );
+    }
+}
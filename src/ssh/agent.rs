@@ -0,0 +1,240 @@
+use std::io::{self, Read, Write};
+
+use super::{public_key_blob, put_string, signature_blob, Reader};
+use crate::signing::Keypair;
+
+// The server side of the ssh-agent protocol (draft-miller-ssh-agent),
+// holding Ed25519 keys in memory. It answers the two requests a client
+// needs to use the keys: listing them (ssh-add -L) and signing with one
+// (ssh logging in, or ssh-keygen -Y sign with a key that is only in the
+// agent). Everything else, including adding and removing keys over the
+// protocol, gets SSH_AGENT_FAILURE; keys are added with Agent::add.
+//
+// Each message is a uint32 length followed by that many bytes, the first
+// of which is the message type. Agent::handle answers one message and
+// Agent::serve answers messages on a stream until the client hangs up, so
+// serving a Unix socket is
+//
+//   for stream in UnixListener::bind(path)?.incoming() {
+//       agent.serve(&mut stream?)?;
+//   }
+//
+// with SSH_AUTH_SOCK pointing at path.
+
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+
+// OpenSSH's limit on the length of one message.
+const MAX_MESSAGE_LEN: usize = 256 * 1024;
+
+#[derive(Debug, Default)]
+pub struct Agent {
+    identities: Vec<(Keypair, String)>,
+}
+
+impl Agent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds a key, listed with the given comment (usually user@host).
+    pub fn add(&mut self, keypair: Keypair, comment: &str) {
+        self.identities.push((keypair, comment.to_owned()));
+    }
+
+    // The reply to one request, both without their length prefix.
+    // Malformed requests, requests for keys the agent doesn't hold, and
+    // requests of other types get SSH_AGENT_FAILURE.
+    pub fn handle(&self, request: &[u8]) -> Vec<u8> {
+        let reply = match request.split_first() {
+            Some((&SSH_AGENTC_REQUEST_IDENTITIES, [])) => Some(self.identities_answer()),
+            Some((&SSH_AGENTC_SIGN_REQUEST, body)) => self.sign_response(body),
+            _ => None,
+        };
+        reply.unwrap_or_else(|| vec![SSH_AGENT_FAILURE])
+    }
+
+    // Answers requests from stream until it reaches end of file. A message
+    // longer than OpenSSH allows is an InvalidData error, since the stream
+    // can't be resynchronised after one.
+    pub fn serve<S: Read + Write>(&self, stream: &mut S) -> io::Result<()> {
+        loop {
+            let mut len = [0; 4];
+            match stream.read_exact(&mut len) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                result => result?,
+            }
+            let len = u32::from_be_bytes(len) as usize;
+            if len > MAX_MESSAGE_LEN {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "ssh-agent message too long",
+                ));
+            }
+            let mut request = vec![0; len];
+            stream.read_exact(&mut request)?;
+
+            let mut framed = Vec::new();
+            put_string(&mut framed, &self.handle(&request));
+            stream.write_all(&framed)?;
+            stream.flush()?;
+        }
+    }
+
+    fn identities_answer(&self) -> Vec<u8> {
+        let mut reply = vec![SSH_AGENT_IDENTITIES_ANSWER];
+        reply.extend_from_slice(&(self.identities.len() as u32).to_be_bytes());
+        for (keypair, comment) in &self.identities {
+            put_string(&mut reply, &public_key_blob(keypair.public()));
+            put_string(&mut reply, comment.as_bytes());
+        }
+        reply
+    }
+
+    // A sign request is the key blob, the data and a uint32 of flags. The
+    // flags only select among RSA signature hashes, so they are ignored.
+    fn sign_response(&self, body: &[u8]) -> Option<Vec<u8>> {
+        let mut reader = Reader(body);
+        let key = reader.string()?;
+        let data = reader.string()?;
+        reader.u32()?;
+        reader.finish()?;
+
+        let (keypair, _) = self
+            .identities
+            .iter()
+            .find(|(keypair, _)| public_key_blob(keypair.public()) == key)?;
+        let mut reply = vec![SSH_AGENT_SIGN_RESPONSE];
+        put_string(&mut reply, &signature_blob(&keypair.sign(data)));
+        Some(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssh::{parse_public_key_blob, parse_signature_blob};
+
+    fn keypair(seed: u8) -> Keypair {
+        Keypair::from_seed([seed; 32])
+    }
+
+    fn sign_request(keypair: &Keypair, data: &[u8]) -> Vec<u8> {
+        let mut request = vec![SSH_AGENTC_SIGN_REQUEST];
+        put_string(&mut request, &public_key_blob(keypair.public()));
+        put_string(&mut request, data);
+        request.extend_from_slice(&0u32.to_be_bytes());
+        request
+    }
+
+    #[test]
+    fn request_identities() {
+        let mut agent = Agent::new();
+        assert_eq!(agent.handle(&[11]), [12, 0, 0, 0, 0]);
+
+        agent.add(keypair(1), "alice@laptop");
+        agent.add(keypair(2), "deploy");
+        let reply = agent.handle(&[11]);
+        let mut reader = Reader(&reply[1..]);
+        assert_eq!(reply[0], SSH_AGENT_IDENTITIES_ANSWER);
+        assert_eq!(reader.u32(), Some(2));
+        for (seed, comment) in [(1, "alice@laptop"), (2, "deploy")] {
+            let key = parse_public_key_blob(reader.string().unwrap()).unwrap();
+            assert_eq!(&key, keypair(seed).public());
+            assert_eq!(reader.string(), Some(comment.as_bytes()));
+        }
+        assert_eq!(reader.finish(), Some(()));
+    }
+
+    #[test]
+    fn sign_request_signs() {
+        let mut agent = Agent::new();
+        agent.add(keypair(1), "");
+        agent.add(keypair(2), "");
+
+        let reply = agent.handle(&sign_request(&keypair(2), b"session data"));
+        assert_eq!(reply[0], SSH_AGENT_SIGN_RESPONSE);
+        let mut reader = Reader(&reply[1..]);
+        let signature = parse_signature_blob(reader.string().unwrap()).unwrap();
+        assert_eq!(reader.finish(), Some(()));
+        assert_eq!(
+            keypair(2).public().verify(b"session data", &signature),
+            Ok(())
+        );
+
+        // a key the agent doesn't have
+        assert_eq!(agent.handle(&sign_request(&keypair(3), b"x")), [5]);
+    }
+
+    #[test]
+    fn failures() {
+        let mut agent = Agent::new();
+        agent.add(keypair(1), "");
+        let request = sign_request(&keypair(1), b"x");
+        // empty, unknown type (17 is add identity), truncated, trailing data
+        assert_eq!(agent.handle(&[]), [5]);
+        assert_eq!(agent.handle(&[17]), [5]);
+        assert_eq!(agent.handle(&request[..request.len() - 1]), [5]);
+        assert_eq!(agent.handle(&[&request[..], &[0]].concat()), [5]);
+        assert_eq!(agent.handle(&[11, 0]), [5]);
+    }
+
+    // A byte stream with canned input that records what is written.
+    struct Pipe {
+        input: io::Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn serve() {
+        let mut agent = Agent::new();
+        agent.add(keypair(1), "k");
+        let mut input = Vec::new();
+        put_string(&mut input, &[11]);
+        put_string(&mut input, &sign_request(&keypair(1), b"data"));
+        put_string(&mut input, &[99]);
+        let mut pipe = Pipe {
+            input: io::Cursor::new(input),
+            output: Vec::new(),
+        };
+        agent.serve(&mut pipe).unwrap();
+
+        let mut replies = Reader(&pipe.output);
+        assert_eq!(replies.string(), Some(&agent.handle(&[11])[..]));
+        assert_eq!(
+            replies.string(),
+            Some(&agent.handle(&sign_request(&keypair(1), b"data"))[..])
+        );
+        assert_eq!(replies.string(), Some(&[5][..]));
+        assert_eq!(replies.finish(), Some(()));
+
+        // a length over the limit
+        let mut pipe = Pipe {
+            input: io::Cursor::new(u32::MAX.to_be_bytes().to_vec()),
+            output: Vec::new(),
+        };
+        assert_eq!(
+            agent.serve(&mut pipe).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
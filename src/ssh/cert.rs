@@ -0,0 +1,461 @@
+use super::{
+    parse_public_key_blob, parse_signature_blob, public_key_blob, put_string, signature_blob,
+    Reader, SshError,
+};
+use crate::base64;
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, Signature, SignatureError};
+
+// OpenSSH certificates for Ed25519 keys (PROTOCOL.certkeys), the
+// "ssh-ed25519-cert-v01@openssh.com" key type. A certificate is a public
+// key together with what a certificate authority says it may be used for,
+// signed by the CA's key:
+//
+//   string  "ssh-ed25519-cert-v01@openssh.com"
+//   string  nonce
+//   string  public key (the 32 bytes of A)
+//   uint64  serial
+//   uint32  type (1 for a user, 2 for a host)
+//   string  key id
+//   string  valid principals (a list of strings)
+//   uint64  valid after
+//   uint64  valid before
+//   string  critical options (a list of name, data string pairs)
+//   string  extensions (likewise)
+//   string  reserved
+//   string  signature key (the CA's public key blob)
+//   string  signature (over everything above)
+//
+// Only Ed25519 CAs are supported. The nonce is there so that the signed
+// data isn't chosen entirely by whoever asked for the certificate;
+// ssh-keygen uses 32 random bytes.
+
+pub const CERT_KEY_TYPE: &str = "ssh-ed25519-cert-v01@openssh.com";
+
+// The critical options OpenSSH knows about. A verifier has to refuse a
+// certificate with any other critical option, since it can't honour a
+// restriction it doesn't understand.
+const KNOWN_CRITICAL_OPTIONS: [&str; 3] = ["force-command", "source-address", "verify-required"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertificateType {
+    User,
+    Host,
+}
+
+impl CertificateType {
+    fn to_u32(self) -> u32 {
+        match self {
+            CertificateType::User => 1,
+            CertificateType::Host => 2,
+        }
+    }
+
+    fn from_u32(n: u32) -> Option<Self> {
+        match n {
+            1 => Some(CertificateType::User),
+            2 => Some(CertificateType::Host),
+            _ => None,
+        }
+    }
+}
+
+// Everything in a certificate except the CA's key and signature, i.e.
+// what a CA is asked to sign. Times are Unix time in seconds, and the
+// certificate is valid from valid_after up to but not including
+// valid_before (u64::MAX for "forever"). An empty principals list makes
+// the certificate valid for any principal. Options and extensions are
+// (name, data) pairs, where data is stored as it appears inside its
+// string: for force-command that is itself a string holding the command,
+// and for flags like permit-pty it is empty. OpenSSH expects both lists
+// sorted by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsignedCertificate {
+    pub nonce: Vec<u8>,
+    pub public: PublicKey,
+    pub serial: u64,
+    pub cert_type: CertificateType,
+    pub key_id: String,
+    pub principals: Vec<String>,
+    pub valid_after: u64,
+    pub valid_before: u64,
+    pub critical_options: Vec<(String, Vec<u8>)>,
+    pub extensions: Vec<(String, Vec<u8>)>,
+}
+
+impl UnsignedCertificate {
+    // Signs the certificate as the CA whose key pair is ca, like
+    // ssh-keygen -s.
+    #[cfg(feature = "signing")]
+    pub fn sign(self, ca: &Keypair) -> Certificate {
+        let signature_key = *ca.public();
+        let signature = ca.sign(&self.signed_data(&signature_key));
+        Certificate {
+            fields: self,
+            signature_key,
+            signature,
+        }
+    }
+
+    // The encoding up to and including the signature key, which is what
+    // the CA signs.
+    fn signed_data(&self, signature_key: &PublicKey) -> Vec<u8> {
+        let mut data = Vec::new();
+        put_string(&mut data, CERT_KEY_TYPE.as_bytes());
+        put_string(&mut data, &self.nonce);
+        put_string(&mut data, self.public.as_bytes());
+        data.extend_from_slice(&self.serial.to_be_bytes());
+        data.extend_from_slice(&self.cert_type.to_u32().to_be_bytes());
+        put_string(&mut data, self.key_id.as_bytes());
+        let mut principals = Vec::new();
+        for principal in &self.principals {
+            put_string(&mut principals, principal.as_bytes());
+        }
+        put_string(&mut data, &principals);
+        data.extend_from_slice(&self.valid_after.to_be_bytes());
+        data.extend_from_slice(&self.valid_before.to_be_bytes());
+        put_string(&mut data, &encode_options(&self.critical_options));
+        put_string(&mut data, &encode_options(&self.extensions));
+        put_string(&mut data, &[]);
+        put_string(&mut data, &public_key_blob(signature_key));
+        data
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    fields: UnsignedCertificate,
+    signature_key: PublicKey,
+    signature: Signature,
+}
+
+impl Certificate {
+    pub fn fields(&self) -> &UnsignedCertificate {
+        &self.fields
+    }
+
+    // The certified key.
+    pub fn public(&self) -> &PublicKey {
+        &self.fields.public
+    }
+
+    // The CA's key, as recorded in the certificate.
+    pub fn signature_key(&self) -> &PublicKey {
+        &self.signature_key
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    // Checks that the certificate was signed by ca and allows principal
+    // to use the key as a cert_type at time now, which is roughly what
+    // sshd checks before accepting a user certificate (with the user name
+    // as the principal) and ssh before accepting a host certificate (with
+    // the host name). As with SshSignature::verify, the caller says which
+    // CA it trusts; a certificate signed by another key is rejected with
+    // KeyMismatch. Critical options other than OpenSSH's three are
+    // rejected too, but enforcing the known ones (running only the forced
+    // command, say) is up to the caller.
+    pub fn validate(
+        &self,
+        ca: &PublicKey,
+        cert_type: CertificateType,
+        principal: &str,
+        now: u64,
+    ) -> Result<(), SshError> {
+        if self.signature_key != *ca {
+            return Err(SignatureError::KeyMismatch.into());
+        }
+        ca.verify(&self.fields.signed_data(ca), &self.signature)?;
+
+        let fields = &self.fields;
+        if fields.cert_type != cert_type {
+            return Err(SshError::WrongCertificateType);
+        }
+        if now < fields.valid_after || now >= fields.valid_before {
+            return Err(SshError::NotValidNow);
+        }
+        if !fields.principals.is_empty() && !fields.principals.iter().any(|p| p == principal) {
+            return Err(SshError::PrincipalNotAllowed);
+        }
+        if fields
+            .critical_options
+            .iter()
+            .any(|(name, _)| !KNOWN_CRITICAL_OPTIONS.contains(&name.as_str()))
+        {
+            return Err(SshError::UnknownCriticalOption);
+        }
+        Ok(())
+    }
+
+    pub fn to_blob(&self) -> Vec<u8> {
+        let mut blob = self.fields.signed_data(&self.signature_key);
+        put_string(&mut blob, &signature_blob(&self.signature));
+        blob
+    }
+
+    // Parses a certificate blob. This only checks the encoding; see
+    // validate for the signature and the rest.
+    pub fn from_blob(blob: &[u8]) -> Result<Self, SshError> {
+        let malformed = SshError::Malformed;
+        let text =
+            |bytes: &[u8]| String::from_utf8(bytes.to_vec()).map_err(|_| SshError::Malformed);
+        let mut reader = Reader(blob);
+        if reader.string().ok_or(malformed)? != CERT_KEY_TYPE.as_bytes() {
+            return Err(SshError::UnsupportedAlgorithm);
+        }
+        let nonce = reader.string().ok_or(malformed)?.to_vec();
+        let public = reader.string().ok_or(malformed)?;
+        let public = PublicKey::from_bytes(public.try_into().map_err(|_| malformed)?)?;
+        let serial = reader.u64().ok_or(malformed)?;
+        let cert_type =
+            CertificateType::from_u32(reader.u32().ok_or(malformed)?).ok_or(malformed)?;
+        let key_id = text(reader.string().ok_or(malformed)?)?;
+        let mut principal_list = Reader(reader.string().ok_or(malformed)?);
+        let mut principals = Vec::new();
+        while principal_list.finish().is_none() {
+            principals.push(text(principal_list.string().ok_or(malformed)?)?);
+        }
+        let valid_after = reader.u64().ok_or(malformed)?;
+        let valid_before = reader.u64().ok_or(malformed)?;
+        let critical_options = decode_options(reader.string().ok_or(malformed)?)?;
+        let extensions = decode_options(reader.string().ok_or(malformed)?)?;
+        if reader.string() != Some(&[]) {
+            return Err(malformed);
+        }
+        let signature_key = parse_public_key_blob(reader.string().ok_or(malformed)?)?;
+        let signature = parse_signature_blob(reader.string().ok_or(malformed)?)?;
+        reader.finish().ok_or(malformed)?;
+
+        Ok(Self {
+            fields: UnsignedCertificate {
+                nonce,
+                public,
+                serial,
+                cert_type,
+                key_id,
+                principals,
+                valid_after,
+                valid_before,
+                critical_options,
+                extensions,
+            },
+            signature_key,
+            signature,
+        })
+    }
+
+    // The certificate as a line of a -cert.pub file (without a comment).
+    pub fn to_openssh(&self) -> String {
+        format!("{CERT_KEY_TYPE} {}", base64::encode(&self.to_blob(), true))
+    }
+
+    // Parses a line of a -cert.pub file: the key type, the base64 blob
+    // and an optional comment, which is ignored.
+    pub fn from_openssh(line: &str) -> Result<Self, SshError> {
+        let mut parts = line.split_whitespace();
+        if parts.next() != Some(CERT_KEY_TYPE) {
+            return Err(SshError::UnsupportedAlgorithm);
+        }
+        let blob = parts.next().ok_or(SshError::Malformed)?;
+        let blob = base64::decode(blob).ok_or(SshError::InvalidBase64)?;
+        Self::from_blob(&blob)
+    }
+}
+
+fn encode_options(options: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (name, data) in options {
+        put_string(&mut out, name.as_bytes());
+        put_string(&mut out, data);
+    }
+    out
+}
+
+fn decode_options(encoded: &[u8]) -> Result<Vec<(String, Vec<u8>)>, SshError> {
+    let malformed = SshError::Malformed;
+    let mut reader = Reader(encoded);
+    let mut options = Vec::new();
+    while reader.finish().is_none() {
+        let name = reader.string().ok_or(malformed)?;
+        let name = String::from_utf8(name.to_vec()).map_err(|_| malformed)?;
+        let data = reader.string().ok_or(malformed)?.to_vec();
+        options.push((name, data));
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ssh-keygen -s ca -I alice@example -n alice,deploy
+    //     -V 20260101000000:20270101000000 -z 42 user.pub
+    // with the seeds read out of the two OpenSSH private key files.
+    #[cfg(feature = "signing")]
+    const CA_SEED: &str = "7167e71e2b0f9f072a92b1d0c81c05e98e16f0770e90bd5a82514487202802cd";
+    const CA_PUBLIC: &str = "24eab78120f6cb8e4a3c3ac02ceb58d6289b8f05a55fae9d13800011a246d2cb";
+    const USER_PUBLIC: &str = "25f1cfcba31025cdab9a5ce24a1a684ca1f81078e6a4eed629a100a33d5f428f";
+    const CERT: &str = "ssh-ed25519-cert-v01@openssh.com \
+        AAAAIHNzaC1lZDI1NTE5LWNlcnQtdjAxQG9wZW5zc2guY29tAAAAIOYpbnZ/66f5G7A3+1g+gniZMry4xw6lUL0zP8r4\
+        bjKHAAAAICXxz8ujECXNq5pc4koaaEyh+BB45qTu1imhAKM9X0KPAAAAAAAAACoAAAABAAAADWFsaWNlQGV4YW1wbGUA\
+        AAATAAAABWFsaWNlAAAABmRlcGxveQAAAABpVbkAAAAAAGs27IAAAAAAAAAAggAAABVwZXJtaXQtWDExLWZvcndhcmRp\
+        bmcAAAAAAAAAF3Blcm1pdC1hZ2VudC1mb3J3YXJkaW5nAAAAAAAAABZwZXJtaXQtcG9ydC1mb3J3YXJkaW5nAAAAAAAA\
+        AApwZXJtaXQtcHR5AAAAAAAAAA5wZXJtaXQtdXNlci1yYwAAAAAAAAAAAAAAMwAAAAtzc2gtZWQyNTUxOQAAACAk6reB\
+        IPbLjko8OsAs61jWKJuPBaVfrp0TgAARokbSywAAAFMAAAALc3NoLWVkMjU1MTkAAABAMQF+f0Fe/6Jr5Uzz53q8uuW9\
+        /H53FCt94CW15oO99+LgiUNtUpVcbD+HNC7fsigwz2Kt4FI8bLOlPGCedZEFCw== user";
+
+    // 2026-01-01 and 2027-01-01, 00:00 UTC.
+    const VALID_AFTER: u64 = 1767225600;
+    const VALID_BEFORE: u64 = 1798761600;
+
+    fn key(h: &str) -> PublicKey {
+        PublicKey::from_bytes(hex::decode(h).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parse_ssh_keygen_certificate() {
+        let cert = Certificate::from_openssh(CERT).unwrap();
+        let fields = cert.fields();
+        assert_eq!(cert.public(), &key(USER_PUBLIC));
+        assert_eq!(cert.signature_key(), &key(CA_PUBLIC));
+        assert_eq!(fields.nonce.len(), 32);
+        assert_eq!(fields.serial, 42);
+        assert_eq!(fields.cert_type, CertificateType::User);
+        assert_eq!(fields.key_id, "alice@example");
+        assert_eq!(fields.principals, ["alice", "deploy"]);
+        assert_eq!(fields.valid_after, VALID_AFTER);
+        assert_eq!(fields.valid_before, VALID_BEFORE);
+        assert!(fields.critical_options.is_empty());
+        let extensions: Vec<_> = fields.extensions.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            extensions,
+            [
+                "permit-X11-forwarding",
+                "permit-agent-forwarding",
+                "permit-port-forwarding",
+                "permit-pty",
+                "permit-user-rc"
+            ]
+        );
+        assert!(fields.extensions.iter().all(|(_, data)| data.is_empty()));
+        assert_eq!(
+            cert.to_openssh(),
+            CERT.trim_end_matches(" user"),
+            "re-encoding"
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let cert = Certificate::from_openssh(CERT).unwrap();
+        let ca = key(CA_PUBLIC);
+        let user = CertificateType::User;
+        assert_eq!(cert.validate(&ca, user, "alice", VALID_AFTER), Ok(()));
+        assert_eq!(cert.validate(&ca, user, "deploy", VALID_BEFORE - 1), Ok(()));
+
+        assert_eq!(
+            cert.validate(&ca, user, "mallory", VALID_AFTER),
+            Err(SshError::PrincipalNotAllowed)
+        );
+        assert_eq!(
+            cert.validate(&ca, user, "alice", VALID_AFTER - 1),
+            Err(SshError::NotValidNow)
+        );
+        assert_eq!(
+            cert.validate(&ca, user, "alice", VALID_BEFORE),
+            Err(SshError::NotValidNow)
+        );
+        assert_eq!(
+            cert.validate(&ca, CertificateType::Host, "alice", VALID_AFTER),
+            Err(SshError::WrongCertificateType)
+        );
+        assert_eq!(
+            cert.validate(&key(USER_PUBLIC), user, "alice", VALID_AFTER),
+            Err(SshError::Signature(SignatureError::KeyMismatch))
+        );
+
+        // Any change to the signed fields breaks the signature.
+        let mut widened = cert.clone();
+        widened.fields.principals.push("root".to_owned());
+        assert_eq!(
+            widened.validate(&ca, user, "root", VALID_AFTER),
+            Err(SshError::Signature(SignatureError::VerificationFailed))
+        );
+    }
+
+    // Ed25519 is deterministic, so signing the same fields with the same
+    // CA reproduces ssh-keygen's certificate exactly.
+    #[cfg(feature = "signing")]
+    #[test]
+    fn sign_matches_ssh_keygen() {
+        let ca = Keypair::from_seed(hex::decode(CA_SEED).unwrap().try_into().unwrap());
+        let parsed = Certificate::from_openssh(CERT).unwrap();
+        let cert = parsed.fields().clone().sign(&ca);
+        assert_eq!(cert, parsed);
+
+        // A host certificate with no principals and a critical option.
+        let host = UnsignedCertificate {
+            nonce: vec![7; 32],
+            public: key(USER_PUBLIC),
+            serial: 1,
+            cert_type: CertificateType::Host,
+            key_id: "host".to_owned(),
+            principals: Vec::new(),
+            valid_after: 0,
+            valid_before: u64::MAX,
+            critical_options: vec![("verify-required".to_owned(), Vec::new())],
+            extensions: Vec::new(),
+        }
+        .sign(&ca);
+        let reparsed = Certificate::from_openssh(&host.to_openssh()).unwrap();
+        assert_eq!(reparsed, host);
+        assert_eq!(
+            reparsed.validate(
+                cert.signature_key(),
+                CertificateType::Host,
+                "any.example",
+                1 << 40
+            ),
+            Ok(())
+        );
+
+        let mut unknown_option = host.fields().clone();
+        unknown_option.critical_options = vec![("no-such-option".to_owned(), Vec::new())];
+        let unknown_option = unknown_option.sign(&ca);
+        assert_eq!(
+            unknown_option.validate(ca.public(), CertificateType::Host, "h", 0),
+            Err(SshError::UnknownCriticalOption)
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let blob = Certificate::from_openssh(CERT).unwrap().to_blob();
+        assert_eq!(
+            Certificate::from_blob(&blob[..blob.len() - 1]),
+            Err(SshError::Malformed)
+        );
+        let mut trailing = blob.clone();
+        trailing.push(0);
+        assert_eq!(Certificate::from_blob(&trailing), Err(SshError::Malformed));
+        // type 3; it follows the 36-byte key type, 36-byte nonce, 36-byte
+        // key and 8-byte serial
+        let mut bad_type = blob.clone();
+        bad_type[119] = 3;
+        assert_eq!(Certificate::from_blob(&bad_type), Err(SshError::Malformed));
+        // a plain key where a certificate was expected
+        assert_eq!(
+            Certificate::from_openssh(
+                "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAINdamAGCsQq31Uv+08lkBzoO4XLz2qYjJa8CGmj3B1Ea"
+            ),
+            Err(SshError::UnsupportedAlgorithm)
+        );
+        assert_eq!(
+            Certificate::from_openssh(&CERT.replacen("AAAAIH", "AAAAI*", 1)),
+            Err(SshError::InvalidBase64)
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use sha2::{Digest, Sha256, Sha512};
+
+use super::{
+    parse_public_key_blob, parse_signature_blob, public_key_blob, put_string, signature_blob,
+    Reader, SshError,
+};
+use crate::pem;
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, Signature, SignatureError};
+
+// Detached signatures in OpenSSH's SSHSIG format (PROTOCOL.sshsig), as made
+// by ssh-keygen -Y sign and checked by ssh-keygen -Y verify, and so by git
+// with gpg.format = ssh.
+//
+// What gets signed is not the message but
+//
+//   "SSHSIG" || string(namespace) || string(reserved)
+//            || string(hash algorithm) || string(H(message))
+//
+// where the namespace says what the signature is for ("git" for commits,
+// "file" for ssh-keygen's default), so a signature made for one purpose
+// can't be passed off as one for another. The signature itself is
+//
+//   "SSHSIG" || uint32(1) || string(public key) || string(namespace)
+//            || string(reserved) || string(hash algorithm)
+//            || string(string("ssh-ed25519") || string(R || S))
+//
+// armored as PEM with the label "SSH SIGNATURE". The reserved field is
+// always empty.
+
+const MAGIC: &[u8; 6] = b"SSHSIG";
+const VERSION: u32 = 1;
+const LABEL: &str = "SSH SIGNATURE";
+
+// The hash applied to the message before signing. ssh-keygen signs with
+// SHA-512 and accepts either when verifying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    fn from_name(name: &[u8]) -> Result<Self, SshError> {
+        match name {
+            b"sha256" => Ok(HashAlgorithm::Sha256),
+            b"sha512" => Ok(HashAlgorithm::Sha512),
+            _ => Err(SshError::UnsupportedAlgorithm),
+        }
+    }
+
+    fn digest(&self, message: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => Sha256::digest(message).to_vec(),
+            HashAlgorithm::Sha512 => Sha512::digest(message).to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshSignature {
+    public: PublicKey,
+    namespace: String,
+    hash: HashAlgorithm,
+    signature: Signature,
+}
+
+impl SshSignature {
+    // Signs message for use in namespace, as ssh-keygen -Y sign -n
+    // namespace does. The namespace must not be empty.
+    #[cfg(feature = "signing")]
+    pub fn sign(keypair: &Keypair, namespace: &str, message: &[u8]) -> Result<Self, SshError> {
+        if namespace.is_empty() {
+            return Err(SshError::WrongNamespace);
+        }
+        let hash = HashAlgorithm::Sha512;
+        let signature = keypair.sign(&signed_data(namespace, hash, message));
+        Ok(Self {
+            public: *keypair.public(),
+            namespace: namespace.to_owned(),
+            hash,
+            signature,
+        })
+    }
+
+    // The key that made the signature, as recorded in it.
+    pub fn public(&self) -> &PublicKey {
+        &self.public
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    // Checks that this is public's signature on message for namespace.
+    // The key embedded in the signature says nothing about who signed,
+    // since anyone can make a signature with their own key; the caller
+    // has to say which key they trust (ssh-keygen looks it up in an
+    // allowed_signers file). A signature by a different key fails with
+    // KeyMismatch and one for a different namespace with WrongNamespace,
+    // before any curve arithmetic.
+    pub fn verify(
+        &self,
+        public: &PublicKey,
+        namespace: &str,
+        message: &[u8],
+    ) -> Result<(), SshError> {
+        if self.public != *public {
+            return Err(SignatureError::KeyMismatch.into());
+        }
+        if self.namespace != namespace {
+            return Err(SshError::WrongNamespace);
+        }
+        public.verify(
+            &signed_data(&self.namespace, self.hash, message),
+            &self.signature,
+        )?;
+        Ok(())
+    }
+
+    pub fn to_blob(&self) -> Vec<u8> {
+        let mut blob = MAGIC.to_vec();
+        blob.extend_from_slice(&VERSION.to_be_bytes());
+        put_string(&mut blob, &public_key_blob(&self.public));
+        put_string(&mut blob, self.namespace.as_bytes());
+        put_string(&mut blob, &[]);
+        put_string(&mut blob, self.hash.name().as_bytes());
+        put_string(&mut blob, &signature_blob(&self.signature));
+        blob
+    }
+
+    pub fn from_blob(blob: &[u8]) -> Result<Self, SshError> {
+        let malformed = SshError::Malformed;
+        let mut reader = Reader(blob);
+        if reader.bytes(6) != Some(MAGIC) || reader.u32() != Some(VERSION) {
+            return Err(malformed);
+        }
+        let public = parse_public_key_blob(reader.string().ok_or(malformed)?)?;
+        let namespace = reader.string().ok_or(malformed)?;
+        let namespace = std::str::from_utf8(namespace).map_err(|_| malformed)?;
+        if namespace.is_empty() || reader.string() != Some(&[]) {
+            return Err(malformed);
+        }
+        let hash = HashAlgorithm::from_name(reader.string().ok_or(malformed)?)?;
+        let signature = reader.string().ok_or(malformed)?;
+        reader.finish().ok_or(malformed)?;
+        Ok(Self {
+            public,
+            namespace: namespace.to_owned(),
+            hash,
+            signature: parse_signature_blob(signature)?,
+        })
+    }
+
+    // The armored form ssh-keygen writes to a .sig file.
+    pub fn to_armored(&self) -> String {
+        pem::encode(LABEL, &self.to_blob(), 70)
+    }
+
+    pub fn from_armored(armored: &str) -> Result<Self, SshError> {
+        Self::from_blob(&pem::decode(LABEL, armored)?)
+    }
+}
+
+fn signed_data(namespace: &str, hash: HashAlgorithm, message: &[u8]) -> Vec<u8> {
+    let mut data = MAGIC.to_vec();
+    put_string(&mut data, namespace.as_bytes());
+    put_string(&mut data, &[]);
+    put_string(&mut data, hash.name().as_bytes());
+    put_string(&mut data, &hash.digest(message));
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pem::PemError;
+
+    const MESSAGE: &[u8] = b"hello sshsig\n";
+
+    // ssh-keygen -Y sign -f key -n file on MESSAGE. The key's seed was
+    // read out of the OpenSSH private key file.
+    #[cfg(feature = "signing")]
+    const SEED: &str = "98d288972f8c2059b920c231bb7f1b056a7ad16b7cc443de9ec320a02654ba8e";
+    const PUBLIC: &str = "c2e7ea12623ab18811ccc9d97660bd9fef01ce17e1bc018a666552b8cf448959";
+    const ARMORED: &str = "-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAADMAAAALc3NoLWVkMjU1MTkAAAAgwufqEmI6sYgRzMnZdmC9n+8Bzh
+fhvAGKZmVSuM9EiVkAAAAEZmlsZQAAAAAAAAAGc2hhNTEyAAAAUwAAAAtzc2gtZWQyNTUx
+OQAAAECwlzC8zmHXhV6K/pd+OgktDzN5plrKberHk7If5cnfdHzghY2HG62DGqs5CiZSeO
+jSRuGLpVDpXeXpx7znaMUK
+-----END SSH SIGNATURE-----
+";
+
+    fn public() -> PublicKey {
+        PublicKey::from_bytes(hex::decode(PUBLIC).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn verify_ssh_keygen_signature() {
+        let signature = SshSignature::from_armored(ARMORED).unwrap();
+        assert_eq!(signature.public(), &public());
+        assert_eq!(signature.namespace(), "file");
+        assert_eq!(signature.hash_algorithm(), HashAlgorithm::Sha512);
+        assert_eq!(signature.to_armored(), ARMORED);
+        assert_eq!(signature.verify(&public(), "file", MESSAGE), Ok(()));
+
+        assert_eq!(
+            signature.verify(&public(), "git", MESSAGE),
+            Err(SshError::WrongNamespace)
+        );
+        assert_eq!(
+            signature.verify(&public(), "file", b"hello sshsig"),
+            Err(SshError::Signature(SignatureError::VerificationFailed))
+        );
+        let other = PublicKey::from_bytes(
+            hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            signature.verify(&other, "file", MESSAGE),
+            Err(SshError::Signature(SignatureError::KeyMismatch))
+        );
+    }
+
+    // Ed25519 is deterministic, so signing the same message with the same
+    // key reproduces ssh-keygen's file byte for byte.
+    #[cfg(feature = "signing")]
+    #[test]
+    fn sign_matches_ssh_keygen() {
+        let keypair = Keypair::from_seed(hex::decode(SEED).unwrap().try_into().unwrap());
+        let signature = SshSignature::sign(&keypair, "file", MESSAGE).unwrap();
+        assert_eq!(signature.to_armored(), ARMORED);
+
+        let git = SshSignature::sign(&keypair, "git", MESSAGE).unwrap();
+        assert_eq!(git.verify(&public(), "git", MESSAGE), Ok(()));
+        assert_eq!(
+            SshSignature::sign(&keypair, "", MESSAGE),
+            Err(SshError::WrongNamespace)
+        );
+    }
+
+    #[test]
+    fn sha256() {
+        // The same signature relabelled as SHA-256 no longer verifies; the
+        // hash algorithm is part of what is signed.
+        let mut signature = SshSignature::from_armored(ARMORED).unwrap();
+        signature.hash = HashAlgorithm::Sha256;
+        let relabelled = SshSignature::from_blob(&signature.to_blob()).unwrap();
+        assert_eq!(relabelled.hash_algorithm(), HashAlgorithm::Sha256);
+        assert_eq!(
+            relabelled.verify(&public(), "file", MESSAGE),
+            Err(SshError::Signature(SignatureError::VerificationFailed))
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let blob = SshSignature::from_armored(ARMORED).unwrap().to_blob();
+        let reparse = |f: &dyn Fn(&mut Vec<u8>)| {
+            let mut blob = blob.clone();
+            f(&mut blob);
+            SshSignature::from_blob(&blob)
+        };
+        // wrong magic, version 2, trailing data, truncated
+        assert_eq!(reparse(&|b| b[0] = b'X'), Err(SshError::Malformed));
+        assert_eq!(reparse(&|b| b[9] = 2), Err(SshError::Malformed));
+        assert_eq!(reparse(&|b| b.push(0)), Err(SshError::Malformed));
+        assert_eq!(
+            reparse(&|b| {
+                b.pop();
+            }),
+            Err(SshError::Malformed)
+        );
+        // an unknown hash ("sha384"; the name comes after 10 bytes of
+        // header, 55 of key, 8 of namespace, 4 of reserved and its length)
+        assert_eq!(
+            reparse(&|b| b[81..87].copy_from_slice(b"sha384")),
+            Err(SshError::UnsupportedAlgorithm)
+        );
+        // S >= L
+        assert_eq!(
+            reparse(&|b| {
+                let last = b.len() - 1;
+                b[last] = 0xff;
+            }),
+            Err(SshError::Signature(SignatureError::NonCanonicalScalar))
+        );
+        // damaged armor
+        assert_eq!(
+            SshSignature::from_armored(&ARMORED.replace("SSH SIGNATURE", "PUBLIC KEY")),
+            Err(SshError::Pem(PemError::WrongLabel))
+        );
+        assert_eq!(
+            SshSignature::from_armored(&ARMORED.replacen("U1NI", "U1N", 1)),
+            Err(SshError::Pem(PemError::InvalidBase64))
+        );
+    }
+}
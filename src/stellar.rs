@@ -0,0 +1,176 @@
+use std::fmt;
+
+use zeroize::Zeroizing;
+
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, SignatureError};
+
+// Stellar's StrKey encoding of account IDs ("G...") and secret seeds
+// ("S..."). The 32-byte key or seed is prefixed with a version byte and
+// followed by a CRC16-XModem checksum of both, little-endian, and the 35
+// bytes are written in unpadded RFC 4648 base32, which comes to exactly
+// 56 characters. The version byte is what makes the first character G or
+// S.
+//
+// The checksum only catches typos; decoding is strict otherwise too, so a
+// seed can't be read as an account ID or the other way around. Other
+// StrKey types (muxed accounts, pre-auth transactions, ...) are refused.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StellarError {
+    // A StrKey that can't be read: not 56 base32 characters, or the wrong
+    // version byte or checksum.
+    Malformed,
+    // An account ID that isn't a point.
+    Signature(SignatureError),
+}
+
+impl fmt::Display for StellarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StellarError::Malformed => f.write_str("malformed Stellar key"),
+            StellarError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for StellarError {}
+
+impl From<SignatureError> for StellarError {
+    fn from(e: SignatureError) -> Self {
+        StellarError::Signature(e)
+    }
+}
+
+const ACCOUNT_ID: u8 = 6 << 3;
+#[cfg(feature = "signing")]
+const SECRET_SEED: u8 = 18 << 3;
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+pub fn encode_public_key(key: &PublicKey) -> String {
+    encode(ACCOUNT_ID, key.as_bytes())
+}
+
+pub fn decode_public_key(text: &str) -> Result<PublicKey, StellarError> {
+    Ok(PublicKey::from_bytes(decode(ACCOUNT_ID, text)?)?)
+}
+
+// The secret seed. Unlike Solana, Stellar stores only the seed.
+#[cfg(feature = "signing")]
+pub fn encode_keypair(keypair: &Keypair) -> Zeroizing<String> {
+    let seed = Zeroizing::new(keypair.secret().to_bytes());
+    Zeroizing::new(encode(SECRET_SEED, &seed))
+}
+
+#[cfg(feature = "signing")]
+pub fn decode_keypair(text: &str) -> Result<Keypair, StellarError> {
+    let seed = Zeroizing::new(decode(SECRET_SEED, text)?);
+    Ok(Keypair::from_seed(*seed))
+}
+
+fn encode(version: u8, payload: &[u8; 32]) -> String {
+    let mut data = Zeroizing::new([0u8; 35]);
+    data[0] = version;
+    data[1..33].copy_from_slice(payload);
+    let checksum = crc16(&data[..33]);
+    data[33..].copy_from_slice(&checksum.to_le_bytes());
+
+    // Five bytes at a time make eight characters.
+    let mut out = String::with_capacity(56);
+    for chunk in data.chunks(5) {
+        let mut group = [0u8; 8];
+        group[3..].copy_from_slice(chunk);
+        let n = u64::from_be_bytes(group);
+        for i in 0..8 {
+            out.push(ALPHABET[(n >> (35 - 5 * i)) as usize & 31] as char);
+        }
+    }
+    out
+}
+
+fn decode(version: u8, text: &str) -> Result<[u8; 32], StellarError> {
+    let malformed = StellarError::Malformed;
+    if text.len() != 56 {
+        return Err(malformed);
+    }
+    let mut data = Zeroizing::new([0u8; 35]);
+    for (chunk, out) in text.as_bytes().chunks(8).zip(data.chunks_mut(5)) {
+        let mut n = 0u64;
+        for c in chunk {
+            let value = ALPHABET.iter().position(|a| a == c).ok_or(malformed)?;
+            n = n << 5 | value as u64;
+        }
+        out.copy_from_slice(&n.to_be_bytes()[3..]);
+    }
+    if data[0] != version || crc16(&data[..33]).to_le_bytes() != data[33..] {
+        return Err(malformed);
+    }
+    Ok(data[1..33].try_into().unwrap())
+}
+
+// CRC-16/XMODEM: polynomial 0x1021, initial value 0, no reflection.
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                crc << 1 ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The key pair from Stellar's account creation tutorial.
+    const ACCOUNT: &str = "GC2BKLYOOYPDEFJKLKY6FNNRQMGFLVHJKQRGNSSRRGSMPGF32LHCQVGF";
+    const PUBLIC: &str = "b4152f0e761e32152a5ab1e2b5b1830c55d4e9542266ca5189a4c798bbd2ce28";
+    #[cfg(feature = "signing")]
+    const SEED: &str = "SCZANGBA5YHTNYVVV4C3U252E2B6P6F5T3U6MM63WBSBZATAQI3EBTQ4";
+
+    #[test]
+    fn account_ids() {
+        let key = decode_public_key(ACCOUNT).unwrap();
+        assert_eq!(hex::encode(key.as_bytes()), PUBLIC);
+        assert_eq!(encode_public_key(&key), ACCOUNT);
+
+        // CRC-16/XMODEM's check value
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+
+        // a typo (caught by the checksum), lower case, a character
+        // outside the alphabet, a character short
+        let typo = ACCOUNT.replacen("GC2B", "GC2C", 1);
+        let lower = ACCOUNT.to_lowercase();
+        let digit = ACCOUNT.replacen("GC2B", "GC21", 1);
+        let short = &ACCOUNT[..55];
+        for bad in [&typo, &lower, &digit, short] {
+            assert_eq!(
+                decode_public_key(bad),
+                Err(StellarError::Malformed),
+                "{bad}"
+            );
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn secret_seeds() {
+        let keypair = decode_keypair(SEED).unwrap();
+        assert_eq!(encode_public_key(keypair.public()), ACCOUNT);
+        assert_eq!(*encode_keypair(&keypair), SEED);
+
+        // each kind where the other is expected
+        assert_eq!(decode_public_key(SEED), Err(StellarError::Malformed));
+        assert!(matches!(
+            decode_keypair(ACCOUNT),
+            Err(StellarError::Malformed)
+        ));
+    }
+}
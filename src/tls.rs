@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use rustls::pki_types::{alg_id, CertificateDer, SubjectPublicKeyInfoDer};
+use rustls::sign::{self, CertifiedKey, Signer, SigningKey};
+use rustls::{SignatureAlgorithm, SignatureScheme};
+
+use crate::signing::Keypair;
+
+// rustls's SigningKey and Signer for Keypair, so a TLS 1.3 server (or a
+// client doing certificate authentication) can sign its handshake with a
+// key held here rather than one loaded through a rustls CryptoProvider.
+//
+// TLS calls Ed25519 the "ed25519" signature scheme (RFC 8446 section
+// 4.2.3): plain Ed25519 over the handshake transcript, no prehashing. The
+// key is only offered when the peer lists that scheme.
+//
+// Typical use is a certificate from x509 and
+//
+//   let key = tls::certified_key(keypair, vec![certificate.into()]);
+//   ServerConfig::builder()
+//       .with_no_client_auth()
+//       .with_cert_resolver(Arc::new(SingleCertAndKey::from(key)))
+//
+// The rest of the handshake (key exchange, record encryption, checking the
+// peer's certificates) still needs a provider such as rustls's ring or
+// aws-lc-rs.
+
+impl SigningKey for Keypair {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        if offered.contains(&SignatureScheme::ED25519) {
+            Some(Box::new(self.clone()))
+        } else {
+            None
+        }
+    }
+
+    // Lets rustls check that the key matches its certificate.
+    fn public_key(&self) -> Option<SubjectPublicKeyInfoDer<'_>> {
+        Some(sign::public_key_to_spki(
+            &alg_id::ED25519,
+            self.public().as_bytes(),
+        ))
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::ED25519
+    }
+}
+
+// Signing can't fail.
+impl Signer for Keypair {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        Ok(Keypair::sign(self, message).to_bytes().to_vec())
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::ED25519
+    }
+}
+
+// The key pair with its certificate chain, end-entity certificate first,
+// ready for a certificate resolver.
+pub fn certified_key(keypair: Keypair, chain: Vec<CertificateDer<'static>>) -> CertifiedKey {
+    CertifiedKey::new(chain, Arc::new(keypair))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_ed25519_only() {
+        let keypair = Keypair::from_seed([7; 32]);
+        let offered = [
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            SignatureScheme::ED25519,
+        ];
+        let signer = keypair.choose_scheme(&offered).unwrap();
+        assert_eq!(signer.scheme(), SignatureScheme::ED25519);
+        let signature = signer.sign(b"transcript").unwrap();
+        let signature =
+            crate::signing::Signature::from_bytes(&signature.try_into().unwrap()).unwrap();
+        assert!(keypair.public().verify(b"transcript", &signature).is_ok());
+
+        assert!(keypair
+            .choose_scheme(&[SignatureScheme::RSA_PSS_SHA256])
+            .is_none());
+        assert_eq!(SigningKey::algorithm(&keypair), SignatureAlgorithm::ED25519);
+    }
+
+    #[cfg(feature = "x509")]
+    #[test]
+    fn matches_certificate() {
+        use crate::x509::CertificateParams;
+
+        let keypair = Keypair::from_seed([7; 32]);
+        let params = CertificateParams {
+            serial: 1,
+            common_name: "localhost".to_owned(),
+            not_before: 1700000000,
+            not_after: 2000000000,
+            dns_names: vec!["localhost".to_owned()],
+            is_ca: false,
+        };
+        let certificate = params.self_signed(&keypair);
+        assert_eq!(
+            keypair.public_key().unwrap().as_ref(),
+            crate::pkcs8::encode_public_key(keypair.public())
+        );
+
+        let key = certified_key(keypair, vec![certificate.clone().into()]);
+        assert!(key.keys_match().is_ok());
+        let other = certified_key(Keypair::from_seed([9; 32]), vec![certificate.into()]);
+        assert!(other.keys_match().is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+#[cfg(feature = "signing")]
+use crate::ristretto::RISTRETTO_BASEPOINT_TABLE;
+use crate::ristretto::{CompressedRistretto, RistrettoPoint, RISTRETTO_BASEPOINT};
+use crate::scalar::Scalar;
+use crate::signing::SignatureError;
+
+// Fiat-Shamir with Merlin transcripts. A sigma protocol made
+// non-interactive has to hash everything the verifier would have seen
+// before each challenge: the statement, the public parameters, every
+// commitment, in an unambiguous encoding. Forgetting one (a public key
+// left out of the hash, two messages that concatenate the same way) is the
+// classic way such proofs break. A Merlin transcript makes the right thing
+// the easy one: every message is appended with a label and its length,
+// challenges are squeezed out of everything appended so far, and the
+// caller can bind the proof to its context by appending to the transcript
+// before handing it over.
+//
+// TranscriptProtocol adds the appends and challenges for this crate's
+// types to merlin's Transcript, in the encodings schnorrkel uses, so that
+// proofs written with it match proofs written with schnorrkel's. sr25519
+// signing is built on it, and DlogProof below is the simplest proof that
+// is: RFC 8235's Schnorr proof of knowledge of a with A = a * B.
+//
+// Only ristretto255 points are supported. With Edwards points the
+// cofactor gives every statement several encodings, which is exactly the
+// kind of ambiguity transcripts are meant to rule out.
+
+pub trait TranscriptProtocol {
+    // Names the protocol, so proofs from one can't be replayed as another.
+    fn proto_name(&mut self, name: &'static [u8]);
+
+    fn append_point(&mut self, label: &'static [u8], point: &CompressedRistretto);
+
+    // append_point for points chosen by the prover, refusing the identity
+    // (InvalidPoint): a zero commitment or key would make the proof
+    // trivial to forge for some protocols.
+    fn validate_and_append_point(
+        &mut self,
+        label: &'static [u8],
+        point: &CompressedRistretto,
+    ) -> Result<(), SignatureError>;
+
+    fn append_scalar(&mut self, label: &'static [u8], scalar: &Scalar);
+
+    // A challenge: 64 bytes reduced mod L, so it is uniform.
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar;
+
+    // A secret nonce derived from the transcript so far, the secret and
+    // 32 bytes from rng. It is unpredictable if either the secret or the
+    // RNG is good, and differs whenever the transcript does.
+    fn witness_scalar<R: RngCore + CryptoRng>(
+        &self,
+        label: &'static [u8],
+        secret: &[u8],
+        rng: &mut R,
+    ) -> Scalar;
+}
+
+impl TranscriptProtocol for Transcript {
+    fn proto_name(&mut self, name: &'static [u8]) {
+        self.append_message(b"proto-name", name);
+    }
+
+    fn append_point(&mut self, label: &'static [u8], point: &CompressedRistretto) {
+        self.append_message(label, point.as_bytes());
+    }
+
+    fn validate_and_append_point(
+        &mut self,
+        label: &'static [u8],
+        point: &CompressedRistretto,
+    ) -> Result<(), SignatureError> {
+        if point.as_bytes() == &[0; 32] {
+            return Err(SignatureError::InvalidPoint);
+        }
+        self.append_point(label, point);
+        Ok(())
+    }
+
+    fn append_scalar(&mut self, label: &'static [u8], scalar: &Scalar) {
+        self.append_message(label, scalar.as_bytes());
+    }
+
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        let mut bytes = [0; 64];
+        self.challenge_bytes(label, &mut bytes);
+        Scalar::from_bytes_mod_order_wide(bytes)
+    }
+
+    fn witness_scalar<R: RngCore + CryptoRng>(
+        &self,
+        label: &'static [u8],
+        secret: &[u8],
+        rng: &mut R,
+    ) -> Scalar {
+        let mut bytes = [0; 64];
+        self.build_rng()
+            .rekey_with_witness_bytes(label, secret)
+            .finalize(rng)
+            .fill_bytes(&mut bytes);
+        let scalar = Scalar::from_bytes_mod_order_wide(bytes);
+        bytes.zeroize();
+        scalar
+    }
+}
+
+// A non-interactive Schnorr proof of knowledge of the discrete log a of
+// A = a * B, as RFC 8235 specifies it: the prover picks a nonce v, sends
+// V = v * B and r = v - a * c, where
+//
+//   c = H(B || V || A || UserID || OtherInfo)
+//
+// and the verifier checks that A is a valid point other than the
+// identity and that V = r * B + c * A. Here H is the transcript: B, A,
+// UserID and V are appended under those labels after the protocol name
+// "Schnorr-NIZK", and OtherInfo is whatever the caller appended before
+// handing the transcript over (a session ID, the other party's key). The
+// proof is only as bound to its context as the transcript is, so append
+// the same before verifying.
+//
+// UserID names the prover, so that a proof can't be replayed by someone
+// else claiming the same key. The nonce comes from witness_scalar, so it
+// is safe even with a poor RNG.
+//
+// prove_possession and verify_possession are the same proof under a
+// transcript of their own, with the key as the only statement: a proof of
+// possession, which a service registering public keys can demand to rule
+// out rogue keys, made up from other parties' keys, in aggregate
+// signatures or multi-party protocols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DlogProof {
+    commitment: CompressedRistretto,
+    response: Scalar,
+}
+
+impl DlogProof {
+    #[cfg(feature = "signing")]
+    pub fn prove<R: RngCore + CryptoRng>(
+        transcript: &mut Transcript,
+        user_id: &[u8],
+        secret: &Scalar,
+        rng: &mut R,
+    ) -> DlogProof {
+        let public = (secret * &*RISTRETTO_BASEPOINT_TABLE).compress();
+        statement(transcript, &public, user_id);
+
+        let mut v = transcript.witness_scalar(b"v", secret.as_bytes(), rng);
+        let commitment = (&v * &*RISTRETTO_BASEPOINT_TABLE).compress();
+        transcript.append_point(b"V", &commitment);
+        let c = transcript.challenge_scalar(b"c");
+        let response = Scalar::muladd(&-c, secret, &v);
+        v.zeroize();
+        DlogProof {
+            commitment,
+            response,
+        }
+    }
+
+    // Fails with InvalidPoint if public or the commitment is the
+    // identity, and with VerificationFailed if the proof doesn't hold.
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        user_id: &[u8],
+        public: &RistrettoPoint,
+    ) -> Result<(), SignatureError> {
+        if *public == RistrettoPoint::IDENTITY {
+            return Err(SignatureError::InvalidPoint);
+        }
+        statement(transcript, &public.compress(), user_id);
+        transcript.validate_and_append_point(b"V", &self.commitment)?;
+        let c = transcript.challenge_scalar(b"c");
+        let v = RistrettoPoint::vartime_double_scalar_mul_basepoint(&c, public, &self.response);
+        if v.compress() == self.commitment {
+            Ok(())
+        } else {
+            Err(SignatureError::VerificationFailed)
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    pub fn prove_possession<R: RngCore + CryptoRng>(secret: &Scalar, rng: &mut R) -> DlogProof {
+        DlogProof::prove(&mut Transcript::new(POSSESSION), b"", secret, rng)
+    }
+
+    pub fn verify_possession(&self, public: &RistrettoPoint) -> Result<(), SignatureError> {
+        self.verify(&mut Transcript::new(POSSESSION), b"", public)
+    }
+
+    // V || r.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(self.commitment.as_bytes());
+        bytes[32..].copy_from_slice(self.response.as_bytes());
+        bytes
+    }
+
+    // Fails with NonCanonicalScalar if r isn't reduced; V is only checked
+    // by verify.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, SignatureError> {
+        Ok(DlogProof {
+            commitment: CompressedRistretto(bytes[..32].try_into().unwrap()),
+            response: Scalar::from_canonical_bytes(bytes[32..].try_into().unwrap())
+                .ok_or(SignatureError::NonCanonicalScalar)?,
+        })
+    }
+}
+
+// The transcript label for proofs of possession.
+const POSSESSION: &[u8] = b"proof-of-possession";
+
+// Everything RFC 8235 hashes before V.
+fn statement(transcript: &mut Transcript, public: &CompressedRistretto, user_id: &[u8]) {
+    transcript.proto_name(b"Schnorr-NIZK");
+    transcript.append_point(b"B", &RISTRETTO_BASEPOINT.compress());
+    transcript.append_point(b"A", public);
+    transcript.append_message(b"UserID", user_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn transcript() -> Transcript {
+        let mut t = Transcript::new(b"test");
+        t.append_message(b"session", b"1");
+        t
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn dlog_proof() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let x = Scalar::from_bytes_mod_order([42; 32]);
+        let public = &x * &*RISTRETTO_BASEPOINT_TABLE;
+        let proof = DlogProof::prove(&mut transcript(), b"alice", &x, &mut rng);
+        assert_eq!(proof.verify(&mut transcript(), b"alice", &public), Ok(()));
+        let bytes = proof.to_bytes();
+        assert_eq!(DlogProof::from_bytes(&bytes), Ok(proof));
+
+        // RFC 8235's check, spelled out: V = r * B + c * A
+        let mut t = transcript();
+        statement(&mut t, &public.compress(), b"alice");
+        t.append_point(b"V", &proof.commitment);
+        let c = t.challenge_scalar(b"c");
+        assert_eq!(
+            (&proof.response * &*RISTRETTO_BASEPOINT_TABLE + public * c).compress(),
+            proof.commitment
+        );
+
+        // another session, another prover, another statement
+        let mut other = Transcript::new(b"test");
+        other.append_message(b"session", b"2");
+        assert_eq!(
+            proof.verify(&mut other, b"alice", &public),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            proof.verify(&mut transcript(), b"bob", &public),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            proof.verify(&mut transcript(), b"alice", &(public + public)),
+            Err(SignatureError::VerificationFailed)
+        );
+
+        // an identity commitment or key is refused before any arithmetic
+        let mut zero = bytes;
+        zero[..32].fill(0);
+        assert_eq!(
+            DlogProof::from_bytes(&zero)
+                .unwrap()
+                .verify(&mut transcript(), b"alice", &public),
+            Err(SignatureError::InvalidPoint)
+        );
+        let proof = DlogProof::prove(&mut transcript(), b"alice", &Scalar::ZERO, &mut rng);
+        assert_eq!(
+            proof.verify(&mut transcript(), b"alice", &RistrettoPoint::IDENTITY),
+            Err(SignatureError::InvalidPoint)
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn proof_of_possession() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let x = Scalar::from_bytes_mod_order([42; 32]);
+        let public = &x * &*RISTRETTO_BASEPOINT_TABLE;
+        let proof = DlogProof::prove_possession(&x, &mut rng);
+        assert_eq!(proof.verify_possession(&public), Ok(()));
+        assert_eq!(
+            proof.verify_possession(&(public + RISTRETTO_BASEPOINT)),
+            Err(SignatureError::VerificationFailed)
+        );
+        // not interchangeable with a proof under another transcript
+        assert_eq!(
+            proof.verify(&mut transcript(), b"", &public),
+            Err(SignatureError::VerificationFailed)
+        );
+        let proof = DlogProof::prove(&mut transcript(), b"", &x, &mut rng);
+        assert_eq!(
+            proof.verify_possession(&public),
+            Err(SignatureError::VerificationFailed)
+        );
+    }
+
+    // The transcript state, not just the last message, decides the
+    // challenge, and the witness depends on both the secret and the RNG.
+    #[test]
+    fn challenges_and_witnesses() {
+        let mut a = transcript();
+        let mut b = transcript();
+        a.append_scalar(b"x", &Scalar::ONE);
+        b.append_scalar(b"y", &Scalar::ONE);
+        assert_ne!(a.challenge_scalar(b"c"), b.challenge_scalar(b"c"));
+
+        let t = transcript();
+        let witness =
+            |secret: &[u8], seed| t.witness_scalar(b"r", secret, &mut StdRng::seed_from_u64(seed));
+        assert_eq!(witness(b"secret", 1), witness(b"secret", 1));
+        assert_ne!(witness(b"secret", 1), witness(b"secret", 2));
+        assert_ne!(witness(b"secret", 1), witness(b"secreT", 1));
+    }
+}
@@ -0,0 +1,244 @@
+use sha2::{Digest, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::signing::{PublicKey, Signature};
+#[cfg(feature = "signing")]
+use crate::sodium;
+use crate::x25519::{x25519, x25519_base};
+
+// TweetNaCl's functions under their C names and with their C signatures,
+// for people porting code written against it: outputs are written into
+// caller-provided buffers, lengths are u64, and the return value is 0 on
+// success and -1 on failure. Like the C code, these don't check buffer
+// sizes beyond what indexing does, so a buffer that is too short panics
+// instead of being overrun.
+//
+// Where this crate is stricter than TweetNaCl, the stricter behaviour wins:
+// a signature whose S is not reduced mod L fails to open (TweetNaCl
+// accepts it), crypto_sign_open uses the strict verification libsodium
+// does, rejecting small-order keys and R values (TweetNaCl lets a
+// small-order key "sign" anything), and crypto_sign fails if the public
+// half of the secret key doesn't belong to its seed (TweetNaCl signs with
+// it anyway).
+
+pub const CRYPTO_SIGN_BYTES: u64 = 64;
+pub const CRYPTO_SIGN_PUBLICKEYBYTES: u64 = 32;
+pub const CRYPTO_SIGN_SECRETKEYBYTES: u64 = 64;
+pub const CRYPTO_SCALARMULT_BYTES: u64 = 32;
+pub const CRYPTO_HASH_BYTES: u64 = 64;
+
+// TweetNaCl calls randombytes internally; here the randomness comes from
+// the caller's RNG instead.
+#[cfg(all(feature = "signing", feature = "rand"))]
+pub fn crypto_sign_keypair<R: rand_core::RngCore + rand_core::CryptoRng>(
+    pk: &mut [u8],
+    sk: &mut [u8],
+    rng: &mut R,
+) -> i32 {
+    let (public, secret) = sodium::crypto_sign_keypair(rng);
+    pk[..32].copy_from_slice(&public);
+    sk[..64].copy_from_slice(&secret);
+    0
+}
+
+// Writes the signature followed by the n bytes of m to sm, and n + 64 to
+// smlen.
+#[cfg(feature = "signing")]
+pub fn crypto_sign(sm: &mut [u8], smlen: &mut u64, m: &[u8], n: u64, sk: &[u8]) -> i32 {
+    let n = n as usize;
+    let Ok(signed) = sodium::crypto_sign(&m[..n], sk[..64].try_into().unwrap()) else {
+        return -1;
+    };
+    sm[..n + 64].copy_from_slice(&signed);
+    *smlen = n as u64 + 64;
+    0
+}
+
+// Checks the n-byte signed message sm and writes the message to m (which,
+// as in TweetNaCl, must have room for n bytes) and its length to mlen. On
+// failure mlen is set to -1 (u64::MAX) and m is zeroed.
+pub fn crypto_sign_open(m: &mut [u8], mlen: &mut u64, sm: &[u8], n: u64, pk: &[u8]) -> i32 {
+    let n = n as usize;
+    *mlen = u64::MAX;
+    let m = &mut m[..n];
+    m.fill(0);
+    if n < 64 {
+        return -1;
+    }
+
+    let Ok(public) = PublicKey::from_bytes(pk[..32].try_into().unwrap()) else {
+        return -1;
+    };
+    let Ok(signature) = Signature::from_bytes(sm[..64].try_into().unwrap()) else {
+        return -1;
+    };
+    let message = &sm[64..n];
+    if public.verify_strict(message, &signature).is_err() {
+        return -1;
+    }
+    m[..n - 64].copy_from_slice(message);
+    *mlen = (n - 64) as u64;
+    0
+}
+
+// q = X25519(n, p)
+pub fn crypto_scalarmult(q: &mut [u8], n: &[u8], p: &[u8]) -> i32 {
+    q[..32].copy_from_slice(&x25519(
+        n[..32].try_into().unwrap(),
+        p[..32].try_into().unwrap(),
+    ));
+    0
+}
+
+// q = X25519(n, 9)
+pub fn crypto_scalarmult_base(q: &mut [u8], n: &[u8]) -> i32 {
+    q[..32].copy_from_slice(&x25519_base(n[..32].try_into().unwrap()));
+    0
+}
+
+// SHA-512 of the first n bytes of m.
+pub fn crypto_hash(out: &mut [u8], m: &[u8], n: u64) -> i32 {
+    out[..64].copy_from_slice(&Sha512::digest(&m[..n as usize]));
+    0
+}
+
+// Constant-time comparisons: 0 if the first 16 (or 32) bytes of x and y are
+// equal, -1 otherwise.
+pub fn crypto_verify_16(x: &[u8], y: &[u8]) -> i32 {
+    verify_n(&x[..16], &y[..16])
+}
+
+pub fn crypto_verify_32(x: &[u8], y: &[u8]) -> i32 {
+    verify_n(&x[..32], &y[..32])
+}
+
+fn verify_n(x: &[u8], y: &[u8]) -> i32 {
+    if bool::from(x.ct_eq(y)) {
+        0
+    } else {
+        -1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 8032 section 7.1, test 2
+    const PUBLIC: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const SIGNED: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+                          085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c0072";
+
+    #[test]
+    fn sign_open() {
+        let pk = hex::decode(PUBLIC).unwrap();
+        let sm = hex::decode(SIGNED).unwrap();
+        let mut m = [0xff; 65];
+        let mut mlen = 0;
+        assert_eq!(crypto_sign_open(&mut m, &mut mlen, &sm, 65, &pk), 0);
+        assert_eq!(mlen, 1);
+        assert_eq!(m[0], 0x72);
+
+        let mut tampered = sm.clone();
+        tampered[64] ^= 1;
+        assert_eq!(crypto_sign_open(&mut m, &mut mlen, &tampered, 65, &pk), -1);
+        assert_eq!(mlen, u64::MAX);
+        assert_eq!(m, [0; 65]);
+        m = [0xff; 65];
+        assert_eq!(crypto_sign_open(&mut m, &mut mlen, &sm, 63, &pk), -1);
+        assert_eq!(m[..63], [0; 63]);
+
+        // the identity as a public key, with R the identity and S = 0,
+        // opens in TweetNaCl but not here
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        let mut forged = [0; 65];
+        forged[0] = 1;
+        m = [0xff; 65];
+        assert_eq!(
+            crypto_sign_open(&mut m, &mut mlen, &forged, 65, &identity),
+            -1
+        );
+        assert_eq!(mlen, u64::MAX);
+        assert_eq!(m, [0; 65]);
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn sign() {
+        let seed = hex::decode("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb");
+        let mut sk = seed.unwrap();
+        sk.extend_from_slice(&hex::decode(PUBLIC).unwrap());
+        let mut sm = [0; 65];
+        let mut smlen = 0;
+        assert_eq!(crypto_sign(&mut sm, &mut smlen, &[0x72], 1, &sk), 0);
+        assert_eq!(smlen, 65);
+        assert_eq!(hex::encode(sm), SIGNED);
+
+        sk[63] ^= 1;
+        assert_eq!(crypto_sign(&mut sm, &mut smlen, &[0x72], 1, &sk), -1);
+    }
+
+    #[cfg(all(feature = "signing", feature = "rand"))]
+    #[test]
+    fn keypair() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let (mut pk, mut sk) = ([0; 32], [0; 64]);
+        assert_eq!(
+            crypto_sign_keypair(&mut pk, &mut sk, &mut StdRng::seed_from_u64(3)),
+            0
+        );
+        assert_eq!(pk, sk[32..]);
+        let mut sm = [0; 66];
+        let mut smlen = 0;
+        assert_eq!(crypto_sign(&mut sm, &mut smlen, b"hi", 2, &sk), 0);
+        let mut m = [0; 66];
+        let mut mlen = 0;
+        assert_eq!(crypto_sign_open(&mut m, &mut mlen, &sm, smlen, &pk), 0);
+        assert_eq!(&m[..2], b"hi");
+    }
+
+    // RFC 7748 section 6.1, Alice's key pair and the shared secret.
+    #[test]
+    fn scalarmult() {
+        let alice = hex::decode("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob = hex::decode("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        let (alice, bob) = (alice.unwrap(), bob.unwrap());
+        let mut alice_public = [0; 32];
+        assert_eq!(crypto_scalarmult_base(&mut alice_public, &alice), 0);
+        assert_eq!(
+            hex::encode(alice_public),
+            "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a"
+        );
+        let mut bob_public = [0; 32];
+        crypto_scalarmult_base(&mut bob_public, &bob);
+        let mut shared = [0; 32];
+        assert_eq!(crypto_scalarmult(&mut shared, &alice, &bob_public), 0);
+        assert_eq!(
+            hex::encode(shared),
+            "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742"
+        );
+    }
+
+    #[test]
+    fn hash_and_verify() {
+        let mut out = [0; 64];
+        assert_eq!(crypto_hash(&mut out, b"abcdef", 3), 0);
+        assert_eq!(
+            hex::encode(out),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+
+        let x = [7; 32];
+        let mut y = x;
+        assert_eq!(crypto_verify_32(&x, &y), 0);
+        assert_eq!(crypto_verify_16(&x, &y), 0);
+        y[20] = 0;
+        assert_eq!(crypto_verify_32(&x, &y), -1);
+        assert_eq!(crypto_verify_16(&x, &y), 0);
+        y[0] = 0;
+        assert_eq!(crypto_verify_16(&x, &y), -1);
+    }
+}
@@ -0,0 +1,336 @@
+use sha2::{Digest, Sha512};
+#[cfg(feature = "signing")]
+use zeroize::Zeroize;
+
+#[cfg(feature = "signing")]
+use crate::edwards::ED25519_BASEPOINT_TABLE;
+use crate::edwards::{CompressedEdwardsY, EdwardsPoint};
+use crate::scalar::Scalar;
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, SignatureError};
+
+// ECVRF-EDWARDS25519-SHA512-TAI, the verifiable random function of
+// RFC 9381 (suite 0x03) on Ed25519 keys. A VRF is a keyed hash whose
+// output anyone holding the public key can check: prove(key, alpha) gives
+// a proof pi, from which proof_to_hash gives a 64-byte output beta, and
+// verify(public, alpha, pi) gives the same beta, or an error if pi wasn't
+// made by that key for that alpha. Nobody without the secret key can
+// predict beta for a new alpha, and the key holder can't produce two
+// valid outputs for one alpha; that is what leader elections, lotteries
+// and sortition (who may propose the next block) need.
+//
+// The proof is a Chaum-Pedersen proof that Gamma = x * H shares its
+// discrete log with Y = x * B, for a point H hashed from alpha:
+//
+//   H     = encode_to_curve(Y, alpha)
+//   Gamma = x * H
+//   k     = SHA-512(prefix || H) mod L      nonce as in Ed25519 signing
+//   c     = challenge(Y, H, Gamma, k * B, k * H)
+//   s     = k + c * x
+//   pi    = Gamma || c || s                 32 + 16 + 32 bytes
+//
+// and verification recomputes c from U = s * B - c * Y and
+// V = s * H - c * Gamma. The output is beta = SHA-512(0x03 || 0x03 ||
+// 8 * Gamma || 0x00), a hash of Gamma alone, so it is fixed by the key and
+// alpha whatever nonce the prover used.
+//
+// H is found by try-and-increment: SHA-512(0x03 || 0x01 || Y || alpha ||
+// ctr || 0x00) is decoded as a point for ctr = 0, 1, ... until one
+// decodes, and multiplied by the cofactor. It takes two tries on average,
+// and the number of tries depends on alpha, so proving takes variable
+// time in alpha (not in the key). The other suite, -ELL2, hashes with
+// Elligator 2 in constant time and isn't implemented here.
+//
+// verify rejects public keys of small order, as the RFC's validate_key
+// option does: with Y = identity the "proof" (identity, c, 0) passes for
+// every alpha, making beta anything its author likes.
+
+// The suite string for ECVRF-EDWARDS25519-SHA512-TAI.
+const SUITE: u8 = 0x03;
+
+// An 80-byte proof: Gamma, the 16-byte challenge c and the response s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VrfProof {
+    gamma: EdwardsPoint,
+    c: [u8; 16],
+    s: Scalar,
+}
+
+impl VrfProof {
+    // Fails with InvalidPoint or NonCanonicalPoint if Gamma doesn't decode
+    // as RFC 8032 requires, and with NonCanonicalScalar if s isn't
+    // reduced.
+    pub fn from_bytes(bytes: &[u8; 80]) -> Result<Self, SignatureError> {
+        Ok(Self {
+            gamma: string_to_point(bytes[..32].try_into().unwrap())?,
+            c: bytes[32..48].try_into().unwrap(),
+            s: Scalar::from_canonical_bytes(bytes[48..].try_into().unwrap())
+                .ok_or(SignatureError::NonCanonicalScalar)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 80] {
+        let mut bytes = [0; 80];
+        bytes[..32].copy_from_slice(self.gamma.compress().as_bytes());
+        bytes[32..48].copy_from_slice(&self.c);
+        bytes[48..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    // The VRF output beta. This doesn't check the proof: only use it on
+    // proofs that verified, or that you made.
+    pub fn to_hash(&self) -> [u8; 64] {
+        Sha512::new()
+            .chain_update([SUITE, 0x03])
+            .chain_update(self.gamma.mul_by_cofactor().compress().as_bytes())
+            .chain_update([0x00])
+            .finalize()
+            .into()
+    }
+}
+
+#[cfg(feature = "signing")]
+pub fn prove(keypair: &Keypair, alpha: &[u8]) -> VrfProof {
+    let (mut x, mut prefix) = keypair.secret().expand::<Sha512>();
+    let y = keypair.public().as_bytes();
+    let h = encode_to_curve(y, alpha);
+    let h_string = h.compress();
+    let gamma = h * x;
+
+    let mut k = Scalar::from_bytes_mod_order_wide(
+        Sha512::new()
+            .chain_update(prefix)
+            .chain_update(h_string.as_bytes())
+            .finalize()
+            .into(),
+    );
+    let c = challenge(
+        y,
+        &h_string,
+        &gamma,
+        &(&k * &*ED25519_BASEPOINT_TABLE),
+        &(h * k),
+    );
+    let s = Scalar::muladd(&challenge_scalar(&c), &x, &k);
+    x.zeroize();
+    prefix.zeroize();
+    k.zeroize();
+    VrfProof { gamma, c, s }
+}
+
+// RFC 9381 section 5.3 with validate_key: the VRF output if proof is
+// public's proof for alpha. Fails with NonCanonicalPoint or
+// SmallOrderPoint for a bad key, and with VerificationFailed for a bad
+// proof.
+pub fn verify(
+    public: &PublicKey,
+    alpha: &[u8],
+    proof: &VrfProof,
+) -> Result<[u8; 64], SignatureError> {
+    let y = public.as_point();
+    if y.compress().as_bytes() != public.as_bytes() {
+        return Err(SignatureError::NonCanonicalPoint);
+    }
+    if y.is_small_order() {
+        return Err(SignatureError::SmallOrderPoint);
+    }
+
+    let h = encode_to_curve(public.as_bytes(), alpha);
+    let c = challenge_scalar(&proof.c);
+    let u = EdwardsPoint::vartime_double_scalar_mul_basepoint(&c, &-y, &proof.s);
+    let v = EdwardsPoint::vartime_multiscalar_mul(&[proof.s, -c], &[h, proof.gamma]);
+    if challenge(public.as_bytes(), &h.compress(), &proof.gamma, &u, &v) == proof.c {
+        Ok(proof.to_hash())
+    } else {
+        Err(SignatureError::VerificationFailed)
+    }
+}
+
+// RFC 8032's decoding, which unlike CompressedEdwardsY::decompress
+// refuses y >= p and x = 0 with the sign bit set: a point has exactly one
+// encoding that decodes.
+fn string_to_point(bytes: &[u8; 32]) -> Result<EdwardsPoint, SignatureError> {
+    let compressed = CompressedEdwardsY(*bytes);
+    let point = compressed
+        .decompress()
+        .ok_or(SignatureError::InvalidPoint)?;
+    if point.compress() != compressed {
+        return Err(SignatureError::NonCanonicalPoint);
+    }
+    Ok(point)
+}
+
+// ECVRF_encode_to_curve_try_and_increment, salted with the public key.
+fn encode_to_curve(public: &[u8; 32], alpha: &[u8]) -> EdwardsPoint {
+    for ctr in 0..=u8::MAX {
+        let hash = Sha512::new()
+            .chain_update([SUITE, 0x01])
+            .chain_update(public)
+            .chain_update(alpha)
+            .chain_update([ctr, 0x00])
+            .finalize();
+        if let Ok(point) = string_to_point(hash[..32].try_into().unwrap()) {
+            return point.mul_by_cofactor();
+        }
+    }
+    // each try fails with probability about 1/2
+    unreachable!("no point found in 256 tries")
+}
+
+// ECVRF_challenge_generation: the first 16 bytes of a hash of the five
+// points.
+fn challenge(
+    public: &[u8; 32],
+    h: &CompressedEdwardsY,
+    gamma: &EdwardsPoint,
+    u: &EdwardsPoint,
+    v: &EdwardsPoint,
+) -> [u8; 16] {
+    let hash = Sha512::new()
+        .chain_update([SUITE, 0x02])
+        .chain_update(public)
+        .chain_update(h.as_bytes())
+        .chain_update(gamma.compress().as_bytes())
+        .chain_update(u.compress().as_bytes())
+        .chain_update(v.compress().as_bytes())
+        .chain_update([0x00])
+        .finalize();
+    hash[..16].try_into().unwrap()
+}
+
+// c as a scalar: 128 bits, so already reduced.
+fn challenge_scalar(c: &[u8; 16]) -> Scalar {
+    let mut bytes = [0; 32];
+    bytes[..16].copy_from_slice(c);
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 9381 appendix B.3, examples 16 to 18: secret key, public key,
+    // alpha, pi and beta.
+    const RFC9381: [(&str, &str, &str, &str, &str); 3] = [
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f\
+             26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab12\
+             68a1b0db10836d9826a528ca76567805",
+            "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff\
+             66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "f3141cd382dc42909d19ec5110469e4feae18300e94f304590abdced48aed593\
+             3bf0864a62558b3ed7f2fea45c92a465301b3bbf5e3e54ddf2d935be3b67926d\
+             a3ef39226bbc355bdc9850112c8f4b02",
+            "eb4440665d3891d668e7e0fcaf587f1b4bd7fbfe99d0eb2211ccec90496310eb\
+             5e33821bc613efb94db5e5b54c70a848a0bef4553a41befc57663b56373a5031",
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            "af82",
+            "9bc0f79119cc5604bf02d23b4caede71393cedfbb191434dd016d30177ccbf80\
+             96bb474e53895c362d8628ee9f9ea3c0e52c7a5c691b6c18c9979866568add7a\
+             2d41b00b05081ed0f58ee5e31b3a970e",
+            "645427e5d00c62a23fb703732fa5d892940935942101e456ecca7bb217c61c45\
+             2118fec1219202a0edcf038bb6373241578be7217ba85a2687f7a0310b2df19f",
+        ),
+    ];
+
+    fn public(hex: &str) -> PublicKey {
+        PublicKey::from_bytes(hex::decode(hex).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    fn proof(hex: &str) -> VrfProof {
+        VrfProof::from_bytes(&hex::decode(hex).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn rfc9381_prove() {
+        for (secret, public, alpha, pi, beta) in RFC9381 {
+            let keypair = Keypair::from_seed(hex::decode(secret).unwrap().try_into().unwrap());
+            assert_eq!(hex::encode(keypair.public().as_bytes()), public);
+            let proof = prove(&keypair, &hex::decode(alpha).unwrap());
+            assert_eq!(hex::encode(proof.to_bytes()), pi);
+            assert_eq!(hex::encode(proof.to_hash()), beta);
+        }
+    }
+
+    #[test]
+    fn rfc9381_verify() {
+        for (_, public_hex, alpha, pi, beta) in RFC9381 {
+            let alpha = hex::decode(alpha).unwrap();
+            let beta = hex::decode(beta).unwrap();
+            assert_eq!(
+                verify(&public(public_hex), &alpha, &proof(pi)).unwrap()[..],
+                beta
+            );
+
+            // another message, another key
+            assert_eq!(
+                verify(&public(public_hex), b"x", &proof(pi)),
+                Err(SignatureError::VerificationFailed)
+            );
+            assert_eq!(
+                verify(&public(RFC9381[1].1), &alpha, &proof(RFC9381[2].3)),
+                Err(SignatureError::VerificationFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        let (_, public_hex, alpha, pi, _) = RFC9381[1];
+        let alpha = hex::decode(alpha).unwrap();
+        let bytes: [u8; 80] = hex::decode(pi).unwrap().try_into().unwrap();
+
+        // a flipped bit in c or s
+        for i in [40, 60] {
+            let mut tampered = bytes;
+            tampered[i] ^= 1;
+            assert_eq!(
+                verify(&public(public_hex), &alpha, &proof(&hex::encode(tampered))),
+                Err(SignatureError::VerificationFailed)
+            );
+        }
+
+        // s not reduced, Gamma not a point or not canonical
+        let mut tampered = bytes;
+        tampered[79] = 0xff;
+        assert_eq!(
+            VrfProof::from_bytes(&tampered),
+            Err(SignatureError::NonCanonicalScalar)
+        );
+        let mut tampered = bytes;
+        tampered[..32].copy_from_slice(&[0xff; 32]);
+        tampered[31] = 0x7f;
+        assert!(VrfProof::from_bytes(&tampered).is_err());
+        let mut tampered = bytes;
+        // the identity with the sign bit set
+        tampered[..32].copy_from_slice(&[0; 32]);
+        tampered[0] = 1;
+        tampered[31] = 0x80;
+        assert_eq!(
+            VrfProof::from_bytes(&tampered),
+            Err(SignatureError::NonCanonicalPoint)
+        );
+
+        // a small-order key, for which anything would verify
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        let identity = PublicKey::from_bytes(identity).unwrap();
+        assert_eq!(
+            verify(&identity, &alpha, &proof(pi)),
+            Err(SignatureError::SmallOrderPoint)
+        );
+    }
+}
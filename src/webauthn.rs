@@ -0,0 +1,178 @@
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+
+use crate::signing::{PublicKey, Signature, SignatureError};
+
+// Checking a WebAuthn assertion (a passkey or security key login) made
+// with an Ed25519 credential, COSE algorithm -8 (EdDSA).
+//
+// The authenticator signs
+//
+//   authenticatorData || SHA-256(clientDataJSON)
+//
+// with plain Ed25519. authenticatorData starts with the SHA-256 of the
+// relying party ID, a flags byte and a 32-bit big-endian signature
+// counter; anything after that (extensions) is signed but not read here.
+// The credential's public key is the 32-byte "x" of its COSE_Key (kty OKP,
+// crv Ed25519), stored at registration.
+//
+// verify_assertion checks the signature and returns the fixed fields.
+// The rest of the relying party's checks are policy and stay with the
+// caller: that the rpIdHash is its own (see AuthenticatorData::is_for),
+// the flags it requires, that the counter went up, and the type, challenge
+// and origin in clientDataJSON.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebAuthnError {
+    // authenticatorData shorter than its fixed 37-byte start.
+    Malformed,
+    // A signature that doesn't verify.
+    Signature(SignatureError),
+}
+
+impl fmt::Display for WebAuthnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebAuthnError::Malformed => f.write_str("malformed WebAuthn authenticator data"),
+            WebAuthnError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for WebAuthnError {}
+
+impl From<SignatureError> for WebAuthnError {
+    fn from(e: SignatureError) -> Self {
+        WebAuthnError::Signature(e)
+    }
+}
+
+// Flags in authenticatorData: the user was present (touched the key), the
+// user was verified (PIN or biometrics).
+pub const USER_PRESENT: u8 = 0x01;
+pub const USER_VERIFIED: u8 = 0x04;
+
+// rpIdHash, flags and signCount.
+const FIXED_LEN: usize = 37;
+
+// The fixed start of authenticatorData.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthenticatorData {
+    pub rp_id_hash: [u8; 32],
+    pub flags: u8,
+    pub sign_count: u32,
+}
+
+impl AuthenticatorData {
+    pub fn from_bytes(data: &[u8]) -> Result<Self, WebAuthnError> {
+        if data.len() < FIXED_LEN {
+            return Err(WebAuthnError::Malformed);
+        }
+        Ok(Self {
+            rp_id_hash: data[..32].try_into().unwrap(),
+            flags: data[32],
+            sign_count: u32::from_be_bytes(data[33..37].try_into().unwrap()),
+        })
+    }
+
+    // Whether the assertion was made for the relying party ID rp_id (a
+    // domain such as "example.com").
+    pub fn is_for(&self, rp_id: &str) -> bool {
+        self.rp_id_hash == <[u8; 32]>::from(Sha256::digest(rp_id.as_bytes()))
+    }
+}
+
+// The hash of the clientDataJSON bytes exactly as the client sent them.
+pub fn client_data_hash(client_data_json: &[u8]) -> [u8; 32] {
+    Sha256::digest(client_data_json).into()
+}
+
+// Checks signature over authenticator_data and client_data_hash against
+// the credential's key, and returns the fixed part of authenticator_data.
+pub fn verify_assertion(
+    public: &PublicKey,
+    authenticator_data: &[u8],
+    client_data_hash: &[u8; 32],
+    signature: &Signature,
+) -> Result<AuthenticatorData, WebAuthnError> {
+    let parsed = AuthenticatorData::from_bytes(authenticator_data)?;
+    let mut signed = Vec::with_capacity(authenticator_data.len() + 32);
+    signed.extend_from_slice(authenticator_data);
+    signed.extend_from_slice(client_data_hash);
+    public.verify(&signed, signature)?;
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 8032 test 1's key.
+    const PUBLIC: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const CLIENT_DATA: &str = r#"{"type":"webauthn.get","challenge":"AAECAwQFBgcICQoLDA0ODw","origin":"https://example.com","crossOrigin":false}"#;
+
+    fn public() -> PublicKey {
+        PublicKey::from_bytes(hex::decode(PUBLIC).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    // For example.com, user present and verified, counter 7, and an
+    // extension map after the fixed part.
+    fn authenticator_data() -> Vec<u8> {
+        let mut data = Sha256::digest(b"example.com").to_vec();
+        data.push(USER_PRESENT | USER_VERIFIED | 0x80);
+        data.extend_from_slice(&7u32.to_be_bytes());
+        data.extend_from_slice(&[0xa1, 0x61, 0x78, 0xf5]);
+        data
+    }
+
+    // The signature over authenticator_data() and CLIENT_DATA's hash with
+    // RFC 8032 test 1's seed.
+    const SIGNATURE: &str = "21953d19e5ec12ed3a90276bb9725aea19036885535e885eaad9f394d109388a\
+        a5878958c2a59d7468e2fbd80533c17dc81b113116be1d8be18dc1c816d71203";
+
+    fn signature() -> Signature {
+        Signature::try_from(&hex::decode(SIGNATURE).unwrap()[..]).unwrap()
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn signed_payload() {
+        let seed = hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+            .unwrap();
+        let keypair = crate::signing::Keypair::from_seed(seed.try_into().unwrap());
+        let mut signed = authenticator_data();
+        signed.extend_from_slice(&client_data_hash(CLIENT_DATA.as_bytes()));
+        assert_eq!(hex::encode(keypair.sign(&signed).to_bytes()), SIGNATURE);
+    }
+
+    #[test]
+    fn assertion() {
+        let hash = client_data_hash(CLIENT_DATA.as_bytes());
+        let data = verify_assertion(&public(), &authenticator_data(), &hash, &signature()).unwrap();
+        assert!(data.is_for("example.com"));
+        assert!(!data.is_for("example.org"));
+        assert_eq!(data.flags & USER_VERIFIED, USER_VERIFIED);
+        assert_eq!(data.sign_count, 7);
+
+        // another counter, another client data, a truncated extension
+        let mut counter = authenticator_data();
+        counter[36] += 1;
+        let other_hash = client_data_hash(CLIENT_DATA.replace("com", "org").as_bytes());
+        let truncated = &authenticator_data()[..40];
+        for (data, hash) in [
+            (&counter[..], &hash),
+            (&authenticator_data()[..], &other_hash),
+            (truncated, &hash),
+        ] {
+            assert_eq!(
+                verify_assertion(&public(), data, hash, &signature()),
+                Err(WebAuthnError::Signature(SignatureError::VerificationFailed))
+            );
+        }
+        assert_eq!(
+            verify_assertion(&public(), &authenticator_data()[..36], &hash, &signature()),
+            Err(WebAuthnError::Malformed)
+        );
+    }
+}
@@ -0,0 +1,353 @@
+use std::fmt;
+
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+use crate::montgomery::{MontgomeryPoint, X25519_BASEPOINT};
+use crate::scalar::Scalar;
+
+// X25519 Diffie-Hellman (RFC 7748) on top of the Montgomery ladder.
+//
+// Each party picks 32 random bytes k as its secret and publishes
+// x25519(k, 9); the shared secret is x25519(k, their public key), which is
+// the same for both since (a * b) * P = (b * a) * P.
+
+// The raw X25519 function: clamps k and multiplies the point with
+// u-coordinate u by it, returning the u-coordinate of the result.
+pub fn x25519(k: [u8; 32], u: [u8; 32]) -> [u8; 32] {
+    MontgomeryPoint(u).mul_clamped(&Scalar::clamp(k)).to_bytes()
+}
+
+// x25519(k, 9): the public key for the secret k.
+pub fn x25519_base(k: [u8; 32]) -> [u8; 32] {
+    x25519(k, X25519_BASEPOINT.to_bytes())
+}
+
+// One secret against many public keys, e.g. a server with a static key
+// answering many handshakes. The secret is clamped once, and with the
+// parallel feature the exchanges are spread over rayon's thread pool.
+pub fn x25519_batch(secret: &StaticSecret, public_keys: &[PublicKey]) -> Vec<SharedSecret> {
+    let mut k = Scalar::clamp(secret.0);
+    let exchange =
+        |public: &PublicKey| SharedSecret(MontgomeryPoint(public.0).mul_clamped(&k).to_bytes());
+
+    #[cfg(feature = "parallel")]
+    let shared = {
+        use rayon::prelude::*;
+        public_keys.par_iter().map(exchange).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let shared = public_keys.iter().map(exchange).collect();
+
+    k.zeroize();
+    shared
+}
+
+// The types below wrap x25519 so that the usual mistakes are hard to
+// make: secrets are zeroed when dropped and never printed, an
+// EphemeralSecret can only be used for one exchange (diffie_hellman takes
+// it by value), and the shared secret comes back as an opaque SharedSecret
+// rather than bytes that look like a key.
+
+// A secret for a single key exchange. It can't be cloned, serialized or
+// built from bytes, only generated and then used up.
+pub struct EphemeralSecret([u8; 32]);
+
+impl EphemeralSecret {
+    #[cfg(feature = "rand")]
+    pub fn random_from_rng<R: rand_core::RngCore + rand_core::CryptoRng>(rng: &mut R) -> Self {
+        let mut bytes = [0; 32];
+        rng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    pub fn diffie_hellman(self, their_public: &PublicKey) -> SharedSecret {
+        SharedSecret(x25519(self.0, their_public.0))
+    }
+
+    // diffie_hellman, but None if the exchange wasn't contributory (see
+    // SharedSecret::was_contributory).
+    pub fn diffie_hellman_contributory(self, their_public: &PublicKey) -> Option<SharedSecret> {
+        Some(self.diffie_hellman(their_public)).filter(SharedSecret::was_contributory)
+    }
+}
+
+// A secret that can be reused for many exchanges and stored, e.g. a
+// server's long-term key.
+#[derive(Clone)]
+pub struct StaticSecret([u8; 32]);
+
+impl StaticSecret {
+    #[cfg(feature = "rand")]
+    pub fn random_from_rng<R: rand_core::RngCore + rand_core::CryptoRng>(rng: &mut R) -> Self {
+        let mut bytes = [0; 32];
+        rng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    pub fn diffie_hellman(&self, their_public: &PublicKey) -> SharedSecret {
+        SharedSecret(x25519(self.0, their_public.0))
+    }
+
+    pub fn diffie_hellman_contributory(&self, their_public: &PublicKey) -> Option<SharedSecret> {
+        Some(self.diffie_hellman(their_public)).filter(SharedSecret::was_contributory)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for StaticSecret {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicKey([u8; 32]);
+
+impl PublicKey {
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for PublicKey {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&EphemeralSecret> for PublicKey {
+    fn from(secret: &EphemeralSecret) -> Self {
+        Self(x25519_base(secret.0))
+    }
+}
+
+impl From<&StaticSecret> for PublicKey {
+    fn from(secret: &StaticSecret) -> Self {
+        Self(x25519_base(secret.0))
+    }
+}
+
+// The output of an exchange. It should go through a KDF before being used
+// as a key, so it deliberately offers nothing but its bytes.
+pub struct SharedSecret([u8; 32]);
+
+impl SharedSecret {
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    // If their public key has small order (there are a handful of such u
+    // values), the clamped secret, a multiple of 8, sends it to the point
+    // at infinity and the shared secret is all zeros whatever our secret
+    // was. Protocols that need both parties to contribute to the secret
+    // (RFC 7748 section 6.1 suggests checking) should reject that case.
+    // The comparison is constant time.
+    pub fn was_contributory(&self) -> bool {
+        !bool::from(self.0.ct_eq(&[0; 32]))
+    }
+}
+
+macro_rules! impl_secret {
+    ($name:ident) => {
+        impl Zeroize for $name {
+            fn zeroize(&mut self) {
+                self.0.zeroize();
+            }
+        }
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                self.zeroize();
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}(<redacted>)", stringify!($name))
+            }
+        }
+    };
+}
+
+impl_secret!(EphemeralSecret);
+impl_secret!(StaticSecret);
+impl_secret!(SharedSecret);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(h: &str) -> [u8; 32] {
+        hex::decode(h).unwrap().try_into().unwrap()
+    }
+
+    // RFC 7748, section 5.2
+    #[test]
+    fn rfc7748_vectors() {
+        let vectors = [
+            (
+                "a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4",
+                "e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c",
+                "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552",
+            ),
+            (
+                "4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d",
+                "e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493",
+                "95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957",
+            ),
+        ];
+        for (k, u, expected) in vectors {
+            assert_eq!(x25519(bytes(k), bytes(u)), bytes(expected));
+        }
+    }
+
+    // The iterated test from RFC 7748, section 5.2: starting from k = u = 9,
+    // repeatedly set k, u = x25519(k, u), k.
+    fn iterate(k: &mut [u8; 32], u: &mut [u8; 32], rounds: usize) {
+        for _ in 0..rounds {
+            let output = x25519(*k, *u);
+            *u = *k;
+            *k = output;
+        }
+    }
+
+    #[test]
+    fn rfc7748_iterated() {
+        let mut k = X25519_BASEPOINT.to_bytes();
+        let mut u = X25519_BASEPOINT.to_bytes();
+        iterate(&mut k, &mut u, 1);
+        assert_eq!(
+            k,
+            bytes("422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079")
+        );
+        iterate(&mut k, &mut u, 999);
+        assert_eq!(
+            k,
+            bytes("684cf59ba83309552800ef566f2f4d3c1c3887c49360e3875f2eb94d99532c51")
+        );
+    }
+
+    // A million rounds take minutes even in release mode, so this only
+    // runs with the expensive-tests feature.
+    #[test]
+    #[cfg(feature = "expensive-tests")]
+    fn rfc7748_iterated_million() {
+        let mut k = X25519_BASEPOINT.to_bytes();
+        let mut u = X25519_BASEPOINT.to_bytes();
+        iterate(&mut k, &mut u, 1_000_000);
+        assert_eq!(
+            k,
+            bytes("7c3911e0ab2586fd864497297e575e6f3bc601c0883c30df5f4dd2d24f665424")
+        );
+    }
+
+    // RFC 7748, section 6.1
+    #[test]
+    fn rfc7748_diffie_hellman() {
+        let alice = bytes("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let alice_public =
+            bytes("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a");
+        let bob = bytes("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        let bob_public = bytes("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+        let shared = bytes("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+
+        assert_eq!(x25519_base(alice), alice_public);
+        assert_eq!(x25519_base(bob), bob_public);
+        assert_eq!(x25519(alice, bob_public), shared);
+        assert_eq!(x25519(bob, alice_public), shared);
+    }
+
+    #[test]
+    fn static_secrets() {
+        // the RFC 7748 keys from above
+        let alice = StaticSecret::from(bytes(
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+        ));
+        let bob = StaticSecret::from(bytes(
+            "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+        ));
+        let shared = alice.diffie_hellman(&PublicKey::from(&bob));
+        assert_eq!(
+            shared.to_bytes(),
+            bytes("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742")
+        );
+        assert_eq!(
+            bob.diffie_hellman(&PublicKey::from(&alice)).as_bytes(),
+            shared.as_bytes()
+        );
+        assert_eq!(format!("{:?}", alice), "StaticSecret(<redacted>)");
+        assert_eq!(format!("{:?}", shared), "SharedSecret(<redacted>)");
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn ephemeral_secrets() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let alice = EphemeralSecret::random_from_rng(&mut rng);
+        let alice_public = PublicKey::from(&alice);
+        let bob = StaticSecret::random_from_rng(&mut rng);
+        let bob_public = PublicKey::from(&bob);
+
+        let alice_shared = alice.diffie_hellman(&bob_public);
+        let bob_shared = bob.diffie_hellman(&alice_public);
+        assert_eq!(alice_shared.as_bytes(), bob_shared.as_bytes());
+    }
+
+    #[test]
+    fn non_contributory() {
+        let secret = StaticSecret::from([0x42; 32]);
+        // u-coordinates of points of order 1, 2, 4 and 8, plus u + p
+        // encodings of the first two
+        let small_order = [
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "e0eb7a7c3b41b8ae1656e3faf19fc46ada098deb9c32b1fd866205165f49b800",
+            "5f9c95bca3508c24b1d0b1559c83ef5b04445cc4581c8e86d8224eddd09f1157",
+            "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        ];
+        for u in small_order {
+            let public = PublicKey::from(bytes(u));
+            assert!(!secret.diffie_hellman(&public).was_contributory());
+            assert!(secret.diffie_hellman_contributory(&public).is_none());
+        }
+
+        let public = PublicKey::from(&StaticSecret::from([7; 32]));
+        assert!(secret.diffie_hellman(&public).was_contributory());
+        assert!(secret.diffie_hellman_contributory(&public).is_some());
+    }
+
+    #[test]
+    fn batch() {
+        let secret = StaticSecret::from([0x42; 32]);
+        let public_keys: Vec<PublicKey> = (0..5u8)
+            .map(|i| PublicKey::from(&StaticSecret::from([i; 32])))
+            .collect();
+
+        let shared = x25519_batch(&secret, &public_keys);
+        assert_eq!(shared.len(), public_keys.len());
+        for (public, shared) in public_keys.iter().zip(shared.iter()) {
+            assert_eq!(shared.as_bytes(), secret.diffie_hellman(public).as_bytes());
+        }
+        assert!(x25519_batch(&secret, &[]).is_empty());
+    }
+}
@@ -0,0 +1,567 @@
+use std::fmt;
+
+use hkdf::Hkdf;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256, Sha512};
+use subtle::{Choice, ConditionallySelectable};
+use zeroize::Zeroize;
+
+use crate::edwards::ED25519_BASEPOINT_TABLE;
+use crate::field::FieldElement;
+use crate::montgomery::MontgomeryPoint;
+use crate::scalar::Scalar;
+use crate::signing::{self, Signature, SignatureError};
+use crate::x25519::{PublicKey, StaticSecret};
+
+// Signal's X3DH key agreement ("The X3DH Key Agreement Protocol", Marlinspike
+// and Perrin, 2016), with X25519, SHA-256 and XEdDSA. Bob publishes a
+// prekey bundle to a server ahead of time:
+//
+//   IK_B    his identity key
+//   SPK_B   a signed prekey, replaced every so often, and
+//           Sig(IK_B, Encode(SPK_B))
+//   OPK_B   optionally, one of a batch of one-time prekeys, each handed
+//           out once
+//
+// Alice, wanting to talk to Bob while he is offline, fetches the bundle,
+// checks the signature, makes an ephemeral key EK_A and computes
+//
+//   DH1 = DH(IK_A, SPK_B)   DH2 = DH(EK_A, IK_B)   DH3 = DH(EK_A, SPK_B)
+//   DH4 = DH(EK_A, OPK_B)   (if there was one)
+//
+//   SK = HKDF-SHA256(salt = 32 zero bytes,
+//                    ikm = 32 0xFF bytes || DH1 || DH2 || DH3 [|| DH4],
+//                    info)
+//
+// DH1 and DH2 authenticate each side to the other, DH3 and DH4 give
+// forward secrecy. She sends IK_A, EK_A and which of Bob's prekeys she
+// used; Bob does the same exchanges from his side. Both also get the
+// associated data AD = Encode(IK_A) || Encode(IK_B), which the first
+// message's AEAD should cover, and which each should check against the
+// identity they expect (safety numbers, in Signal's app). Encode(PK) is
+// 0x05, the type byte libsignal uses for Curve25519, followed by u.
+//
+// Signal signs with the X25519 identity key itself, using XEdDSA (Perrin,
+// 2016), rather than keeping a second, Ed25519 key. There is no XEdDSA
+// elsewhere in this tree, so the signing and verification the bundle needs
+// are here: the Montgomery key k is turned into an Edwards one by picking
+// the sign of A = k * B that makes it "positive" (negating k if need be),
+// and then it is Ed25519 with a hedged nonce,
+//
+//   r = SHA-512(0xFE || 31 0xFF bytes || a || M || Z) mod L
+//
+// for 64 random bytes Z. A verifier maps u to the Edwards point with
+// sign bit 0 and checks an ordinary Ed25519 signature.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum X3dhError {
+    // A bundle or initial message of the wrong length.
+    Malformed,
+    // An initial message naming prekeys other than the ones given.
+    UnknownPrekey,
+    // A public key of small order, which would make an exchange all zeros.
+    SmallOrderKey,
+    // The bundle's signature doesn't parse or verify.
+    Signature(SignatureError),
+}
+
+impl fmt::Display for X3dhError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            X3dhError::Malformed => f.write_str("malformed X3DH message"),
+            X3dhError::UnknownPrekey => f.write_str("unknown prekey"),
+            X3dhError::SmallOrderKey => f.write_str("small-order public key"),
+            X3dhError::Signature(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for X3dhError {}
+
+impl From<SignatureError> for X3dhError {
+    fn from(e: SignatureError) -> Self {
+        X3dhError::Signature(e)
+    }
+}
+
+// An identity key: an X25519 key that can also sign, with XEdDSA. The
+// secret is zeroized when dropped and redacted from Debug.
+#[derive(Clone)]
+pub struct IdentityKeyPair {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl IdentityKeyPair {
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self::from(StaticSecret::random_from_rng(rng))
+    }
+
+    pub fn public(&self) -> &PublicKey {
+        &self.public
+    }
+
+    pub fn secret(&self) -> &StaticSecret {
+        &self.secret
+    }
+
+    pub fn sign<R: RngCore + CryptoRng>(&self, message: &[u8], rng: &mut R) -> Signature {
+        xeddsa_sign(&self.secret, message, rng)
+    }
+}
+
+impl From<StaticSecret> for IdentityKeyPair {
+    fn from(secret: StaticSecret) -> Self {
+        let public = PublicKey::from(&secret);
+        Self { secret, public }
+    }
+}
+
+impl fmt::Debug for IdentityKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdentityKeyPair")
+            .field("secret", &"<redacted>")
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
+// What Bob publishes: IK_B || SPK_B || signature [|| OPK_B], 128 or 160
+// bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreKeyBundle {
+    identity_key: PublicKey,
+    signed_prekey: PublicKey,
+    signature: Signature,
+    one_time_prekey: Option<PublicKey>,
+}
+
+impl PreKeyBundle {
+    // Signs signed_prekey with identity.
+    pub fn new<R: RngCore + CryptoRng>(
+        identity: &IdentityKeyPair,
+        signed_prekey: &PublicKey,
+        one_time_prekey: Option<PublicKey>,
+        rng: &mut R,
+    ) -> Self {
+        Self {
+            identity_key: identity.public,
+            signed_prekey: *signed_prekey,
+            signature: identity.sign(&encode(signed_prekey), rng),
+            one_time_prekey,
+        }
+    }
+
+    pub fn identity_key(&self) -> &PublicKey {
+        &self.identity_key
+    }
+
+    pub fn signed_prekey(&self) -> &PublicKey {
+        &self.signed_prekey
+    }
+
+    pub fn one_time_prekey(&self) -> Option<&PublicKey> {
+        self.one_time_prekey.as_ref()
+    }
+
+    // Checks the signed prekey's signature; see xeddsa_verify.
+    pub fn verify(&self) -> Result<(), SignatureError> {
+        xeddsa_verify(
+            &self.identity_key,
+            &encode(&self.signed_prekey),
+            &self.signature,
+        )
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(160);
+        bytes.extend_from_slice(self.identity_key.as_bytes());
+        bytes.extend_from_slice(self.signed_prekey.as_bytes());
+        bytes.extend_from_slice(&self.signature.to_bytes());
+        if let Some(opk) = &self.one_time_prekey {
+            bytes.extend_from_slice(opk.as_bytes());
+        }
+        bytes
+    }
+
+    // Fails with Malformed unless there are 128 or 160 bytes, and with
+    // Signature(NonCanonicalScalar) if the signature's S isn't reduced. The
+    // signature itself is checked by verify.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, X3dhError> {
+        if bytes.len() != 128 && bytes.len() != 160 {
+            return Err(X3dhError::Malformed);
+        }
+        Ok(Self {
+            identity_key: key(&bytes[..32]),
+            signed_prekey: key(&bytes[32..64]),
+            signature: Signature::from_bytes(bytes[64..128].try_into().unwrap())?,
+            one_time_prekey: bytes.get(128..).filter(|b| !b.is_empty()).map(key),
+        })
+    }
+}
+
+// What Alice sends with her first message: IK_A || EK_A || SPK_B
+// [|| OPK_B], naming the prekeys of Bob's she used by their public keys.
+// 96 or 128 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InitialMessage {
+    identity_key: PublicKey,
+    ephemeral_key: PublicKey,
+    signed_prekey: PublicKey,
+    one_time_prekey: Option<PublicKey>,
+}
+
+impl InitialMessage {
+    pub fn identity_key(&self) -> &PublicKey {
+        &self.identity_key
+    }
+
+    pub fn signed_prekey(&self) -> &PublicKey {
+        &self.signed_prekey
+    }
+
+    pub fn one_time_prekey(&self) -> Option<&PublicKey> {
+        self.one_time_prekey.as_ref()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(128);
+        bytes.extend_from_slice(self.identity_key.as_bytes());
+        bytes.extend_from_slice(self.ephemeral_key.as_bytes());
+        bytes.extend_from_slice(self.signed_prekey.as_bytes());
+        if let Some(opk) = &self.one_time_prekey {
+            bytes.extend_from_slice(opk.as_bytes());
+        }
+        bytes
+    }
+
+    // Fails with Malformed unless there are 96 or 128 bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, X3dhError> {
+        if bytes.len() != 96 && bytes.len() != 128 {
+            return Err(X3dhError::Malformed);
+        }
+        Ok(Self {
+            identity_key: key(&bytes[..32]),
+            ephemeral_key: key(&bytes[32..64]),
+            signed_prekey: key(&bytes[64..96]),
+            one_time_prekey: bytes.get(96..).filter(|b| !b.is_empty()).map(key),
+        })
+    }
+}
+
+// The shared key SK and the associated data AD. Zeroized when dropped;
+// Debug redacts the key.
+pub struct X3dhOutput {
+    shared_key: [u8; 32],
+    associated_data: [u8; 66],
+}
+
+impl X3dhOutput {
+    pub fn shared_key(&self) -> &[u8; 32] {
+        &self.shared_key
+    }
+
+    pub fn associated_data(&self) -> &[u8; 66] {
+        &self.associated_data
+    }
+}
+
+impl Drop for X3dhOutput {
+    fn drop(&mut self) {
+        self.shared_key.zeroize();
+    }
+}
+
+impl fmt::Debug for X3dhOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("X3dhOutput")
+            .field("shared_key", &"<redacted>")
+            .field("associated_data", &self.associated_data)
+            .finish()
+    }
+}
+
+// Alice's side: checks bundle and agrees a key with its owner, using
+// the one-time prekey if there is one. info names the application. Fails
+// with Signature(..) if the bundle's signature doesn't verify, and with
+// SmallOrderKey if one of Bob's keys has small order.
+pub fn initiate<R: RngCore + CryptoRng>(
+    identity: &IdentityKeyPair,
+    bundle: &PreKeyBundle,
+    info: &[u8],
+    rng: &mut R,
+) -> Result<(X3dhOutput, InitialMessage), X3dhError> {
+    bundle.verify()?;
+    let ephemeral = StaticSecret::random_from_rng(rng);
+    let mut exchanges = vec![
+        (identity.secret(), &bundle.signed_prekey),
+        (&ephemeral, &bundle.identity_key),
+        (&ephemeral, &bundle.signed_prekey),
+    ];
+    if let Some(opk) = &bundle.one_time_prekey {
+        exchanges.push((&ephemeral, opk));
+    }
+    let output = agree(&exchanges, &identity.public, &bundle.identity_key, info)?;
+    let message = InitialMessage {
+        identity_key: identity.public,
+        ephemeral_key: PublicKey::from(&ephemeral),
+        signed_prekey: bundle.signed_prekey,
+        one_time_prekey: bundle.one_time_prekey,
+    };
+    Ok((output, message))
+}
+
+// Bob's side, with the secrets of the prekeys message names (looked up by
+// the caller, who should then delete the one-time one). Fails with
+// UnknownPrekey if they aren't the ones named, and with SmallOrderKey if
+// one of Alice's keys has small order.
+pub fn respond(
+    identity: &IdentityKeyPair,
+    signed_prekey: &StaticSecret,
+    one_time_prekey: Option<&StaticSecret>,
+    message: &InitialMessage,
+    info: &[u8],
+) -> Result<X3dhOutput, X3dhError> {
+    if PublicKey::from(signed_prekey) != message.signed_prekey
+        || one_time_prekey.map(PublicKey::from) != message.one_time_prekey
+    {
+        return Err(X3dhError::UnknownPrekey);
+    }
+    let mut exchanges = vec![
+        (signed_prekey, &message.identity_key),
+        (identity.secret(), &message.ephemeral_key),
+        (signed_prekey, &message.ephemeral_key),
+    ];
+    if let Some(opk) = one_time_prekey {
+        exchanges.push((opk, &message.ephemeral_key));
+    }
+    agree(&exchanges, &message.identity_key, &identity.public, info)
+}
+
+// XEdDSA signing with an X25519 secret key.
+pub fn xeddsa_sign<R: RngCore + CryptoRng>(
+    secret: &StaticSecret,
+    message: &[u8],
+    rng: &mut R,
+) -> Signature {
+    let mut k = Scalar::clamp(*secret.as_bytes()).to_scalar();
+    let mut public = (&k * &*ED25519_BASEPOINT_TABLE).compress().to_bytes();
+    let negative = Choice::from(public[31] >> 7);
+    let mut a = Scalar::conditional_select(&k, &-k, negative);
+    k.zeroize();
+    public[31] &= 0x7f;
+
+    let mut z = [0; 64];
+    rng.fill_bytes(&mut z);
+    let mut h = Sha512::new();
+    h.update([0xfe]);
+    h.update([0xff; 31]);
+    h.update(a.as_bytes());
+    h.update(message);
+    h.update(z);
+    let mut r = Scalar::from_bytes_mod_order_wide(h.finalize().into());
+    let big_r = (&r * &*ED25519_BASEPOINT_TABLE).compress();
+
+    let mut h = Sha512::new();
+    h.update(big_r.as_bytes());
+    h.update(public);
+    h.update(message);
+    let c = Scalar::from_bytes_mod_order_wide(h.finalize().into());
+    let s = Scalar::muladd(&c, &a, &r);
+    a.zeroize();
+    r.zeroize();
+    z.zeroize();
+
+    let mut bytes = [0; 64];
+    bytes[..32].copy_from_slice(big_r.as_bytes());
+    bytes[32..].copy_from_slice(s.as_bytes());
+    Signature::from_bytes(&bytes).unwrap()
+}
+
+// XEdDSA verification. Fails with InvalidPoint if u isn't reduced or
+// isn't on the curve, and otherwise as PublicKey::verify.
+pub fn xeddsa_verify(
+    public: &PublicKey,
+    message: &[u8],
+    signature: &Signature,
+) -> Result<(), SignatureError> {
+    let u = public.to_bytes();
+    if FieldElement::from_bytes(u).to_bytes() != u {
+        return Err(SignatureError::InvalidPoint);
+    }
+    let point = MontgomeryPoint(u)
+        .to_edwards(0)
+        .ok_or(SignatureError::InvalidPoint)?;
+    signing::PublicKey::from_bytes(point.compress().to_bytes())?.verify(message, signature)
+}
+
+// The DHs in order, through the KDF.
+fn agree(
+    exchanges: &[(&StaticSecret, &PublicKey)],
+    initiator: &PublicKey,
+    responder: &PublicKey,
+    info: &[u8],
+) -> Result<X3dhOutput, X3dhError> {
+    let mut ikm = vec![0xff; 32];
+    for (secret, public) in exchanges {
+        let shared = secret
+            .diffie_hellman_contributory(public)
+            .ok_or(X3dhError::SmallOrderKey)?;
+        ikm.extend_from_slice(shared.as_bytes());
+    }
+    let mut shared_key = [0; 32];
+    Hkdf::<Sha256>::new(Some(&[0; 32]), &ikm)
+        .expand(info, &mut shared_key)
+        .unwrap();
+    ikm.zeroize();
+
+    let mut associated_data = [0; 66];
+    associated_data[..33].copy_from_slice(&encode(initiator));
+    associated_data[33..].copy_from_slice(&encode(responder));
+    Ok(X3dhOutput {
+        shared_key,
+        associated_data,
+    })
+}
+
+fn encode(key: &PublicKey) -> [u8; 33] {
+    let mut bytes = [0x05; 33];
+    bytes[1..].copy_from_slice(key.as_bytes());
+    bytes
+}
+
+fn key(bytes: &[u8]) -> PublicKey {
+    PublicKey::from(<[u8; 32]>::try_from(bytes).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const INFO: &[u8] = b"toy-ed25519 X3DH test";
+
+    #[test]
+    fn xeddsa() {
+        let mut rng = StdRng::seed_from_u64(1);
+        // enough keys that both signs of k * B come up
+        for _ in 0..8 {
+            let key = IdentityKeyPair::random(&mut rng);
+            let signature = key.sign(b"message", &mut rng);
+            assert_eq!(xeddsa_verify(key.public(), b"message", &signature), Ok(()));
+            assert_eq!(
+                xeddsa_verify(key.public(), b"massage", &signature),
+                Err(SignatureError::VerificationFailed)
+            );
+            // the nonce is hedged: two signatures of one message differ
+            assert_ne!(key.sign(b"message", &mut rng), signature);
+        }
+
+        let key = IdentityKeyPair::random(&mut rng);
+        let signature = key.sign(b"message", &mut rng);
+        // p + 9, an unreduced encoding of the basepoint
+        let mut unreduced = [0xff; 32];
+        unreduced[0] = 0xf6;
+        unreduced[31] = 0x7f;
+        assert_eq!(
+            xeddsa_verify(&PublicKey::from(unreduced), b"message", &signature),
+            Err(SignatureError::InvalidPoint)
+        );
+    }
+
+    #[test]
+    fn handshake() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let alice = IdentityKeyPair::random(&mut rng);
+        let bob = IdentityKeyPair::random(&mut rng);
+        let spk = StaticSecret::random_from_rng(&mut rng);
+        let opk = StaticSecret::random_from_rng(&mut rng);
+
+        // with a one-time prekey
+        let bundle = PreKeyBundle::new(
+            &bob,
+            &PublicKey::from(&spk),
+            Some(PublicKey::from(&opk)),
+            &mut rng,
+        );
+        let bundle = PreKeyBundle::from_bytes(&bundle.to_bytes()).unwrap();
+        let (ours, message) = initiate(&alice, &bundle, INFO, &mut rng).unwrap();
+        let message = InitialMessage::from_bytes(&message.to_bytes()).unwrap();
+        let theirs = respond(&bob, &spk, Some(&opk), &message, INFO).unwrap();
+        assert_eq!(ours.shared_key(), theirs.shared_key());
+        assert_eq!(ours.associated_data(), theirs.associated_data());
+        assert_eq!(&ours.associated_data()[1..33], alice.public().as_bytes());
+        assert_eq!(message.identity_key(), alice.public());
+
+        // without one
+        let bundle = PreKeyBundle::new(&bob, &PublicKey::from(&spk), None, &mut rng);
+        assert_eq!(bundle.to_bytes().len(), 128);
+        let (without, message) = initiate(&alice, &bundle, INFO, &mut rng).unwrap();
+        assert_eq!(message.to_bytes().len(), 96);
+        let theirs = respond(&bob, &spk, None, &message, INFO).unwrap();
+        assert_eq!(without.shared_key(), theirs.shared_key());
+        assert_ne!(without.shared_key(), ours.shared_key());
+
+        // another application's info gives another key
+        let other = respond(&bob, &spk, None, &message, b"other").unwrap();
+        assert_ne!(other.shared_key(), theirs.shared_key());
+        assert!(!format!("{theirs:?}").contains(&format!("{:?}", theirs.shared_key)));
+    }
+
+    #[test]
+    fn bad_bundles_and_messages() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let alice = IdentityKeyPair::random(&mut rng);
+        let bob = IdentityKeyPair::random(&mut rng);
+        let mallory = IdentityKeyPair::random(&mut rng);
+        let spk = StaticSecret::random_from_rng(&mut rng);
+        let opk = StaticSecret::random_from_rng(&mut rng);
+
+        // a signed prekey swapped for another, or signed by someone else
+        let bundle = PreKeyBundle::new(
+            &bob,
+            &PublicKey::from(&spk),
+            Some(PublicKey::from(&opk)),
+            &mut rng,
+        );
+        let mut swapped = bundle.clone();
+        swapped.signed_prekey = PublicKey::from(&opk);
+        assert_eq!(
+            initiate(&alice, &swapped, INFO, &mut rng).unwrap_err(),
+            X3dhError::Signature(SignatureError::VerificationFailed)
+        );
+        let mut impersonated = PreKeyBundle::new(&mallory, &PublicKey::from(&spk), None, &mut rng);
+        impersonated.identity_key = *bob.public();
+        assert_eq!(
+            impersonated.verify(),
+            Err(SignatureError::VerificationFailed)
+        );
+        assert_eq!(
+            PreKeyBundle::from_bytes(&bundle.to_bytes()[..140]),
+            Err(X3dhError::Malformed)
+        );
+
+        // Bob holding different prekeys from the ones Alice used
+        let (_, message) = initiate(&alice, &bundle, INFO, &mut rng).unwrap();
+        assert_eq!(
+            respond(&bob, &spk, None, &message, INFO).unwrap_err(),
+            X3dhError::UnknownPrekey
+        );
+        assert_eq!(
+            respond(&bob, &opk, Some(&opk), &message, INFO).unwrap_err(),
+            X3dhError::UnknownPrekey
+        );
+        assert_eq!(
+            InitialMessage::from_bytes(&message.to_bytes()[..64]),
+            Err(X3dhError::Malformed)
+        );
+
+        // a small-order ephemeral key
+        let mut small = message;
+        small.ephemeral_key = PublicKey::from([0; 32]);
+        assert_eq!(
+            respond(&bob, &spk, Some(&opk), &small, INFO).unwrap_err(),
+            X3dhError::SmallOrderKey
+        );
+    }
+}
@@ -0,0 +1,443 @@
+use crate::pkcs8::{self, DerError, Reader, BIT_STRING, INTEGER, SEQUENCE};
+#[cfg(feature = "signing")]
+use crate::pkcs8::{put, put_algorithm, put_bit_string, OCTET_STRING, OID};
+#[cfg(feature = "signing")]
+use crate::signing::Keypair;
+use crate::signing::{PublicKey, Signature};
+
+// Minimal X.509 certificates (RFC 5280) for Ed25519 keys (RFC 8410): enough
+// to mint a self-signed certificate for raw-public-key style TLS, or the
+// root and leaves of a throwaway test PKI.
+//
+// A certificate is
+//
+//   SEQUENCE { tbsCertificate, signatureAlgorithm, signatureValue }
+//
+// where the signature is an Ed25519 signature over the DER of
+// tbsCertificate, and signatureAlgorithm is the same bare id-Ed25519
+// identifier that SubjectPublicKeyInfo uses (see pkcs8). The
+// tbsCertificate written here is a v3 certificate with
+//
+// - a positive serial number,
+// - issuer and subject names that are a single common name (CN), as a
+//   UTF8String,
+// - a validity period, as UTCTime up to 2049 and GeneralizedTime after,
+//   as RFC 5280 section 4.1.2.5 requires,
+// - the subject's key, and
+// - optionally, a subjectAltName of DNS names and a critical
+//   basicConstraints marking a CA.
+//
+// That is all a TLS stack needs to accept a pinned or self-signed
+// certificate. Reading is looser: any certificate signed with Ed25519 is
+// taken apart far enough to check its signature and get its key, but
+// names, validity and extensions aren't interpreted. Checking that a
+// certificate is fit for a purpose is the job of a path validator.
+
+// 2.5.4.3 (id-at-commonName), 2.5.29.17 (id-ce-subjectAltName) and 2.5.29.19
+// (id-ce-basicConstraints), encoded.
+#[cfg(feature = "signing")]
+const COMMON_NAME_OID: [u8; 3] = [0x55, 0x04, 0x03];
+#[cfg(feature = "signing")]
+const SUBJECT_ALT_NAME_OID: [u8; 3] = [0x55, 0x1d, 0x11];
+#[cfg(feature = "signing")]
+const BASIC_CONSTRAINTS_OID: [u8; 3] = [0x55, 0x1d, 0x13];
+
+#[cfg(feature = "signing")]
+const BOOLEAN: u8 = 0x01;
+#[cfg(feature = "signing")]
+const UTF8_STRING: u8 = 0x0c;
+#[cfg(feature = "signing")]
+const UTC_TIME: u8 = 0x17;
+#[cfg(feature = "signing")]
+const GENERALIZED_TIME: u8 = 0x18;
+#[cfg(feature = "signing")]
+const SET: u8 = 0x31;
+#[cfg(feature = "signing")]
+const DNS_NAME: u8 = 0x82;
+#[cfg(feature = "signing")]
+const EXTENSIONS: u8 = 0xa3;
+// [0] EXPLICIT Version, absent in v1 certificates.
+const VERSION: u8 = 0xa0;
+
+// What goes into a certificate besides the key. Times are Unix times in
+// seconds; RFC 5280 uses 99991231235959Z (253402300799) for a certificate
+// with no expiry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateParams {
+    pub serial: u64,
+    pub common_name: String,
+    pub not_before: u64,
+    pub not_after: u64,
+    pub dns_names: Vec<String>,
+    pub is_ca: bool,
+}
+
+impl CertificateParams {
+    // A certificate for keypair's public key, signed by keypair itself,
+    // with the common name as both issuer and subject.
+    #[cfg(feature = "signing")]
+    pub fn self_signed(&self, keypair: &Keypair) -> Vec<u8> {
+        self.signed_by(keypair.public(), keypair, &self.common_name)
+    }
+
+    // A certificate for subject, signed by the issuer's key pair under the
+    // issuer's common name (the common name of the issuer's own
+    // certificate, for a chain to link up).
+    #[cfg(feature = "signing")]
+    pub fn signed_by(&self, subject: &PublicKey, issuer: &Keypair, issuer_name: &str) -> Vec<u8> {
+        let tbs = self.tbs_certificate(subject, issuer_name);
+        let signature = issuer.sign(&tbs);
+
+        let mut body = tbs;
+        put_algorithm(&mut body);
+        put_bit_string(&mut body, BIT_STRING, &signature.to_bytes());
+        let mut der = Vec::with_capacity(body.len() + 4);
+        put(&mut der, SEQUENCE, &body);
+        der
+    }
+
+    #[cfg(feature = "signing")]
+    fn tbs_certificate(&self, subject: &PublicKey, issuer_name: &str) -> Vec<u8> {
+        let mut body = Vec::new();
+        let mut version = Vec::with_capacity(3);
+        put(&mut version, INTEGER, &[2]);
+        put(&mut body, VERSION, &version);
+        put(&mut body, INTEGER, &serial_number(self.serial));
+        put_algorithm(&mut body);
+        put_name(&mut body, issuer_name);
+        let mut validity = Vec::with_capacity(34);
+        put_time(&mut validity, self.not_before);
+        put_time(&mut validity, self.not_after);
+        put(&mut body, SEQUENCE, &validity);
+        put_name(&mut body, &self.common_name);
+        body.extend_from_slice(&pkcs8::encode_public_key(subject));
+
+        let mut extensions = Vec::new();
+        if !self.dns_names.is_empty() {
+            let mut names = Vec::new();
+            for name in &self.dns_names {
+                put(&mut names, DNS_NAME, name.as_bytes());
+            }
+            let mut value = Vec::with_capacity(names.len() + 4);
+            put(&mut value, SEQUENCE, &names);
+            put_extension(&mut extensions, &SUBJECT_ALT_NAME_OID, false, &value);
+        }
+        if self.is_ca {
+            // BasicConstraints { cA TRUE }, with no path length limit.
+            let mut value = Vec::with_capacity(5);
+            put(&mut value, SEQUENCE, &[BOOLEAN, 1, 0xff]);
+            put_extension(&mut extensions, &BASIC_CONSTRAINTS_OID, true, &value);
+        }
+        if !extensions.is_empty() {
+            let mut sequence = Vec::with_capacity(extensions.len() + 4);
+            put(&mut sequence, SEQUENCE, &extensions);
+            put(&mut body, EXTENSIONS, &sequence);
+        }
+
+        let mut tbs = Vec::with_capacity(body.len() + 4);
+        put(&mut tbs, SEQUENCE, &body);
+        tbs
+    }
+}
+
+// Checks der's signature against the issuer's key and returns the subject's
+// key. The certificate's key must be an Ed25519 key too. Errors are
+// pkcs8's, whose DER reader this uses.
+pub fn verify(der: &[u8], issuer: &PublicKey) -> Result<PublicKey, DerError> {
+    let (tbs, subject, signature) = parse(der)?;
+    issuer.verify(tbs, &signature)?;
+    Ok(subject)
+}
+
+// Checks that der is signed by its own key, and returns the key.
+pub fn verify_self_signed(der: &[u8]) -> Result<PublicKey, DerError> {
+    let (tbs, subject, signature) = parse(der)?;
+    subject.verify(tbs, &signature)?;
+    Ok(subject)
+}
+
+// The signed bytes (the whole tbsCertificate, header included), the
+// subject's key and the signature.
+fn parse(der: &[u8]) -> Result<(&[u8], PublicKey, Signature), DerError> {
+    let mut outer = Reader(der);
+    let mut certificate = Reader(outer.read(SEQUENCE)?);
+    outer.finish()?;
+    let (tbs, contents) = read_whole(&mut certificate, SEQUENCE)?;
+    pkcs8::read_algorithm(&mut certificate)?;
+    let signature: &[u8; 64] = pkcs8::read_bit_string(&mut certificate, BIT_STRING)?
+        .try_into()
+        .map_err(|_| DerError::Malformed)?;
+    certificate.finish()?;
+
+    let mut body = Reader(contents);
+    if body.peek() == Some(VERSION) {
+        body.read(VERSION)?;
+    }
+    body.read(INTEGER)?;
+    // The inner signature algorithm has to agree with the outer one.
+    pkcs8::read_algorithm(&mut body)?;
+    // issuer, validity, subject
+    for _ in 0..3 {
+        body.read(SEQUENCE)?;
+    }
+    let (spki, _) = read_whole(&mut body, SEQUENCE)?;
+    let subject = pkcs8::decode_public_key(spki)?;
+    // Unique identifiers and extensions may follow; none are needed here.
+
+    Ok((tbs, subject, Signature::from_bytes(signature)?))
+}
+
+// Reads a value like Reader::read, but also returns its encoding with the
+// tag and length, which is what gets signed.
+fn read_whole<'a>(reader: &mut Reader<'a>, tag: u8) -> Result<(&'a [u8], &'a [u8]), DerError> {
+    let start = reader.0;
+    let contents = reader.read(tag)?;
+    Ok((&start[..start.len() - reader.0.len()], contents))
+}
+
+// A Name holding one RDN with one attribute, the common name.
+#[cfg(feature = "signing")]
+fn put_name(out: &mut Vec<u8>, common_name: &str) {
+    let mut attribute = Vec::with_capacity(common_name.len() + 9);
+    put(&mut attribute, OID, &COMMON_NAME_OID);
+    put(&mut attribute, UTF8_STRING, common_name.as_bytes());
+    let mut pair = Vec::with_capacity(attribute.len() + 4);
+    put(&mut pair, SEQUENCE, &attribute);
+    let mut rdn = Vec::with_capacity(pair.len() + 4);
+    put(&mut rdn, SET, &pair);
+    put(out, SEQUENCE, &rdn);
+}
+
+#[cfg(feature = "signing")]
+fn put_extension(out: &mut Vec<u8>, oid: &[u8], critical: bool, value: &[u8]) {
+    let mut extension = Vec::with_capacity(value.len() + 12);
+    put(&mut extension, OID, oid);
+    // DER leaves out a BOOLEAN that has its default value, FALSE.
+    if critical {
+        put(&mut extension, BOOLEAN, &[0xff]);
+    }
+    put(&mut extension, OCTET_STRING, value);
+    put(out, SEQUENCE, &extension);
+}
+
+// A non-negative INTEGER: the big-endian bytes without leading zeros, plus
+// a zero byte if the top bit would otherwise make it negative.
+#[cfg(feature = "signing")]
+fn serial_number(serial: u64) -> Vec<u8> {
+    let bytes = serial.to_be_bytes();
+    let first = bytes.iter().position(|&b| b != 0).unwrap_or(7);
+    let mut integer = Vec::with_capacity(9);
+    if bytes[first] & 0x80 != 0 {
+        integer.push(0);
+    }
+    integer.extend_from_slice(&bytes[first..]);
+    integer
+}
+
+// YYMMDDHHMMSSZ for the years 1950 to 2049, YYYYMMDDHHMMSSZ after.
+#[cfg(feature = "signing")]
+fn put_time(out: &mut Vec<u8>, time: u64) {
+    let (year, month, day) = civil_from_days(time / 86400);
+    let seconds = time % 86400;
+    assert!(year <= 9999, "time past the year 9999");
+    let clock = format!(
+        "{month:02}{day:02}{:02}{:02}{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    if year < 2050 {
+        put(
+            out,
+            UTC_TIME,
+            format!("{:02}{clock}", year % 100).as_bytes(),
+        );
+    } else {
+        put(
+            out,
+            GENERALIZED_TIME,
+            format!("{year:04}{clock}").as_bytes(),
+        );
+    }
+}
+
+// The proleptic Gregorian date of a day count since 1970-01-01, by
+// splitting it into 400-year eras of 146097 days that start on March 1st
+// (so the leap day falls at the end of a year).
+#[cfg(feature = "signing")]
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pem;
+    use crate::signing::SignatureError;
+
+    // From openssl req -x509 -newkey ed25519 -subj /CN=o -days 1, with
+    // the subject and authority key identifier extensions openssl adds.
+    const OPENSSL: &str = "-----BEGIN CERTIFICATE-----
+MIIBLDCB36ADAgECAhRdZEdGY2RHc3sLTDra5FXC6sF2oDAFBgMrZXAwDDEKMAgG
+A1UEAwwBbzAeFw0yNjEwMTUwNjAwMDZaFw0yNjEwMTYwNjAwMDZaMAwxCjAIBgNV
+BAMMAW8wKjAFBgMrZXADIQDHtmbdZ7G20lc/aNTguHxzXb3cpZiABnV0Xf8J5PQ1
+RKNTMFEwHQYDVR0OBBYEFJR/TKW6yxd51qbQ06MprgQRG8oqMB8GA1UdIwQYMBaA
+FJR/TKW6yxd51qbQ06MprgQRG8oqMA8GA1UdEwEB/wQFMAMBAf8wBQYDK2VwA0EA
+GbYFX1HnTikgPBpNaPJkc2EdTS1ko35E2Zwc4chpcGPwvLqRZMMTkwXsgnJr8dwE
+x4XG5SWC9ty3sPfjGgI/Dw==
+-----END CERTIFICATE-----
+";
+    const OPENSSL_KEY: &str = "c7b666dd67b1b6d2573f68d4e0b87c735dbddca598800675745dff09e4f43544";
+
+    // CA_PARAMS self-signed with the seed [7; 32]; openssl x509 -text reads
+    // it and openssl verify accepts it.
+    #[cfg(feature = "signing")]
+    const CA: &str = "-----BEGIN CERTIFICATE-----
+MIHoMIGboAMCAQICAgCAMAUGAytlcDASMRAwDgYDVQQDDAdUZXN0IENBMCAXDTIz
+MTExNDIyMTMyMFoYDzk5OTkxMjMxMjM1OTU5WjASMRAwDgYDVQQDDAdUZXN0IENB
+MCowBQYDK2VwAyEA6kpsY+KcUgq+9VB7Ey7F+ZVHdq6+vnuSQh7qaRRG0iyjEzAR
+MA8GA1UdEwEB/wQFMAMBAf8wBQYDK2VwA0EAidHEPgx+gBH2SYzQ8eCh4yKx8AiY
+DvYpXX1/SrotNI3sN0uITnNK8eZ36MfTO1b23rDDbs6NPon4U9T+E04tDg==
+-----END CERTIFICATE-----
+";
+
+    #[cfg(feature = "signing")]
+    fn ca_params() -> CertificateParams {
+        CertificateParams {
+            serial: 0x80,
+            common_name: "Test CA".to_owned(),
+            not_before: 1700000000,
+            not_after: 253402300799,
+            dns_names: Vec::new(),
+            is_ca: true,
+        }
+    }
+
+    #[test]
+    fn openssl_certificate() {
+        let der = pem::decode_certificate(OPENSSL).unwrap();
+        let key = verify_self_signed(&der).unwrap();
+        assert_eq!(hex::encode(key.as_bytes()), OPENSSL_KEY);
+        assert_eq!(verify(&der, &key), Ok(key));
+
+        // a changed byte in the validity period
+        let mut tampered = der.clone();
+        tampered[60] ^= 1;
+        assert_eq!(
+            verify_self_signed(&tampered),
+            Err(DerError::Signature(SignatureError::VerificationFailed))
+        );
+        // trailing data
+        let mut trailing = der;
+        trailing.push(0);
+        assert_eq!(verify_self_signed(&trailing), Err(DerError::Malformed));
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn self_signed() {
+        let keypair = Keypair::from_seed([7; 32]);
+        let der = ca_params().self_signed(&keypair);
+        assert_eq!(pem::encode_certificate(&der), CA);
+        assert_eq!(verify_self_signed(&der), Ok(*keypair.public()));
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn issued() {
+        let ca = Keypair::from_seed([7; 32]);
+        let leaf = Keypair::from_seed([9; 32]);
+        let params = CertificateParams {
+            serial: 12345678901234,
+            common_name: "localhost".to_owned(),
+            not_before: 1700000000,
+            not_after: 2000000000,
+            dns_names: vec!["localhost".to_owned(), "example.test".to_owned()],
+            is_ca: false,
+        };
+        let der = params.signed_by(leaf.public(), &ca, "Test CA");
+        assert_eq!(verify(&der, ca.public()), Ok(*leaf.public()));
+        assert_eq!(
+            verify_self_signed(&der),
+            Err(DerError::Signature(SignatureError::VerificationFailed))
+        );
+
+        // long enough for a two-byte length
+        let params = CertificateParams {
+            dns_names: vec!["a".repeat(200); 2],
+            ..params
+        };
+        let der = params.signed_by(leaf.public(), &ca, "Test CA");
+        assert_eq!(der[1], 0x82);
+        assert_eq!(verify(&der, ca.public()), Ok(*leaf.public()));
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn encodings() {
+        assert_eq!(serial_number(0), [0]);
+        assert_eq!(serial_number(0x7f), [0x7f]);
+        assert_eq!(serial_number(0x80), [0, 0x80]);
+        assert_eq!(serial_number(0x0100), [1, 0]);
+        assert_eq!(
+            serial_number(u64::MAX),
+            [0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+        );
+
+        let time = |t| {
+            let mut out = Vec::new();
+            put_time(&mut out, t);
+            (out[0], String::from_utf8(out[2..].to_vec()).unwrap())
+        };
+        assert_eq!(time(0), (UTC_TIME, "700101000000Z".to_owned()));
+        // the leap day of 2000, and the last second UTCTime can hold
+        assert_eq!(time(951782400), (UTC_TIME, "000229000000Z".to_owned()));
+        assert_eq!(time(2524607999), (UTC_TIME, "491231235959Z".to_owned()));
+        assert_eq!(
+            time(2524608000),
+            (GENERALIZED_TIME, "20500101000000Z".to_owned())
+        );
+        assert_eq!(
+            time(253402300799),
+            (GENERALIZED_TIME, "99991231235959Z".to_owned())
+        );
+    }
+
+    #[test]
+    fn other_algorithms() {
+        // The OID turns up three times: as the signature algorithm inside
+        // the signed part, in the key and as the outer signature algorithm.
+        // Either signature algorithm changed to Ed448 (1.3.101.113) is
+        // refused.
+        let der = pem::decode_certificate(OPENSSL).unwrap();
+        let oids: Vec<usize> = der
+            .windows(5)
+            .enumerate()
+            .filter(|(_, w)| *w == [pkcs8::OID, 3, 0x2b, 0x65, 0x70])
+            .map(|(at, _)| at + 4)
+            .collect();
+        assert_eq!(oids.len(), 3);
+        for at in [oids[0], oids[2]] {
+            let mut ed448 = der.clone();
+            ed448[at] = 0x71;
+            assert_eq!(
+                verify_self_signed(&ed448),
+                Err(DerError::UnsupportedAlgorithm)
+            );
+        }
+    }
+}
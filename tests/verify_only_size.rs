@@ -0,0 +1,35 @@
+use std::process::Command;
+
+// Builds examples/verify_only.rs in release without the signing feature,
+// in a target directory of its own, and fails if the binary is larger
+// than MAX_BYTES (default 640 KiB), so a change that pulls signing or
+// other heavy code into a verify-only build shows up. It runs a release
+// build, so it is ignored by default:
+//
+//   cargo test --test verify_only_size -- --ignored --nocapture
+#[test]
+#[ignore]
+fn verify_only_binary_size() {
+    let max_bytes: u64 = std::env::var("MAX_BYTES")
+        .map(|max| max.parse().expect("MAX_BYTES is a number of bytes"))
+        .unwrap_or(655360);
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let target_dir = format!("{manifest_dir}/target/size-check");
+
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--release", "--example", "verify_only"])
+        .args(["--no-default-features"])
+        .args(["--manifest-path", &format!("{manifest_dir}/Cargo.toml")])
+        .args(["--target-dir", &target_dir])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let binary = format!(
+        "{target_dir}/release/examples/verify_only{}",
+        std::env::consts::EXE_SUFFIX
+    );
+    let size = std::fs::metadata(binary).unwrap().len();
+
+    println!("verify_only: {size} bytes (limit {max_bytes})");
+    assert!(size <= max_bytes, "verify_only is over the size limit");
+}